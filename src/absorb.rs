@@ -0,0 +1,440 @@
+//! `git stack absorb`: fold staged hunks into the commits on the current branch that last touched
+//! the same lines.
+//!
+//! Each staged hunk is blamed against HEAD, restricted to the branch's own commits (those between
+//! its LKG parent — or merge-base with its stack parent — and its tip). A hunk whose lines all
+//! come from one such commit gets a `fixup!` commit for it; anything else (lines from several
+//! commits or from below the branch, new/deleted/renamed/binary files) is fixed up into the tip
+//! commit with a warning. An autosquash rebase then folds the fixups in place.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Result, anyhow, bail};
+use colored::Colorize;
+
+use crate::{
+    git::{run_git, run_git_passthrough, run_git_status},
+    git2_ops::GitRepo,
+    state::State,
+};
+
+/// One `@@` hunk of a zero-context staged diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Hunk {
+    old_start: usize,
+    old_count: usize,
+    /// The `@@` header followed by the hunk's `-`/`+`/`\` lines.
+    lines: Vec<String>,
+}
+
+/// A file section of the staged diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FilePatch {
+    /// Path on the old side (`--- a/<path>`).
+    path: String,
+    /// `diff --git` line through `+++`, replayed verbatim in generated patches.
+    header: Vec<String>,
+    hunks: Vec<Hunk>,
+    /// False for creations, deletions, renames, mode changes, and binary files: those have no
+    /// old lines to blame, so the whole file is left for the tip commit.
+    absorbable: bool,
+}
+
+/// Parse `git diff --cached -U0` output into per-file hunks.
+fn parse_staged_diff(diff: &str) -> Vec<FilePatch> {
+    let mut files: Vec<FilePatch> = Vec::new();
+    for line in diff.lines() {
+        if line.starts_with("diff --git ") {
+            files.push(FilePatch {
+                path: String::new(),
+                header: vec![line.to_string()],
+                hunks: Vec::new(),
+                absorbable: true,
+            });
+            continue;
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+        if let Some(header) = line.strip_prefix("@@ ") {
+            let Some((old_start, old_count)) = parse_old_range(header) else {
+                file.absorbable = false;
+                continue;
+            };
+            file.hunks.push(Hunk {
+                old_start,
+                old_count,
+                lines: vec![line.to_string()],
+            });
+        } else if let Some(hunk) = file.hunks.last_mut() {
+            hunk.lines.push(line.to_string());
+        } else {
+            if let Some(path) = line.strip_prefix("--- a/") {
+                file.path = path.to_string();
+            } else if line.starts_with("new file mode")
+                || line.starts_with("deleted file mode")
+                || line.starts_with("rename from")
+                || line.starts_with("old mode")
+                || line.starts_with("Binary files")
+            {
+                file.absorbable = false;
+            }
+            file.header.push(line.to_string());
+        }
+    }
+    files
+}
+
+/// Parse the old-side range from the text after `@@ `: `-12,3 +12,4 @@` → `(12, 3)`. An omitted
+/// count means 1.
+fn parse_old_range(header: &str) -> Option<(usize, usize)> {
+    let old = header.strip_prefix('-')?.split_whitespace().next()?;
+    match old.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((old.parse().ok()?, 1)),
+    }
+}
+
+/// Old-side line numbers (1-based) whose blame decides a hunk's target: the replaced lines, or
+/// for a pure insertion the lines on either side of the insertion point.
+fn blame_lines(hunk: &Hunk) -> Vec<usize> {
+    if hunk.old_count == 0 {
+        [hunk.old_start, hunk.old_start + 1]
+            .into_iter()
+            .filter(|&line| line > 0)
+            .collect()
+    } else {
+        (hunk.old_start..hunk.old_start + hunk.old_count).collect()
+    }
+}
+
+/// The single stack commit responsible for `lines`, or `None` if they're attributed to more than
+/// one commit, to a commit outside `stack_commits`, or past the end of the file (`blame`).
+fn resolve_target(
+    lines: &[usize],
+    blame: &[String],
+    stack_commits: &HashSet<String>,
+) -> Option<String> {
+    let mut target: Option<&String> = None;
+    for &line in lines {
+        // An insertion after the last line only has one neighbor; skip the missing one.
+        let Some(commit) = blame.get(line - 1) else {
+            if lines.len() > 1 && line == lines[lines.len() - 1] {
+                continue;
+            }
+            return None;
+        };
+        if !stack_commits.contains(commit) || target.is_some_and(|t| t != commit) {
+            return None;
+        }
+        target = Some(commit);
+    }
+    target.cloned()
+}
+
+/// Render a patch containing only the selected hunks of each file. Hunk positions all refer to
+/// the same pre-image (HEAD), so any subset applies cleanly to it.
+fn render_patch(files: &[FilePatch], selected: &HashSet<(usize, usize)>) -> String {
+    let mut out = String::new();
+    for (file_index, file) in files.iter().enumerate() {
+        let hunks: Vec<&Hunk> = file
+            .hunks
+            .iter()
+            .enumerate()
+            .filter(|(hunk_index, _)| selected.contains(&(file_index, *hunk_index)))
+            .map(|(_, hunk)| hunk)
+            .collect();
+        if hunks.is_empty() {
+            continue;
+        }
+        for line in &file.header {
+            out.push_str(line);
+            out.push('\n');
+        }
+        for hunk in hunks {
+            for line in &hunk.lines {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
+
+/// Absorb the staged changes into the checked-out branch. Staged hunks and the autosquash rebase
+/// both belong to `HEAD`, so there is no branch to choose.
+pub(crate) fn absorb(git_repo: &GitRepo, state: &State, repo: &str) -> Result<()> {
+    let _lock = git_repo.lock()?;
+    let branch = &git_repo.current_branch()?;
+
+    if git_repo.am_in_progress() || git_repo.rebase_in_progress() {
+        bail!("A rebase or `git am` is in progress; finish or abort it before absorbing.");
+    }
+    let parent = state.get_parent_branch_of(repo, branch).ok_or_else(|| {
+        anyhow!(
+            "Branch {} is not in the stack tree (or is its root).",
+            branch
+        )
+    })?;
+    let tree_branch = state
+        .get_tree_branch(repo, branch)
+        .ok_or_else(|| anyhow!("Branch {} is not in the stack tree.", branch))?;
+    let base = match tree_branch.lkg_parent.clone() {
+        Some(lkg) => lkg,
+        None => git_repo.merge_base(&parent.name, branch)?,
+    };
+
+    let log = run_git(&["log", "--format=%H %s", &format!("{base}..HEAD")])?;
+    let subjects: HashMap<String, String> = log
+        .as_ref()
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .map(|(sha, subject)| (sha.to_string(), subject.to_string()))
+        .collect();
+    let Some(tip) = log.as_ref().lines().next().map(|l| l[..40].to_string()) else {
+        bail!(
+            "Branch {} has no commits above {} to absorb into.",
+            branch.yellow(),
+            parent.name.yellow()
+        );
+    };
+    let stack_commits: HashSet<String> = subjects.keys().cloned().collect();
+
+    let diff = run_git(&[
+        "diff",
+        "--cached",
+        "-U0",
+        "--no-color",
+        "--no-ext-diff",
+        "--no-renames",
+    ])?;
+    if diff.is_empty() {
+        bail!("Nothing staged to absorb. Stage changes with `git add` first.");
+    }
+    let files = parse_staged_diff(diff.as_ref());
+
+    // Assign each hunk a target commit; unresolved hunks stay staged for the tip.
+    let mut targets: Vec<(String, HashSet<(usize, usize)>)> = Vec::new();
+    let mut unresolved = 0usize;
+    for (file_index, file) in files.iter().enumerate() {
+        let blame = if file.absorbable {
+            git_repo.blame_since(&file.path, &base).ok()
+        } else {
+            None
+        };
+        for (hunk_index, hunk) in file.hunks.iter().enumerate() {
+            let target = blame
+                .as_ref()
+                .and_then(|blame| resolve_target(&blame_lines(hunk), blame, &stack_commits));
+            match target {
+                Some(commit) => match targets.iter_mut().find(|(c, _)| *c == commit) {
+                    Some((_, hunks)) => {
+                        hunks.insert((file_index, hunk_index));
+                    }
+                    None => targets.push((commit, HashSet::from([(file_index, hunk_index)]))),
+                },
+                None => unresolved += 1,
+            }
+        }
+        if file.hunks.is_empty() {
+            unresolved += 1;
+        }
+    }
+
+    // Build the fixup commits off to the side (commit-tree), then move the branch once. Each
+    // fixup's tree is HEAD plus every hunk absorbed so far, applied to a fresh index from HEAD so
+    // hunk positions never shift. The index is restored to the full staged tree at the end.
+    let staged_tree = run_git(&["write-tree"])?.output_or("write-tree produced no tree")?;
+    let result = build_fixups(&files, &targets, &tip, &staged_tree);
+    run_git(&["read-tree", &staged_tree])?;
+    let (new_head, tip_fixup) = result?;
+
+    // The index already holds the staged tree, which is exactly `new_head`'s tree.
+    run_git(&[
+        "update-ref",
+        &format!("refs/heads/{branch}"),
+        &new_head,
+        &tip,
+    ])?;
+
+    for (commit, hunks) in &targets {
+        println!(
+            "Absorbed {} hunk(s) into {} {}",
+            hunks.len(),
+            commit[..8].yellow(),
+            subjects.get(commit).map(String::as_str).unwrap_or("")
+        );
+    }
+    if tip_fixup {
+        eprintln!(
+            "{} {} staged change(s) had no single owning commit on {}; fixed up into the tip \
+             commit {}.",
+            "warning:".yellow().bold(),
+            unresolved,
+            branch.yellow(),
+            tip[..8].yellow()
+        );
+    }
+
+    let status = run_git_passthrough(&[
+        "-c",
+        "sequence.editor=:",
+        "rebase",
+        "-i",
+        "--autosquash",
+        "--autostash",
+        &base,
+    ])?;
+    if !status.success() {
+        eprintln!("The autosquash rebase stopped on a conflict.");
+        eprintln!(
+            "Resolve it and run `git rebase --continue`, or `git rebase --abort` to keep the \
+             fixup commits unsquashed on {}.",
+            branch
+        );
+        bail!("absorb did not finish");
+    }
+
+    let children: Vec<&str> = tree_branch
+        .branches
+        .iter()
+        .map(|b| b.name.as_str())
+        .collect();
+    if !children.is_empty() {
+        println!(
+            "{} was rewritten; restack its children with `git stack restack -b <child>`: {}",
+            branch.yellow(),
+            children.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Create one `fixup!` commit per target on top of `tip`, plus a final fixup of `tip` for
+/// whatever staged content wasn't absorbed. Returns the new head and whether that tip fixup was
+/// needed. Leaves the index modified; the caller restores it.
+fn build_fixups(
+    files: &[FilePatch],
+    targets: &[(String, HashSet<(usize, usize)>)],
+    tip: &str,
+    staged_tree: &str,
+) -> Result<(String, bool)> {
+    let mut head = tip.to_string();
+    let mut head_tree =
+        run_git(&["rev-parse", &format!("{tip}^{{tree}}")])?.output_or("HEAD has no tree")?;
+    let mut absorbed: HashSet<(usize, usize)> = HashSet::new();
+    for (commit, hunks) in targets {
+        absorbed.extend(hunks.iter().copied());
+        run_git(&["read-tree", tip])?;
+        let patch = render_patch(files, &absorbed);
+        let status = run_git_status(&["apply", "--cached", "--unidiff-zero", "-"], Some(&patch))?;
+        if !status.success() {
+            bail!(
+                "Failed to apply the hunks for {} to the index",
+                &commit[..8]
+            );
+        }
+        head_tree = run_git(&["write-tree"])?.output_or("write-tree produced no tree")?;
+        head = commit_tree(&head_tree, &head, &format!("fixup! {commit}"))?;
+    }
+    let tip_fixup = head_tree != staged_tree;
+    if tip_fixup {
+        head = commit_tree(staged_tree, &head, &format!("fixup! {tip}"))?;
+    }
+    Ok((head, tip_fixup))
+}
+
+fn commit_tree(tree: &str, parent: &str, message: &str) -> Result<String> {
+    run_git(&["commit-tree", tree, "-p", parent, "-m", message])?
+        .output_or("commit-tree produced no commit")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -3 +3 @@ fn a() {
+-    old();
++    new();
+@@ -10,0 +11,2 @@ fn b() {
++    added();
++    added_too();
+diff --git a/new.txt b/new.txt
+new file mode 100644
+index 0000000..3333333
+--- /dev/null
++++ b/new.txt
+@@ -0,0 +1 @@
++hello
+";
+
+    #[test]
+    fn parses_files_hunks_and_unabsorbable_files() {
+        let files = parse_staged_diff(DIFF);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "src/lib.rs");
+        assert!(files[0].absorbable);
+        assert_eq!(files[0].header.len(), 4);
+        assert_eq!(
+            files[0]
+                .hunks
+                .iter()
+                .map(|h| (h.old_start, h.old_count, h.lines.len()))
+                .collect::<Vec<_>>(),
+            vec![(3, 1, 3), (10, 0, 3)]
+        );
+        assert!(!files[1].absorbable);
+    }
+
+    #[test]
+    fn insertions_blame_both_neighbors() {
+        let files = parse_staged_diff(DIFF);
+        assert_eq!(blame_lines(&files[0].hunks[0]), vec![3]);
+        assert_eq!(blame_lines(&files[0].hunks[1]), vec![10, 11]);
+        let at_top = Hunk {
+            old_start: 0,
+            old_count: 0,
+            lines: vec![],
+        };
+        assert_eq!(blame_lines(&at_top), vec![1]);
+    }
+
+    #[test]
+    fn resolves_only_a_single_stack_commit() {
+        let blame: Vec<String> = ["base", "c1", "c1", "c2"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let stack = HashSet::from(["c1".to_string(), "c2".to_string()]);
+        assert_eq!(resolve_target(&[2, 3], &blame, &stack), Some("c1".into()));
+        // Lines from two stack commits are ambiguous.
+        assert_eq!(resolve_target(&[3, 4], &blame, &stack), None);
+        // A line from below the stack is out of scope.
+        assert_eq!(resolve_target(&[1, 2], &blame, &stack), None);
+        // Appending after the last line only needs the line before it.
+        assert_eq!(resolve_target(&[4, 5], &blame, &stack), Some("c2".into()));
+    }
+
+    #[test]
+    fn render_patch_keeps_headers_only_for_selected_files() {
+        let files = parse_staged_diff(DIFF);
+        let patch = render_patch(&files, &HashSet::from([(0, 1)]));
+        let expected = [
+            "diff --git a/src/lib.rs b/src/lib.rs",
+            "index 1111111..2222222 100644",
+            "--- a/src/lib.rs",
+            "+++ b/src/lib.rs",
+            "@@ -10,0 +11,2 @@ fn b() {",
+            "+    added();",
+            "+    added_too();",
+            "",
+        ]
+        .join("\n");
+        assert_eq!(patch, expected);
+    }
+}
@@ -0,0 +1,170 @@
+//! Names for new branches: validation against git's ref-name rules (`git check-ref-format
+//! --branch`), `checkout --slug`, and the per-repo branch prefix.
+
+use anyhow::{Result, bail};
+use colored::Colorize;
+
+/// Characters git never allows in a ref name.
+const FORBIDDEN: &[char] = &[' ', '~', '^', ':', '?', '*', '[', '\\'];
+
+/// Fail with a description of everything wrong with `name` as a branch name.
+pub(crate) fn validate(name: &str) -> Result<()> {
+    let problems = problems(name);
+    if !problems.is_empty() {
+        bail!(
+            "{name} is not a valid branch name: {}.",
+            problems.join("; "),
+            name = format!("'{name}'").red()
+        );
+    }
+    Ok(())
+}
+
+/// Why `name` isn't a valid branch name; empty when it is.
+fn problems(name: &str) -> Vec<String> {
+    if name.is_empty() {
+        return vec!["it is empty".to_string()];
+    }
+    let mut problems = Vec::new();
+
+    let mut offending: Vec<char> = Vec::new();
+    for c in name.chars() {
+        if (c.is_ascii_control() || FORBIDDEN.contains(&c)) && !offending.contains(&c) {
+            offending.push(c);
+        }
+    }
+    if !offending.is_empty() {
+        let listed: Vec<String> = offending.iter().map(|&c| describe(c)).collect();
+        problems.push(format!("it contains {}", listed.join(", ")));
+    }
+
+    if name.starts_with('-') {
+        problems.push("it starts with '-'".to_string());
+    }
+    if name == "@" {
+        problems.push("'@' alone is reserved".to_string());
+    }
+    if name.contains("..") {
+        problems.push("it contains '..'".to_string());
+    }
+    if name.contains("@{") {
+        problems.push("it contains '@{'".to_string());
+    }
+    if name.starts_with('/') || name.ends_with('/') || name.contains("//") {
+        problems.push("it has an empty '/'-separated part".to_string());
+    }
+    if name.ends_with('.') {
+        problems.push("it ends with '.'".to_string());
+    }
+    if name.split('/').any(|part| part.starts_with('.')) {
+        problems.push("a '/'-separated part starts with '.'".to_string());
+    }
+    if name.split('/').any(|part| part.ends_with(".lock")) {
+        problems.push("a '/'-separated part ends with '.lock'".to_string());
+    }
+    problems
+}
+
+fn describe(c: char) -> String {
+    match c {
+        ' ' => "' ' (space)".to_string(),
+        '\t' => "'\\t' (tab)".to_string(),
+        c if c.is_ascii_control() => format!("{:?} (control character)", c),
+        c => format!("'{c}'"),
+    }
+}
+
+/// Turn free text into a branch name: "Fix the login bug" becomes `fix-the-login-bug`. ASCII
+/// letters and digits are kept (lowercased), as are `/`, `_`, and `.` where git allows them;
+/// everything else becomes a single `-`.
+pub(crate) fn slugify(text: &str) -> String {
+    text.split('/')
+        .map(|part| {
+            let mut slug = String::new();
+            for c in part.chars() {
+                match c {
+                    c if c.is_ascii_alphanumeric() => slug.push(c.to_ascii_lowercase()),
+                    '_' => slug.push('_'),
+                    '.' if !slug.ends_with('.') => slug.push('.'),
+                    _ if !slug.ends_with(['-', '.']) => slug.push('-'),
+                    _ => {}
+                }
+            }
+            let slug = slug.trim_matches(['-', '.']);
+            slug.strip_suffix(".lock").unwrap_or(slug).to_string()
+        })
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// `name` with `prefix` in front, unless it already starts with it.
+pub(crate) fn with_prefix(prefix: Option<&str>, name: &str) -> String {
+    match prefix {
+        Some(prefix) if !name.starts_with(prefix) => format!("{prefix}{name}"),
+        _ => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_ordinary_branch_names() {
+        for name in ["feature", "user/fix-login", "v1.2_hotfix", "a/b/c", "UPPER"] {
+            assert!(problems(name).is_empty(), "{name}: {:?}", problems(name));
+        }
+    }
+
+    #[test]
+    fn lists_every_offending_character() {
+        let error = validate("fix the login bug?").unwrap_err().to_string();
+        assert!(error.contains("it contains ' ' (space), '?'"), "{error}");
+
+        for (name, problem) in [
+            ("", "it is empty"),
+            ("-x", "starts with '-'"),
+            ("a..b", "'..'"),
+            ("a@{1}", "'@{'"),
+            ("a//b", "empty '/'-separated part"),
+            ("a/", "empty '/'-separated part"),
+            ("a.", "ends with '.'"),
+            ("a/.hidden", "starts with '.'"),
+            ("a.lock/b", "ends with '.lock'"),
+            ("a~1^2", "'~', '^'"),
+            ("tab\there", "'\\t' (tab)"),
+            ("@", "reserved"),
+        ] {
+            let problems = problems(name).join("; ");
+            assert!(problems.contains(problem), "{name:?}: {problems}");
+        }
+    }
+
+    #[test]
+    fn slugify_makes_valid_names() {
+        for (text, slug) in [
+            ("Fix the login bug", "fix-the-login-bug"),
+            ("  Fix: the *login* bug!  ", "fix-the-login-bug"),
+            ("user/Add OAuth (v2)", "user/add-oauth-v2"),
+            ("bump to 1.2..3", "bump-to-1.2.3"),
+            ("snake_case stays", "snake_case-stays"),
+            ("a//b/", "a/b"),
+            (".hidden.lock", "hidden"),
+            ("café au lait", "caf-au-lait"),
+            ("?!", ""),
+        ] {
+            assert_eq!(slugify(text), slug, "{text:?}");
+            if !slug.is_empty() {
+                assert!(problems(slug).is_empty(), "{slug}");
+            }
+        }
+    }
+
+    #[test]
+    fn prefix_is_added_once() {
+        assert_eq!(with_prefix(Some("me/"), "fix"), "me/fix");
+        assert_eq!(with_prefix(Some("me/"), "me/fix"), "me/fix");
+        assert_eq!(with_prefix(None, "fix"), "fix");
+    }
+}
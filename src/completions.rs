@@ -0,0 +1,168 @@
+//! Shell completion scripts. clap_complete generates the static part (subcommands and flags); on
+//! top of that, bash, zsh, and fish complete the branch argument of [`BRANCH_SUBCOMMANDS`] by
+//! asking `git-stack __complete_branches` for the current repo's tracked branches.
+
+use std::io::Write;
+
+use anyhow::Result;
+use clap_complete::{Shell, generate};
+
+use crate::state::Branch;
+
+/// The hidden subcommand the scripts call for branch names.
+pub(crate) const COMPLETE_BRANCHES: &str = "__complete_branches";
+
+/// Subcommands whose positional argument names a tracked branch.
+const BRANCH_SUBCOMMANDS: &[&str] = &["checkout", "mount", "delete", "log", "diff"];
+
+/// Write the completion script for `shell` to `out`.
+pub(crate) fn write_script(
+    shell: Shell,
+    cmd: &mut clap::Command,
+    out: &mut impl Write,
+) -> Result<()> {
+    let mut script = Vec::new();
+    generate(shell, cmd, "git-stack", &mut script);
+    let script = String::from_utf8(script).expect("clap_complete writes UTF-8");
+    let script = match shell {
+        Shell::Bash => format!("{script}{}", bash_branches()),
+        Shell::Zsh => zsh_with_branches(&script),
+        Shell::Fish => format!("{script}{}", fish_branches()),
+        _ => script,
+    };
+    out.write_all(script.as_bytes())?;
+    Ok(())
+}
+
+/// Print `tree`'s branches, trunk first, one per line.
+pub(crate) fn print_branches(tree: &Branch) {
+    for branch in tree.iter() {
+        println!("{}", branch.name);
+    }
+}
+
+/// Wrap the generated `_git-stack` so a branch subcommand's positional completes tracked branches,
+/// then re-register it.
+fn bash_branches() -> String {
+    format!(
+        r#"
+_git-stack_branches() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}" subcommand="" i
+    for (( i = 1; i < COMP_CWORD; i++ )); do
+        [[ "${{COMP_WORDS[i]}}" == -* ]] && continue
+        subcommand="${{COMP_WORDS[i]}}"
+        break
+    done
+    if [[ "${{cur}}" != -* && " ${{COMP_WORDS[*]:1:COMP_CWORD-1}} " != *" -- "* ]]; then
+        case "${{subcommand}}" in
+            {})
+                COMPREPLY=( $(compgen -W "$(git-stack __complete_branches 2>/dev/null)" -- "${{cur}}") )
+                return 0
+                ;;
+        esac
+    fi
+    _git-stack "$@"
+}}
+
+if [[ "${{BASH_VERSINFO[0]}}" -eq 4 && "${{BASH_VERSINFO[1]}}" -ge 4 || "${{BASH_VERSINFO[0]}}" -gt 4 ]]; then
+    complete -F _git-stack_branches -o nosort -o bashdefault -o default git-stack
+else
+    complete -F _git-stack_branches -o bashdefault -o default git-stack
+fi
+"#,
+        BRANCH_SUBCOMMANDS.join("|")
+    )
+}
+
+/// Rename the generated `_git-stack` to `_git-stack_static` and define a `_git-stack` wrapper in
+/// its place, so both `compdef` and an autoloaded `_git-stack` file go through the wrapper.
+fn zsh_with_branches(script: &str) -> String {
+    let wrapper = format!(
+        r#"_git-stack() {{
+    local i subcommand
+    for (( i = 2; i < CURRENT; i++ )); do
+        [[ ${{words[i]}} == -* ]] && continue
+        subcommand=${{words[i]}}
+        break
+    done
+    if [[ -n $subcommand && ${{words[CURRENT]}} != -* && ${{words[(I)--]}} -eq 0 ]]; then
+        case $subcommand in
+            ({})
+                local -a branches
+                branches=(${{(f)"$(git-stack __complete_branches 2>/dev/null)"}})
+                compadd -a branches
+                return
+                ;;
+        esac
+    fi
+    _git-stack_static "$@"
+}}
+
+"#,
+        BRANCH_SUBCOMMANDS.join("|")
+    );
+    let script = script.replacen("\n_git-stack() {\n", "\n_git-stack_static() {\n", 1);
+    match script.rfind("if [ \"$funcstack[1]\" = \"_git-stack\" ]") {
+        Some(at) => format!("{}{wrapper}{}", &script[..at], &script[at..]),
+        None => script,
+    }
+}
+
+fn fish_branches() -> String {
+    BRANCH_SUBCOMMANDS
+        .iter()
+        .map(|subcommand| {
+            format!(
+                "complete -c git-stack -n \"__fish_git_stack_using_subcommand {subcommand}\" -f -a \"(git-stack __complete_branches 2>/dev/null)\"\n"
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+    use crate::Args;
+
+    fn script(shell: Shell) -> String {
+        let mut out = Vec::new();
+        write_script(shell, &mut Args::command(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn scripts_complete_branches_dynamically() {
+        let bash = script(Shell::Bash);
+        assert!(bash.contains("checkout|mount|delete|log|diff)"), "{bash}");
+        assert!(bash.trim_end().ends_with("fi"));
+        assert!(bash.contains("complete -F _git-stack_branches -o nosort"));
+
+        let zsh = script(Shell::Zsh);
+        assert_eq!(zsh.matches("\n_git-stack() {\n").count(), 1, "{zsh}");
+        assert!(zsh.contains("\n_git-stack_static() {\n"), "{zsh}");
+        let wrapper = zsh.find("__complete_branches").unwrap();
+        assert!(wrapper < zsh.rfind("if [ \"$funcstack[1]\"").unwrap());
+
+        let fish = script(Shell::Fish);
+        for subcommand in BRANCH_SUBCOMMANDS {
+            assert!(
+                fish.contains(&format!(
+                    "\"__fish_git_stack_using_subcommand {subcommand}\" -f -a \"(git-stack __complete_branches 2>/dev/null)\""
+                )),
+                "{fish}"
+            );
+        }
+
+        assert!(!script(Shell::PowerShell).contains("__complete_branches"));
+    }
+
+    #[test]
+    fn branch_subcommands_exist() {
+        let cmd = Args::command();
+        for subcommand in BRANCH_SUBCOMMANDS {
+            assert!(cmd.find_subcommand(subcommand).is_some(), "{subcommand}");
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use std::{
     process::{Command, ExitStatus},
+    sync::Once,
     time::Instant,
 };
 
@@ -42,6 +43,7 @@ impl AsRef<str> for GitOutput {
 
 /// Run a git command and return the output. If the git command fails, this will return an error.
 pub(crate) fn run_git_passthrough(args: &[&str]) -> Result<ExitStatus> {
+    crate::read_only::check_git(args)?;
     let start = Instant::now();
     tracing::debug!("Running `git {}`", args.join(" "));
     let mut child = Command::new("git").args(args).spawn()?;
@@ -62,6 +64,7 @@ struct RawGitOutput {
 /// failure. Used by callers (e.g. the fetch path) that need to react to the
 /// raw stderr instead of a pre-formatted error message.
 fn run_git_capture(args: &[&str]) -> Result<RawGitOutput> {
+    crate::read_only::check_git(args)?;
     let start = Instant::now();
     tracing::debug!("Running `git {}`", args.join(" "));
     let out = Command::new("git")
@@ -135,6 +138,7 @@ fn is_ref_lock_contention(stderr: &str) -> bool {
 }
 
 pub(crate) fn run_git_status(args: &[&str], stdin: Option<&str>) -> Result<ExitStatus> {
+    crate::read_only::check_git(args)?;
     let start = Instant::now();
     tracing::debug!("Running `git {}`", args.join(" "));
     let status = if let Some(stdin_text) = stdin {
@@ -163,15 +167,58 @@ pub(crate) fn run_git_status(args: &[&str], stdin: Option<&str>) -> Result<ExitS
     Ok(status)
 }
 
-pub(crate) fn git_fetch() -> Result<()> {
-    fetch_with_recovery(&["fetch", "--prune"])
+/// What [`git_fetch`] asks of `git fetch` beyond refreshing the remote-tracking branches.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct FetchOptions {
+    /// Drop remote-tracking refs whose branch is gone from the remote (`--prune`).
+    pub(crate) prune: bool,
+    /// Also fetch tags, overwriting local tags the remote moved (`--tags -f`).
+    pub(crate) tags: bool,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self {
+            prune: true,
+            tags: false,
+        }
+    }
+}
+
+/// Fetch from the default remote. Every git-stack fetch goes through here. A repo without that
+/// remote has nothing to fetch, so this succeeds without running git.
+pub(crate) fn git_fetch(git_repo: &GitRepo, options: FetchOptions) -> Result<()> {
+    if !has_default_remote()? {
+        tracing::debug!("No {DEFAULT_REMOTE} remote; skipping fetch");
+        return Ok(());
+    }
+    // A fetch can move `origin/HEAD` (`remote.origin.followRemoteHEAD`).
+    git_repo.forget_trunk();
+    fetch_with_recovery(&fetch_args(options))
+}
+
+fn has_default_remote() -> Result<bool> {
+    let out = run_git_capture(&["remote"])?;
+    Ok(out.stdout.lines().any(|remote| remote == DEFAULT_REMOTE))
+}
+
+fn fetch_args(options: FetchOptions) -> Vec<&'static str> {
+    let mut args = vec!["fetch"];
+    if options.tags {
+        args.extend(["--tags", "-f"]);
+    }
+    if options.prune {
+        args.push("--prune");
+    }
+    args.push(DEFAULT_REMOTE);
+    args
 }
 
 /// Run a fetch, and if it fails specifically because of a case-insensitive
 /// remote-ref collision, attempt to self-heal (delete stale twins) and retry
 /// once. The happy path runs the fetch and returns immediately — no extra ref
 /// scan or network call.
-pub(crate) fn fetch_with_recovery(args: &[&str]) -> Result<()> {
+fn fetch_with_recovery(args: &[&str]) -> Result<()> {
     let out = run_git_capture(args)?;
     if out.status.success() {
         // HAPPY PATH — zero extra work.
@@ -508,8 +555,69 @@ pub(crate) fn checkout_tracked_branch(git_repo: &GitRepo, branch: &str) -> Resul
     Ok(())
 }
 
+/// Whether tracked files have no uncommitted changes. Untracked files don't count: checkouts and
+/// rebases leave them alone unless they collide, and then git's own error names them.
 pub(crate) fn run_git_status_clean() -> Result<bool> {
-    Ok(run_git(&["status", "--porcelain"])?.is_empty())
+    Ok(run_git(&["status", "--porcelain", "--untracked-files=no"])?.is_empty())
+}
+
+/// `restack --autostash` and `checkout --autostash`: stash local changes (untracked files
+/// included) and return the stash commit, or `None` when the working tree is already clean.
+pub(crate) fn autostash_push() -> Result<Option<String>> {
+    if run_git_status_clean()? {
+        return Ok(None);
+    }
+    run_git(&["stash", "push", "-u", "-m", "git-stack autostash"])?;
+    let sha = run_git(&["rev-parse", "refs/stash"])?.output_or("git stash created no entry")?;
+    println!("Stashed local changes as {sha}.");
+    Ok(Some(sha))
+}
+
+/// Run `checkout`, which moves `HEAD` to `start` as branch `target`. When that changes the
+/// checked-out commit and tracked files have local changes, git would refuse or carry them along
+/// depending on what they touch, so refuse up front, or with `autostash` stash them and re-apply
+/// them once `target` is checked out (on the original branch if the checkout fails).
+pub(crate) fn guarded_checkout(
+    git_repo: &GitRepo,
+    start: &str,
+    target: &str,
+    autostash: bool,
+    checkout: impl FnOnce() -> Result<()>,
+) -> Result<()> {
+    let moves_head = git_repo.sha(start).ok() != git_repo.sha("HEAD").ok();
+    if !moves_head || run_git_status_clean()? {
+        return checkout();
+    }
+    if !autostash {
+        bail!(
+            "You have uncommitted changes, so {target} wasn't checked out. Commit or stash them \
+             first, or pass --autostash to carry them over to {target}."
+        );
+    }
+    let stash = autostash_push()?;
+    let result = checkout();
+    if let Some(stash) = stash.as_deref() {
+        autostash_pop(stash)?;
+    }
+    result
+}
+
+/// Re-apply an `autostash_push` stash to the checked-out branch and drop it. If it doesn't apply
+/// cleanly, it stays in the stash list and the user is told how to get it back.
+pub(crate) fn autostash_pop(sha: &str) -> Result<()> {
+    if !run_git_status(&["stash", "apply", sha], None)?.success() {
+        println!(
+            "Applying the autostash hit a conflict. Your changes are safe in the stash as {sha}; \
+             resolve the conflicts, then `git stash drop` it."
+        );
+        return Ok(());
+    }
+    let stashes = run_git(&["stash", "list", "--format=%H"])?;
+    if let Some(index) = stashes.stdout.lines().position(|line| line == sha) {
+        run_git(&["stash", "drop", "-q", &format!("stash@{{{index}}}")])?;
+    }
+    println!("Restored stashed local changes.");
+    Ok(())
 }
 
 /// Counts of local changes by category from `git status --porcelain`
@@ -579,7 +687,7 @@ pub(crate) fn git_checkout_main(repo: &GitRepo, new_branch: Option<&str>) -> Res
     if !run_git_status_clean()? {
         bail!("git status is not clean, please commit or stash your changes.")
     }
-    git_fetch()?;
+    git_fetch(repo, FetchOptions::default())?;
     let remote = DEFAULT_REMOTE;
     let trunk = git_trunk(repo).ok_or_else(|| anyhow!("No remote configured"))?;
 
@@ -616,18 +724,52 @@ pub(crate) fn git_checkout_main(repo: &GitRepo, new_branch: Option<&str>) -> Res
     Ok(())
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct GitTrunk {
     pub(crate) remote_main: String,
     pub(crate) main_branch: String,
 }
 
+/// Resolve the trunk branch: the repo's `trunk` override from the state file if it has one,
+/// otherwise whatever `refs/remotes/origin/HEAD` points at. The latter is looked up once per run
+/// and then served from `git_repo` until [`GitRepo::forget_trunk`].
 pub(crate) fn git_trunk(git_repo: &GitRepo) -> Option<GitTrunk> {
-    let remote_main = git_repo.remote_main(DEFAULT_REMOTE).ok()?;
+    if let Some(main_branch) = git_repo.trunk_override() {
+        return Some(GitTrunk {
+            remote_main: format!("{DEFAULT_REMOTE}/{main_branch}"),
+            main_branch: main_branch.to_string(),
+        });
+    }
+    if let Some(trunk) = git_repo.cached_trunk() {
+        return Some(trunk);
+    }
+    let Ok(remote_main) = git_repo.remote_main(DEFAULT_REMOTE) else {
+        hint_set_head(git_repo);
+        return None;
+    };
     let main_branch = after_text(&remote_main, format!("{DEFAULT_REMOTE}/"))?.to_string();
-    Some(GitTrunk {
+    let trunk = GitTrunk {
         remote_main,
         main_branch,
-    })
+    };
+    git_repo.cache_trunk(&trunk);
+    Some(trunk)
+}
+
+/// Auto-detection failed even though `origin` exists, so `origin/HEAD` is simply unset (common
+/// after `git init` + `git remote add`). Say how to fix it, once per process.
+fn hint_set_head(git_repo: &GitRepo) {
+    static HINTED: Once = Once::new();
+    if git_repo.get_remote_url(DEFAULT_REMOTE).is_err() {
+        return;
+    }
+    HINTED.call_once(|| {
+        tracing::warn!(
+            "Could not resolve {DEFAULT_REMOTE}/HEAD to find the trunk branch. Run `git remote \
+             set-head {DEFAULT_REMOTE} -a` to detect it, or `git stack --trunk <branch>` to set \
+             it for this repo."
+        );
+    });
 }
 
 #[cfg(test)]
@@ -643,6 +785,28 @@ mod tests {
         assert!(is_ref_lock_contention(stderr));
     }
 
+    #[test]
+    fn fetch_args_follow_the_options() {
+        assert_eq!(
+            fetch_args(FetchOptions::default()),
+            ["fetch", "--prune", "origin"]
+        );
+        assert_eq!(
+            fetch_args(FetchOptions {
+                prune: true,
+                tags: true
+            }),
+            ["fetch", "--tags", "-f", "--prune", "origin"]
+        );
+        assert_eq!(
+            fetch_args(FetchOptions {
+                prune: false,
+                tags: false
+            }),
+            ["fetch", "origin"]
+        );
+    }
+
     #[test]
     fn ignores_unrelated_errors() {
         assert!(!is_ref_lock_contention(
@@ -3,12 +3,14 @@
 //! This module provides a `GitRepo` struct that wraps git2::Repository
 //! for fast read-only operations without spawning git processes.
 
-use std::{path::Path, time::Instant};
+use std::{cell::RefCell, collections::HashMap, path::Path, sync::Arc, time::Instant};
 
 use anyhow::{Context, Result, anyhow};
 use git2::{BranchType, Repository};
 
-use crate::{lock::RepoLock, merge_base_cache::MergeBaseCacheHandle, stats::GitBenchmark};
+use crate::{
+    git::GitTrunk, lock::RepoLock, merge_base_cache::MergeBaseCacheHandle, stats::GitBenchmark,
+};
 
 pub const DEFAULT_REMOTE: &str = "origin";
 
@@ -16,6 +18,10 @@ pub const DEFAULT_REMOTE: &str = "origin";
 pub(crate) struct UpstreamStatus {
     pub(crate) symbolic_name: String,
     pub(crate) synced: bool,
+    /// Commits on the branch that the upstream doesn't have.
+    pub(crate) ahead: usize,
+    /// Commits on the upstream that the branch doesn't have.
+    pub(crate) behind: usize,
 }
 
 #[derive(Debug)]
@@ -32,9 +38,28 @@ pub struct GitRepo {
     repo: Repository,
     /// Persistent cache for `merge_base` / `is_ancestor` results. `None` when the cache could not
     /// be opened (e.g. another process holds redb's exclusive lock), degrading to uncached.
-    merge_base_cache: Option<MergeBaseCacheHandle>,
+    /// Shared with handles from `init_repo_for_thread`, since redb allows one open per process.
+    merge_base_cache: Option<Arc<MergeBaseCacheHandle>>,
     /// Canonicalized common git dir, used as the cache scope key.
     repo_scope: String,
+    /// Per-run memo in front of `merge_base_cache`, keyed by resolved OIDs. A single `status`
+    /// render asks the same ancestry questions many times (per node, and again from sibling sort
+    /// comparators); OID-keyed answers never go stale, so repeats skip the redb read entirely.
+    memo: RefCell<GitMemo>,
+    /// Per-repo trunk branch from the state file's `trunk` field. When set, `git_trunk` uses it
+    /// instead of resolving `refs/remotes/origin/HEAD`.
+    trunk_override: Option<String>,
+    /// The trunk `git_trunk` resolved from `origin/HEAD`, reused for the rest of the run so each
+    /// caller doesn't repeat the symbolic-ref lookup. Dropped by `forget_trunk` after anything
+    /// that can move `origin/HEAD` (a fetch, `git remote set-head`).
+    trunk: RefCell<Option<GitTrunk>>,
+}
+
+/// In-memory answers for `is_ancestor` / `merge_base`, keyed by `(oid1, oid2)`.
+#[derive(Default)]
+struct GitMemo {
+    is_ancestor: HashMap<(String, String), bool>,
+    merge_base: HashMap<(String, String), String>,
 }
 
 impl GitRepo {
@@ -48,18 +73,37 @@ impl GitRepo {
                 None
             }
         };
-        Self::open_inner(path, cache)
+        Self::open_inner(path, cache.map(Arc::new))
+    }
+
+    /// Open a repository without the merge-base cache, for a quick look at a repo other than the
+    /// one this command runs in.
+    pub(crate) fn open_uncached(path: impl AsRef<Path>) -> Result<Self> {
+        Self::open_inner(path, None)
+    }
+
+    /// Open a second handle on this repository for a worker thread. `git2::Repository` is not
+    /// `Sync`, so parallel git2 work needs one handle per thread; the new handle shares this one's
+    /// merge-base cache but starts with an empty memo.
+    pub fn init_repo_for_thread(&self) -> Result<Self> {
+        let path = self.repo.workdir().unwrap_or_else(|| self.repo.path());
+        let mut repo = Self::open_inner(path, self.merge_base_cache.clone())?;
+        repo.trunk_override = self.trunk_override.clone();
+        Ok(repo)
     }
 
     /// Open a repository with the merge-base cache at an explicit path, keeping tests isolated
     /// from the real user cache.
     #[cfg(test)]
     pub fn open_with_cache_at(path: impl AsRef<Path>, cache_path: &Path) -> Result<Self> {
-        let cache = Some(MergeBaseCacheHandle::open_at(cache_path)?);
+        let cache = Some(Arc::new(MergeBaseCacheHandle::open_at(cache_path)?));
         Self::open_inner(path, cache)
     }
 
-    fn open_inner(path: impl AsRef<Path>, cache: Option<MergeBaseCacheHandle>) -> Result<Self> {
+    fn open_inner(
+        path: impl AsRef<Path>,
+        cache: Option<Arc<MergeBaseCacheHandle>>,
+    ) -> Result<Self> {
         let _bench = GitBenchmark::start("git2:open");
         let repo = Repository::open(path.as_ref())
             .with_context(|| format!("Failed to open repository at {:?}", path.as_ref()))?;
@@ -71,9 +115,22 @@ impl GitRepo {
             repo,
             merge_base_cache: cache,
             repo_scope,
+            memo: RefCell::new(GitMemo::default()),
+            trunk_override: None,
+            trunk: RefCell::new(None),
         })
     }
 
+    /// Use `branch` as this repo's trunk instead of whatever `origin/HEAD` points at.
+    pub fn set_trunk_override(&mut self, branch: Option<String>) {
+        self.trunk_override = branch;
+        self.forget_trunk();
+    }
+
+    pub fn trunk_override(&self) -> Option<&str> {
+        self.trunk_override.as_deref()
+    }
+
     /// The cache scope key (canonicalized common git dir). Test-only accessor for priming the
     /// cache with the exact key `GitRepo` uses.
     #[cfg(test)]
@@ -81,6 +138,25 @@ impl GitRepo {
         &self.repo_scope
     }
 
+    pub(crate) fn cached_trunk(&self) -> Option<GitTrunk> {
+        self.trunk.borrow().clone()
+    }
+
+    pub(crate) fn cache_trunk(&self, trunk: &GitTrunk) {
+        *self.trunk.borrow_mut() = Some(trunk.clone());
+    }
+
+    /// Re-resolve the trunk on the next `git_trunk` call.
+    pub(crate) fn forget_trunk(&self) {
+        *self.trunk.borrow_mut() = None;
+    }
+
+    /// Drop the per-run `is_ancestor` / `merge_base` memo (the persistent cache is untouched).
+    #[cfg(test)]
+    pub fn reset_memo(&self) {
+        *self.memo.borrow_mut() = GitMemo::default();
+    }
+
     /// Clear the merge-base / is-ancestor cache for this repo's scope. No-op if the cache never
     /// opened.
     pub fn clear_merge_base_cache(&self) -> Result<()> {
@@ -127,13 +203,23 @@ impl GitRepo {
             return Ok(true);
         }
 
-        let anc_oid = ancestor_obj.id().to_string();
-        let desc_oid = descendant_obj.id().to_string();
+        let key = (
+            ancestor_obj.id().to_string(),
+            descendant_obj.id().to_string(),
+        );
+        if let Some(&val) = self.memo.borrow().is_ancestor.get(&key) {
+            let _bench = GitBenchmark::start("memo:is-ancestor-hit");
+            return Ok(val);
+        }
+        let (anc_oid, desc_oid) = (&key.0, &key.1);
 
         // Cache hit skips the graph walk (and its benchmark span) entirely.
         if let Some(cache) = &self.merge_base_cache {
-            match cache.get_is_ancestor(&self.repo_scope, &anc_oid, &desc_oid) {
-                Ok(Some(val)) => return Ok(val),
+            match cache.get_is_ancestor(&self.repo_scope, anc_oid, desc_oid) {
+                Ok(Some(val)) => {
+                    self.memo.borrow_mut().is_ancestor.insert(key, val);
+                    return Ok(val);
+                }
                 Ok(None) => {}
                 Err(e) => tracing::debug!("merge-base cache read failed, computing live: {e:#}"),
             }
@@ -146,10 +232,11 @@ impl GitRepo {
             .unwrap_or(false);
 
         if let Some(cache) = &self.merge_base_cache
-            && let Err(e) = cache.put_is_ancestor(&self.repo_scope, &anc_oid, &desc_oid, val)
+            && let Err(e) = cache.put_is_ancestor(&self.repo_scope, anc_oid, desc_oid, val)
         {
             tracing::debug!("merge-base cache write failed: {e:#}");
         }
+        self.memo.borrow_mut().is_ancestor.insert(key, val);
         Ok(val)
     }
 
@@ -165,13 +252,20 @@ impl GitRepo {
             .revparse_single(ref2)
             .with_context(|| format!("Failed to resolve ref: {}", ref2))?;
 
-        let oid1 = obj1.id().to_string();
-        let oid2 = obj2.id().to_string();
+        let key = (obj1.id().to_string(), obj2.id().to_string());
+        if let Some(base) = self.memo.borrow().merge_base.get(&key) {
+            let _bench = GitBenchmark::start("memo:merge-base-hit");
+            return Ok(base.clone());
+        }
+        let (oid1, oid2) = (&key.0, &key.1);
 
         // Cache hit skips the graph walk (and its benchmark span) entirely.
         if let Some(cache) = &self.merge_base_cache {
-            match cache.get_merge_base(&self.repo_scope, &oid1, &oid2) {
-                Ok(Some(base)) => return Ok(base),
+            match cache.get_merge_base(&self.repo_scope, oid1, oid2) {
+                Ok(Some(base)) => {
+                    self.memo.borrow_mut().merge_base.insert(key, base.clone());
+                    return Ok(base);
+                }
                 Ok(None) => {}
                 Err(e) => tracing::debug!("merge-base cache read failed, computing live: {e:#}"),
             }
@@ -186,10 +280,11 @@ impl GitRepo {
 
         // Only successful results are cached; a real "no merge base" error propagates uncached.
         if let Some(cache) = &self.merge_base_cache
-            && let Err(e) = cache.put_merge_base(&self.repo_scope, &oid1, &oid2, &base)
+            && let Err(e) = cache.put_merge_base(&self.repo_scope, oid1, oid2, &base)
         {
             tracing::debug!("merge-base cache write failed: {e:#}");
         }
+        self.memo.borrow_mut().merge_base.insert(key, base.clone());
         Ok(base)
     }
 
@@ -236,6 +331,26 @@ impl GitRepo {
         self.repo.find_branch(branch, BranchType::Local).is_ok()
     }
 
+    /// Names of all local branches.
+    pub fn local_branch_names(&self) -> Vec<String> {
+        let _bench = GitBenchmark::start("git2:local-branches");
+        let Ok(branches) = self.repo.branches(Some(BranchType::Local)) else {
+            return Vec::new();
+        };
+        branches
+            .filter_map(|branch| Some(branch.ok()?.0.name().ok()??.to_string()))
+            .collect()
+    }
+
+    /// Whether `<remote>/<branch>` exists as a remote-tracking branch. Unlike [`Self::ref_exists`]
+    /// on the same name, a local branch or tag called `<remote>/<branch>` doesn't count.
+    pub fn remote_branch_exists(&self, remote: &str, branch: &str) -> bool {
+        let _bench = GitBenchmark::start("git2:remote-branch-exists");
+        self.repo
+            .find_branch(&format!("{remote}/{branch}"), BranchType::Remote)
+            .is_ok()
+    }
+
     /// Check if a ref exists (local branch, remote ref, or any resolvable ref).
     pub fn ref_exists(&self, ref_name: &str) -> bool {
         let _bench = GitBenchmark::start("git2:ref-exists");
@@ -314,7 +429,7 @@ impl GitRepo {
             Some(branch.to_string())
         } else {
             let remote_ref = format!("origin/{}", branch);
-            if self.ref_exists(&remote_ref) {
+            if self.remote_branch_exists(DEFAULT_REMOTE, branch) {
                 Some(remote_ref)
             } else {
                 None
@@ -327,46 +442,70 @@ impl GitRepo {
         parent_branch: Option<&str>,
         branch: &str,
     ) -> Result<GitBranchStatus> {
-        let exists = self.branch_exists(branch);
+        let parent_branch = self.status_parent_ref(parent_branch, branch);
+        let parent_sha = self.sha(&parent_branch).ok();
+        self.branch_status_with_parent_sha(&parent_branch, parent_sha.as_deref(), branch)
+    }
 
-        // Resolve parent branch - use origin/<parent> if local doesn't exist
-        let parent_branch = match parent_branch {
+    /// The ref `branch_status` measures `branch` against: `parent_branch`, or `origin/<parent>`
+    /// when only the remote one exists; the trunk base for the root (`None`).
+    pub fn status_parent_ref(&self, parent_branch: Option<&str>, branch: &str) -> String {
+        match parent_branch {
             Some(parent_branch) => {
                 if self.branch_exists(parent_branch) {
                     parent_branch.to_string()
                 } else {
                     let remote_parent = format!("origin/{}", parent_branch);
-                    if self.ref_exists(&remote_parent) {
+                    if self.remote_branch_exists(DEFAULT_REMOTE, parent_branch) {
                         remote_parent
                     } else {
                         parent_branch.to_string()
                     }
                 }
             }
-            None => self.remote_main(DEFAULT_REMOTE)?,
-        };
+            None => self.trunk_status_base(branch),
+        }
+    }
+
+    /// [`Self::branch_status`] against a parent already resolved by [`Self::status_parent_ref`]
+    /// (and its SHA, when it resolves), so siblings can share one lookup of their parent.
+    pub fn branch_status_with_parent_sha(
+        &self,
+        parent_branch: &str,
+        parent_sha: Option<&str>,
+        branch: &str,
+    ) -> Result<GitBranchStatus> {
+        let exists = self.branch_exists(branch);
+        let parent_branch = parent_branch.to_string();
+        // An unresolvable parent keeps its name so `is_ancestor` reports it.
+        let parent_rev = parent_sha.unwrap_or(&parent_branch);
 
         // Only compute these if the branch exists
         let (sha, is_descendent, upstream_status) = if exists {
             let sha = self.sha(branch)?;
-            let is_descendent = self.is_ancestor(&parent_branch, branch)?;
+            let is_descendent = self.is_ancestor(parent_rev, branch)?;
             let upstream_symbolic_name = self.get_upstream(branch);
-            let upstream_synced = upstream_symbolic_name
-                .as_ref()
-                .is_some_and(|upstream| self.shas_match(upstream, branch));
-            let upstream_status = upstream_symbolic_name.map(|symbolic_name| UpstreamStatus {
-                symbolic_name,
-                synced: upstream_synced,
+            let upstream_status = upstream_symbolic_name.map(|symbolic_name| {
+                let synced = self.shas_match(&symbolic_name, branch);
+                let (ahead, behind) = if synced {
+                    (0, 0)
+                } else {
+                    self.ahead_behind(branch, &symbolic_name).unwrap_or((0, 0))
+                };
+                UpstreamStatus {
+                    symbolic_name,
+                    synced,
+                    ahead,
+                    behind,
+                }
             });
             (sha, is_descendent, upstream_status)
         } else {
             // Local branch doesn't exist - try using origin/<branch> instead
             let remote_ref = format!("origin/{}", branch);
-            if self.ref_exists(&remote_ref) {
+            if self.remote_branch_exists(DEFAULT_REMOTE, branch) {
                 let sha = self.sha(&remote_ref).unwrap_or_default();
-                let is_descendent = self
-                    .is_ancestor(&parent_branch, &remote_ref)
-                    .unwrap_or(false);
+                let is_descendent = self.is_ancestor(parent_rev, &remote_ref).unwrap_or(false);
                 (sha, is_descendent, None)
             } else {
                 // Neither local nor remote exists - use placeholder values
@@ -383,6 +522,25 @@ impl GitRepo {
         })
     }
 
+    /// What the trunk root's status is measured against: the remote trunk (`origin/<override>`, or
+    /// `origin/HEAD`'s target). Without `origin/HEAD` (fresh clones, mirrors) this falls back to the
+    /// root branch's own remote-tracking ref, or the branch itself, so status still renders; the
+    /// missing `origin/HEAD` is reported once by `git_trunk`.
+    fn trunk_status_base(&self, root: &str) -> String {
+        if let Some(trunk) = self.trunk_override() {
+            return format!("{DEFAULT_REMOTE}/{trunk}");
+        }
+        if let Ok(remote_main) = self.remote_main(DEFAULT_REMOTE) {
+            return remote_main;
+        }
+        let remote_root = format!("{DEFAULT_REMOTE}/{root}");
+        if self.remote_branch_exists(DEFAULT_REMOTE, root) {
+            remote_root
+        } else {
+            root.to_string()
+        }
+    }
+
     /// Get the remote main branch name (e.g., "origin/main").
     /// Equivalent to `git symbolic-ref refs/remotes/<remote>/HEAD`
     pub fn remote_main(&self, remote: &str) -> Result<String> {
@@ -403,6 +561,30 @@ impl GitRepo {
             .to_string())
     }
 
+    /// Count how far `local` and `upstream` have diverged, as `(ahead, behind)`: `ahead` is the
+    /// commits reachable from `local` but not `upstream`, `behind` the reverse.
+    /// Equivalent to `git rev-list --left-right --count <local>...<upstream>`
+    pub fn ahead_behind(&self, local: &str, upstream: &str) -> Result<(usize, usize)> {
+        let local_oid = self
+            .repo
+            .revparse_single(local)
+            .with_context(|| format!("Failed to resolve ref: {}", local))?
+            .peel_to_commit()
+            .with_context(|| format!("Failed to peel to commit: {}", local))?
+            .id();
+        let upstream_oid = self
+            .repo
+            .revparse_single(upstream)
+            .with_context(|| format!("Failed to resolve ref: {}", upstream))?
+            .peel_to_commit()
+            .with_context(|| format!("Failed to peel to commit: {}", upstream))?
+            .id();
+        let _bench = GitBenchmark::start("git2:ahead-behind");
+        self.repo
+            .graph_ahead_behind(local_oid, upstream_oid)
+            .with_context(|| format!("counting commits between {local} and {upstream}"))
+    }
+
     /// Check if two refs point to the same commit.
     /// Equivalent to comparing `git rev-parse <ref1>` and `git rev-parse <ref2>`
     pub fn shas_match(&self, ref1: &str, ref2: &str) -> bool {
@@ -416,6 +598,24 @@ impl GitRepo {
         obj1.id() == obj2.id()
     }
 
+    /// In a linked worktree (`git worktree add`), the main working tree's root, which is where
+    /// git-stack keeps the repo's state. `None` in the main working tree itself, or when the main
+    /// repository is bare.
+    pub fn main_worktree_root(&self) -> Option<String> {
+        if !self.repo.is_worktree() {
+            return None;
+        }
+        let main = Repository::open(self.repo.commondir()).ok()?;
+        let workdir = main.workdir()?;
+        Some(workdir.to_str()?.trim_end_matches('/').to_string())
+    }
+
+    /// This worktree's git dir (holding its `HEAD`) and the common git dir (holding `refs/` and
+    /// `packed-refs`). Outside a linked worktree they're the same directory.
+    pub fn git_dirs(&self) -> (&Path, &Path) {
+        (self.repo.path(), self.repo.commondir())
+    }
+
     /// Get the repo root path.
     /// Equivalent to `git rev-parse --show-toplevel`
     pub fn root(&self) -> Result<String> {
@@ -493,6 +693,39 @@ impl GitRepo {
         Ok((!patch.is_empty()).then(|| patch.to_string()))
     }
 
+    /// Blame `path` as of HEAD, walking history no further back than `oldest`. Returns the commit
+    /// that last touched each line (index `i` is line `i + 1`); lines older than `oldest` are
+    /// attributed to `oldest` itself. Equivalent to `git blame -s <oldest>..HEAD -- <path>`.
+    pub fn blame_since(&self, path: &str, oldest: &str) -> Result<Vec<String>> {
+        let _bench = GitBenchmark::start("git2:blame");
+        let oldest_oid = self
+            .repo
+            .revparse_single(oldest)
+            .with_context(|| format!("Failed to resolve ref: {}", oldest))?
+            .peel_to_commit()
+            .with_context(|| format!("Failed to peel to commit: {}", oldest))?
+            .id();
+        let head_oid = self
+            .repo
+            .head()
+            .context("Failed to get HEAD")?
+            .peel_to_commit()
+            .context("Failed to peel HEAD to commit")?
+            .id();
+        let mut opts = git2::BlameOptions::new();
+        opts.newest_commit(head_oid).oldest_commit(oldest_oid);
+        let blame = self
+            .repo
+            .blame_file(Path::new(path), Some(&mut opts))
+            .with_context(|| format!("Failed to blame {}", path))?;
+        let mut lines = Vec::new();
+        for hunk in blame.iter() {
+            let commit = hunk.final_commit_id().to_string();
+            lines.extend(std::iter::repeat_n(commit, hunk.lines_in_hunk()));
+        }
+        Ok(lines)
+    }
+
     /// Get current branch name.
     /// Equivalent to `git rev-parse --abbrev-ref HEAD`
     pub fn current_branch(&self) -> Result<String> {
@@ -512,6 +745,31 @@ impl GitRepo {
         }
     }
 
+    /// Number of entries in `HEAD`'s reflog (0 when it has none).
+    pub fn head_reflog_len(&self) -> usize {
+        let _bench = GitBenchmark::start("git2:reflog");
+        self.repo.reflog("HEAD").map_or(0, |reflog| reflog.len())
+    }
+
+    /// The branches (or SHAs) `git checkout` moved `HEAD` off of, newest first, among the newest
+    /// `count` entries of `HEAD`'s reflog. Like git's `@{-N}`, this reads the entries'
+    /// `checkout: moving from <a> to <b>` messages.
+    pub fn head_checked_out_from(&self, count: usize) -> Vec<String> {
+        let _bench = GitBenchmark::start("git2:reflog");
+        let Ok(reflog) = self.repo.reflog("HEAD") else {
+            return Vec::new();
+        };
+        reflog
+            .iter()
+            .take(count)
+            .filter_map(|entry| {
+                let moved = entry.message()?.strip_prefix("checkout: moving from ")?;
+                let (from, _to) = moved.rsplit_once(" to ")?;
+                Some(from.to_string())
+            })
+            .collect()
+    }
+
     /// Get the upstream tracking branch for a local branch.
     /// Equivalent to `git rev-parse --abbrev-ref --symbolic-full-name <branch>@{upstream}`
     pub fn get_upstream(&self, branch: &str) -> Option<String> {
@@ -1172,6 +1430,54 @@ mod tests {
         git(dir, &["commit", "--allow-empty", "-q", "-m", "main commit"]);
     }
 
+    #[test]
+    fn ahead_behind_counts_both_sides() {
+        let dir = tempfile::tempdir().unwrap();
+        init_divergent_repo(dir.path());
+        git(
+            dir.path(),
+            &["commit", "--allow-empty", "-q", "-m", "main 2"],
+        );
+        let git_repo = GitRepo::open_with_cache_at(dir.path(), &dir.path().join("c.redb")).unwrap();
+
+        assert_eq!(git_repo.ahead_behind("feature", "main").unwrap(), (1, 2));
+        assert_eq!(git_repo.ahead_behind("main", "feature").unwrap(), (2, 1));
+        assert_eq!(git_repo.ahead_behind("main", "main").unwrap(), (0, 0));
+    }
+
+    #[test]
+    fn local_and_remote_branch_checks_ignore_lookalike_refs() {
+        let dir = tempfile::tempdir().unwrap();
+        init_divergent_repo(dir.path());
+        // A local branch and a tag whose names look like remote-tracking branches.
+        git(dir.path(), &["branch", "origin/lookalike"]);
+        git(dir.path(), &["tag", "origin/tagged"]);
+        git(dir.path(), &["tag", "release"]);
+        git(
+            dir.path(),
+            &["update-ref", "refs/remotes/origin/feature", "feature"],
+        );
+        let git_repo = GitRepo::open_with_cache_at(dir.path(), &dir.path().join("c.redb")).unwrap();
+
+        assert!(git_repo.remote_branch_exists(DEFAULT_REMOTE, "feature"));
+        assert!(!git_repo.remote_branch_exists(DEFAULT_REMOTE, "main"));
+        for name in ["lookalike", "tagged"] {
+            assert!(git_repo.ref_exists(&format!("origin/{name}")), "{name}");
+            assert!(
+                !git_repo.remote_branch_exists(DEFAULT_REMOTE, name),
+                "{name}"
+            );
+        }
+        assert!(git_repo.branch_exists("feature"));
+        assert!(!git_repo.branch_exists("release"));
+        assert!(!git_repo.branch_exists("origin/feature"));
+        assert_eq!(
+            git_repo.resolve_branch_ref("lookalike"),
+            None,
+            "a local `origin/lookalike` isn't the remote copy of `lookalike`"
+        );
+    }
+
     /// Acceptance criterion: `is_ancestor`'s read path genuinely short-circuits to the cache. We
     /// seed a deliberately *wrong* answer (`true`) for a pair that is not actually ancestor-related
     /// and prove `is_ancestor` returns the cached value rather than doing a live walk.
@@ -1233,6 +1539,184 @@ mod tests {
         assert_eq!(git_repo.merge_base("feature", "main").unwrap(), bogus_base);
     }
 
+    /// Repeat ancestry questions within a run are answered from the in-memory memo, not redb:
+    /// a bogus row written behind the memo's back is invisible until `reset_memo`.
+    #[test]
+    fn memo_answers_repeats_until_reset() {
+        let dir = tempfile::tempdir().unwrap();
+        init_divergent_repo(dir.path());
+        let cache_path = dir.path().join("mb_cache.redb");
+        let git_repo = GitRepo::open_with_cache_at(dir.path(), &cache_path).unwrap();
+
+        let oid_feature = git_rev_parse(dir.path(), "feature");
+        let oid_main = git_rev_parse(dir.path(), "main");
+        let bogus_base = "deadbeefdeadbeefdeadbeefdeadbeefdeadbeef";
+
+        assert!(!git_repo.is_ancestor("feature", "main").unwrap());
+        let real_base = git_repo.merge_base("feature", "main").unwrap();
+
+        let cache = git_repo.merge_base_cache.as_ref().unwrap();
+        cache
+            .put_is_ancestor(git_repo.repo_scope(), &oid_feature, &oid_main, true)
+            .unwrap();
+        cache
+            .put_merge_base(git_repo.repo_scope(), &oid_feature, &oid_main, bogus_base)
+            .unwrap();
+
+        crate::stats::reset_stats();
+        assert!(!git_repo.is_ancestor("feature", "main").unwrap());
+        assert_eq!(git_repo.merge_base("feature", "main").unwrap(), real_base);
+        let stats = crate::stats::get_stats();
+        assert_eq!(stats.by_command["memo:is-ancestor-hit"].count, 1);
+        assert_eq!(stats.by_command["memo:merge-base-hit"].count, 1);
+
+        git_repo.reset_memo();
+        assert!(git_repo.is_ancestor("feature", "main").unwrap());
+        assert_eq!(git_repo.merge_base("feature", "main").unwrap(), bogus_base);
+    }
+
+    /// A worker handle opened on another thread sees the same repo and shares the persistent
+    /// cache (a row primed through the parent handle is visible to it).
+    #[test]
+    fn init_repo_for_thread_shares_the_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        init_divergent_repo(dir.path());
+        let cache_path = dir.path().join("mb_cache.redb");
+        let git_repo = GitRepo::open_with_cache_at(dir.path(), &cache_path).unwrap();
+
+        let oid_feature = git_rev_parse(dir.path(), "feature");
+        let oid_main = git_rev_parse(dir.path(), "main");
+        git_repo
+            .merge_base_cache
+            .as_ref()
+            .unwrap()
+            .put_is_ancestor(git_repo.repo_scope(), &oid_feature, &oid_main, true)
+            .unwrap();
+
+        let worker = git_repo.init_repo_for_thread().unwrap();
+        let (scope, answer) = std::thread::spawn(move || {
+            (
+                worker.repo_scope().to_string(),
+                worker.is_ancestor("feature", "main").unwrap(),
+            )
+        })
+        .join()
+        .unwrap();
+        assert_eq!(scope, git_repo.repo_scope());
+        assert!(answer);
+    }
+
+    #[test]
+    fn git_trunk_prefers_the_override_over_origin_head() {
+        let dir = tempfile::tempdir().unwrap();
+        init_repo(dir.path());
+        commit_file(dir.path(), "a.txt", "a\n", "root");
+        let cache_path = dir.path().join("mb_cache.redb");
+        let mut git_repo = GitRepo::open_with_cache_at(dir.path(), &cache_path).unwrap();
+
+        // No origin/HEAD: auto-detection has nothing to go on.
+        assert!(crate::git::git_trunk(&git_repo).is_none());
+
+        git_repo.set_trunk_override(Some("develop".to_string()));
+        let trunk = crate::git::git_trunk(&git_repo).unwrap();
+        assert_eq!(trunk.main_branch, "develop");
+        assert_eq!(trunk.remote_main, "origin/develop");
+
+        // Worker handles see the same trunk.
+        let worker = git_repo.init_repo_for_thread().unwrap();
+        assert_eq!(worker.trunk_override(), Some("develop"));
+    }
+
+    #[test]
+    fn git_trunk_is_resolved_once_until_forgotten() {
+        let dir = tempfile::tempdir().unwrap();
+        init_repo(dir.path());
+        commit_file(dir.path(), "a.txt", "a\n", "root");
+        git(
+            dir.path(),
+            &["update-ref", "refs/remotes/origin/main", "HEAD"],
+        );
+        git(
+            dir.path(),
+            &["update-ref", "refs/remotes/origin/develop", "HEAD"],
+        );
+        let set_head = |target: &str| {
+            git(
+                dir.path(),
+                &["symbolic-ref", "refs/remotes/origin/HEAD", target],
+            )
+        };
+        set_head("refs/remotes/origin/main");
+        let cache_path = dir.path().join("mb_cache.redb");
+        let git_repo = GitRepo::open_with_cache_at(dir.path(), &cache_path).unwrap();
+
+        assert_eq!(
+            crate::git::git_trunk(&git_repo).unwrap().main_branch,
+            "main"
+        );
+        set_head("refs/remotes/origin/develop");
+        assert_eq!(
+            crate::git::git_trunk(&git_repo).unwrap().main_branch,
+            "main"
+        );
+
+        git_repo.forget_trunk();
+        assert_eq!(
+            crate::git::git_trunk(&git_repo).unwrap().main_branch,
+            "develop"
+        );
+    }
+
+    #[test]
+    fn trunk_status_falls_back_to_the_root_branch_without_origin_head() {
+        let dir = tempfile::tempdir().unwrap();
+        init_repo(dir.path());
+        commit_file(dir.path(), "a.txt", "a\n", "root");
+        let cache_path = dir.path().join("mb_cache.redb");
+        let git_repo = GitRepo::open_with_cache_at(dir.path(), &cache_path).unwrap();
+
+        // No origin at all: the root is measured against itself.
+        let status = git_repo.branch_status(None, "main").unwrap();
+        assert_eq!(status.parent_branch, "main");
+        assert!(status.is_descendent);
+
+        // A remote-tracking trunk without origin/HEAD (e.g. a fresh mirror) is used when present.
+        let head = git_rev_parse(dir.path(), "main");
+        git(
+            dir.path(),
+            &["update-ref", "refs/remotes/origin/main", &head],
+        );
+        let status = git_repo.branch_status(None, "main").unwrap();
+        assert_eq!(status.parent_branch, "origin/main");
+        assert!(status.is_descendent);
+    }
+
+    #[test]
+    fn branch_status_with_parent_sha_matches_branch_status() {
+        let dir = tempfile::tempdir().unwrap();
+        init_divergent_repo(dir.path());
+        let git_repo = GitRepo::open_with_cache_at(dir.path(), &dir.path().join("c.redb")).unwrap();
+
+        let parent = git_repo.status_parent_ref(Some("main"), "feature");
+        assert_eq!(parent, "main");
+        let parent_sha = git_rev_parse(dir.path(), "main");
+        let shared = git_repo
+            .branch_status_with_parent_sha(&parent, Some(&parent_sha), "feature")
+            .unwrap();
+        let fresh = git_repo.branch_status(Some("main"), "feature").unwrap();
+        assert_eq!(shared.parent_branch, fresh.parent_branch);
+        assert_eq!(shared.sha, fresh.sha);
+        assert!(!shared.is_descendent);
+        assert!(!fresh.is_descendent);
+
+        // A parent that doesn't resolve still fails the status, as before.
+        assert!(
+            git_repo
+                .branch_status_with_parent_sha("gone", None, "feature")
+                .is_err()
+        );
+    }
+
     /// `commits_reachable_excluding` is the bounded revwalk that replaced `sync`'s per-SHA
     /// is_ancestor loop. It must return exactly the commits reachable from the given tips but not
     /// from the exclude boundary — the same set the old "reachable from a tracked branch and not
@@ -8,12 +8,13 @@ use std::{
     io::{self, Write},
     path::{Path, PathBuf},
     process::Command,
+    sync::atomic::{AtomicBool, Ordering},
 };
 
 use anyhow::{Context, Result, anyhow, bail};
 use serde::{Deserialize, Serialize};
 
-use crate::{git2_ops::GitRepo, state::write_file_secure, stats::GitBenchmark};
+use crate::{git2_ops::GitRepo, read_only, state::write_file_secure, stats::GitBenchmark};
 
 // ============== Configuration Types ==============
 
@@ -76,6 +77,9 @@ pub struct PrBranchRef {
     pub sha: String,
     /// Repository info (may be null if the fork was deleted)
     pub repo: Option<PrRepoRef>,
+    /// `owner:branch`, which GitHub keeps even when `repo` is null.
+    #[serde(default)]
+    pub label: Option<String>,
 }
 
 /// Minimal repo info for PR head/base references
@@ -93,7 +97,8 @@ pub enum PrState {
 }
 
 /// Display-friendly PR state (computed from API fields)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum PrDisplayState {
     Draft,
     Open,
@@ -121,16 +126,27 @@ impl PullRequest {
     /// Check if this PR is from a fork (head repo differs from base repo)
     ///
     /// Returns true if:
-    /// - The head repo is missing (fork was deleted)
     /// - The head repo full_name differs from the base repo full_name
+    /// - The head repo is missing and the head label's owner isn't the base repo's owner (the
+    ///   fork was deleted). A same-repo PR whose head branch was deleted keeps its label owner.
     pub fn is_from_fork(&self) -> bool {
         match (&self.head.repo, &self.base.repo) {
-            // If head repo is missing, the fork was probably deleted - treat as fork PR
-            (None, _) => true,
             // If base repo is missing, something is weird but assume not a fork
             (_, None) => false,
             // Compare the full names
             (Some(head_repo), Some(base_repo)) => head_repo.full_name != base_repo.full_name,
+            // Head repo is missing: fall back to the label's owner, and treat an unknown owner as
+            // a deleted fork
+            (None, Some(base_repo)) => {
+                let head_owner = self
+                    .head
+                    .label
+                    .as_deref()
+                    .and_then(|label| label.split_once(':'))
+                    .map(|(owner, _)| owner);
+                let base_owner = base_repo.full_name.split_once('/').map(|(owner, _)| owner);
+                head_owner.is_none() || head_owner != base_owner
+            }
         }
     }
 
@@ -170,6 +186,195 @@ pub struct UpdatePrRequest<'a> {
     pub body: Option<&'a str>,
 }
 
+/// How `land` asks the provider to merge a PR.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeMethod {
+    /// A merge commit.
+    #[default]
+    Merge,
+    /// One squashed commit on the base.
+    Squash,
+    /// The PR's commits rebased onto the base.
+    Rebase,
+}
+
+impl std::fmt::Display for MergeMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Merge => write!(f, "merge"),
+            Self::Squash => write!(f, "squash"),
+            Self::Rebase => write!(f, "rebase"),
+        }
+    }
+}
+
+/// The merge-button settings of a GitHub repo (`GET /repos/{owner}/{repo}`).
+#[derive(Debug, Deserialize)]
+pub(crate) struct RepoMergeSettings {
+    #[serde(default)]
+    allow_merge_commit: Option<bool>,
+    #[serde(default)]
+    allow_squash_merge: Option<bool>,
+    #[serde(default)]
+    allow_rebase_merge: Option<bool>,
+}
+
+impl RepoMergeSettings {
+    fn allowed(&self) -> Vec<MergeMethod> {
+        [
+            (self.allow_merge_commit, MergeMethod::Merge),
+            (self.allow_squash_merge, MergeMethod::Squash),
+            (self.allow_rebase_merge, MergeMethod::Rebase),
+        ]
+        .into_iter()
+        .filter(|(allowed, _)| allowed.unwrap_or(true))
+        .map(|(_, method)| method)
+        .collect()
+    }
+}
+
+/// A PR (issue) comment, or a GitLab merge-request note; both carry an id and a markdown body.
+#[derive(Debug, Clone, Deserialize)]
+pub struct IssueComment {
+    pub id: u64,
+    #[serde(default)]
+    pub body: String,
+}
+
+/// What `create_or_update_comment` did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentUpsert {
+    Created,
+    Updated,
+    /// The marked comment already had exactly this body.
+    Unchanged,
+}
+
+/// Decide how to upsert `body` given a PR's existing comments: the first comment containing
+/// `marker` is the one git-stack owns. Returns the comment id to update, or None to create.
+pub(crate) fn plan_comment_upsert(
+    comments: &[IssueComment],
+    marker: &str,
+    body: &str,
+) -> Result<Option<u64>, CommentUpsert> {
+    match comments.iter().find(|c| c.body.contains(marker)) {
+        Some(c) if c.body.trim() == body.trim() => Err(CommentUpsert::Unchanged),
+        Some(c) => Ok(Some(c.id)),
+        None => Ok(None),
+    }
+}
+
+/// Tally of the CI results on a PR's head commit (check runs and commit statuses together).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CheckCounts {
+    pub passing: usize,
+    pub failing: usize,
+    pub pending: usize,
+}
+
+/// Where a PR's review stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewDecision {
+    Approved,
+    ChangesRequested,
+    /// No approval yet (and no outstanding request for changes).
+    ReviewRequired,
+}
+
+impl std::fmt::Display for ReviewDecision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Approved => write!(f, "approved"),
+            Self::ChangesRequested => write!(f, "changes requested"),
+            Self::ReviewRequired => write!(f, "review required"),
+        }
+    }
+}
+
+/// What `pr status` shows for one PR: whether it is ready to merge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrReadiness {
+    /// The provider's mergeability summary (GitHub `mergeable_state`, e.g. `clean`, `dirty`,
+    /// `blocked`; GitLab `detailed_merge_status`), when it has computed one.
+    pub mergeable: Option<String>,
+    pub checks: CheckCounts,
+    pub review: ReviewDecision,
+}
+
+/// A check run on a commit (`GET /repos/{owner}/{repo}/commits/{sha}/check-runs`).
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct CheckRun {
+    /// `queued`, `in_progress`, or `completed` (plus `waiting`/`requested`/`pending`).
+    pub status: String,
+    /// Set once `status` is `completed`.
+    pub conclusion: Option<String>,
+}
+
+/// A commit status (`GET /repos/{owner}/{repo}/commits/{sha}/status`).
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct CommitStatus {
+    /// `success`, `pending`, `failure`, or `error`.
+    pub state: String,
+}
+
+/// A PR review (`GET /repos/{owner}/{repo}/pulls/{n}/reviews`), oldest first.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct PrReview {
+    pub user: Option<PrUser>,
+    /// `APPROVED`, `CHANGES_REQUESTED`, `COMMENTED`, `DISMISSED`, or `PENDING`.
+    pub state: String,
+}
+
+/// Count check runs and commit statuses as passing, failing, or still pending. Neutral and
+/// skipped check runs count as passing, as they don't block a merge.
+pub(crate) fn count_checks(check_runs: &[CheckRun], statuses: &[CommitStatus]) -> CheckCounts {
+    let mut counts = CheckCounts::default();
+    for run in check_runs {
+        match (run.status.as_str(), run.conclusion.as_deref()) {
+            ("completed", Some("success" | "neutral" | "skipped")) => counts.passing += 1,
+            ("completed", _) => counts.failing += 1,
+            _ => counts.pending += 1,
+        }
+    }
+    for status in statuses {
+        match status.state.as_str() {
+            "success" => counts.passing += 1,
+            "pending" => counts.pending += 1,
+            _ => counts.failing += 1,
+        }
+    }
+    counts
+}
+
+/// Decide a PR's review state from its reviews: each reviewer's latest approval or change
+/// request counts (a dismissal withdraws it; comments don't change it), and any outstanding
+/// change request outweighs approvals.
+pub(crate) fn review_decision(reviews: &[PrReview]) -> ReviewDecision {
+    let mut latest: std::collections::HashMap<&str, &str> = std::collections::HashMap::new();
+    for review in reviews {
+        let Some(user) = review.user.as_ref() else {
+            continue;
+        };
+        match review.state.as_str() {
+            "APPROVED" | "CHANGES_REQUESTED" => {
+                latest.insert(&user.login, &review.state);
+            }
+            "DISMISSED" => {
+                latest.remove(user.login.as_str());
+            }
+            _ => {}
+        }
+    }
+    if latest.values().any(|state| *state == "CHANGES_REQUESTED") {
+        ReviewDecision::ChangesRequested
+    } else if latest.is_empty() {
+        ReviewDecision::ReviewRequired
+    } else {
+        ReviewDecision::Approved
+    }
+}
+
 // ============== PR Cache Types ==============
 //
 // The cache storage itself (schema, per-repo scoped access) lives in `crate::pr_cache`. The
@@ -195,12 +400,29 @@ pub struct CachedPullRequest {
     pub updated_at: String,
 }
 
+impl CachedPullRequest {
+    /// Same mapping as `PullRequest::display_state`, over the cached fields.
+    pub fn display_state(&self) -> PrDisplayState {
+        if self.merged || self.merged_at.is_some() {
+            PrDisplayState::Merged
+        } else if self.state == PrState::Closed {
+            PrDisplayState::Closed
+        } else if self.draft {
+            PrDisplayState::Draft
+        } else {
+            PrDisplayState::Open
+        }
+    }
+}
+
 /// Cached branch reference (mirrors PrBranchRef with Serialize)
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CachedPrBranchRef {
     pub ref_name: String,
     pub sha: String,
     pub repo: Option<CachedPrRepoRef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
 }
 
 /// Cached repo reference
@@ -224,6 +446,51 @@ pub struct PrListResult {
     pub all_authors: std::collections::HashMap<String, String>,
 }
 
+/// Bounds on how far back a closed-PR fetch pages (`sync --limit` / `--since`), so the first sync
+/// of a repo with thousands of closed PRs doesn't page through all of them. The watermark still
+/// advances to the newest PR fetched, so later syncs stay incremental.
+#[derive(Debug, Clone, Default)]
+pub struct ClosedPrLimits {
+    /// Fetch at most this many closed PRs, most recently updated first.
+    pub limit: Option<usize>,
+    /// Skip closed PRs last updated before this RFC 3339 UTC time.
+    pub since: Option<String>,
+}
+
+impl ClosedPrLimits {
+    /// Whether a fetch that already kept `kept` closed PRs may keep `pr`. Fetches run newest
+    /// first, so the first PR refused ends the fetch.
+    pub(crate) fn admits(&self, kept: usize, pr: &PullRequest) -> bool {
+        self.limit.is_none_or(|limit| kept < limit)
+            && self
+                .since
+                .as_deref()
+                .is_none_or(|since| pr.updated_at.as_str() >= since)
+    }
+}
+
+/// How a closed-PR fetch uses the on-disk cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClosedPrCacheMode {
+    /// Fetch back to the cached watermark and fold the fresh PRs into the cache.
+    #[default]
+    Incremental,
+    /// Ignore the cached PRs and watermark and fetch again (`sync --no-cache`). With `persist`,
+    /// the result replaces the repo's cached closed PRs and watermark once the fetch succeeds.
+    Rebuild { persist: bool },
+}
+
+/// Closed PRs from the cache merged with a fresh fetch, and how that fetch went.
+#[derive(Debug)]
+pub struct ClosedPrList {
+    pub result: PrListResult,
+    /// Closed PRs the fresh fetch returned.
+    pub fetched: usize,
+    /// Whether `ClosedPrLimits` stopped the fetch before it reached the watermark (or the oldest
+    /// PR), leaving older closed PRs unfetched.
+    pub truncated: bool,
+}
+
 /// Per-branch outcome of a stack-scoped open-PR fetch, so the caller can distinguish "this
 /// branch has no open PR" (drop it from the cache) from "the query for this branch failed"
 /// (keep its cached entry as last-known-good).
@@ -325,6 +592,8 @@ pub enum GitHubError {
     Api { status: u16, message: String },
     /// Org forbids classic PATs; carries the org name when parseable from the 403 body.
     ClassicPatForbidden { org: Option<String> },
+    /// A write request blocked by `--read-only`
+    ReadOnly(String),
 }
 
 impl std::fmt::Display for GitHubError {
@@ -340,6 +609,7 @@ impl std::fmt::Display for GitHubError {
                 write!(f, "Branch '{}' not pushed to remote", branch)
             }
             Self::Network(msg) => write!(f, "Network error: {}", msg),
+            Self::ReadOnly(msg) => write!(f, "{msg}"),
             Self::Api { status, message } => {
                 write!(f, "GitHub API error ({}): {}", status, message)
             }
@@ -373,6 +643,38 @@ impl std::error::Error for GitHubError {}
 
 // ============== Client ==============
 
+/// How long a request may wait to connect, then for the response headers, then for the whole
+/// response body, before it fails with a "timed out" [`GitHubError::Network`] instead of hanging.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HttpTimeouts {
+    pub connect: std::time::Duration,
+    pub read: std::time::Duration,
+}
+
+impl Default for HttpTimeouts {
+    fn default() -> Self {
+        Self {
+            connect: std::time::Duration::from_secs(10),
+            read: std::time::Duration::from_secs(30),
+        }
+    }
+}
+
+impl HttpTimeouts {
+    /// An agent that enforces these timeouts and returns non-2xx responses as `Ok`, so callers
+    /// can read the provider's explanatory body (e.g. the classic-PAT-forbidden 403 message)
+    /// instead of getting a body-less StatusCode error.
+    pub(crate) fn agent(self) -> ureq::Agent {
+        ureq::Agent::config_builder()
+            .http_status_as_error(false)
+            .timeout_connect(Some(self.connect))
+            .timeout_recv_response(Some(self.read))
+            .timeout_recv_body(Some(self.read))
+            .build()
+            .new_agent()
+    }
+}
+
 /// GitHub API client
 pub struct GitHubClient {
     config: GitHubConfig,
@@ -381,24 +683,30 @@ pub struct GitHubClient {
 
 impl GitHubClient {
     pub fn new(config: GitHubConfig) -> Self {
-        let agent = ureq::Agent::config_builder()
-            // Return non-2xx as Ok(response) so we can read GitHub's explanatory body
-            // (e.g. the classic-PAT-forbidden 403 message) instead of a body-less StatusCode error.
-            .http_status_as_error(false)
-            .build()
-            .new_agent();
-        Self { config, agent }
+        Self::with_timeouts(config, HttpTimeouts::default())
+    }
+
+    pub fn with_timeouts(config: GitHubConfig, timeouts: HttpTimeouts) -> Self {
+        Self {
+            config,
+            agent: timeouts.agent(),
+        }
     }
 
     /// Load config from environment/git config/config file
     pub fn from_env(repo_id: &RepoIdentifier) -> Result<Self, GitHubError> {
         let token = find_github_config(&repo_id.host)?;
-        let api_base = if repo_id.host == "github.com" {
-            "https://api.github.com".to_string()
-        } else {
-            format!("https://{}/api/v3", repo_id.host)
-        };
-        Ok(Self::new(GitHubConfig { token, api_base }))
+        let api_base = config_host_api_base(&repo_id.host).unwrap_or_else(|| {
+            if repo_id.host == "github.com" {
+                "https://api.github.com".to_string()
+            } else {
+                format!("https://{}/api/v3", repo_id.host)
+            }
+        });
+        Ok(Self::with_timeouts(
+            GitHubConfig { token, api_base },
+            http_timeouts(),
+        ))
     }
 
     /// Get a reference to the client's config
@@ -420,11 +728,13 @@ impl GitHubClient {
         bench: &'static str,
     ) -> Result<T, GitHubError> {
         let _bench = GitBenchmark::start(bench);
-        let response = self
-            .auth_headers(self.agent.get(url))
-            .call()
-            .map_err(transport_error)?;
-        read_checked(response)
+        with_rate_limit_retry(|| {
+            let response = self
+                .auth_headers(self.agent.get(url))
+                .call()
+                .map_err(transport_error)?;
+            read_checked(response)
+        })
     }
 
     /// Issue a POST with a JSON body and deserialize the JSON response.
@@ -434,12 +744,17 @@ impl GitHubClient {
         body: &impl Serialize,
         bench: &'static str,
     ) -> Result<T, GitHubError> {
+        if let Some(message) = read_only::blocked_request("POST", url) {
+            return Err(GitHubError::ReadOnly(message));
+        }
         let _bench = GitBenchmark::start(bench);
-        let response = self
-            .auth_headers(self.agent.post(url))
-            .send_json(body)
-            .map_err(transport_error)?;
-        read_checked(response)
+        with_rate_limit_retry(|| {
+            let response = self
+                .auth_headers(self.agent.post(url))
+                .send_json(body)
+                .map_err(transport_error)?;
+            read_checked(response)
+        })
     }
 
     /// Issue a PATCH with a JSON body and deserialize the JSON response.
@@ -449,12 +764,52 @@ impl GitHubClient {
         body: &impl Serialize,
         bench: &'static str,
     ) -> Result<T, GitHubError> {
+        if let Some(message) = read_only::blocked_request("PATCH", url) {
+            return Err(GitHubError::ReadOnly(message));
+        }
+        let _bench = GitBenchmark::start(bench);
+        with_rate_limit_retry(|| {
+            let response = self
+                .auth_headers(self.agent.patch(url))
+                .send_json(body)
+                .map_err(transport_error)?;
+            read_checked(response)
+        })
+    }
+
+    /// Issue a PUT with a JSON body and deserialize the JSON response.
+    fn put_json<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+        body: &impl Serialize,
+        bench: &'static str,
+    ) -> Result<T, GitHubError> {
+        if let Some(message) = read_only::blocked_request("PUT", url) {
+            return Err(GitHubError::ReadOnly(message));
+        }
         let _bench = GitBenchmark::start(bench);
-        let response = self
-            .auth_headers(self.agent.patch(url))
-            .send_json(body)
-            .map_err(transport_error)?;
-        read_checked(response)
+        with_rate_limit_retry(|| {
+            let response = self
+                .auth_headers(self.agent.put(url))
+                .send_json(body)
+                .map_err(transport_error)?;
+            read_checked(response)
+        })
+    }
+
+    /// Issue a DELETE, ignoring any response body.
+    fn delete(&self, url: &str, bench: &'static str) -> Result<(), GitHubError> {
+        if let Some(message) = read_only::blocked_request("DELETE", url) {
+            return Err(GitHubError::ReadOnly(message));
+        }
+        let _bench = GitBenchmark::start(bench);
+        with_rate_limit_retry(|| {
+            let mut response = self
+                .auth_headers(self.agent.delete(url))
+                .call()
+                .map_err(transport_error)?;
+            check_status(&mut response)
+        })
     }
 
     /// Get PR by number
@@ -528,7 +883,24 @@ impl GitHubClient {
             self.config.api_base, repo.owner, repo.repo
         );
 
-        self.post_json(&url, &request, "github:create-pr")
+        match self.post_json(&url, &request, "github:create-pr") {
+            // GitHub's 422 names the head but not the PR, so look the PR up to report its number.
+            Err(GitHubError::Api {
+                status: 422,
+                message,
+            }) if is_pr_already_exists(&message) => {
+                match self.find_pr_for_branch(repo, request.head)? {
+                    Some(pr) => Err(GitHubError::PrAlreadyExists {
+                        pr_number: pr.number,
+                    }),
+                    None => Err(GitHubError::Api {
+                        status: 422,
+                        message,
+                    }),
+                }
+            }
+            result => result,
+        }
     }
 
     /// List PRs for a repository with a given state filter
@@ -576,11 +948,12 @@ impl GitHubClient {
             .collect();
 
         // Build map of head branch name -> PR, filtering out PRs from forks
+        let keep_forks = include_forks();
         let prs: std::collections::HashMap<String, PullRequest> = all_prs
             .into_iter()
             .filter(|pr| {
                 // Filter out PRs from forks (we can't track remote branches for forks)
-                if pr.is_from_fork() {
+                if !keep_forks && pr.is_from_fork() {
                     tracing::debug!(
                         "Skipping PR #{} '{}' - from fork (head: {:?})",
                         pr.number,
@@ -606,189 +979,68 @@ impl GitHubClient {
         self.list_prs(repo, "open", on_progress)
     }
 
-    /// Fetch open PRs for exactly `branches` (the stack's branches) with bounded parallelism,
-    /// scaling with stack size rather than total repo PR activity. Each branch is looked up with
-    /// the single-branch `find_pr_for_branch` query (head=`owner:branch`, non-fork by
-    /// construction). Best-effort: never returns `Result` — a per-branch error omits that branch
-    /// from both outcome lists so the caller keeps its cached (last-known-good) entry.
-    pub fn list_open_prs_for_branches(
-        &self,
-        repo: &RepoIdentifier,
-        branches: &[String],
-    ) -> ScopedOpenPrs {
-        if branches.is_empty() {
-            return ScopedOpenPrs::default();
-        }
-
-        // branches is non-empty (early return above), so this is always >= 1.
-        let worker_count = std::thread::available_parallelism()
-            .map(|n| n.get())
-            .unwrap_or(1)
-            .min(branches.len())
-            .min(8);
-
-        // Partition branches round-robin across workers.
-        let mut buckets: Vec<Vec<&String>> = (0..worker_count).map(|_| Vec::new()).collect();
-        for (i, branch) in branches.iter().enumerate() {
-            buckets[i % worker_count].push(branch);
-        }
-
-        let mut result = ScopedOpenPrs::default();
-        std::thread::scope(|scope| {
-            let handles: Vec<_> = buckets
-                .into_iter()
-                .map(|bucket| {
-                    scope.spawn(move || {
-                        let mut found: Vec<(String, PullRequest)> = Vec::new();
-                        let mut absent: Vec<String> = Vec::new();
-                        for branch in bucket {
-                            match self.find_pr_for_branch(repo, branch) {
-                                Ok(Some(pr)) => found.push((branch.clone(), pr)),
-                                Ok(None) => absent.push(branch.clone()),
-                                Err(e) => {
-                                    tracing::debug!(
-                                        "Scoped open-PR fetch failed for branch {}: {}",
-                                        branch,
-                                        e
-                                    );
-                                }
-                            }
-                        }
-                        // `GitBenchmark` records into thread-local stats, so hand this worker's
-                        // `github:find-pr` spans back for merging into the caller's thread.
-                        (found, absent, crate::stats::get_stats())
-                    })
-                })
-                .collect();
-
-            for handle in handles {
-                if let Ok((found, absent, stats)) = handle.join() {
-                    for (branch, pr) in found {
-                        result.found.insert(branch, pr);
-                    }
-                    result.confirmed_absent.extend(absent);
-                    crate::stats::merge_into_current(&stats);
-                }
-            }
-        });
-
-        result
-    }
-
     /// List closed PRs with caching support.
     ///
-    /// Uses a watermark timestamp strategy:
-    /// 1. Loads cached closed PRs for this repo from the cache handle
-    /// 2. Fetches PRs from API sorted by `updated_at` descending
-    /// 3. Stops fetching when encountering a PR older than the watermark
-    /// 4. Merges fresh data with cache (fresh data wins for any branch name)
-    /// 5. Persists the merged data and an updated watermark (best-effort; a persistence
-    ///    failure only costs the *next* call's warm cache, not this call's result)
+    /// Uses a watermark timestamp strategy: fetches PRs from the API sorted by `updated_at`
+    /// descending, stops when encountering a PR older than the cached watermark, then folds the
+    /// fresh page(s) into the cache via `merge_closed_prs_into_cache`. `limits` can stop the
+    /// fetch sooner; `mode` can ignore the cache instead.
     pub fn list_closed_prs_with_cache(
         &self,
         repo: &RepoIdentifier,
         cache: &crate::pr_cache::PrCacheHandle,
+        limits: &ClosedPrLimits,
+        mode: ClosedPrCacheMode,
         on_progress: Option<&dyn Fn(usize, usize)>,
-    ) -> Result<PrListResult, GitHubError> {
+    ) -> Result<ClosedPrList, GitHubError> {
         let repo_key = repo.full_name();
-
-        let mut closed_prs = cache.closed_prs_for_repo(&repo_key).unwrap_or_else(|e| {
-            tracing::warn!("Failed to read PR cache for {}: {}", repo_key, e);
-            std::collections::HashMap::new()
-        });
-        let watermark = cache.watermark(&repo_key).unwrap_or_else(|e| {
-            tracing::warn!("Failed to read PR cache watermark for {}: {}", repo_key, e);
-            None
-        });
-        tracing::debug!(
-            "PR cache for {}: {} cached closed PRs, watermark={:?}",
-            repo_key,
-            closed_prs.len(),
-            watermark
-        );
+        let watermark = cached_watermark(cache, &repo_key, mode);
 
         // Fetch PRs with early termination based on watermark
-        let fresh_prs =
-            self.list_prs_until_watermark(repo, "closed", watermark.as_deref(), on_progress)?;
-        tracing::debug!(
-            "Fetched {} fresh closed PRs for {} (a small number means the watermark cache hit; \
-             a number near the repo's total closed-PR count means a full backfill happened)",
-            fresh_prs.len(),
-            repo_key
-        );
-
-        // Track the newest updated_at for new watermark
-        let mut newest_updated_at: Option<String> = None;
-        let mut fresh_cached: std::collections::HashMap<String, CachedPullRequest> =
-            std::collections::HashMap::new();
-
-        for (branch_name, pr) in &fresh_prs {
-            if newest_updated_at
-                .as_ref()
-                .is_none_or(|ts| pr.updated_at > *ts)
-            {
-                newest_updated_at = Some(pr.updated_at.clone());
-            }
-
-            let cached_pr = CachedPullRequest::from(pr);
-            closed_prs.insert(branch_name.clone(), cached_pr.clone());
-            fresh_cached.insert(branch_name.clone(), cached_pr);
-        }
-
-        let new_watermark = match (&watermark, &newest_updated_at) {
-            (None, Some(ts)) => Some(ts.clone()),
-            (Some(current), Some(ts)) if ts > current => Some(ts.clone()),
-            _ => None,
-        };
-
-        tracing::debug!(
-            "Updated PR cache watermark for {}: {:?} -> {:?}",
-            repo_key,
-            watermark,
-            new_watermark
-        );
-        if let Err(e) = cache.commit_fresh_prs(
-            &repo_key,
-            fresh_cached.iter().map(|(k, v)| (k.as_str(), v)),
-            new_watermark.as_deref(),
-        ) {
-            tracing::warn!("Failed to persist PR cache for {}: {}", repo_key, e);
-        }
-
-        // Collect all authors from cache before filtering (for pruning decisions)
-        let all_authors: std::collections::HashMap<String, String> = closed_prs
-            .iter()
-            .map(|(branch, cached_pr)| (branch.clone(), cached_pr.user.login.clone()))
-            .collect();
-
-        // Convert cache to return type, applying filters
-        let prs: std::collections::HashMap<String, PullRequest> = closed_prs
-            .iter()
-            .map(|(k, v)| (k.clone(), PullRequest::from(v)))
-            .filter(|(_, pr)| self.should_include_pr(pr))
-            .collect();
-
-        Ok(PrListResult { prs, all_authors })
+        let (fresh_prs, truncated) = self.list_prs_until_watermark(
+            repo,
+            "closed",
+            watermark.as_deref(),
+            limits,
+            on_progress,
+        )?;
+
+        Ok(ClosedPrList {
+            fetched: fresh_prs.len(),
+            truncated,
+            result: merge_closed_prs_into_cache(
+                &repo_key,
+                cache,
+                watermark,
+                &fresh_prs,
+                closed_pr_retention(),
+                mode,
+            ),
+        })
     }
 
     /// Check if a PR should be included based on fork filtering
-    fn should_include_pr(&self, pr: &PullRequest) -> bool {
+    fn should_include_pr(&self, pr: &PullRequest, keep_forks: bool) -> bool {
         // Filter out PRs from forks (we can't track remote branches for forks)
-        !pr.is_from_fork()
+        keep_forks || !pr.is_from_fork()
     }
 
-    /// Fetch PRs with early termination when hitting the watermark
+    /// Fetch PRs with early termination when hitting the watermark or `limits`. Also returns
+    /// whether `limits` cut the fetch short.
     fn list_prs_until_watermark(
         &self,
         repo: &RepoIdentifier,
         state: &str,
         watermark: Option<&str>,
+        limits: &ClosedPrLimits,
         on_progress: Option<&dyn Fn(usize, usize)>,
-    ) -> Result<std::collections::HashMap<String, PullRequest>, GitHubError> {
+    ) -> Result<(std::collections::HashMap<String, PullRequest>, bool), GitHubError> {
         let mut all_prs = Vec::new();
         let mut page = 1;
         let per_page = 100;
         let mut hit_watermark = false;
+        let mut hit_limit = false;
+        let mut truncated = false;
 
         loop {
             // Use sort=updated and direction=desc for watermark strategy
@@ -803,6 +1055,12 @@ impl GitHubClient {
 
             // Check each PR against watermark
             for pr in prs {
+                if !limits.admits(all_prs.len(), &pr) {
+                    hit_limit = true;
+                    // Past the watermark, the rest are cached already; nothing was skipped.
+                    truncated = !hit_watermark;
+                    break;
+                }
                 // If we have a watermark and this PR's updated_at is older or equal, we can stop
                 // after this page (still include PRs on this page to handle edge cases)
                 if let Some(wm) = watermark
@@ -818,21 +1076,22 @@ impl GitHubClient {
                 callback(page, all_prs.len());
             }
 
-            // Stop if we hit the watermark or reached the end
-            if hit_watermark || count < per_page {
+            // Stop if we hit the watermark or a limit, or reached the end
+            if hit_watermark || hit_limit || count < per_page {
                 break;
             }
             page += 1;
         }
 
         // Build map of head branch name -> PR, filtering out irrelevant PRs
+        let keep_forks = include_forks();
         let pr_map: std::collections::HashMap<String, PullRequest> = all_prs
             .into_iter()
-            .filter(|pr| self.should_include_pr(pr))
+            .filter(|pr| self.should_include_pr(pr, keep_forks))
             .map(|pr| (pr.head.ref_name.clone(), pr))
             .collect();
 
-        Ok(pr_map)
+        Ok((pr_map, truncated))
     }
 
     /// Update PR (e.g., to retarget base)
@@ -850,6 +1109,148 @@ impl GitHubClient {
         self.patch_json(&url, &request, "github:update-pr")
     }
 
+    /// Post `body` as a comment on PR `pr_number`, or edit the existing comment that contains
+    /// `marker` (a hidden HTML comment) so repeated calls keep a single, current comment.
+    pub fn create_or_update_comment(
+        &self,
+        repo: &RepoIdentifier,
+        pr_number: u64,
+        marker: &str,
+        body: &str,
+    ) -> Result<CommentUpsert, GitHubError> {
+        let mut comments: Vec<IssueComment> = Vec::new();
+        let per_page = 100;
+        for page in 1.. {
+            let url = format!(
+                "{}/repos/{}/{}/issues/{}/comments?per_page={}&page={}",
+                self.config.api_base, repo.owner, repo.repo, pr_number, per_page, page
+            );
+            let batch: Vec<IssueComment> = self.get_json(&url, "github:list-comments")?;
+            let count = batch.len();
+            comments.extend(batch);
+            if count < per_page {
+                break;
+            }
+        }
+
+        let request = serde_json::json!({ "body": body });
+        match plan_comment_upsert(&comments, marker, body) {
+            Err(unchanged) => Ok(unchanged),
+            Ok(Some(id)) => {
+                let url = format!(
+                    "{}/repos/{}/{}/issues/comments/{}",
+                    self.config.api_base, repo.owner, repo.repo, id
+                );
+                self.patch_json::<IssueComment>(&url, &request, "github:update-comment")?;
+                Ok(CommentUpsert::Updated)
+            }
+            Ok(None) => {
+                let url = format!(
+                    "{}/repos/{}/{}/issues/{}/comments",
+                    self.config.api_base, repo.owner, repo.repo, pr_number
+                );
+                self.post_json::<IssueComment>(&url, &request, "github:create-comment")?;
+                Ok(CommentUpsert::Created)
+            }
+        }
+    }
+
+    /// Merge PR `pr_number` with `method`. `head_sha` must still be the PR's head, so a push that
+    /// lands in between makes GitHub refuse rather than merge commits nobody checked.
+    /// `commit_title` replaces GitHub's default title for a merge or squash commit.
+    pub fn merge_pr(
+        &self,
+        repo: &RepoIdentifier,
+        pr_number: u64,
+        head_sha: &str,
+        method: MergeMethod,
+        commit_title: Option<&str>,
+    ) -> Result<(), GitHubError> {
+        #[derive(Deserialize)]
+        struct MergeResponse {}
+
+        let url = format!(
+            "{}/repos/{}/{}/pulls/{}/merge",
+            self.config.api_base, repo.owner, repo.repo, pr_number
+        );
+        let mut body = serde_json::json!({ "merge_method": method, "sha": head_sha });
+        if let Some(commit_title) = commit_title {
+            body["commit_title"] = commit_title.into();
+        }
+        self.put_json::<MergeResponse>(&url, &body, "github:merge-pr")?;
+        Ok(())
+    }
+
+    /// Delete `ref_name` (`heads/<branch>` for a branch) from the repo.
+    pub fn delete_ref(&self, repo: &RepoIdentifier, ref_name: &str) -> Result<(), GitHubError> {
+        let url = format!(
+            "{}/repos/{}/{}/git/refs/{}",
+            self.config.api_base, repo.owner, repo.repo, ref_name
+        );
+        self.delete(&url, "github:delete-ref")
+    }
+
+    /// The merge methods the repo's settings allow. GitHub only reports them to users with push
+    /// access; anything it leaves out is assumed allowed.
+    pub fn allowed_merge_methods(
+        &self,
+        repo: &RepoIdentifier,
+    ) -> Result<Vec<MergeMethod>, GitHubError> {
+        let url = format!(
+            "{}/repos/{}/{}",
+            self.config.api_base, repo.owner, repo.repo
+        );
+        let settings: RepoMergeSettings = self.get_json(&url, "github:repo-settings")?;
+        Ok(settings.allowed())
+    }
+
+    /// Mergeability, CI checks, and review state of PR `pr_number` (for `pr status`). Reads the
+    /// first 100 check runs, statuses, and reviews, which covers any realistic PR.
+    pub fn pr_readiness(
+        &self,
+        repo: &RepoIdentifier,
+        pr_number: u64,
+    ) -> Result<PrReadiness, GitHubError> {
+        #[derive(Deserialize)]
+        struct PrMergeability {
+            mergeable_state: Option<String>,
+            head: PrBranchRef,
+        }
+        #[derive(Deserialize)]
+        struct CheckRunList {
+            check_runs: Vec<CheckRun>,
+        }
+        #[derive(Deserialize)]
+        struct CombinedStatus {
+            statuses: Vec<CommitStatus>,
+        }
+
+        let repo_url = format!(
+            "{}/repos/{}/{}",
+            self.config.api_base, repo.owner, repo.repo
+        );
+        let pr: PrMergeability =
+            self.get_json(&format!("{repo_url}/pulls/{pr_number}"), "github:get-pr")?;
+        let sha = &pr.head.sha;
+        let runs: CheckRunList = self.get_json(
+            &format!("{repo_url}/commits/{sha}/check-runs?per_page=100"),
+            "github:check-runs",
+        )?;
+        let status: CombinedStatus = self.get_json(
+            &format!("{repo_url}/commits/{sha}/status?per_page=100"),
+            "github:commit-status",
+        )?;
+        let reviews: Vec<PrReview> = self.get_json(
+            &format!("{repo_url}/pulls/{pr_number}/reviews?per_page=100"),
+            "github:reviews",
+        )?;
+        Ok(PrReadiness {
+            mergeable: pr.mergeable_state,
+            checks: count_checks(&runs.check_runs, &status.statuses),
+            review: review_decision(&reviews),
+        })
+    }
+
     /// The GraphQL endpoint for this host. github.com's REST base is `https://api.github.com`
     /// (GraphQL at `…/graphql`); GHE's REST base is `https://{host}/api/v3` (GraphQL at
     /// `https://{host}/api/graphql`).
@@ -871,11 +1272,13 @@ impl GitHubClient {
         let _bench = GitBenchmark::start("github:graphql");
         let url = self.graphql_url();
         let body = serde_json::json!({ "query": query, "variables": variables });
-        let response = self
-            .auth_headers(self.agent.post(&url))
-            .send_json(&body)
-            .map_err(transport_error)?;
-        let parsed: GraphQlResponse<T> = read_checked(response)?;
+        let parsed: GraphQlResponse<T> = with_rate_limit_retry(|| {
+            let response = self
+                .auth_headers(self.agent.post(&url))
+                .send_json(&body)
+                .map_err(transport_error)?;
+            read_checked(response)
+        })?;
         if !parsed.errors.is_empty() {
             let message = parsed
                 .errors
@@ -954,6 +1357,258 @@ impl GitHubClient {
     }
 }
 
+impl crate::provider::RemoteProvider for GitHubClient {
+    fn whoami(&self) -> Result<String, GitHubError> {
+        GitHubClient::whoami(self)
+    }
+
+    fn get_pr(&self, repo: &RepoIdentifier, pr_number: u64) -> Result<PullRequest, GitHubError> {
+        GitHubClient::get_pr(self, repo, pr_number)
+    }
+
+    fn find_pr_for_branch(
+        &self,
+        repo: &RepoIdentifier,
+        branch: &str,
+    ) -> Result<Option<PullRequest>, GitHubError> {
+        GitHubClient::find_pr_for_branch(self, repo, branch)
+    }
+
+    fn create_pr(
+        &self,
+        repo: &RepoIdentifier,
+        request: CreatePrRequest,
+    ) -> Result<PullRequest, GitHubError> {
+        GitHubClient::create_pr(self, repo, request)
+    }
+
+    fn update_pr(
+        &self,
+        repo: &RepoIdentifier,
+        pr_number: u64,
+        request: UpdatePrRequest,
+    ) -> Result<PullRequest, GitHubError> {
+        GitHubClient::update_pr(self, repo, pr_number, request)
+    }
+
+    fn list_open_prs(
+        &self,
+        repo: &RepoIdentifier,
+        on_progress: Option<&dyn Fn(usize, usize)>,
+    ) -> Result<PrListResult, GitHubError> {
+        GitHubClient::list_open_prs(self, repo, on_progress)
+    }
+
+    fn list_closed_prs_with_cache(
+        &self,
+        repo: &RepoIdentifier,
+        cache: &crate::pr_cache::PrCacheHandle,
+        limits: &ClosedPrLimits,
+        mode: ClosedPrCacheMode,
+        on_progress: Option<&dyn Fn(usize, usize)>,
+    ) -> Result<ClosedPrList, GitHubError> {
+        GitHubClient::list_closed_prs_with_cache(self, repo, cache, limits, mode, on_progress)
+    }
+
+    fn list_open_prs_by_authors(
+        &self,
+        repo: &RepoIdentifier,
+        authors: &[String],
+    ) -> Result<Vec<PullRequest>, GitHubError> {
+        GitHubClient::list_open_prs_by_authors(self, repo, authors)
+    }
+
+    fn create_or_update_comment(
+        &self,
+        repo: &RepoIdentifier,
+        pr_number: u64,
+        marker: &str,
+        body: &str,
+    ) -> Result<CommentUpsert, GitHubError> {
+        GitHubClient::create_or_update_comment(self, repo, pr_number, marker, body)
+    }
+
+    fn pr_readiness(
+        &self,
+        repo: &RepoIdentifier,
+        pr_number: u64,
+    ) -> Result<PrReadiness, GitHubError> {
+        GitHubClient::pr_readiness(self, repo, pr_number)
+    }
+
+    fn merge_pr(
+        &self,
+        repo: &RepoIdentifier,
+        pr_number: u64,
+        head_sha: &str,
+        method: MergeMethod,
+        commit_title: Option<&str>,
+    ) -> Result<(), GitHubError> {
+        GitHubClient::merge_pr(self, repo, pr_number, head_sha, method, commit_title)
+    }
+
+    fn delete_branch(&self, repo: &RepoIdentifier, branch: &str) -> Result<(), GitHubError> {
+        GitHubClient::delete_ref(self, repo, &format!("heads/{branch}"))
+    }
+
+    fn allowed_merge_methods(
+        &self,
+        repo: &RepoIdentifier,
+    ) -> Result<Vec<MergeMethod>, GitHubError> {
+        GitHubClient::allowed_merge_methods(self, repo)
+    }
+}
+
+/// The watermark a closed-PR fetch in `mode` stops at: the cached one, or none when rebuilding.
+pub(crate) fn cached_watermark(
+    cache: &crate::pr_cache::PrCacheHandle,
+    repo_key: &str,
+    mode: ClosedPrCacheMode,
+) -> Option<String> {
+    if let ClosedPrCacheMode::Rebuild { .. } = mode {
+        return None;
+    }
+    cache.watermark(repo_key).unwrap_or_else(|e| {
+        tracing::warn!("Failed to read PR cache watermark for {}: {}", repo_key, e);
+        None
+    })
+}
+
+/// Fold freshly fetched closed PRs (everything updated since `watermark`) into the on-disk
+/// closed-PR cache for `repo_key` and return the merged view:
+/// 1. Loads cached closed PRs for this repo from the cache handle
+/// 2. Merges fresh data with cache (fresh data wins for any branch name)
+/// 3. Persists the merged data and an updated watermark (best-effort; a persistence failure only
+///    costs the *next* call's warm cache, not this call's result)
+/// 4. With a `retention` window, drops closed PRs last updated before it from both the result and
+///    the cache, so entries for renamed or deleted branches don't accumulate forever
+///
+/// In [`ClosedPrCacheMode::Rebuild`] mode, the cache isn't read: the result is the fresh PRs alone,
+/// and they replace the cached ones only if the mode says to persist them.
+///
+/// Shared by every `RemoteProvider`'s `list_closed_prs_with_cache`; only the fetch differs.
+pub(crate) fn merge_closed_prs_into_cache(
+    repo_key: &str,
+    cache: &crate::pr_cache::PrCacheHandle,
+    watermark: Option<String>,
+    fresh_prs: &std::collections::HashMap<String, PullRequest>,
+    retention: Option<chrono::TimeDelta>,
+    mode: ClosedPrCacheMode,
+) -> PrListResult {
+    let mut closed_prs = match mode {
+        ClosedPrCacheMode::Incremental => cache.closed_prs_for_repo(repo_key).unwrap_or_else(|e| {
+            tracing::warn!("Failed to read PR cache for {}: {}", repo_key, e);
+            std::collections::HashMap::new()
+        }),
+        ClosedPrCacheMode::Rebuild { .. } => std::collections::HashMap::new(),
+    };
+    tracing::debug!(
+        "PR cache for {}: {} cached closed PRs, watermark={:?}",
+        repo_key,
+        closed_prs.len(),
+        watermark
+    );
+    tracing::debug!(
+        "Fetched {} fresh closed PRs for {} (a small number means the watermark cache hit; \
+         a number near the repo's total closed-PR count means a full backfill happened)",
+        fresh_prs.len(),
+        repo_key
+    );
+
+    // Track the newest updated_at for new watermark
+    let mut newest_updated_at: Option<String> = None;
+    let mut fresh_cached: std::collections::HashMap<String, CachedPullRequest> =
+        std::collections::HashMap::new();
+
+    for (branch_name, pr) in fresh_prs {
+        if newest_updated_at
+            .as_ref()
+            .is_none_or(|ts| pr.updated_at > *ts)
+        {
+            newest_updated_at = Some(pr.updated_at.clone());
+        }
+
+        let cached_pr = CachedPullRequest::from(pr);
+        closed_prs.insert(branch_name.clone(), cached_pr.clone());
+        fresh_cached.insert(branch_name.clone(), cached_pr);
+    }
+
+    // Evict anything older than the retention window. `updated_at` is RFC 3339 UTC, so the string
+    // comparison is chronological (as for the watermark).
+    if let Some(retention) = retention {
+        let cutoff =
+            (chrono::Utc::now() - retention).to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        let stale: Vec<String> = closed_prs
+            .iter()
+            .filter(|(_, pr)| pr.updated_at < cutoff)
+            .map(|(branch, _)| branch.clone())
+            .collect();
+        for branch in &stale {
+            closed_prs.remove(branch);
+            fresh_cached.remove(branch);
+        }
+        if !stale.is_empty() && mode == ClosedPrCacheMode::Incremental {
+            tracing::debug!(
+                "Evicting {} closed PRs last updated before {} from the PR cache for {}",
+                stale.len(),
+                cutoff,
+                repo_key
+            );
+            let stale: Vec<&str> = stale.iter().map(String::as_str).collect();
+            if let Err(e) = cache.remove_closed_prs(repo_key, &stale) {
+                tracing::warn!(
+                    "Failed to evict stale PR cache entries for {}: {}",
+                    repo_key,
+                    e
+                );
+            }
+        }
+    }
+
+    let new_watermark = match (&watermark, &newest_updated_at) {
+        (None, Some(ts)) => Some(ts.clone()),
+        (Some(current), Some(ts)) if ts > current => Some(ts.clone()),
+        _ => None,
+    };
+
+    tracing::debug!(
+        "Updated PR cache watermark for {}: {:?} -> {:?}",
+        repo_key,
+        watermark,
+        new_watermark
+    );
+    let fresh_entries = fresh_cached.iter().map(|(k, v)| (k.as_str(), v));
+    let persisted = match mode {
+        ClosedPrCacheMode::Incremental => {
+            cache.commit_fresh_prs(repo_key, fresh_entries, new_watermark.as_deref())
+        }
+        ClosedPrCacheMode::Rebuild { persist: true } => {
+            cache.replace_closed_prs(repo_key, fresh_entries, new_watermark.as_deref())
+        }
+        ClosedPrCacheMode::Rebuild { persist: false } => Ok(()),
+    };
+    if let Err(e) = persisted {
+        tracing::warn!("Failed to persist PR cache for {}: {}", repo_key, e);
+    }
+
+    // Collect all authors from cache before filtering (for pruning decisions)
+    let all_authors: std::collections::HashMap<String, String> = closed_prs
+        .iter()
+        .map(|(branch, cached_pr)| (branch.clone(), cached_pr.user.login.clone()))
+        .collect();
+
+    // Convert cache to return type, filtering out PRs from forks (we can't track remote branches
+    // for forks)
+    let keep_forks = include_forks();
+    let prs: std::collections::HashMap<String, PullRequest> = closed_prs
+        .iter()
+        .map(|(k, v)| (k.clone(), PullRequest::from(v)))
+        .filter(|(_, pr)| keep_forks || !pr.is_from_fork())
+        .collect();
+
+    PrListResult { prs, all_authors }
+}
+
 /// Build the GitHub search string for author-scoped open-PR discovery: the repo, `is:pr is:open`,
 /// and one `author:` qualifier per login (multiple `author:` qualifiers OR together in search).
 fn build_author_search_query(repo: &RepoIdentifier, authors: &[String]) -> String {
@@ -964,15 +1619,16 @@ fn build_author_search_query(repo: &RepoIdentifier, authors: &[String]) -> Strin
     query
 }
 
-/// Map GraphQL `search` nodes into `PullRequest`s, dropping fork PRs (`isCrossRepository`) and any
-/// node missing the core PR fields (e.g. an empty non-PR result). All results come from an
-/// `is:open` search, so `state` is hardcoded to `PrState::Open`; the base SHA is unused downstream
-/// (`RemotePr` carries only `base.ref_name`), so it's left empty.
+/// Map GraphQL `search` nodes into `PullRequest`s, dropping fork PRs (`isCrossRepository`, unless
+/// [`include_forks`]) and any node missing the core PR fields (e.g. an empty non-PR result). All
+/// results come from an `is:open` search, so `state` is hardcoded to `PrState::Open`; the base SHA
+/// is unused downstream (`RemotePr` carries only `base.ref_name`), so it's left empty.
 fn pull_requests_from_search_nodes(nodes: &[SearchNode]) -> Vec<PullRequest> {
+    let keep_forks = include_forks();
     nodes
         .iter()
         .filter_map(|node| {
-            if node.is_cross_repository == Some(true) {
+            if !keep_forks && node.is_cross_repository == Some(true) {
                 return None;
             }
             let number = node.number?;
@@ -989,6 +1645,7 @@ fn pull_requests_from_search_nodes(nodes: &[SearchNode]) -> Vec<PullRequest> {
                     repo: node.base_repository.as_ref().map(|r| PrRepoRef {
                         full_name: r.name_with_owner.clone(),
                     }),
+                    label: None,
                 },
                 head: PrBranchRef {
                     ref_name: head_ref_name,
@@ -996,6 +1653,7 @@ fn pull_requests_from_search_nodes(nodes: &[SearchNode]) -> Vec<PullRequest> {
                     repo: node.head_repository.as_ref().map(|r| PrRepoRef {
                         full_name: r.name_with_owner.clone(),
                     }),
+                    label: None,
                 },
                 user: PrUser {
                     login: node
@@ -1015,23 +1673,153 @@ fn pull_requests_from_search_nodes(nodes: &[SearchNode]) -> Vec<PullRequest> {
 
 /// A genuine transport failure (DNS/connect/TLS/timeout); status codes never land here now
 /// because the agent has `http_status_as_error(false)`.
-fn transport_error(error: ureq::Error) -> GitHubError {
-    GitHubError::Network(error.to_string())
+pub(crate) fn transport_error(error: ureq::Error) -> GitHubError {
+    match error {
+        ureq::Error::Timeout(timeout) => {
+            let phase = match timeout {
+                ureq::Timeout::Connect | ureq::Timeout::Resolve => "connecting",
+                ureq::Timeout::RecvResponse => "waiting for the response",
+                ureq::Timeout::RecvBody => "reading the response",
+                _ => "sending the request",
+            };
+            GitHubError::Network(format!(
+                "request timed out while {phase} (see `connect_timeout_secs` / \
+                 `read_timeout_secs` in github.yaml)"
+            ))
+        }
+        // A read failing mid-parse (e.g. the body timing out) comes back wrapped in the JSON error.
+        ureq::Error::Json(error) if error.is_io() => {
+            transport_error(ureq::Error::from(std::io::Error::from(error)))
+        }
+        error => GitHubError::Network(error.to_string()),
+    }
 }
 
-/// Status-check + JSON-deserialize. Non-2xx reads the body and classifies the error.
-fn read_checked<T: serde::de::DeserializeOwned>(
+/// Status-check + JSON-deserialize. Non-2xx reads the body and classifies the error; a rate-limit
+/// response becomes `RateLimited` so `with_rate_limit_retry` can wait it out.
+pub(crate) fn read_checked<T: serde::de::DeserializeOwned>(
     mut response: ureq::http::Response<ureq::Body>,
 ) -> Result<T, GitHubError> {
+    check_status(&mut response)?;
+    response.body_mut().read_json().map_err(transport_error)
+}
+
+/// The status check of [`read_checked`], for responses without a body worth reading (`204`).
+pub(crate) fn check_status(
+    response: &mut ureq::http::Response<ureq::Body>,
+) -> Result<(), GitHubError> {
     let status = response.status().as_u16();
+    let headers = response.headers();
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    if let Some(reset_at) = rate_limit_reset(status, header, unix_now()) {
+        return Err(GitHubError::RateLimited { reset_at });
+    }
     if !(200..300).contains(&status) {
         let body = response.body_mut().read_to_string().unwrap_or_default();
         return Err(classify_status_error(status, &body));
     }
-    response
-        .body_mut()
-        .read_json()
-        .map_err(|e| GitHubError::Network(e.to_string()))
+    Ok(())
+}
+
+/// Retries of a rate-limited request before its `RateLimited` error is surfaced.
+const RATE_LIMIT_MAX_RETRIES: u32 = 4;
+/// Longest single wait between retries, however far away the advertised reset is.
+const RATE_LIMIT_MAX_WAIT: std::time::Duration = std::time::Duration::from_secs(120);
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// If this response is a rate-limit rejection, the Unix time at which it lifts. GitHub signals
+/// its primary limit with a 403 and `X-RateLimit-Remaining: 0`, and its secondary limits with a
+/// 403/429 carrying `Retry-After`; GitLab uses 429 with `RateLimit-*` headers. `Retry-After`
+/// (seconds) wins over the reset timestamp, and a 429 without either lifts "now" so the caller's
+/// backoff alone decides the wait.
+fn rate_limit_reset<'a>(
+    status: u16,
+    header: impl Fn(&str) -> Option<&'a str>,
+    now: u64,
+) -> Option<u64> {
+    let parse = |name: &str| header(name).and_then(|v| v.trim().parse::<u64>().ok());
+    let retry_after = parse("retry-after");
+    let exhausted = ["x-ratelimit-remaining", "ratelimit-remaining"]
+        .iter()
+        .any(|name| parse(name) == Some(0));
+    let limited = match status {
+        429 => true,
+        403 => exhausted || retry_after.is_some(),
+        _ => false,
+    };
+    if !limited {
+        return None;
+    }
+    Some(match retry_after {
+        Some(secs) => now + secs,
+        None => parse("x-ratelimit-reset")
+            .or_else(|| parse("ratelimit-reset"))
+            .unwrap_or(now),
+    })
+}
+
+/// How long to sleep before retry number `attempt` (1-based): until the reset, but at least an
+/// exponential backoff (2s, 4s, 8s, ...) and never more than `RATE_LIMIT_MAX_WAIT`.
+fn rate_limit_delay(reset_at: u64, now: u64, attempt: u32) -> std::time::Duration {
+    let backoff = 1u64 << attempt.min(6);
+    std::time::Duration::from_secs(reset_at.saturating_sub(now).max(backoff))
+        .min(RATE_LIMIT_MAX_WAIT)
+}
+
+/// Run `call`, sleeping and retrying (up to `RATE_LIMIT_MAX_RETRIES` times) while it fails with
+/// `RateLimited`, so a long pagination or a sync's PR updates ride out the limit rather than
+/// dying halfway through. Other errors are returned immediately.
+pub(crate) fn with_rate_limit_retry<T>(
+    call: impl FnMut() -> Result<T, GitHubError>,
+) -> Result<T, GitHubError> {
+    retry_rate_limited(call, unix_now, wait_for_rate_limit)
+}
+
+fn retry_rate_limited<T>(
+    mut call: impl FnMut() -> Result<T, GitHubError>,
+    now: impl Fn() -> u64,
+    mut wait: impl FnMut(std::time::Duration, u32),
+) -> Result<T, GitHubError> {
+    let mut attempt = 0;
+    loop {
+        match call() {
+            Err(GitHubError::RateLimited { reset_at }) if attempt < RATE_LIMIT_MAX_RETRIES => {
+                attempt += 1;
+                wait(rate_limit_delay(reset_at, now(), attempt), attempt);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Sleep out a rate limit, with a spinner when stderr is a terminal.
+fn wait_for_rate_limit(delay: std::time::Duration, attempt: u32) {
+    use std::io::IsTerminal;
+    let message = format!(
+        "Rate limited by the API; retrying in {}s (attempt {attempt}/{RATE_LIMIT_MAX_RETRIES})",
+        delay.as_secs()
+    );
+    if !io::stderr().is_terminal() {
+        tracing::warn!("{message}");
+        std::thread::sleep(delay);
+        return;
+    }
+    let spinner = indicatif::ProgressBar::new_spinner();
+    spinner.set_style(
+        indicatif::ProgressStyle::default_spinner()
+            .template("{spinner:.yellow} {msg}")
+            .expect("valid template"),
+    );
+    spinner.set_message(message);
+    spinner.enable_steady_tick(std::time::Duration::from_millis(100));
+    std::thread::sleep(delay);
+    spinner.finish_and_clear();
 }
 
 const CLASSIC_PAT_MARKER: &str = "forbids access via a personal access token (classic)";
@@ -1053,17 +1841,35 @@ fn classify_status_error(status: u16, body: &str) -> GitHubError {
     }
 }
 
-/// Extract GitHub's `message` field from a JSON error body; fall back to the raw body, then to a
-/// bare "HTTP <status>".
+/// Extract GitHub's `message` field from a JSON error body, followed by the `message` of each entry
+/// in its `errors` list (where a 422 says what actually failed validation); fall back to the raw
+/// body, then to a bare "HTTP <status>".
 fn api_error_message(body: &str, status: u16) -> String {
+    #[derive(Deserialize)]
+    struct ApiErrorDetail {
+        message: Option<String>,
+    }
     #[derive(Deserialize)]
     struct ApiErrorBody {
         message: Option<String>,
+        #[serde(default)]
+        errors: Vec<ApiErrorDetail>,
     }
     serde_json::from_str::<ApiErrorBody>(body)
         .ok()
-        .and_then(|b| b.message)
-        .filter(|m| !m.is_empty())
+        .and_then(|b| {
+            let message = b.message.filter(|m| !m.is_empty())?;
+            let details: Vec<String> = b
+                .errors
+                .into_iter()
+                .filter_map(|e| e.message.filter(|m| !m.is_empty()))
+                .collect();
+            Some(if details.is_empty() {
+                message
+            } else {
+                format!("{message}: {}", details.join("; "))
+            })
+        })
         .unwrap_or_else(|| {
             let t = body.trim();
             if t.is_empty() {
@@ -1074,6 +1880,13 @@ fn api_error_message(body: &str, status: u16) -> String {
         })
 }
 
+/// Whether a `create_pr` 422 message is GitHub's "A pull request already exists for owner:branch."
+fn is_pr_already_exists(message: &str) -> bool {
+    message
+        .to_ascii_lowercase()
+        .contains("a pull request already exists")
+}
+
 /// Pull the org login out of the classic-PAT-forbidden message. GitHub phrases it
 /// "<org> forbids access via a personal access token (classic)"; the org is the whitespace/quote-
 /// delimited token immediately before the marker. Returns None if it doesn't look like a login.
@@ -1102,10 +1915,11 @@ pub fn pr_list_result_from_cached(
         .map(|(branch, pr)| (branch.clone(), pr.user.login.clone()))
         .collect();
 
+    let keep_forks = include_forks();
     let prs: std::collections::HashMap<String, PullRequest> = cached
         .iter()
         .map(|(branch, cached_pr)| (branch.clone(), PullRequest::from(cached_pr)))
-        .filter(|(_, pr)| !pr.is_from_fork())
+        .filter(|(_, pr)| keep_forks || !pr.is_from_fork())
         .collect();
 
     PrListResult { prs, all_authors }
@@ -1213,6 +2027,57 @@ pub fn restack_push_no_verify() -> bool {
         .unwrap_or(false)
 }
 
+/// Set at startup by `--include-forks`.
+static INCLUDE_FORKS: AtomicBool = AtomicBool::new(false);
+
+/// Keep fork PRs for the rest of the process, whatever the config says.
+pub fn include_forks_for_run() {
+    INCLUDE_FORKS.store(true, Ordering::Relaxed);
+}
+
+/// Whether fork PRs are kept: `--include-forks` for this run, or `include_forks` in the config.
+/// Fork heads aren't on the remote, so sync can't fetch them; the branch has to exist locally.
+pub fn include_forks() -> bool {
+    INCLUDE_FORKS.load(Ordering::Relaxed)
+        || load_github_config_file()
+            .map(|config| config.include_forks)
+            .unwrap_or(false)
+}
+
+/// How long closed PRs stay in the PR cache when `closed_pr_retention_days` isn't configured.
+pub const DEFAULT_CLOSED_PR_RETENTION_DAYS: u32 = 180;
+
+/// How long a closed PR stays in the PR cache after its last update, from
+/// `closed_pr_retention_days` (default [`DEFAULT_CLOSED_PR_RETENTION_DAYS`]). `None` when set to
+/// `0`, which keeps closed PRs forever.
+pub fn closed_pr_retention() -> Option<chrono::TimeDelta> {
+    let days = load_github_config_file()
+        .and_then(|config| config.closed_pr_retention_days)
+        .unwrap_or(DEFAULT_CLOSED_PR_RETENTION_DAYS);
+    (days > 0).then(|| chrono::TimeDelta::days(i64::from(days)))
+}
+
+/// The API timeouts: [`HttpTimeouts::default`], with `connect_timeout_secs` / `read_timeout_secs`
+/// from the config file taking precedence.
+pub fn http_timeouts() -> HttpTimeouts {
+    let config = load_github_config_file();
+    let config = config.as_ref();
+    let defaults = HttpTimeouts::default();
+    // `0` would fail every request at once; take it as the shortest timeout instead.
+    let secs = |value: Option<u64>, default| {
+        value.map_or(default, |secs: u64| {
+            std::time::Duration::from_secs(secs.max(1))
+        })
+    };
+    HttpTimeouts {
+        connect: secs(
+            config.and_then(|c| c.connect_timeout_secs),
+            defaults.connect,
+        ),
+        read: secs(config.and_then(|c| c.read_timeout_secs), defaults.read),
+    }
+}
+
 /// Pure resolution core for the three-state author filter, with all identity inputs injected so
 /// the "can't resolve → error" path is unit-testable with no live API.
 ///
@@ -1252,7 +2117,7 @@ fn resolve_effective_authors_filter_core(
 /// Errors — never guesses — when a `Default` filter can't be resolved to a login by any means.
 pub fn resolve_effective_authors_filter(
     repo_id: &RepoIdentifier,
-    live_client: Option<&GitHubClient>,
+    live_client: Option<&dyn crate::provider::RemoteProvider>,
 ) -> Result<Vec<String>> {
     let configured = configured_authors_filter();
     // Explicit config never needs identity resolution.
@@ -1268,7 +2133,7 @@ pub fn resolve_effective_authors_filter(
     // Fetch a live login only when it's worth it: refresh on the always-online callers, and on the
     // hot path only when the cache missed (cold cache). A warm cache with no live client fetches
     // nothing.
-    let fetch_and_cache = |client: &GitHubClient| -> Option<String> {
+    let fetch_and_cache = |client: &dyn crate::provider::RemoteProvider| -> Option<String> {
         match client.whoami() {
             Ok(login) => {
                 if let Some(cache) = &cache {
@@ -1286,8 +2151,8 @@ pub fn resolve_effective_authors_filter(
     let fetched_login = if let Some(client) = live_client {
         fetch_and_cache(client)
     } else if cached_login.is_none() {
-        match GitHubClient::from_env(repo_id) {
-            Ok(client) => fetch_and_cache(&client),
+        match crate::provider::client_for(repo_id) {
+            Ok(client) => fetch_and_cache(client.as_ref()),
             Err(e) => {
                 tracing::debug!("could not build client for whoami on {}: {e}", repo_id.host);
                 None
@@ -1321,7 +2186,7 @@ pub fn resolve_effective_authors_filter_cached(repo_id: &RepoIdentifier) -> Opti
 /// Best-effort force-live `whoami` + host-keyed cache write, ignoring all errors. Used by
 /// `auth login` to warm the identity cache after a successful login. Returns the login on success.
 pub fn refresh_self_login(repo_id: &RepoIdentifier) -> Option<String> {
-    let client = GitHubClient::from_env(repo_id).ok()?;
+    let client = crate::provider::client_for(repo_id).ok()?;
     let login = client.whoami().ok()?;
     if let Ok(cache) = crate::pr_cache::PrCacheHandle::open() {
         let _ = cache.put_identity(&repo_id.host, &login);
@@ -1349,14 +2214,29 @@ pub enum AuthSource {
     GhCli,
 }
 
-/// Ask the `gh` CLI for a token for `host`. Returns None if `gh` is absent,
-/// not logged in for the host, or prints nothing. A single `gh auth token`
-/// invocation both detects availability and yields the token.
+/// Ask the `gh` CLI for a token for `host`. Returns None if `gh` is not on `PATH`, not logged in
+/// for the host, or prints nothing. The answer is cached per host for the life of the process, so
+/// the several clients a `sync` builds spawn `gh` at most once.
 fn gh_auth_token(host: &str) -> Option<String> {
-    let output = Command::new("gh")
+    static CACHE: std::sync::Mutex<Option<std::collections::HashMap<String, Option<String>>>> =
+        std::sync::Mutex::new(None);
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    cache
+        .get_or_insert_with(Default::default)
+        .entry(host.to_string())
+        .or_insert_with(|| {
+            let path = std::env::var_os("PATH")?;
+            let gh = find_executable(&path, "gh")?;
+            gh_auth_token_uncached(&gh, host)
+        })
+        .clone()
+}
+
+fn gh_auth_token_uncached(gh: &Path, host: &str) -> Option<String> {
+    let output = Command::new(gh)
         .args(["auth", "token", "--hostname", host])
         .output()
-        .ok()?; // command-not-found -> None
+        .ok()?;
     if !output.status.success() {
         return None; // not logged in for host -> None
     }
@@ -1364,6 +2244,17 @@ fn gh_auth_token(host: &str) -> Option<String> {
     if token.is_empty() { None } else { Some(token) }
 }
 
+/// Find `name` in the directories of a `PATH`-style list without spawning anything.
+fn find_executable(path: &std::ffi::OsStr, name: &str) -> Option<PathBuf> {
+    std::env::split_paths(path)
+        .flat_map(|dir| {
+            let exe = dir.join(name);
+            let windows_exe = dir.join(format!("{name}.exe"));
+            [exe, windows_exe]
+        })
+        .find(|candidate| candidate.is_file())
+}
+
 /// Resolve the active token and where it came from (PAT-wins order).
 fn resolve_github_auth(host: &str) -> Option<(String, AuthSource)> {
     let env_github_token = std::env::var("GITHUB_TOKEN").ok();
@@ -1427,10 +2318,10 @@ fn resolve_auth_core(
     if let Some(config) = config_file {
         // 4. Host-specific token first
         if let Some(hosts) = &config.hosts
-            && let Some(token) = hosts.get(host)
+            && let Some(token) = hosts.get(host).and_then(HostConfig::token)
         {
             tracing::debug!("Using GitHub token from config file (host-specific)");
-            return Some((token.clone(), AuthSource::ConfigHostToken));
+            return Some((token.to_string(), AuthSource::ConfigHostToken));
         }
         // 5. Default token (PAT) wins over OAuth
         if let Some(token) = config.default_token {
@@ -1466,6 +2357,27 @@ pub fn find_auth_source(host: &str) -> Option<AuthSource> {
     resolve_github_auth(host).map(|(_, src)| src)
 }
 
+/// Token configured for `host` under `hosts:` in the config file. Used by non-GitHub providers,
+/// which share the config file but not GitHub's env/`gh` token sources.
+pub(crate) fn config_host_token(host: &str) -> Option<String> {
+    load_github_config_file()?
+        .hosts?
+        .get(host)
+        .and_then(HostConfig::token)
+        .filter(|token| !token.is_empty())
+        .map(str::to_string)
+}
+
+/// API base URL configured for `host` under `hosts:` (e.g. a GitHub Enterprise Server reached
+/// through a proxy), without a trailing `/`. `None` means the provider's default URL for the host.
+pub(crate) fn config_host_api_base(host: &str) -> Option<String> {
+    load_github_config_file()?
+        .hosts?
+        .get(host)?
+        .api_base()
+        .map(str::to_string)
+}
+
 /// Find a GitHub token from various sources.
 fn find_github_config(host: &str) -> Result<String, GitHubError> {
     match resolve_github_auth(host) {
@@ -1479,7 +2391,7 @@ fn find_github_config(host: &str) -> Result<String, GitHubError> {
 #[serde(deny_unknown_fields)]
 struct GitHubConfigFile {
     default_token: Option<String>,
-    hosts: Option<std::collections::HashMap<String, String>>,
+    hosts: Option<std::collections::HashMap<String, HostConfig>>,
     /// GitHub usernames whose PRs should be displayed prominently in status. Three states:
     /// **absent** (`None`) → default to filtering to your own login; **`[]`** → show everyone
     /// (filtering off); **`[a, b]`** → filter to exactly those authors. When filtering is active,
@@ -1499,6 +2411,18 @@ struct GitHubConfigFile {
     /// Add `--no-verify` to pushes performed by `git stack restack --push`.
     #[serde(default, skip_serializing_if = "std::ops::Not::not")]
     restack_push_no_verify: bool,
+    /// Keep PRs whose head lives in a fork instead of filtering them out of sync and status.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    include_forks: bool,
+    /// Days a closed PR stays in the PR cache after its last update; `0` keeps them forever.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    closed_pr_retention_days: Option<u32>,
+    /// Seconds to wait for a connection to the API before giving up (default 10).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    connect_timeout_secs: Option<u64>,
+    /// Seconds to wait on each read of an API response before giving up (default 30).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    read_timeout_secs: Option<u64>,
     /// OAuth device-flow token (distinct from `default_token`, which holds a PAT).
     #[serde(skip_serializing_if = "Option::is_none")]
     oauth_token: Option<String>,
@@ -1507,6 +2431,45 @@ struct GitHubConfigFile {
     oauth_scope: Option<String>,
 }
 
+/// One `hosts:` entry: either just the host's token, or a map that can also override the API base
+/// URL. Serializes back in whichever shape it was read.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+enum HostConfig {
+    Token(String),
+    Settings(HostSettings),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct HostSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+    /// E.g. `https://ghe.example.com/api/v3`; GraphQL goes to the matching `/api/graphql`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    api_base: Option<String>,
+}
+
+impl HostConfig {
+    fn token(&self) -> Option<&str> {
+        match self {
+            HostConfig::Token(token) => Some(token),
+            HostConfig::Settings(settings) => settings.token.as_deref(),
+        }
+    }
+
+    fn api_base(&self) -> Option<&str> {
+        match self {
+            HostConfig::Token(_) => None,
+            HostConfig::Settings(settings) => settings
+                .api_base
+                .as_deref()
+                .map(|url| url.trim_end_matches('/'))
+                .filter(|url| !url.is_empty()),
+        }
+    }
+}
+
 /// Get path to GitHub config file
 fn get_github_config_path() -> Result<PathBuf> {
     let base_dirs = xdg::BaseDirectories::with_prefix("git-stack");
@@ -1823,6 +2786,7 @@ impl From<&PullRequest> for CachedPullRequest {
                 repo: pr.base.repo.as_ref().map(|r| CachedPrRepoRef {
                     full_name: r.full_name.clone(),
                 }),
+                label: pr.base.label.clone(),
             },
             head: CachedPrBranchRef {
                 ref_name: pr.head.ref_name.clone(),
@@ -1830,6 +2794,7 @@ impl From<&PullRequest> for CachedPullRequest {
                 repo: pr.head.repo.as_ref().map(|r| CachedPrRepoRef {
                     full_name: r.full_name.clone(),
                 }),
+                label: pr.head.label.clone(),
             },
             user: CachedPrUser {
                 login: pr.user.login.clone(),
@@ -1855,6 +2820,7 @@ impl From<&CachedPullRequest> for PullRequest {
                 repo: cached.base.repo.as_ref().map(|r| PrRepoRef {
                     full_name: r.full_name.clone(),
                 }),
+                label: cached.base.label.clone(),
             },
             head: PrBranchRef {
                 ref_name: cached.head.ref_name.clone(),
@@ -1862,6 +2828,7 @@ impl From<&CachedPullRequest> for PullRequest {
                 repo: cached.head.repo.as_ref().map(|r| PrRepoRef {
                     full_name: r.full_name.clone(),
                 }),
+                label: cached.head.label.clone(),
             },
             user: PrUser {
                 login: cached.user.login.clone(),
@@ -1880,6 +2847,242 @@ mod tests {
 
     use std::cell::Cell;
 
+    fn headers<'a>(pairs: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<&'a str> {
+        move |name| pairs.iter().find(|(k, _)| *k == name).map(|(_, v)| *v)
+    }
+
+    #[test]
+    fn closed_pr_limits_stop_at_the_count_or_date() {
+        let pr = |updated_at: &str| {
+            let mut pr = PullRequest::from(&cached_pr("feature", "me", false));
+            pr.updated_at = updated_at.to_string();
+            pr
+        };
+        let recent = pr("2024-06-01T00:00:00Z");
+        let old = pr("2023-06-01T00:00:00Z");
+
+        let unbounded = ClosedPrLimits::default();
+        assert!(unbounded.admits(10_000, &old));
+
+        let limited = ClosedPrLimits {
+            limit: Some(2),
+            since: None,
+        };
+        assert!(limited.admits(1, &old));
+        assert!(!limited.admits(2, &recent));
+
+        let since = ClosedPrLimits {
+            limit: None,
+            since: Some("2024-01-01T00:00:00Z".to_string()),
+        };
+        assert!(since.admits(0, &recent));
+        assert!(!since.admits(0, &old));
+    }
+
+    #[test]
+    fn merge_settings_left_out_count_as_allowed() {
+        let settings: RepoMergeSettings = serde_json::from_str(
+            r#"{"allow_merge_commit": false, "allow_squash_merge": true, "allow_rebase_merge": false}"#,
+        )
+        .unwrap();
+        assert_eq!(settings.allowed(), [MergeMethod::Squash]);
+
+        // Without push access GitHub omits the settings entirely.
+        let settings: RepoMergeSettings = serde_json::from_str("{}").unwrap();
+        assert_eq!(
+            settings.allowed(),
+            [MergeMethod::Merge, MergeMethod::Squash, MergeMethod::Rebase]
+        );
+    }
+
+    #[test]
+    fn check_runs_and_statuses_are_counted_together() {
+        let runs: Vec<CheckRun> = serde_json::from_value(serde_json::json!([
+            { "status": "completed", "conclusion": "success" },
+            { "status": "completed", "conclusion": "skipped" },
+            { "status": "completed", "conclusion": "failure" },
+            { "status": "in_progress", "conclusion": null }
+        ]))
+        .unwrap();
+        let statuses: Vec<CommitStatus> = serde_json::from_value(serde_json::json!([
+            { "state": "success" },
+            { "state": "error" },
+            { "state": "pending" }
+        ]))
+        .unwrap();
+        assert_eq!(
+            count_checks(&runs, &statuses),
+            CheckCounts {
+                passing: 3,
+                failing: 2,
+                pending: 2
+            }
+        );
+    }
+
+    #[test]
+    fn review_decision_uses_each_reviewers_latest_verdict() {
+        fn reviews(list: &[(&str, &str)]) -> Vec<PrReview> {
+            list.iter()
+                .map(|(login, state)| PrReview {
+                    user: Some(PrUser {
+                        login: login.to_string(),
+                    }),
+                    state: state.to_string(),
+                })
+                .collect()
+        }
+        assert_eq!(review_decision(&[]), ReviewDecision::ReviewRequired);
+        assert_eq!(
+            review_decision(&reviews(&[("ann", "COMMENTED")])),
+            ReviewDecision::ReviewRequired
+        );
+        // Ann's later approval replaces her change request; a comment doesn't undo it.
+        assert_eq!(
+            review_decision(&reviews(&[
+                ("ann", "CHANGES_REQUESTED"),
+                ("ann", "APPROVED"),
+                ("ann", "COMMENTED"),
+            ])),
+            ReviewDecision::Approved
+        );
+        assert_eq!(
+            review_decision(&reviews(&[
+                ("ann", "APPROVED"),
+                ("bob", "CHANGES_REQUESTED")
+            ])),
+            ReviewDecision::ChangesRequested
+        );
+        assert_eq!(
+            review_decision(&reviews(&[
+                ("bob", "CHANGES_REQUESTED"),
+                ("bob", "DISMISSED")
+            ])),
+            ReviewDecision::ReviewRequired
+        );
+    }
+
+    #[test]
+    fn comment_upsert_targets_the_first_marked_comment() {
+        let comment = |id, body: &str| IssueComment {
+            id,
+            body: body.to_string(),
+        };
+        let marker = "<!-- m -->";
+        let comments = [
+            comment(1, "LGTM"),
+            comment(2, "<!-- m -->\nold stack"),
+            comment(3, "<!-- m -->\nduplicate"),
+        ];
+        assert_eq!(
+            plan_comment_upsert(&comments, marker, "<!-- m -->\nnew stack"),
+            Ok(Some(2))
+        );
+        assert_eq!(
+            plan_comment_upsert(&comments, marker, "<!-- m -->\nold stack\n"),
+            Err(CommentUpsert::Unchanged)
+        );
+        assert_eq!(
+            plan_comment_upsert(&comments[..1], marker, "<!-- m -->\nnew stack"),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn rate_limit_reset_reads_github_and_gitlab_headers() {
+        let now = 1_000;
+        // GitHub primary limit: 403 with the quota exhausted.
+        assert_eq!(
+            rate_limit_reset(
+                403,
+                headers(&[
+                    ("x-ratelimit-remaining", "0"),
+                    ("x-ratelimit-reset", "1600")
+                ]),
+                now
+            ),
+            Some(1600)
+        );
+        // Secondary limit: Retry-After seconds win over the reset timestamp.
+        assert_eq!(
+            rate_limit_reset(
+                403,
+                headers(&[("retry-after", "30"), ("x-ratelimit-reset", "1600")]),
+                now
+            ),
+            Some(1030)
+        );
+        // GitLab: 429 with RateLimit-Reset.
+        assert_eq!(
+            rate_limit_reset(429, headers(&[("ratelimit-reset", "1200")]), now),
+            Some(1200)
+        );
+        assert_eq!(rate_limit_reset(429, headers(&[]), now), Some(now));
+        // A 403 with quota to spare is a permissions problem, not a rate limit.
+        assert_eq!(
+            rate_limit_reset(403, headers(&[("x-ratelimit-remaining", "42")]), now),
+            None
+        );
+        assert_eq!(
+            rate_limit_reset(200, headers(&[("retry-after", "5")]), now),
+            None
+        );
+    }
+
+    #[test]
+    fn rate_limit_delay_backs_off_and_caps() {
+        use std::time::Duration;
+        assert_eq!(rate_limit_delay(1_000, 1_000, 1), Duration::from_secs(2));
+        assert_eq!(rate_limit_delay(1_000, 1_000, 3), Duration::from_secs(8));
+        assert_eq!(rate_limit_delay(1_030, 1_000, 1), Duration::from_secs(30));
+        assert_eq!(rate_limit_delay(99_999, 1_000, 1), RATE_LIMIT_MAX_WAIT);
+    }
+
+    #[test]
+    fn retry_rate_limited_retries_then_gives_up() {
+        let calls = Cell::new(0);
+        let waits = std::cell::RefCell::new(Vec::new());
+        let result = retry_rate_limited(
+            || {
+                calls.set(calls.get() + 1);
+                if calls.get() < 3 {
+                    Err(GitHubError::RateLimited { reset_at: 0 })
+                } else {
+                    Ok("page")
+                }
+            },
+            || 0,
+            |delay, attempt| waits.borrow_mut().push((delay.as_secs(), attempt)),
+        );
+        assert_eq!(result.unwrap(), "page");
+        assert_eq!(*waits.borrow(), vec![(2, 1), (4, 2)]);
+
+        calls.set(0);
+        let result: Result<(), _> = retry_rate_limited(
+            || {
+                calls.set(calls.get() + 1);
+                Err(GitHubError::RateLimited { reset_at: 0 })
+            },
+            || 0,
+            |_, _| {},
+        );
+        assert!(matches!(result, Err(GitHubError::RateLimited { .. })));
+        assert_eq!(calls.get(), RATE_LIMIT_MAX_RETRIES as usize + 1);
+
+        // Non-rate-limit errors are not retried.
+        calls.set(0);
+        let result: Result<(), _> = retry_rate_limited(
+            || {
+                calls.set(calls.get() + 1);
+                Err(GitHubError::Unauthorized)
+            },
+            || 0,
+            |_, _| panic!("should not wait"),
+        );
+        assert!(matches!(result, Err(GitHubError::Unauthorized)));
+        assert_eq!(calls.get(), 1);
+    }
+
     #[test]
     fn authors_filter_alias_deserializes() {
         let config: GitHubConfigFile = serde_yaml::from_str("display_authors:\n- x\n").unwrap();
@@ -1945,6 +3148,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn closed_pr_retention_days_deserializes_and_is_not_materialized_when_absent() {
+        let config: GitHubConfigFile = serde_yaml::from_str(
+            "closed_pr_retention_days: 0
+",
+        )
+        .unwrap();
+        assert_eq!(config.closed_pr_retention_days, Some(0));
+
+        let config: GitHubConfigFile = serde_yaml::from_str(
+            "default_token: tok
+",
+        )
+        .unwrap();
+        let yaml = serde_yaml::to_string(&config).unwrap();
+        assert!(!yaml.contains("closed_pr_retention_days"), "{yaml}");
+    }
+
     #[test]
     fn restack_push_no_verify_defaults_to_false_when_absent() {
         let config: GitHubConfigFile = serde_yaml::from_str("default_token: tok\n").unwrap();
@@ -2075,6 +3296,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn find_executable_searches_path_entries_in_order() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        fs::write(second.path().join("gh"), "").unwrap();
+        let path = std::env::join_paths([first.path(), second.path()]).unwrap();
+        assert_eq!(find_executable(&path, "gh"), Some(second.path().join("gh")));
+
+        fs::write(first.path().join("gh"), "").unwrap();
+        assert_eq!(find_executable(&path, "gh"), Some(first.path().join("gh")));
+        assert_eq!(find_executable(&path, "not-installed"), None);
+        // A directory named like the binary isn't it.
+        fs::create_dir(second.path().join("git-stack-dir")).unwrap();
+        assert_eq!(find_executable(&path, "git-stack-dir"), None);
+    }
+
     #[test]
     fn no_token_when_gh_also_empty() {
         let result = resolve_auth_core("github.com", None, None, None, None, |_| None);
@@ -2110,6 +3347,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn host_entries_take_a_token_or_settings() {
+        let config: GitHubConfigFile = serde_yaml::from_str(
+            "hosts:\n  github.com: tok\n  ghe.example.com:\n    token: ghetok\n    api_base: https://proxy.example.com/ghe/api/v3/\n  ghe2.example.com:\n    api_base: https://ghe2.example.com/api/v3\n",
+        )
+        .unwrap();
+        let hosts = config.hosts.as_ref().unwrap();
+        assert_eq!(hosts["github.com"].token(), Some("tok"));
+        assert_eq!(hosts["github.com"].api_base(), None);
+        assert_eq!(hosts["ghe.example.com"].token(), Some("ghetok"));
+        assert_eq!(
+            hosts["ghe.example.com"].api_base(),
+            Some("https://proxy.example.com/ghe/api/v3")
+        );
+        assert_eq!(hosts["ghe2.example.com"].token(), None);
+
+        let result = resolve_auth_core("ghe.example.com", None, None, None, Some(config), |_| None);
+        assert_eq!(
+            result,
+            Some(("ghetok".to_string(), AuthSource::ConfigHostToken))
+        );
+
+        // The auth write-back paths keep each entry's shape.
+        let config: GitHubConfigFile = serde_yaml::from_str(
+            "hosts:\n  a.example.com: tok\n  b.example.com:\n    api_base: https://b.example.com/api/v3\n",
+        )
+        .unwrap();
+        let yaml = serde_yaml::to_string(&config).unwrap();
+        assert!(yaml.contains("a.example.com: tok"), "{yaml}");
+        assert!(
+            yaml.contains("api_base: https://b.example.com/api/v3"),
+            "{yaml}"
+        );
+        assert!(!yaml.contains("    token"), "{yaml}");
+
+        assert!(
+            serde_yaml::from_str::<GitHubConfigFile>(
+                "hosts:\n  a.example.com:\n    api_url: https://a.example.com\n"
+            )
+            .is_err()
+        );
+    }
+
     fn cached_pr(branch: &str, login: &str, from_fork: bool) -> CachedPullRequest {
         // Non-fork: head repo full_name matches base repo full_name. Fork: head repo missing.
         let head_repo = if from_fork {
@@ -2130,11 +3410,13 @@ mod tests {
                 repo: Some(CachedPrRepoRef {
                     full_name: "acme/app".to_string(),
                 }),
+                label: None,
             },
             head: CachedPrBranchRef {
                 ref_name: branch.to_string(),
                 sha: "head".to_string(),
                 repo: head_repo,
+                label: None,
             },
             user: CachedPrUser {
                 login: login.to_string(),
@@ -2146,6 +3428,105 @@ mod tests {
         }
     }
 
+    #[test]
+    fn merge_evicts_closed_prs_older_than_the_retention_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache =
+            crate::pr_cache::PrCacheHandle::open_at(&dir.path().join("pr_cache.redb")).unwrap();
+        // An old entry, e.g. for a branch that was since renamed or deleted.
+        let old = cached_pr("renamed-away", "alice", false);
+        cache
+            .commit_fresh_prs(
+                "acme/app",
+                vec![("renamed-away", &old)].into_iter(),
+                Some(old.updated_at.as_str()),
+            )
+            .unwrap();
+
+        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        let recent = CachedPullRequest {
+            updated_at: now.clone(),
+            ..cached_pr("recent", "alice", false)
+        };
+        let fresh =
+            std::collections::HashMap::from([("recent".to_string(), PullRequest::from(&recent))]);
+
+        let result = merge_closed_prs_into_cache(
+            "acme/app",
+            &cache,
+            Some(old.updated_at.clone()),
+            &fresh,
+            Some(chrono::TimeDelta::days(30)),
+            ClosedPrCacheMode::Incremental,
+        );
+
+        assert!(result.prs.contains_key("recent"));
+        assert!(!result.prs.contains_key("renamed-away"));
+        let cached = cache.closed_prs_for_repo("acme/app").unwrap();
+        assert!(cached.contains_key("recent"));
+        assert!(!cached.contains_key("renamed-away"));
+        assert_eq!(cache.watermark("acme/app").unwrap(), Some(now));
+    }
+
+    #[test]
+    fn rebuild_replaces_the_cache_only_when_persisting() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache =
+            crate::pr_cache::PrCacheHandle::open_at(&dir.path().join("pr_cache.redb")).unwrap();
+        let old = cached_pr("gone", "alice", false);
+        cache
+            .commit_fresh_prs(
+                "acme/app",
+                vec![("gone", &old)].into_iter(),
+                Some("2030-01-01T00:00:00Z"),
+            )
+            .unwrap();
+        let fresh = std::collections::HashMap::from([(
+            "refetched".to_string(),
+            PullRequest::from(&cached_pr("refetched", "bob", false)),
+        )]);
+        let cached_branches = || {
+            let mut branches: Vec<String> = cache
+                .closed_prs_for_repo("acme/app")
+                .unwrap()
+                .into_keys()
+                .collect();
+            branches.sort();
+            branches
+        };
+
+        // A dry run sees only the fresh PRs and leaves the cache as it was.
+        let result = merge_closed_prs_into_cache(
+            "acme/app",
+            &cache,
+            None,
+            &fresh,
+            None,
+            ClosedPrCacheMode::Rebuild { persist: false },
+        );
+        assert_eq!(result.prs.keys().collect::<Vec<_>>(), ["refetched"]);
+        assert_eq!(cached_branches(), ["gone"]);
+        assert_eq!(
+            cache.watermark("acme/app").unwrap().as_deref(),
+            Some("2030-01-01T00:00:00Z")
+        );
+
+        // Otherwise the fresh PRs and their watermark replace the cached ones, even an older one.
+        merge_closed_prs_into_cache(
+            "acme/app",
+            &cache,
+            None,
+            &fresh,
+            None,
+            ClosedPrCacheMode::Rebuild { persist: true },
+        );
+        assert_eq!(cached_branches(), ["refetched"]);
+        assert_eq!(
+            cache.watermark("acme/app").unwrap().as_deref(),
+            Some("2024-01-01T00:00:00Z")
+        );
+    }
+
     #[test]
     fn pr_list_result_from_cached_excludes_forks_from_prs_but_keeps_authors() {
         let mut cached = std::collections::HashMap::new();
@@ -2163,6 +3544,36 @@ mod tests {
         assert_eq!(result.all_authors.get("theirs").unwrap(), "bob");
     }
 
+    #[test]
+    fn missing_head_repo_falls_back_to_the_label_owner() {
+        let with_label = |label: Option<&str>| {
+            let mut pr = cached_pr("feature", "alice", true);
+            pr.head.label = label.map(str::to_string);
+            PullRequest::from(&pr)
+        };
+        // Same-repo PR whose head branch was deleted: GitHub nulls `head.repo` but keeps the label.
+        assert!(!with_label(Some("acme:feature")).is_from_fork());
+        // A deleted fork, or no label to go on.
+        assert!(with_label(Some("someone:feature")).is_from_fork());
+        assert!(with_label(None).is_from_fork());
+    }
+
+    #[test]
+    fn include_forks_deserializes_and_survives_serialization() {
+        let config: GitHubConfigFile = serde_yaml::from_str("default_token: tok\n").unwrap();
+        assert!(!config.include_forks);
+        assert!(
+            !serde_yaml::to_string(&config)
+                .unwrap()
+                .contains("include_forks")
+        );
+
+        let config: GitHubConfigFile = serde_yaml::from_str("include_forks: true\n").unwrap();
+        assert!(config.include_forks);
+        let yaml = serde_yaml::to_string(&config).unwrap();
+        assert!(yaml.contains("include_forks: true"), "{yaml}");
+    }
+
     #[test]
     fn test_parse_ssh_url() {
         let repo = parse_remote_url("git@github.com:owner/repo.git").unwrap();
@@ -2333,6 +3744,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn classify_422_keeps_validation_details() {
+        let body = r#"{"message":"Validation Failed","errors":[{"resource":"PullRequest","code":"custom","message":"A pull request already exists for acme:feature."}],"documentation_url":"https://docs.github.com/rest"}"#;
+        match classify_status_error(422, body) {
+            GitHubError::Api { status, message } => {
+                assert_eq!(status, 422);
+                assert_eq!(
+                    message,
+                    "Validation Failed: A pull request already exists for acme:feature."
+                );
+                assert!(is_pr_already_exists(&message));
+            }
+            other => panic!("expected Api, got {other:?}"),
+        }
+        let body = r#"{"message":"Validation Failed","errors":[{"resource":"PullRequest","field":"base","code":"invalid"}]}"#;
+        match classify_status_error(422, body) {
+            GitHubError::Api { message, .. } => {
+                assert_eq!(message, "Validation Failed");
+                assert!(!is_pr_already_exists(&message));
+            }
+            other => panic!("expected Api, got {other:?}"),
+        }
+    }
+
     #[test]
     fn parse_forbidden_org_missing_returns_none() {
         // Marker present but the preceding token isn't a plausible login.
@@ -2372,6 +3807,82 @@ mod tests {
         })
     }
 
+    #[test]
+    fn a_stalled_response_times_out() {
+        // Accepts the connection but never answers.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let api_base = format!("http://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            std::thread::sleep(std::time::Duration::from_secs(1));
+            drop(stream);
+        });
+
+        let client = GitHubClient::with_timeouts(
+            GitHubConfig {
+                token: "t".to_string(),
+                api_base,
+            },
+            HttpTimeouts {
+                connect: std::time::Duration::from_secs(1),
+                read: std::time::Duration::from_millis(200),
+            },
+        );
+        let started = std::time::Instant::now();
+        match client.whoami() {
+            Err(GitHubError::Network(message)) => assert!(
+                message.contains("timed out while waiting for the response"),
+                "{message}"
+            ),
+            other => panic!("expected a timeout, got {other:?}"),
+        }
+        // The read timeout gave up, not the server hanging up.
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn the_read_timeout_bounds_the_whole_body() {
+        // Answers at once, then trickles the body out a byte at a time: each byte comes inside the
+        // read timeout, but the body as a whole takes far longer.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let api_base = format!("http://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            use std::io::Write;
+            let (mut stream, _) = listener.accept().unwrap();
+            let body = "{\"login\":\"octocat\"}";
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+                body.len()
+            );
+            for byte in body.bytes() {
+                std::thread::sleep(std::time::Duration::from_millis(200));
+                if stream.write_all(&[byte]).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let client = GitHubClient::with_timeouts(
+            GitHubConfig {
+                token: "t".to_string(),
+                api_base,
+            },
+            HttpTimeouts {
+                connect: std::time::Duration::from_secs(1),
+                read: std::time::Duration::from_millis(300),
+            },
+        );
+        match client.whoami() {
+            Err(GitHubError::Network(message)) => {
+                assert!(message.contains("request timed out while"), "{message}")
+            }
+            other => panic!("expected a timeout, got {other:?}"),
+        }
+        server.join().unwrap();
+    }
+
     #[test]
     fn build_author_search_query_has_repo_and_per_author_qualifier() {
         let q = build_author_search_query(&test_repo(), &["alice".to_string(), "bob".to_string()]);
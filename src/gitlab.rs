@@ -0,0 +1,800 @@
+//! GitLab merge-request client for git-stack PR integration.
+//!
+//! Implements `RemoteProvider` over GitLab's REST v4 API. Merge requests are mapped onto the
+//! GitHub-shaped `PullRequest` (`iid` → `number`, `source_branch`/`target_branch` →
+//! `head`/`base`, `author.username` → `user.login`), so the sync planner and PR caches don't
+//! care which provider produced them.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    github::{
+        CheckCounts, ClosedPrCacheMode, ClosedPrLimits, ClosedPrList, CommentUpsert,
+        CreatePrRequest, GitHubError, IssueComment, MergeMethod, PrBranchRef, PrListResult,
+        PrReadiness, PrRepoRef, PrState, PrUser, PullRequest, RepoIdentifier, ReviewDecision,
+        UpdatePrRequest, cached_watermark, check_status, closed_pr_retention, config_host_api_base,
+        config_host_token, include_forks, merge_closed_prs_into_cache, plan_comment_upsert,
+        read_checked, transport_error, with_rate_limit_retry,
+    },
+    provider::RemoteProvider,
+    read_only,
+    stats::GitBenchmark,
+};
+
+/// GitLab API client
+pub struct GitLabClient {
+    token: String,
+    api_base: String,
+    agent: ureq::Agent,
+}
+
+/// Merge request as returned by the GitLab API (only the fields git-stack uses).
+#[derive(Debug, Clone, Deserialize)]
+struct MergeRequest {
+    iid: u64,
+    /// `opened`, `closed`, `merged`, or `locked`.
+    state: String,
+    title: String,
+    web_url: String,
+    source_branch: String,
+    target_branch: String,
+    #[serde(default)]
+    sha: Option<String>,
+    author: MergeRequestAuthor,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    merged_at: Option<String>,
+    updated_at: String,
+    source_project_id: u64,
+    target_project_id: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MergeRequestAuthor {
+    username: String,
+}
+
+/// A job in a merge request's head pipeline.
+#[derive(Debug, Clone, Deserialize)]
+struct PipelineJob {
+    /// `success`, `failed`, `canceled`, `skipped`, `manual`, `running`, `pending`, ...
+    status: String,
+    #[serde(default)]
+    allow_failure: bool,
+}
+
+/// Count pipeline jobs the way `github::count_checks` counts check runs. Skipped jobs and failed
+/// jobs that are allowed to fail pass; manual jobs that haven't been started aren't counted.
+fn count_pipeline_jobs(jobs: &[PipelineJob]) -> CheckCounts {
+    let mut counts = CheckCounts::default();
+    for job in jobs {
+        match job.status.as_str() {
+            "success" | "skipped" => counts.passing += 1,
+            "failed" if job.allow_failure => counts.passing += 1,
+            "failed" | "canceled" => counts.failing += 1,
+            "manual" => {}
+            _ => counts.pending += 1,
+        }
+    }
+    counts
+}
+
+/// The merge settings of a project (`GET /projects/:id`).
+#[derive(Debug, Deserialize)]
+struct ProjectMergeSettings {
+    /// `merge`, `rebase_merge`, or `ff`.
+    #[serde(default)]
+    merge_method: Option<String>,
+    /// `never`, `always`, `default_on`, or `default_off`.
+    #[serde(default)]
+    squash_option: Option<String>,
+}
+
+impl ProjectMergeSettings {
+    fn allowed(&self) -> Vec<MergeMethod> {
+        let unsquashed = match self.merge_method.as_deref() {
+            Some("rebase_merge" | "ff") => MergeMethod::Rebase,
+            _ => MergeMethod::Merge,
+        };
+        match self.squash_option.as_deref() {
+            Some("always") => vec![MergeMethod::Squash],
+            Some("never") => vec![unsquashed],
+            _ => vec![unsquashed, MergeMethod::Squash],
+        }
+    }
+}
+
+/// Body for `POST /projects/:id/merge_requests`.
+#[derive(Debug, Serialize)]
+struct CreateMergeRequest<'a> {
+    source_branch: &'a str,
+    target_branch: &'a str,
+    title: &'a str,
+    description: &'a str,
+}
+
+/// Body for `PUT /projects/:id/merge_requests/:iid`.
+#[derive(Debug, Serialize)]
+struct UpdateMergeRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target_branch: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+}
+
+impl MergeRequest {
+    /// Map onto `PullRequest`. A merge request from another project (a fork) gets a head repo
+    /// name that differs from `repo_full_name`, so `PullRequest::is_from_fork` filters it out.
+    fn into_pull_request(self, repo_full_name: &str) -> PullRequest {
+        let head_repo = if self.source_project_id == self.target_project_id {
+            repo_full_name.to_string()
+        } else {
+            format!("project:{}", self.source_project_id)
+        };
+        let merged = self.state == "merged";
+        PullRequest {
+            number: self.iid,
+            state: match self.state.as_str() {
+                "opened" | "locked" => PrState::Open,
+                _ => PrState::Closed,
+            },
+            title: self.title,
+            html_url: self.web_url,
+            base: PrBranchRef {
+                ref_name: self.target_branch,
+                sha: String::new(),
+                repo: Some(PrRepoRef {
+                    full_name: repo_full_name.to_string(),
+                }),
+                label: None,
+            },
+            head: PrBranchRef {
+                ref_name: self.source_branch,
+                sha: self.sha.unwrap_or_default(),
+                repo: Some(PrRepoRef {
+                    full_name: head_repo,
+                }),
+                label: None,
+            },
+            user: PrUser {
+                login: self.author.username,
+            },
+            draft: self.draft,
+            merged,
+            merged_at: self.merged_at,
+            updated_at: utc_timestamp(&self.updated_at),
+        }
+    }
+}
+
+/// `timestamp` in UTC with millisecond precision. GitLab mixes precisions and offsets, so only
+/// timestamps in this one form compare chronologically as strings (as the watermark and
+/// retention code does). Unparseable values pass through unchanged.
+fn utc_timestamp(timestamp: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(timestamp).map_or_else(
+        |_| timestamp.to_string(),
+        |at| {
+            at.with_timezone(&chrono::Utc)
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+        },
+    )
+}
+
+/// Whether `updated_at` is at or before `watermark`, compared as times. A watermark written before
+/// timestamps were normalized may still carry an offset.
+fn at_or_before(updated_at: &str, watermark: &str) -> bool {
+    match (
+        chrono::DateTime::parse_from_rfc3339(updated_at),
+        chrono::DateTime::parse_from_rfc3339(watermark),
+    ) {
+        (Ok(updated_at), Ok(watermark)) => updated_at <= watermark,
+        _ => updated_at <= watermark,
+    }
+}
+
+/// Where the active GitLab token was resolved from. The environment wins over the config file.
+#[derive(Debug, PartialEq, Eq)]
+pub enum TokenSource {
+    EnvGitlabToken,
+    ConfigHostToken,
+}
+
+/// The GitLab token for `host` and its source, as [`GitLabClient::from_env`] uses it.
+pub fn resolve_token(host: &str) -> Option<(String, TokenSource)> {
+    if let Ok(token) = std::env::var("GITLAB_TOKEN")
+        && !token.is_empty()
+    {
+        return Some((token, TokenSource::EnvGitlabToken));
+    }
+    config_host_token(host).map(|token| (token, TokenSource::ConfigHostToken))
+}
+
+/// Percent-encode a path segment or query value (GitLab wants `group%2Fproject` project ids).
+fn encode_component(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    out
+}
+
+impl GitLabClient {
+    pub fn new(token: String, api_base: String) -> Self {
+        let agent = crate::github::http_timeouts().agent();
+        Self {
+            token,
+            api_base,
+            agent,
+        }
+    }
+
+    /// Load the token from `GITLAB_TOKEN`, then the config file's `hosts.<host>` entry.
+    pub fn from_env(repo_id: &RepoIdentifier) -> Result<Self, GitHubError> {
+        let (token, _) = resolve_token(&repo_id.host).ok_or(GitHubError::NoToken)?;
+        let api_base = config_host_api_base(&repo_id.host)
+            .unwrap_or_else(|| format!("https://{}/api/v4", repo_id.host));
+        Ok(Self::new(token, api_base))
+    }
+
+    fn project_url(&self, repo: &RepoIdentifier) -> String {
+        format!(
+            "{}/projects/{}",
+            self.api_base,
+            encode_component(&repo.full_name())
+        )
+    }
+
+    fn auth_headers<B>(&self, rb: ureq::RequestBuilder<B>) -> ureq::RequestBuilder<B> {
+        rb.header("PRIVATE-TOKEN", &self.token)
+            .header("User-Agent", "git-stack")
+    }
+
+    fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+        bench: &'static str,
+    ) -> Result<T, GitHubError> {
+        let _bench = GitBenchmark::start(bench);
+        with_rate_limit_retry(|| {
+            let response = self
+                .auth_headers(self.agent.get(url))
+                .call()
+                .map_err(transport_error)?;
+            read_checked(response)
+        })
+    }
+
+    fn post_json<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+        body: &impl Serialize,
+        bench: &'static str,
+    ) -> Result<T, GitHubError> {
+        if let Some(message) = read_only::blocked_request("POST", url) {
+            return Err(GitHubError::ReadOnly(message));
+        }
+        let _bench = GitBenchmark::start(bench);
+        with_rate_limit_retry(|| {
+            let response = self
+                .auth_headers(self.agent.post(url))
+                .send_json(body)
+                .map_err(transport_error)?;
+            read_checked(response)
+        })
+    }
+
+    fn put_json<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+        body: &impl Serialize,
+        bench: &'static str,
+    ) -> Result<T, GitHubError> {
+        if let Some(message) = read_only::blocked_request("PUT", url) {
+            return Err(GitHubError::ReadOnly(message));
+        }
+        let _bench = GitBenchmark::start(bench);
+        with_rate_limit_retry(|| {
+            let response = self
+                .auth_headers(self.agent.put(url))
+                .send_json(body)
+                .map_err(transport_error)?;
+            read_checked(response)
+        })
+    }
+
+    fn delete(&self, url: &str, bench: &'static str) -> Result<(), GitHubError> {
+        if let Some(message) = read_only::blocked_request("DELETE", url) {
+            return Err(GitHubError::ReadOnly(message));
+        }
+        let _bench = GitBenchmark::start(bench);
+        with_rate_limit_retry(|| {
+            let mut response = self
+                .auth_headers(self.agent.delete(url))
+                .call()
+                .map_err(transport_error)?;
+            check_status(&mut response)
+        })
+    }
+
+    /// Page through `merge_requests?{query}` until a short page (or `stop` says the page
+    /// crossed a watermark), mapping each merge request to a `PullRequest`.
+    fn list_merge_requests(
+        &self,
+        repo: &RepoIdentifier,
+        query: &str,
+        on_progress: Option<&dyn Fn(usize, usize)>,
+        mut stop: impl FnMut(&PullRequest) -> bool,
+    ) -> Result<Vec<PullRequest>, GitHubError> {
+        let full_name = repo.full_name();
+        let per_page = 100;
+        let mut page = 1;
+        let mut all = Vec::new();
+        loop {
+            let url = format!(
+                "{}/merge_requests?{}&per_page={}&page={}",
+                self.project_url(repo),
+                query,
+                per_page,
+                page
+            );
+            let mrs: Vec<MergeRequest> = self.get_json(&url, "gitlab:list-mrs")?;
+            let count = mrs.len();
+            let mut hit_stop = false;
+            for mr in mrs {
+                let pr = mr.into_pull_request(&full_name);
+                hit_stop |= stop(&pr);
+                all.push(pr);
+            }
+            if let Some(callback) = on_progress {
+                callback(page, all.len());
+            }
+            if hit_stop || count < per_page {
+                break;
+            }
+            page += 1;
+        }
+        Ok(all)
+    }
+}
+
+impl RemoteProvider for GitLabClient {
+    fn whoami(&self) -> Result<String, GitHubError> {
+        #[derive(Deserialize)]
+        struct User {
+            username: String,
+        }
+        let url = format!("{}/user", self.api_base);
+        Ok(self.get_json::<User>(&url, "gitlab:whoami")?.username)
+    }
+
+    fn get_pr(&self, repo: &RepoIdentifier, pr_number: u64) -> Result<PullRequest, GitHubError> {
+        let url = format!("{}/merge_requests/{}", self.project_url(repo), pr_number);
+        let mr: MergeRequest = self.get_json(&url, "gitlab:get-mr")?;
+        Ok(mr.into_pull_request(&repo.full_name()))
+    }
+
+    fn find_pr_for_branch(
+        &self,
+        repo: &RepoIdentifier,
+        branch: &str,
+    ) -> Result<Option<PullRequest>, GitHubError> {
+        let url = format!(
+            "{}/merge_requests?state=opened&source_branch={}",
+            self.project_url(repo),
+            encode_component(branch)
+        );
+        let mrs: Vec<MergeRequest> = self.get_json(&url, "gitlab:find-mr")?;
+        let full_name = repo.full_name();
+        Ok(mrs
+            .into_iter()
+            .map(|mr| mr.into_pull_request(&full_name))
+            .find(|pr| !pr.is_from_fork()))
+    }
+
+    fn create_pr(
+        &self,
+        repo: &RepoIdentifier,
+        request: CreatePrRequest,
+    ) -> Result<PullRequest, GitHubError> {
+        // GitLab marks drafts by title prefix rather than a flag.
+        let title = if request.draft == Some(true) {
+            format!("Draft: {}", request.title)
+        } else {
+            request.title.to_string()
+        };
+        let url = format!("{}/merge_requests", self.project_url(repo));
+        let body = CreateMergeRequest {
+            source_branch: request.head,
+            target_branch: request.base,
+            title: &title,
+            description: request.body,
+        };
+        let mr: MergeRequest = self.post_json(&url, &body, "gitlab:create-mr")?;
+        Ok(mr.into_pull_request(&repo.full_name()))
+    }
+
+    fn update_pr(
+        &self,
+        repo: &RepoIdentifier,
+        pr_number: u64,
+        request: UpdatePrRequest,
+    ) -> Result<PullRequest, GitHubError> {
+        let url = format!("{}/merge_requests/{}", self.project_url(repo), pr_number);
+        let body = UpdateMergeRequest {
+            target_branch: request.base,
+            title: request.title,
+            description: request.body,
+        };
+        let mr: MergeRequest = self.put_json(&url, &body, "gitlab:update-mr")?;
+        Ok(mr.into_pull_request(&repo.full_name()))
+    }
+
+    fn list_open_prs(
+        &self,
+        repo: &RepoIdentifier,
+        on_progress: Option<&dyn Fn(usize, usize)>,
+    ) -> Result<PrListResult, GitHubError> {
+        let all = self.list_merge_requests(repo, "state=opened", on_progress, |_| false)?;
+        let all_authors = all
+            .iter()
+            .map(|pr| (pr.head.ref_name.clone(), pr.user.login.clone()))
+            .collect();
+        let keep_forks = include_forks();
+        let prs = all
+            .into_iter()
+            .filter(|pr| keep_forks || !pr.is_from_fork())
+            .map(|pr| (pr.head.ref_name.clone(), pr))
+            .collect();
+        Ok(PrListResult { prs, all_authors })
+    }
+
+    fn list_closed_prs_with_cache(
+        &self,
+        repo: &RepoIdentifier,
+        cache: &crate::pr_cache::PrCacheHandle,
+        limits: &ClosedPrLimits,
+        mode: ClosedPrCacheMode,
+        on_progress: Option<&dyn Fn(usize, usize)>,
+    ) -> Result<ClosedPrList, GitHubError> {
+        let repo_key = repo.full_name();
+        let watermark = cached_watermark(cache, &repo_key, mode);
+
+        // GitLab has no single "closed or merged" state filter, so page through everything by
+        // recency and keep the non-open ones, stopping at the watermark or `limits`.
+        let (mut closed_seen, mut hit_watermark, mut truncated) = (0, false, false);
+        let fresh = self.list_merge_requests(
+            repo,
+            "state=all&order_by=updated_at&sort=desc",
+            on_progress,
+            |pr| {
+                if pr.state == PrState::Closed {
+                    if !limits.admits(closed_seen, pr) {
+                        truncated = !hit_watermark;
+                        return true;
+                    }
+                    closed_seen += 1;
+                }
+                hit_watermark |= watermark
+                    .as_deref()
+                    .is_some_and(|wm| at_or_before(&pr.updated_at, wm));
+                hit_watermark
+            },
+        )?;
+        // The last page runs past where the fetch stopped; apply `limits` again to drop the rest.
+        let mut kept = 0;
+        let keep_forks = include_forks();
+        let fresh_prs: std::collections::HashMap<String, PullRequest> = fresh
+            .into_iter()
+            .filter(|pr| pr.state == PrState::Closed)
+            .filter(|pr| {
+                let admitted = limits.admits(kept, pr);
+                kept += usize::from(admitted);
+                admitted
+            })
+            .filter(|pr| keep_forks || !pr.is_from_fork())
+            .map(|pr| (pr.head.ref_name.clone(), pr))
+            .collect();
+
+        Ok(ClosedPrList {
+            fetched: fresh_prs.len(),
+            truncated,
+            result: merge_closed_prs_into_cache(
+                &repo_key,
+                cache,
+                watermark,
+                &fresh_prs,
+                closed_pr_retention(),
+                mode,
+            ),
+        })
+    }
+
+    fn list_open_prs_by_authors(
+        &self,
+        repo: &RepoIdentifier,
+        authors: &[String],
+    ) -> Result<Vec<PullRequest>, GitHubError> {
+        let keep_forks = include_forks();
+        let mut all = Vec::new();
+        for author in authors {
+            let query = format!("state=opened&author_username={}", encode_component(author));
+            all.extend(
+                self.list_merge_requests(repo, &query, None, |_| false)?
+                    .into_iter()
+                    .filter(|pr| keep_forks || !pr.is_from_fork()),
+            );
+        }
+        Ok(all)
+    }
+
+    fn create_or_update_comment(
+        &self,
+        repo: &RepoIdentifier,
+        pr_number: u64,
+        marker: &str,
+        body: &str,
+    ) -> Result<CommentUpsert, GitHubError> {
+        let notes_url = format!(
+            "{}/merge_requests/{}/notes",
+            self.project_url(repo),
+            pr_number
+        );
+        let mut notes: Vec<IssueComment> = Vec::new();
+        let per_page = 100;
+        for page in 1.. {
+            let url = format!("{notes_url}?per_page={per_page}&page={page}");
+            let batch: Vec<IssueComment> = self.get_json(&url, "gitlab:list-notes")?;
+            let count = batch.len();
+            notes.extend(batch);
+            if count < per_page {
+                break;
+            }
+        }
+
+        let request = serde_json::json!({ "body": body });
+        match plan_comment_upsert(&notes, marker, body) {
+            Err(unchanged) => Ok(unchanged),
+            Ok(Some(id)) => {
+                let url = format!("{notes_url}/{id}");
+                self.put_json::<IssueComment>(&url, &request, "gitlab:update-note")?;
+                Ok(CommentUpsert::Updated)
+            }
+            Ok(None) => {
+                self.post_json::<IssueComment>(&notes_url, &request, "gitlab:create-note")?;
+                Ok(CommentUpsert::Created)
+            }
+        }
+    }
+
+    fn pr_readiness(
+        &self,
+        repo: &RepoIdentifier,
+        pr_number: u64,
+    ) -> Result<PrReadiness, GitHubError> {
+        #[derive(Deserialize)]
+        struct MergeStatus {
+            detailed_merge_status: Option<String>,
+            head_pipeline: Option<Pipeline>,
+        }
+        #[derive(Deserialize)]
+        struct Pipeline {
+            id: u64,
+        }
+        #[derive(Deserialize)]
+        struct Approvals {
+            approved: bool,
+        }
+
+        let mr_url = format!("{}/merge_requests/{}", self.project_url(repo), pr_number);
+        let mr: MergeStatus = self.get_json(&mr_url, "gitlab:get-mr")?;
+        let checks = match mr.head_pipeline {
+            Some(pipeline) => {
+                let url = format!(
+                    "{}/pipelines/{}/jobs?per_page=100",
+                    self.project_url(repo),
+                    pipeline.id
+                );
+                count_pipeline_jobs(&self.get_json::<Vec<PipelineJob>>(&url, "gitlab:jobs")?)
+            }
+            None => CheckCounts::default(),
+        };
+        let approvals: Approvals =
+            self.get_json(&format!("{mr_url}/approvals"), "gitlab:approvals")?;
+        Ok(PrReadiness {
+            mergeable: mr.detailed_merge_status,
+            checks,
+            review: if approvals.approved {
+                ReviewDecision::Approved
+            } else {
+                ReviewDecision::ReviewRequired
+            },
+        })
+    }
+
+    /// GitLab picks merge commit vs. fast-forward from the project's merge method, so `Merge` and
+    /// `Rebase` both defer to it; `Squash` squashes.
+    fn merge_pr(
+        &self,
+        repo: &RepoIdentifier,
+        pr_number: u64,
+        head_sha: &str,
+        method: MergeMethod,
+        commit_title: Option<&str>,
+    ) -> Result<(), GitHubError> {
+        let url = format!(
+            "{}/merge_requests/{}/merge",
+            self.project_url(repo),
+            pr_number
+        );
+        let squash = method == MergeMethod::Squash;
+        let mut body = serde_json::json!({ "sha": head_sha, "squash": squash });
+        if let Some(commit_title) = commit_title {
+            let key = if squash {
+                "squash_commit_message"
+            } else {
+                "merge_commit_message"
+            };
+            body[key] = commit_title.into();
+        }
+        self.put_json::<MergeRequest>(&url, &body, "gitlab:merge-mr")?;
+        Ok(())
+    }
+
+    fn delete_branch(&self, repo: &RepoIdentifier, branch: &str) -> Result<(), GitHubError> {
+        let url = format!(
+            "{}/repository/branches/{}",
+            self.project_url(repo),
+            encode_component(branch)
+        );
+        self.delete(&url, "gitlab:delete-branch")
+    }
+
+    /// `Merge` for a project that makes merge commits and `Rebase` for one that rebases or
+    /// fast-forwards, plus or minus `Squash` as its squash option allows.
+    fn allowed_merge_methods(
+        &self,
+        repo: &RepoIdentifier,
+    ) -> Result<Vec<MergeMethod>, GitHubError> {
+        let project: ProjectMergeSettings =
+            self.get_json(&self.project_url(repo), "gitlab:project-settings")?;
+        Ok(project.allowed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merge_request(state: &str, source_project_id: u64) -> MergeRequest {
+        serde_json::from_value(serde_json::json!({
+            "iid": 7,
+            "state": state,
+            "title": "Add feature",
+            "web_url": "https://gitlab.com/group/proj/-/merge_requests/7",
+            "source_branch": "feature",
+            "target_branch": "main",
+            "sha": "abc123",
+            "author": { "username": "alice" },
+            "draft": false,
+            "merged_at": null,
+            "updated_at": "2026-01-01T00:00:00Z",
+            "source_project_id": source_project_id,
+            "target_project_id": 1
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn merge_request_maps_onto_pull_request() {
+        let pr = merge_request("opened", 1).into_pull_request("group/proj");
+        assert_eq!(pr.number, 7);
+        assert_eq!(pr.state, PrState::Open);
+        assert_eq!(pr.head.ref_name, "feature");
+        assert_eq!(pr.head.sha, "abc123");
+        assert_eq!(pr.base.ref_name, "main");
+        assert_eq!(pr.user.login, "alice");
+        assert!(!pr.is_merged());
+        assert!(!pr.is_from_fork());
+    }
+
+    #[test]
+    fn merged_and_closed_states_map_to_closed() {
+        let merged = merge_request("merged", 1).into_pull_request("group/proj");
+        assert_eq!(merged.state, PrState::Closed);
+        assert!(merged.is_merged());
+
+        let closed = merge_request("closed", 1).into_pull_request("group/proj");
+        assert_eq!(closed.state, PrState::Closed);
+        assert!(!closed.is_merged());
+    }
+
+    #[test]
+    fn merge_request_from_other_project_is_a_fork() {
+        let pr = merge_request("opened", 99).into_pull_request("group/proj");
+        assert!(pr.is_from_fork());
+    }
+
+    #[test]
+    fn project_settings_map_to_merge_methods() {
+        let allowed = |json: &str| {
+            serde_json::from_str::<ProjectMergeSettings>(json)
+                .unwrap()
+                .allowed()
+        };
+        assert_eq!(
+            allowed(r#"{"merge_method": "merge", "squash_option": "default_off"}"#),
+            [MergeMethod::Merge, MergeMethod::Squash]
+        );
+        assert_eq!(
+            allowed(r#"{"merge_method": "ff", "squash_option": "never"}"#),
+            [MergeMethod::Rebase]
+        );
+        assert_eq!(
+            allowed(r#"{"merge_method": "merge", "squash_option": "always"}"#),
+            [MergeMethod::Squash]
+        );
+    }
+
+    #[test]
+    fn pipeline_jobs_count_like_checks() {
+        let jobs: Vec<PipelineJob> = serde_json::from_value(serde_json::json!([
+            { "status": "success" },
+            { "status": "skipped" },
+            { "status": "failed", "allow_failure": true },
+            { "status": "failed" },
+            { "status": "canceled" },
+            { "status": "running" },
+            { "status": "manual" }
+        ]))
+        .unwrap();
+        assert_eq!(
+            count_pipeline_jobs(&jobs),
+            CheckCounts {
+                passing: 3,
+                failing: 2,
+                pending: 1
+            }
+        );
+    }
+
+    #[test]
+    fn timestamps_compare_as_times() {
+        assert_eq!(
+            utc_timestamp("2026-01-01T10:00:00.5+02:00"),
+            "2026-01-01T08:00:00.500Z"
+        );
+        assert_eq!(
+            utc_timestamp("2026-01-01T08:00:00Z"),
+            "2026-01-01T08:00:00.000Z"
+        );
+        assert_eq!(utc_timestamp("garbage"), "garbage");
+        // As strings, the offset timestamp sorts after the UTC one, though it's two hours earlier.
+        assert!(at_or_before(
+            "2026-01-01T10:00:00.000+02:00",
+            "2026-01-01T09:00:00Z"
+        ));
+        assert!(!at_or_before(
+            "2026-01-01T09:00:00.001Z",
+            "2026-01-01T10:00:00+01:00"
+        ));
+        assert!(at_or_before(
+            "2026-01-01T09:00:00Z",
+            "2026-01-01T09:00:00.000Z"
+        ));
+    }
+
+    #[test]
+    fn project_ids_and_branches_are_percent_encoded() {
+        assert_eq!(encode_component("group/sub/proj"), "group%2Fsub%2Fproj");
+        assert_eq!(encode_component("feat/a b"), "feat%2Fa%20b");
+        assert_eq!(encode_component("plain-name_1.2~"), "plain-name_1.2~");
+    }
+}
@@ -39,6 +39,11 @@ mod tests {
             "log",
             "note",
             "diff",
+            "graph",
+            "absorb",
+            "split",
+            "fold",
+            "doctor",
             "checkout",
             "mount",
             "delete",
@@ -17,7 +17,7 @@ use std::{
     path::Path,
 };
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, anyhow};
 
 /// An acquired repo-scoped advisory lock. Held for the duration of a mutating
 /// operation; the lock is released when this value is dropped (the file
@@ -61,6 +61,42 @@ impl RepoLock {
     }
 }
 
+/// An exclusive advisory lock on `state.yaml`, taken through a `state.yaml.lock` sidecar (the
+/// state file itself is replaced on save, so it can't anchor the lock). Unlike `RepoLock` this
+/// never waits: a second process finding it held fails fast instead of racing on the shared state
+/// file. Released when dropped, or when the process exits.
+#[must_use = "the lock is released as soon as the guard is dropped"]
+#[derive(Debug)]
+pub(crate) struct StateLock {
+    _file: File,
+}
+
+impl StateLock {
+    /// Try to take the lock for the state file at `state_path`, creating the sidecar if needed.
+    pub(crate) fn try_acquire(state_path: &Path) -> Result<Self> {
+        let mut path = state_path.as_os_str().to_owned();
+        path.push(".lock");
+        let path = std::path::PathBuf::from(path);
+        let file = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("opening {} to lock the state file", path.display()))?;
+
+        match file.try_lock() {
+            Ok(()) => Ok(Self { _file: file }),
+            Err(TryLockError::WouldBlock) => Err(anyhow!(
+                "Another git-stack process is running (it holds {}); try again once it finishes.",
+                path.display()
+            )),
+            Err(TryLockError::Error(e)) => {
+                Err(e).with_context(|| format!("locking {}", path.display()))
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -86,4 +122,23 @@ mod tests {
         drop(guard);
         let _guard2 = RepoLock::acquire(dir.path()).unwrap();
     }
+
+    /// A held `StateLock` makes a second acquirer fail immediately (no waiting), with the
+    /// "another git-stack process" message; dropping it frees the lock.
+    #[test]
+    fn state_lock_fails_fast_on_contention() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_path = dir.path().join("state.yaml");
+
+        let guard = StateLock::try_acquire(&state_path).unwrap();
+        let err = StateLock::try_acquire(&state_path).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Another git-stack process is running")
+        );
+        assert!(dir.path().join("state.yaml.lock").exists());
+
+        drop(guard);
+        let _guard2 = StateLock::try_acquire(&state_path).unwrap();
+    }
 }
@@ -1,15 +1,23 @@
 #![allow(dead_code, unused_imports, unused_variables)]
-use std::{env, fs::canonicalize};
+use std::{
+    cell::RefCell,
+    env,
+    fs::canonicalize,
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+};
 
 use anyhow::{Context, Result, anyhow, bail, ensure};
 use clap::{CommandFactory, Parser, Subcommand};
-use clap_complete::{Shell, generate};
+use clap_complete::Shell;
 use colored::Colorize;
 use git::{
-    after_text, checkout_tracked_branch, git_checkout_main, git_fetch, git_trunk, run_git_status,
+    FetchOptions, after_text, checkout_tracked_branch, git_checkout_main, git_fetch, git_trunk,
+    run_git_status,
 };
 use state::{
-    Branch, PendingRestackOperation, RestackMethod, RestackResume, RestackStep, StackMethod,
+    Branch, BranchMark, PendingRestackOperation, RestackMethod, RestackResume, RestackStep,
+    StackMethod,
 };
 use tracing::level_filters::LevelFilter;
 use tracing_subscriber::{layer::SubscriberExt as _, util::SubscriberInitExt};
@@ -17,36 +25,56 @@ use tracing_subscriber::{layer::SubscriberExt as _, util::SubscriberInitExt};
 use crate::{
     git::run_git,
     git2_ops::{DEFAULT_REMOTE, GitRepo},
+    restack_log::{RestackLog, RestackLogEntry, RestackOutcome},
     state::State,
 };
 
+mod absorb;
+mod branch_name;
+mod completions;
 mod git;
 mod git2_ops;
 mod github;
+mod gitlab;
 mod llms;
 mod lock;
 mod merge_base_cache;
+mod pr_body;
 mod pr_cache;
+mod provider;
+mod read_only;
 mod render;
+mod restack_log;
+mod stack_comment;
 mod state;
 mod stats;
 mod sync;
 mod tui;
+mod watch;
 #[derive(Parser)]
 #[command(author, version, about, infer_subcommands = true)]
 struct Args {
     #[arg(long, short, global = true, help = "Enable verbose output")]
     verbose: bool,
 
-    #[arg(long, global = true, help = "Show git command performance stats")]
+    #[arg(
+        long,
+        alias = "stats",
+        global = true,
+        help = "Show git command performance stats"
+    )]
     benchmark: bool,
 
+    // Not global, so it can't collide with `status --json`; `--stats-json` works anywhere.
+    #[arg(long, help = "Output benchmark stats as JSON (implies --benchmark)")]
+    json: bool,
+
     #[arg(
         long,
         global = true,
         help = "Output benchmark stats as JSON (implies --benchmark)"
     )]
-    json: bool,
+    stats_json: bool,
 
     #[arg(
         long,
@@ -55,6 +83,35 @@ struct Args {
     )]
     show_all: bool,
 
+    #[arg(
+        long,
+        global = true,
+        value_name = "BRANCH",
+        help = "Use BRANCH as this repo's trunk instead of origin/HEAD (remembered for later runs)"
+    )]
+    trunk: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Disable colored output (also honors the NO_COLOR environment variable)"
+    )]
+    no_color: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Fail instead of running any git command or GitHub/GitLab request that would change something"
+    )]
+    read_only: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Recompute LKG parents even if no branch moved since the last run"
+    )]
+    force_refresh: bool,
+
     /// Subcommand to run.
     #[command(subcommand)]
     command: Option<Command>,
@@ -68,9 +125,33 @@ enum Command {
         /// Whether to fetch the latest changes from the remote before showing the status.
         #[arg(long, short, default_value_t = false)]
         fetch: bool,
+        /// Skip every network fetch `status` would otherwise make on its own (the stack's open PRs,
+        /// the stale whole-repo PR refresh, author lookups) and show the cached PR data instead.
+        #[arg(long, alias = "no-pr-refresh", conflicts_with = "fetch")]
+        no_fetch_trunk: bool,
+        /// Print the status tree as JSON on stdout instead of the colored tree (for shell prompts
+        /// and editor integrations).
+        #[arg(long, default_value_t = false)]
+        json: bool,
+        /// Show only branches whose PR is by this author (repeatable), instead of the configured
+        /// `authors_filter`. With `--show-all`, others are dimmed rather than hidden.
+        #[arg(long = "author", value_name = "LOGIN")]
+        authors: Vec<String>,
+        /// Show at most this many levels below the current branch's depth, with a `… (k more)`
+        /// line for what's left out. The current branch's ancestors are always shown.
+        #[arg(long, value_name = "N")]
+        depth: Option<usize>,
+        /// Keep running, redrawing the tree whenever a branch or `HEAD` moves, until Ctrl-C.
+        #[arg(long, conflicts_with = "json")]
+        watch: bool,
     },
     /// Launch interactive TUI mode for branch navigation and checkout.
-    Interactive,
+    Interactive {
+        /// Show only branches whose PR is by this author (repeatable), instead of the configured
+        /// `authors_filter`. With `--show-all`, others are dimmed rather than hidden.
+        #[arg(long = "author", value_name = "LOGIN")]
+        authors: Vec<String>,
+    },
     /// Move up the stack to the parent branch.
     Up,
     /// Move down the stack to a child branch (only if there's exactly one child).
@@ -81,6 +162,9 @@ enum Command {
         /// Open the GitHub config file (github.yaml) instead of the state file.
         #[arg(long, default_value_t = false)]
         config: bool,
+        /// Edit only the current repo's stack tree, checked before it replaces the saved one.
+        #[arg(long, conflicts_with = "config")]
+        repo: bool,
     },
     /// Restack your active branch onto its parent branch.
     Restack {
@@ -93,6 +177,9 @@ enum Command {
         /// Push any changes up to the remote after restacking.
         #[arg(long, short)]
         push: bool,
+        /// Push every restacked branch, even ones whose remote already matches (implies --push).
+        #[arg(long)]
+        push_all: bool,
         /// Recursively restack all ancestors from trunk up to this branch.
         #[arg(long, short = 'a', default_value_t = false)]
         ancestors: bool,
@@ -110,43 +197,243 @@ enum Command {
         /// Abort an in-progress restack and restore the conflicting branch to its original state.
         #[arg(long, default_value_t = false)]
         abort: bool,
+        /// Print which branches would be restacked (and how) without checking out anything or
+        /// changing any refs.
+        #[arg(long, conflicts_with_all = ["continue", "skip", "abort"])]
+        dry_run: bool,
+        /// Stash local changes (untracked files included) before restacking and restore them on
+        /// the original branch afterwards.
+        #[arg(long, conflicts_with_all = ["continue", "skip", "abort", "dry_run"])]
+        autostash: bool,
+        /// Print the path of this run's restack log (each step's source SHA, method and outcome,
+        /// as JSON lines) when it finishes or stops on a conflict.
+        #[arg(long, conflicts_with_all = ["continue", "skip", "abort", "dry_run"])]
+        log: bool,
+        /// Restack the bottom branch onto this ref (a branch, tag, or SHA) instead of its parent,
+        /// then the rest of the stack onto it as usual. With --fetch, resolved after fetching.
+        #[arg(long, value_name = "REF", conflicts_with_all = ["continue", "skip", "abort"])]
+        onto: Option<String>,
     },
     /// Shows the log between the given branch and its parent (git-stack tree) branch.
     Log {
         /// Specifies the branch whose log should be shown. If omitted, the current branch will
         /// be used.
         branch: Option<String>,
+        /// Show every branch in the branch's stack, from trunk down to its leaf, one labeled
+        /// section per branch.
+        #[arg(long)]
+        stack: bool,
     },
     /// Show or edit per-branch notes.
     Note {
-        #[arg(long, short, default_value_t = false)]
+        #[arg(long, short, default_value_t = false, conflicts_with_all = ["append", "all"])]
         edit: bool,
+        /// Append this line to the note without opening an editor.
+        #[arg(long, value_name = "TEXT", conflicts_with = "all")]
+        append: Option<String>,
+        /// Print the first line of every note in the branch's stack.
+        #[arg(long)]
+        all: bool,
         /// Specifies the branch whose note should be shown. If omitted, the current branch will
         /// be used.
         branch: Option<String>,
     },
+    /// Mark a branch as wip, ready, or blocked (or clear its mark). The mark shows in `status`, and
+    /// `land` won't merge a branch marked wip or blocked without `--force`.
+    Mark {
+        /// The mark to set.
+        #[arg(value_enum)]
+        mark: MarkArg,
+        /// The branch to mark. If omitted, the current branch will be used.
+        branch: Option<String>,
+    },
+    /// Export the git-stack tree as a Graphviz DOT or Mermaid graph on stdout.
+    Graph {
+        /// Output format.
+        #[arg(long, short, value_enum, default_value_t = render::GraphFormat::Dot)]
+        format: render::GraphFormat,
+    },
+    /// Fold staged changes into the commits on the current branch that last touched the same
+    /// lines (like `git absorb`), then autosquash them in place. Hunks with no single owning
+    /// commit on the branch are fixed up into the tip commit.
+    Absorb,
     /// Shows the diff between the given branch and its parent (git-stack tree) branch.
     Diff {
         /// Specifies the branch whose diff should be shown. If omitted, the current branch will
         /// be used.
         branch: Option<String>,
+        /// Show a diffstat instead of the full patch.
+        #[arg(long, conflicts_with = "name_only")]
+        stat: bool,
+        /// Show only the names of changed files.
+        #[arg(long)]
+        name_only: bool,
+        /// Restrict the diff to these paths (after `--`, as with `git diff`).
+        #[arg(last = true)]
+        paths: Vec<String>,
     },
     /// Create a new branch and make it a descendent of the current branch. If the branch already
     /// exists, then it will simply be checked out.
     Checkout {
-        /// The name of the branch to check out.
+        /// The name of the branch to check out, or `-` for the tracked branch you were last on.
         branch_name: String,
+        /// If the branch doesn't exist locally, fetch it from the remote and mount it under the
+        /// base of its open PR instead of creating a new branch.
+        #[arg(long)]
+        remote: bool,
+        /// Create the new branch on this tracked branch instead of the current one.
+        #[arg(long, value_name = "BRANCH", conflicts_with = "remote")]
+        parent: Option<String>,
+        /// Treat the name as free text and make a branch name from it ("Fix the login bug"
+        /// becomes `fix-the-login-bug`) instead of rejecting characters git doesn't allow.
+        #[arg(long)]
+        slug: bool,
+        /// When switching commits with uncommitted changes, stash them (untracked files included)
+        /// and re-apply them on the checked-out branch instead of refusing.
+        #[arg(long)]
+        autostash: bool,
+        /// When creating a new branch, push it with `-u` so it tracks `origin/<branch>` right
+        /// away.
+        #[arg(long, conflicts_with = "remote")]
+        track: bool,
+    },
+    /// Create a linear stack of new branches, each on top of the previous one, starting from the
+    /// current branch. The last one is left checked out.
+    CreateStack {
+        /// The new branches, bottom of the stack first.
+        #[arg(required = true)]
+        branch_names: Vec<String>,
+    },
+    /// Write this repo's stack tree to a standalone YAML file (JSON for a `.json` path), for
+    /// `import` in another clone.
+    Export {
+        /// File to write; stdout when omitted.
+        out: Option<PathBuf>,
+    },
+    /// Load a stack tree written by `export` as this repo's tree. Branches that exist neither
+    /// locally nor on the remote are skipped.
+    Import {
+        /// File written by `export`.
+        file: PathBuf,
+        /// Replace an existing stack tree.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Tidy this repo's stack tree: drop branches that no longer exist, forget stale LKG parents,
+    /// refresh LKGs, and sort the tree into a canonical order.
+    Tidy {
+        /// Show what would change without saving anything.
+        #[arg(long, short = 'n', default_value_t = false)]
+        dry_run: bool,
+        /// Also drop branches that exist only on the remote.
+        #[arg(long)]
+        prune_remote_only: bool,
+    },
+    /// Start tracking this repo, with the trunk branch as the root of the stack tree. Safe to run
+    /// again.
+    Init {
+        /// Stack method for branches added from now on.
+        #[arg(long, value_enum)]
+        stack_method: Option<StackMethod>,
+        /// How `land` merges PRs in this repo. Should match what the repo's settings allow.
+        #[arg(long, value_enum)]
+        merge_method: Option<github::MergeMethod>,
+        /// Put this in front of new branch names from `checkout` (e.g. `me/`); pass "" to stop.
+        #[arg(long, value_name = "PREFIX")]
+        branch_prefix: Option<String>,
     },
     /// Mount the current branch on top of the named parent branch. If no parent branch is named,
     /// then the trunk branch will be used.
     Mount {
         /// The name of the parent branch upon which to stack the current branch.
         parent_branch: Option<String>,
+        /// Mount on the base branch of the current branch's open PR (adding the base to the tree
+        /// if needed), or on trunk if it has no PR.
+        #[arg(long, conflicts_with = "parent_branch")]
+        onto_remote: bool,
+    },
+    /// Re-parent a branch onto another branch and restack it there, along with its descendants.
+    /// Unlike `mount`, the branch's commits move too.
+    Move {
+        /// The branch to move. Defaults to the current branch.
+        branch: Option<String>,
+        /// The new parent branch.
+        #[arg(long)]
+        onto: String,
+        /// Push each branch after restacking it.
+        #[arg(long, short)]
+        push: bool,
+    },
+    /// Run `git rebase -i` over just one branch's own commits (above its LKG parent, or its
+    /// merge-base with its parent), then restack its descendants onto the result.
+    RebaseInteractive {
+        /// The branch to rebase. If omitted, the current branch will be used.
+        branch: Option<String>,
+    },
+    /// Merge the PR of the bottom-most branch in the current stack, drop that branch, retarget its
+    /// children's PRs to trunk, and restack them onto the new trunk.
+    Land {
+        /// Merge even if checks are failing or pending, GitHub doesn't report the PR as
+        /// mergeable, the local branch differs from the PR's head, or the branch is marked wip or
+        /// blocked.
+        #[arg(long)]
+        force: bool,
+        /// How to merge the PR. Defaults to the repo's `init --merge-method`, or `merge`.
+        #[arg(long, value_enum)]
+        method: Option<github::MergeMethod>,
+        /// Push each branch after restacking it.
+        #[arg(long, short)]
+        push: bool,
+    },
+    /// Split a branch in two at a commit: a new branch ends at `at`, and the original branch is
+    /// re-stacked on it, keeping only the commits after `at`.
+    Split {
+        /// The commit (within the branch) where the new lower branch ends.
+        at: String,
+        /// The branch to split. Defaults to the current branch.
+        #[arg(long, short)]
+        branch: Option<String>,
+        /// Name for the new lower branch. Defaults to `<branch>-base`.
+        #[arg(long, short)]
+        name: Option<String>,
+    },
+    /// Squash a branch's commits into its parent branch, then delete the branch (from git and the
+    /// tree) and re-stack its children on the parent. The inverse of `split`.
+    Fold {
+        /// The branch to fold. Defaults to the current branch.
+        branch: Option<String>,
+    },
+    /// Check the state file for problems: duplicate or empty branch names in any repo's tree,
+    /// branches in this repo's tree that no longer exist locally or on the remote, LKG parents that
+    /// aren't known commits, local branches stacked on a tracked branch but missing from the tree,
+    /// and an unset `origin/HEAD`.
+    Doctor {
+        /// Repair what can be repaired: set `origin/HEAD`, drop missing branches (after asking),
+        /// clear unknown LKG parents, and mount the missing local branches. The state file is
+        /// backed up first.
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Fetch from the remote, refreshing remote-tracking branches and the trunk pointer
+    /// (`origin/HEAD`), without doing anything else.
+    Fetch {
+        /// Keep remote-tracking refs whose branch was deleted on the remote.
+        #[arg(long = "no-prune", action = clap::ArgAction::SetFalse)]
+        prune: bool,
+        /// Also fetch tags, overwriting local tags the remote moved.
+        #[arg(long)]
+        tags: bool,
     },
     /// Delete a branch from the git-stack tree.
     Delete {
         /// The name of the branch to delete.
         branch_name: String,
+        /// Also delete the git branch (`git branch -d`), re-stacking its children on its parent.
+        #[arg(long)]
+        branch: bool,
+        /// Like `--branch`, but delete the git branch even if it isn't merged (`git branch -D`).
+        #[arg(long, short)]
+        force: bool,
     },
     /// Clean up branches from the git-stack tree that no longer exist locally.
     Cleanup {
@@ -156,6 +443,10 @@ enum Command {
         /// Clean up all trees in the config, removing invalid repos and cleaning branches.
         #[arg(long, short, default_value_t = false)]
         all: bool,
+        /// Instead, delete local branches whose work has landed on trunk (as `sync` would), without
+        /// contacting the remote.
+        #[arg(long, conflicts_with = "all")]
+        merged: bool,
     },
     /// Manage GitHub Pull Requests for stacked branches.
     Pr {
@@ -167,11 +458,17 @@ enum Command {
         #[command(subcommand)]
         action: AuthAction,
     },
+    /// Show where the token comes from, whom it authenticates as, and which PRs the author and
+    /// fork filters keep. The token itself is never printed.
+    Whoami,
     /// Manage caches (PR cache, seen SHAs).
     Cache {
         #[command(subcommand)]
         action: CacheAction,
     },
+    /// Drop this repo's cached PR data (closed PRs, open PRs, watermark); the next sync refetches
+    /// it. Unlike `cache clear`, seen SHAs and the merge-base cache are kept.
+    PruneCache,
     /// Generate shell completions.
     Completions {
         /// Shell to generate completions for.
@@ -192,9 +489,65 @@ enum Command {
         /// Show what would be done without making changes
         #[arg(long, short = 'n')]
         dry_run: bool,
+        /// Prompt before applying each change: [y]es, [n]o, [a]ll remaining, [q]uit
+        #[arg(long, short = 'i', conflicts_with = "dry_run")]
+        interactive: bool,
+        /// Post (or refresh) a comment on each open PR listing its whole stack with links
+        #[arg(long)]
+        stack_comments: bool,
+        /// Fetch at most this many closed PRs not already cached (most recently updated first),
+        /// e.g. to cap the first sync of a repo with a long PR history
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+        /// Skip closed PRs last updated before this date (`YYYY-MM-DD` or RFC 3339)
+        #[arg(long, value_name = "DATE", value_parser = parse_since)]
+        since: Option<String>,
+        /// Discover and inject PRs by this author (repeatable), instead of the configured
+        /// `authors_filter`. Without it, `--show-all` turns author filtering off for this run.
+        #[arg(long = "author", value_name = "LOGIN")]
+        authors: Vec<String>,
+        /// Keep PRs opened from forks for this run, as if `include_forks` were set in the config
+        #[arg(long)]
+        include_forks: bool,
+        /// Delete the remote branch of each merged PR in the stack, when it still points at the
+        /// merged head
+        #[arg(long, conflicts_with = "pull")]
+        prune_remote: bool,
+        /// Ignore the closed-PR cache for this run: fetch every closed PR again (within --limit
+        /// and --since) and rebuild the cache from what comes back
+        #[arg(long)]
+        no_cache: bool,
     },
 }
 
+impl Command {
+    /// Whether the command changes the stack, and so holds the state lock for its whole run.
+    /// Read-only and long-running commands (`status --watch`, the TUI) don't; a save they make
+    /// locks only for the write.
+    fn locks_state(&self) -> bool {
+        !matches!(
+            self,
+            Command::Status { .. }
+                | Command::Interactive { .. }
+                | Command::Log { .. }
+                | Command::Diff { .. }
+                | Command::Graph { .. }
+                | Command::Export { .. }
+                | Command::Whoami
+                | Command::Auth { .. }
+                | Command::Fetch { .. }
+                | Command::Doctor { fix: false }
+                | Command::Note {
+                    edit: false,
+                    append: None,
+                    ..
+                }
+                | Command::Completions { .. }
+                | Command::Llms(_)
+        )
+    }
+}
+
 #[derive(Subcommand)]
 enum PrAction {
     /// Create a PR for the current branch with git-stack parent as base.
@@ -215,11 +568,14 @@ enum PrAction {
         #[arg(long)]
         web: bool,
     },
-    /// Open PR in web browser.
+    /// Open PR in web browser. Offers to create one when the branch has none.
+    #[command(visible_alias = "open")]
     View {
         /// Branch whose PR to view (defaults to current)
         branch: Option<String>,
     },
+    /// Show the mergeability, CI checks, and review state of each PR in the current stack.
+    Status,
     /// Sync PR bases to match git-stack parent branches.
     Sync {
         /// Sync all PRs in stack (defaults to current branch only)
@@ -258,13 +614,53 @@ enum CacheAction {
     Clear,
 }
 
+/// What `git stack mark` sets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum MarkArg {
+    /// Still being worked on.
+    Wip,
+    /// Done and ready to merge.
+    Ready,
+    /// Waiting on something else.
+    Blocked,
+    /// Remove the mark.
+    Clear,
+}
+
+impl MarkArg {
+    fn mark(self) -> Option<BranchMark> {
+        match self {
+            MarkArg::Wip => Some(BranchMark::Wip),
+            MarkArg::Ready => Some(BranchMark::Ready),
+            MarkArg::Blocked => Some(BranchMark::Blocked),
+            MarkArg::Clear => None,
+        }
+    }
+}
+
 fn main() {
+    // The completion scripts' branch lookup is handled before clap, so it stays out of `--help` and
+    // out of the generated scripts.
+    if std::env::args().nth(1).as_deref() == Some(completions::COMPLETE_BRANCHES) {
+        complete_branches();
+        return;
+    }
+    let args = Args::parse();
+    if args.no_color || render::colors::no_color_env() {
+        render::colors::disable_colors();
+    }
+    if args.read_only {
+        read_only::enable();
+    }
+
     tracing_subscriber::registry()
         // We don't need timestamps in the logs.
         .with(
             tracing_subscriber::fmt::layer()
                 .with_file(true)
-                .with_line_number(true),
+                .with_line_number(true)
+                // Same decision as the rest of the output: off when disabled or not a terminal.
+                .with_ansi(colored::control::SHOULD_COLORIZE.should_colorize()),
         )
         // Allow usage of RUST_LOG environment variable to set the log level.
         .with(
@@ -274,7 +670,13 @@ fn main() {
         )
         .init();
 
-    let result = inner_main();
+    let mut started_on = None;
+    let result = inner_main(args, &mut started_on);
+    if let Some(started_on) = started_on
+        && let Err(e) = record_last_branch(&started_on)
+    {
+        tracing::debug!("Failed to record the previous branch: {e:#}");
+    }
 
     // Check if benchmarking was requested via flag or environment variable
     // Note: We check env var here since Args is consumed by inner_main
@@ -294,25 +696,85 @@ fn main() {
     std::process::exit(0);
 }
 
-fn inner_main() -> Result<()> {
-    // Run from the git root directory.
-    let args = Args::parse();
+/// `git-stack __complete_branches`: print the current repo's tracked branches. Runs on every <TAB>,
+/// so it skips the LKG refresh and the state save, and outside a repo (or with unreadable state) it
+/// quietly prints nothing.
+fn complete_branches() {
+    if let Ok((repo, _)) = open_repo()
+        && let Ok(state) = State::load_state()
+        && let Some(tree) = state.get_tree(&repo)
+    {
+        completions::print_branches(tree);
+    }
+}
+
+/// The repo's canonical root (the main working tree's, from any worktree, since every worktree of a
+/// repo shares its state entry) and the opened repository.
+fn open_repo() -> Result<(String, GitRepo)> {
+    let repo = canonicalize(
+        run_git(&["rev-parse", "--show-toplevel"])?.output_or("No git directory found")?,
+    )?
+    .into_os_string()
+    .into_string()
+    .map_err(|error| anyhow!("Invalid git directory: '{}'", error.to_string_lossy()))?;
+
+    // Open git2 repository for fast read-only operations
+    let git_repo = GitRepo::open(&repo)?;
+
+    let repo = match git_repo.main_worktree_root() {
+        Some(main) => canonicalize(main)?
+            .into_os_string()
+            .into_string()
+            .map_err(|error| anyhow!("Invalid git directory: '{}'", error.to_string_lossy()))?,
+        None => repo,
+    };
+    Ok((repo, git_repo))
+}
+
+/// Where a command started: the repo's state key, the worktree it ran in, the branch (or SHA,
+/// when detached) checked out there, and how long that worktree's `HEAD` reflog was.
+struct StartedOn {
+    repo: String,
+    worktree: String,
+    branch: String,
+    head_reflog_len: usize,
+}
+
+/// After a command that left `HEAD` off the branch it started on, remember for `checkout -` the
+/// last tracked branch `HEAD` was on before it: the newest one the command's own checkouts moved
+/// off of, else the branch it started on. A command that ends where it started (e.g. `restack`
+/// visiting each branch) records nothing.
+fn record_last_branch(started_on: &StartedOn) -> Result<()> {
+    let git_repo = GitRepo::open(&started_on.worktree)?;
+    let current_branch = git_repo.current_branch()?;
+    if current_branch == started_on.branch {
+        return Ok(());
+    }
+    let new_entries = git_repo
+        .head_reflog_len()
+        .saturating_sub(started_on.head_reflog_len);
+    let mut moved_off = git_repo.head_checked_out_from(new_entries);
+    moved_off.push(started_on.branch.clone());
+    let mut state = State::load_state()?;
+    state.record_last_branch(&started_on.repo, &current_branch, &moved_off);
+    state.save_state()
+}
 
+fn inner_main(args: Args, started_on: &mut Option<StartedOn>) -> Result<()> {
     // Set env vars if benchmark flags were passed (for main() to check later)
-    if args.benchmark || args.json {
+    let stats_json = args.json || args.stats_json;
+    if args.benchmark || stats_json {
         // SAFETY: We're single-threaded at this point in startup
         unsafe { std::env::set_var("GIT_STACK_BENCHMARK", "1") };
     }
-    if args.json {
+    if stats_json {
         // SAFETY: We're single-threaded at this point in startup
         unsafe { std::env::set_var("GIT_STACK_BENCHMARK_JSON", "1") };
     }
 
     // Handle completions early (doesn't require git repo)
     if let Some(Command::Completions { shell }) = args.command {
-        let mut cmd = Args::command();
-        generate(shell, &mut cmd, "git-stack", &mut std::io::stdout());
-        return Ok(());
+        return completions::write_script(shell, &mut Args::command(), &mut std::io::stdout());
     }
 
     // Handle llms early (doesn't require git repo)
@@ -320,30 +782,53 @@ fn inner_main() -> Result<()> {
         return llms::run(a);
     }
 
-    let repo = canonicalize(
-        run_git(&["rev-parse", "--show-toplevel"])?.output_or("No git directory found")?,
-    )?
-    .into_os_string()
-    .into_string()
-    .map_err(|error| anyhow!("Invalid git directory: '{}'", error.to_string_lossy()))?;
-
-    // Open git2 repository for fast read-only operations
-    let git_repo = GitRepo::open(&repo)?;
+    let (repo, mut git_repo) = open_repo()?;
 
+    if args.command.as_ref().is_some_and(Command::locks_state) {
+        state::lock_state()?;
+    }
     let mut state = State::load_state().context("loading state")?;
+    if state.relocate_moved_repo(&git_repo, &repo) {
+        state.save_state()?;
+    }
+    if let Some(trunk) = &args.trunk {
+        state.set_trunk_override(&repo, trunk)?;
+    }
+    git_repo.set_trunk_override(state.trunk_override(&repo));
+    let git_repo = git_repo;
 
     tracing::debug!("Current directory: {}", repo);
 
     let run_version = format!("{}", chrono::Utc::now().timestamp());
     let current_branch = git_repo.current_branch()?;
+    *started_on = Some(StartedOn {
+        repo: repo.clone(),
+        worktree: git_repo.root()?,
+        branch: current_branch.clone(),
+        head_reflog_len: git_repo.head_reflog_len(),
+    });
 
-    eager_refresh_lkgs(&git_repo, &mut state, &repo, &current_branch)?;
+    eager_refresh_lkgs(
+        &git_repo,
+        &mut state,
+        &repo,
+        &current_branch,
+        args.force_refresh,
+    )?;
 
     let current_upstream = git_repo.get_upstream("");
     tracing::debug!(run_version, current_branch, current_upstream);
 
     // Check for pending restack operation - block other commands except --continue and --abort
-    if state.has_pending_restack(&repo) {
+    if let Some(pending) = state.get_pending_restack(&repo) {
+        if let Some(worktree) = &pending.resume.worktree
+            && git_repo.root().ok().as_ref() != Some(worktree)
+        {
+            bail!(
+                "A restack operation is in progress in the worktree at {worktree}.\n\
+                 Run `git stack restack --continue`, `--skip`, or `--abort` there."
+            );
+        }
         match &args.command {
             Some(Command::Restack {
                 r#continue: true, ..
@@ -362,16 +847,36 @@ fn inner_main() -> Result<()> {
     }
 
     match args.command {
-        Some(Command::Checkout { branch_name }) => state.checkout(
+        Some(Command::Checkout {
+            branch_name,
+            remote,
+            parent,
+            slug,
+            autostash,
+            track,
+        }) => state.checkout(
             &git_repo,
             &repo,
             current_branch,
             current_upstream,
             branch_name,
+            remote,
+            parent,
+            slug,
+            autostash,
+            track,
         ),
-        Some(Command::Edit { config }) => {
+        Some(Command::Edit {
+            config,
+            repo: edit_repo,
+        }) => {
             if config {
                 state.edit_github_config()
+            } else if edit_repo {
+                state.edit_repo_tree(&repo)?;
+                // Refresh against the edited tree now, so the next command doesn't rewrite the
+                // state file just to catch up.
+                eager_refresh_lkgs(&git_repo, &mut state, &repo, &current_branch, false)
             } else {
                 state.edit_state()
             }
@@ -380,11 +885,16 @@ fn inner_main() -> Result<()> {
             branch,
             fetch,
             push,
+            push_all,
             ancestors,
             squash,
             r#continue,
             skip,
             abort,
+            dry_run,
+            autostash,
+            log,
+            onto,
         }) => {
             // Handle --continue first
             if r#continue {
@@ -398,8 +908,27 @@ fn inner_main() -> Result<()> {
             if abort {
                 return handle_restack_abort(&git_repo, state, &repo);
             }
+            let push = push || push_all;
             let restack_branch = branch.clone().unwrap_or_else(|| current_branch.clone());
             state.try_auto_mount(&git_repo, &repo, &restack_branch)?;
+            if dry_run {
+                return restack_dry_run(
+                    &git_repo,
+                    state,
+                    &repo,
+                    &restack_branch,
+                    push,
+                    push_all,
+                    ancestors,
+                    squash,
+                    onto.as_deref(),
+                );
+            }
+            let autostash = if autostash {
+                git::autostash_push()?
+            } else {
+                None
+            };
             restack(
                 &git_repo,
                 state,
@@ -409,11 +938,34 @@ fn inner_main() -> Result<()> {
                 current_branch,
                 fetch,
                 push,
+                push_all,
                 ancestors,
                 squash,
+                false,
+                log,
+                None,
+                autostash,
+                onto,
             )
         }
-        Some(Command::Mount { parent_branch }) => {
+        Some(Command::CreateStack { branch_names }) => {
+            state.create_stack(&git_repo, &repo, &current_branch, &branch_names)
+        }
+        Some(Command::Export { out }) => state.export_tree(&repo, out.as_deref()),
+        Some(Command::Import { file, force }) => state.import_tree(&git_repo, &repo, &file, force),
+        Some(Command::Tidy {
+            dry_run,
+            prune_remote_only,
+        }) => state.tidy(&git_repo, &repo, prune_remote_only, dry_run),
+        Some(Command::Init {
+            stack_method,
+            merge_method,
+            branch_prefix,
+        }) => state.init(&git_repo, &repo, stack_method, merge_method, branch_prefix),
+        Some(Command::Mount {
+            onto_remote: true, ..
+        }) => state.mount_onto_remote(&git_repo, &repo, &current_branch),
+        Some(Command::Mount { parent_branch, .. }) => {
             state.mount(&git_repo, &repo, &current_branch, parent_branch.clone())?;
 
             // If this branch has a PR, retarget its base to the new parent
@@ -423,7 +975,7 @@ fn inner_main() -> Result<()> {
                 && let Some(branch) = state.get_tree_branch(&repo, &current_branch)
                 && let Some(pr_number) = branch.pr_number
                 && let Ok(repo_id) = github::get_repo_identifier(&git_repo)
-                && let Ok(client) = github::GitHubClient::from_env(&repo_id)
+                && let Ok(client) = provider::client_for(&repo_id)
             {
                 match client.update_pr(
                     &repo_id,
@@ -444,19 +996,95 @@ fn inner_main() -> Result<()> {
             }
             Ok(())
         }
-        Some(Command::Status { fetch }) => {
+        Some(Command::Move { branch, onto, push }) => {
+            let branch = branch.unwrap_or_else(|| current_branch.clone());
+            state.try_auto_mount(&git_repo, &repo, &branch)?;
+            move_branch(
+                &git_repo,
+                state,
+                &repo,
+                run_version,
+                &branch,
+                &onto,
+                current_branch,
+                push,
+            )
+        }
+        Some(Command::RebaseInteractive { branch }) => {
+            let branch = branch.unwrap_or_else(|| current_branch.clone());
+            state.try_auto_mount(&git_repo, &repo, &branch)?;
+            rebase_interactive(
+                &git_repo,
+                state,
+                &repo,
+                run_version,
+                &branch,
+                current_branch,
+            )
+        }
+        Some(Command::Land {
+            force,
+            method,
+            push,
+        }) => land(
+            &git_repo,
+            state,
+            &repo,
+            run_version,
+            current_branch,
+            force,
+            method,
+            push,
+        ),
+        Some(Command::Split { at, branch, name }) => {
+            let branch = branch.unwrap_or_else(|| current_branch.clone());
+            state.try_auto_mount(&git_repo, &repo, &branch)?;
+            split(&git_repo, state, &repo, &branch, &at, name)
+        }
+        Some(Command::Fold { branch }) => {
+            let branch = branch.unwrap_or_else(|| current_branch.clone());
+            state.try_auto_mount(&git_repo, &repo, &branch)?;
+            fold(&git_repo, state, &repo, &branch)
+        }
+        Some(Command::Doctor { fix }) => doctor(&git_repo, state, &repo, fix),
+        Some(Command::Fetch { prune, tags }) => fetch(&git_repo, prune, tags),
+        Some(Command::Status {
+            fetch,
+            no_fetch_trunk,
+            json,
+            authors,
+            depth,
+            watch,
+        }) => {
             state.try_auto_mount(&git_repo, &repo, &current_branch)?;
+            if watch {
+                return watch_status(
+                    &git_repo,
+                    state,
+                    &repo,
+                    fetch,
+                    no_fetch_trunk,
+                    args.verbose,
+                    args.show_all,
+                    &authors,
+                    depth,
+                );
+            }
             status(
                 &git_repo,
                 state,
                 &repo,
                 &current_branch,
                 fetch,
+                no_fetch_trunk,
+                json,
                 args.verbose,
                 args.show_all,
+                &authors,
+                depth,
             )
         }
-        Some(Command::Interactive) => {
+        Some(Command::Interactive { authors }) => {
             state.try_auto_mount(&git_repo, &repo, &current_branch)?;
             interactive(
                 &git_repo,
@@ -465,6 +1093,7 @@ fn inner_main() -> Result<()> {
                 &current_branch,
                 args.verbose,
                 args.show_all,
+                &authors,
             )
         }
         Some(Command::Up) => {
@@ -475,8 +1104,37 @@ fn inner_main() -> Result<()> {
             state.try_auto_mount(&git_repo, &repo, &current_branch)?;
             navigate_down(&git_repo, &state, &repo, &current_branch)
         }
-        Some(Command::Delete { branch_name }) => state.delete_branch(&repo, &branch_name),
-        Some(Command::Cleanup { dry_run, all }) => {
+        Some(Command::Delete {
+            branch_name,
+            branch,
+            force,
+        }) => {
+            if branch || force {
+                delete_with_git_branch(
+                    &git_repo,
+                    state,
+                    &repo,
+                    &current_branch,
+                    &branch_name,
+                    force,
+                )
+            } else {
+                state.delete_branch(&repo, &branch_name)?;
+                if git::git_branch_exists(&git_repo, &branch_name) {
+                    println!(
+                        "The git branch {} still exists; pass --branch to delete it too.",
+                        branch_name.yellow()
+                    );
+                }
+                Ok(())
+            }
+        }
+        Some(Command::Cleanup {
+            dry_run,
+            merged: true,
+            ..
+        }) => sync::cleanup_merged(&git_repo, &mut state, &repo, dry_run),
+        Some(Command::Cleanup { dry_run, all, .. }) => {
             // `--all` ignores author filtering (it has no per-repo current-branch/author context),
             // so it must not require identity resolution — pass an empty filter. Single-repo
             // cleanup resolves the effective filter (unset → your own login).
@@ -492,7 +1150,8 @@ fn inner_main() -> Result<()> {
                 && !authors_filter.is_empty()
                 && let Some(tree) = state.get_tree(&repo).cloned()
             {
-                let (authors, _, _) = resolve_pr_authors(&git_repo, &tree, &current_branch, false);
+                let (authors, _, _) =
+                    resolve_pr_authors(&git_repo, &tree, &current_branch, false, false);
                 authors
             } else {
                 std::collections::HashMap::new()
@@ -507,41 +1166,107 @@ fn inner_main() -> Result<()> {
                 &pr_authors,
             )
         }
-        Some(Command::Diff { branch }) => {
+        Some(Command::Diff {
+            branch,
+            stat,
+            name_only,
+            paths,
+        }) => {
             let branch_to_diff = branch.clone().unwrap_or_else(|| current_branch.clone());
             state.try_auto_mount(&git_repo, &repo, &branch_to_diff)?;
-            diff(&git_repo, state, &repo, &branch.unwrap_or(current_branch))
+            let mut extra_args = Vec::new();
+            if stat {
+                extra_args.push("--stat".to_string());
+            }
+            if name_only {
+                extra_args.push("--name-only".to_string());
+            }
+            if !paths.is_empty() {
+                extra_args.push("--".to_string());
+                extra_args.extend(paths);
+            }
+            diff(&git_repo, state, &repo, &branch_to_diff, &extra_args)
         }
-        Some(Command::Log { branch }) => {
+        Some(Command::Absorb) => {
+            state.try_auto_mount(&git_repo, &repo, &current_branch)?;
+            absorb::absorb(&git_repo, &state, &repo)
+        }
+        Some(Command::Graph { format }) => graph(&git_repo, &state, &repo, format),
+        Some(Command::Log { branch, stack }) => {
             let branch_to_log = branch.clone().unwrap_or_else(|| current_branch.clone());
             state.try_auto_mount(&git_repo, &repo, &branch_to_log)?;
-            show_log(state, &repo, &branch.unwrap_or(current_branch))
+            if stack {
+                show_stack_log(&state, &git_repo, &repo, &branch_to_log)
+            } else {
+                show_log(state, &repo, &branch_to_log)
+            }
         }
-        Some(Command::Note { edit, branch }) => {
+        Some(Command::Note {
+            edit,
+            append,
+            all,
+            branch,
+        }) => {
             let branch = branch.unwrap_or(current_branch);
             state.try_auto_mount(&git_repo, &repo, &branch)?;
             if edit {
                 state.edit_note(&repo, &branch)
+            } else if let Some(text) = append {
+                state.append_note(&repo, &branch, &text)
+            } else if all {
+                show_stack_notes(&state, &git_repo, &repo, &branch)
             } else {
                 state.show_note(&repo, &branch)
             }
         }
+        Some(Command::Mark { mark, branch }) => {
+            let branch = branch.unwrap_or(current_branch);
+            state.try_auto_mount(&git_repo, &repo, &branch)?;
+            state.set_mark(&repo, &branch, mark.mark())
+        }
         Some(Command::Pr { action }) => {
             handle_pr_command(&git_repo, &mut state, &repo, &current_branch, action)
         }
         Some(Command::Auth { action }) => handle_auth_command(&git_repo, action),
+        Some(Command::Whoami) => whoami(&git_repo),
         Some(Command::Cache { action }) => {
             handle_cache_command(&git_repo, &mut state, &repo, action)
         }
+        Some(Command::PruneCache) => prune_pr_cache(&git_repo),
         Some(Command::Sync {
             push,
             pull,
             dry_run,
+            interactive,
+            stack_comments,
+            limit,
+            since,
+            authors,
+            include_forks,
+            prune_remote,
+            no_cache,
         }) => {
+            if include_forks {
+                github::include_forks_for_run();
+            }
+            // `--author` replaces the configured filter; `--show-all` turns it off.
+            let authors_override = if !authors.is_empty() {
+                Some(authors)
+            } else if args.show_all {
+                Some(Vec::new())
+            } else {
+                None
+            };
             let options = sync::SyncOptions {
                 push_only: push,
                 pull_only: pull,
                 dry_run,
+                interactive,
+                stack_comments,
+                closed_pr_limits: github::ClosedPrLimits { limit, since },
+                authors_override,
+                prune_remote,
+                no_cache,
             };
             sync::sync(&git_repo, &mut state, &repo, options)
         }
@@ -555,14 +1280,40 @@ fn inner_main() -> Result<()> {
                 &repo,
                 &current_branch,
                 false,
+                false,
+                false,
                 args.verbose,
                 args.show_all,
+                &[],
+                None,
             )
         }
     }
 }
 
-fn diff(git_repo: &GitRepo, mut state: State, repo: &str, branch: &str) -> Result<()> {
+/// Parse `sync --since` into the RFC 3339 UTC form of PR `updated_at` timestamps, so the two
+/// compare as strings. A bare date means midnight UTC.
+fn parse_since(value: &str) -> Result<String, String> {
+    let time = if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        date.and_time(chrono::NaiveTime::MIN).and_utc()
+    } else {
+        chrono::DateTime::parse_from_rfc3339(value)
+            .map_err(|_| format!("expected YYYY-MM-DD or an RFC 3339 time, got '{value}'"))?
+            .to_utc()
+    };
+    Ok(time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+}
+
+/// `git diff` the branch against its base, passing `extra_args` (`--stat`, `--name-only`, a
+/// `--`-prefixed pathspec) through. The base is the LKG parent when one is recorded, otherwise the
+/// parent branch's tip; which one is announced on stderr so piped output stays a clean diff.
+fn diff(
+    git_repo: &GitRepo,
+    mut state: State,
+    repo: &str,
+    branch: &str,
+    extra_args: &[String],
+) -> Result<()> {
     state.refresh_lkg_for_branch(git_repo, repo, branch)?;
     let parent_branch = state
         .get_parent_branch_of(repo, branch)
@@ -575,35 +1326,852 @@ fn diff(git_repo: &GitRepo, mut state: State, repo: &str, branch: &str) -> Resul
     let branch = state
         .get_tree_branch(repo, branch)
         .ok_or_else(|| anyhow!("No branch found for current branch: {}", branch))?;
-    let status = git::run_git_passthrough(&[
-        "diff",
-        &format!(
-            "{}..{}",
-            branch.lkg_parent.as_deref().unwrap_or(&parent_branch.name),
-            branch.name
-        ),
-    ])?;
+    let base = match branch.lkg_parent.as_deref() {
+        Some(lkg_parent) => {
+            eprintln!(
+                "Diffing {} against its LKG parent {} (the {} commit it was last stacked on).",
+                branch.name.yellow(),
+                lkg_parent[..8.min(lkg_parent.len())].green(),
+                parent_branch.name.green()
+            );
+            lkg_parent
+        }
+        None => {
+            eprintln!(
+                "{} {} has no LKG parent recorded; diffing against the tip of {}, which may \
+                 include {}'s own newer changes.",
+                "Note:".yellow(),
+                branch.name.yellow(),
+                parent_branch.name.green(),
+                parent_branch.name
+            );
+            &parent_branch.name
+        }
+    };
+    let range = format!("{}..{}", base, branch.name);
+    let mut args = vec!["diff", range.as_str()];
+    args.extend(extra_args.iter().map(String::as_str));
+    let status = git::run_git_passthrough(&args)?;
     if !status.success() {
         bail!("git diff failed");
     }
     Ok(())
 }
 
-fn show_log(state: State, repo: &str, branch: &str) -> Result<()> {
-    let parent_branch = state
+/// Split `branch` at commit `at` into a new lower branch (ending at `at`) with `branch` stacked on
+/// top of it. `at` must lie strictly between the branch's boundary (LKG parent, or merge-base with
+/// its parent) and its tip, so both halves keep at least one commit.
+fn split(
+    git_repo: &GitRepo,
+    mut state: State,
+    repo: &str,
+    branch: &str,
+    at: &str,
+    name: Option<String>,
+) -> Result<()> {
+    let parent = state
         .get_parent_branch_of(repo, branch)
-        .ok_or_else(|| anyhow!("No parent branch found for current branch: {}", branch))?;
-    tracing::debug!(
-        parent_branch = &parent_branch.name,
-        branch = branch,
-        "Log changes"
-    );
-    let status = git::run_git_passthrough(&[
-        "log",
-        "--graph",
-        "--oneline",
-        "-p",
-        "--decorate",
+        .ok_or_else(|| {
+            anyhow!(
+                "Branch {} is not in the stack tree (or is its root).",
+                branch
+            )
+        })?
+        .name
+        .clone();
+    let tree_branch = state
+        .get_tree_branch(repo, branch)
+        .ok_or_else(|| anyhow!("Branch {} is not in the stack tree.", branch))?;
+    let base = match tree_branch.lkg_parent.clone() {
+        Some(lkg) => lkg,
+        None => git_repo.merge_base(&parent, branch)?,
+    };
+    let new_branch = name.unwrap_or_else(|| format!("{branch}-base"));
+    if git_repo.branch_exists(&new_branch) || state.branch_exists_in_tree(repo, &new_branch) {
+        bail!("Branch {} already exists.", new_branch.red());
+    }
+
+    let at_sha = git_repo
+        .sha(at)
+        .with_context(|| format!("Cannot resolve split point {at}"))?;
+    let tip_sha = git_repo.sha(branch)?;
+    let strictly_inside = at_sha != base
+        && at_sha != tip_sha
+        && git_repo.is_ancestor(&base, &at_sha)?
+        && git_repo.is_ancestor(&at_sha, &tip_sha)?;
+    if !strictly_inside {
+        bail!(
+            "Split point {} must be a commit strictly between {} and the tip of {}.",
+            at.red(),
+            &base[..base.len().min(8)],
+            branch.yellow()
+        );
+    }
+
+    run_git(&["branch", &new_branch, &at_sha])?;
+    state.split_branch(git_repo, repo, branch, &new_branch, base)?;
+    println!(
+        "Split {} at {}: {} now holds the lower commits.",
+        branch.yellow(),
+        &at_sha[..8],
+        new_branch.green()
+    );
+    if state
+        .get_tree_branch(repo, branch)
+        .is_some_and(|b| b.pr_number.is_some())
+    {
+        println!(
+            "{} has a PR; run `git stack pr create -b {}` for the new branch, then `git stack sync` \
+             to retarget it.",
+            branch.yellow(),
+            new_branch
+        );
+    }
+    Ok(())
+}
+
+/// Fold `branch` into its parent: squash-merge it onto the parent as one commit, repoint its
+/// children to the parent, and delete it from the tree and from git. Leaves the parent checked
+/// out. Folding into the trunk is refused — landing a branch on trunk goes through its PR.
+fn fold(git_repo: &GitRepo, mut state: State, repo: &str, branch: &str) -> Result<()> {
+    let _lock = git_repo.lock()?;
+
+    let parent = state
+        .get_parent_branch_of(repo, branch)
+        .ok_or_else(|| {
+            anyhow!(
+                "Branch {} is not in the stack tree (or is its root).",
+                branch
+            )
+        })?
+        .name
+        .clone();
+    if state.get_tree(repo).is_some_and(|tree| tree.name == parent) {
+        bail!(
+            "Branch {} is stacked directly on trunk ({}); land it through its PR instead of \
+             folding.",
+            branch.yellow(),
+            parent.yellow()
+        );
+    }
+    if !git::run_git_status_clean()? {
+        bail!("Your working tree has uncommitted changes; commit or stash them before folding.");
+    }
+    let pr_number = state
+        .get_tree_branch(repo, branch)
+        .and_then(|b| b.pr_number);
+
+    let merge_base = git_repo.merge_base(&parent, branch)?;
+    let message = get_concatenated_commit_messages(branch, &merge_base)?;
+
+    run_git(&["checkout", "-q", &parent])?;
+    let status = git::run_git_passthrough(&["merge", "--squash", branch])?;
+    if !status.success() {
+        eprintln!(
+            "Squash-merging {} into {} hit a conflict. Resolve it and commit, or run `git reset \
+             --merge` to back out; {} has not been deleted.",
+            branch.yellow(),
+            parent.yellow(),
+            branch
+        );
+        bail!("fold did not finish");
+    }
+    if run_git(&["diff", "--cached", "--quiet"]).is_err() {
+        run_git(&["commit", "-q", "-m", &message])?;
+    } else {
+        println!(
+            "{} has no changes that aren't already in {}.",
+            branch.yellow(),
+            parent.yellow()
+        );
+    }
+
+    sync::unmount_branch_from_tree(git_repo, &mut state, repo, branch, &parent)?;
+    run_git(&["branch", "-D", branch])?;
+    println!("Folded {} into {}.", branch.yellow(), parent.green());
+    if let Some(pr_number) = pr_number {
+        eprintln!(
+            "{} PR #{} for {} is now orphaned; close it, or run `git stack sync` to reconcile.",
+            "warning:".yellow().bold(),
+            pr_number,
+            branch
+        );
+    }
+    Ok(())
+}
+
+/// `git stack delete --branch`/`--force`: delete `branch` from git (`-d`, or `-D` when `force`),
+/// then take it out of the tree with its children re-stacked on its parent. The git branch goes
+/// first, so a refused `-d` leaves the tree untouched.
+fn delete_with_git_branch(
+    git_repo: &GitRepo,
+    mut state: State,
+    repo: &str,
+    current_branch: &str,
+    branch: &str,
+    force: bool,
+) -> Result<()> {
+    let Some(parent) = state
+        .get_parent_branch_of(repo, branch)
+        .map(|p| p.name.clone())
+    else {
+        bail!("Branch {branch} not found in the git-stack tree.");
+    };
+    if branch == current_branch {
+        bail!(
+            "Can't delete {}: it is checked out. Check out another branch first.",
+            branch.yellow()
+        );
+    }
+    if git::git_branch_exists(git_repo, branch) {
+        let flag = if force { "-D" } else { "-d" };
+        run_git(&["branch", flag, branch]).with_context(|| {
+            format!(
+                "Could not delete {branch}; if it isn't merged, pass --force to delete it anyway"
+            )
+        })?;
+        println!("Deleted git branch {}.", branch.yellow());
+    }
+    sync::unmount_branch_from_tree(git_repo, &mut state, repo, branch, &parent)
+}
+
+/// `git stack move`: re-parent `branch` onto `onto` in the tree, then restack it and its
+/// descendants so git history matches. The branch keeps its replay boundary (`lkg_parent`), so
+/// only its own commits are carried over, not the old parent's.
+#[allow(clippy::too_many_arguments)]
+fn move_branch(
+    git_repo: &GitRepo,
+    mut state: State,
+    repo: &str,
+    run_version: String,
+    branch: &str,
+    onto: &str,
+    orig_branch: String,
+    push: bool,
+) -> Result<()> {
+    let Some(subtree) = state.get_tree_branch(repo, branch) else {
+        bail!("Branch {branch} not found in the git-stack tree.");
+    };
+    if state::is_branch_mentioned_in_tree(onto, subtree) {
+        bail!(
+            "Can't move {} onto {}: it is {} or one of its descendants.",
+            branch.yellow(),
+            onto.red(),
+            branch
+        );
+    }
+    if !state.branch_exists_in_tree(repo, onto) {
+        bail!("Parent branch {onto} not found in the git-stack tree.");
+    }
+    if !git::run_git_status_clean()? {
+        bail!("Your working tree has uncommitted changes; commit or stash them before moving.");
+    }
+
+    // Without a usable boundary, restack would fall back to `git rebase <onto>` and carry the old
+    // parent's commits along. Pin it to where the branch forked from its old parent.
+    let lkg_parent = subtree.lkg_parent.clone();
+    if lkg_parent
+        .as_deref()
+        .is_none_or(|lkg| !git_repo.is_ancestor(lkg, branch).unwrap_or(false))
+        && let Some(old_parent) = state.get_parent_branch_of(repo, branch)
+    {
+        let old_parent_ref = git_repo
+            .resolve_branch_ref(&old_parent.name)
+            .unwrap_or_else(|| old_parent.name.clone());
+        let fork_point = git_repo.merge_base(&old_parent_ref, branch).ok();
+        if let Some(node) = state.get_tree_branch_mut(repo, branch) {
+            node.lkg_parent = fork_point;
+        }
+    }
+
+    state.reparent_preserving_lkg(git_repo, repo, branch, onto.to_string())?;
+    let pr_number = state
+        .get_tree_branch(repo, branch)
+        .and_then(|b| b.pr_number);
+    restack(
+        git_repo,
+        state,
+        repo,
+        run_version,
+        Some(branch.to_string()),
+        orig_branch,
+        false,
+        push,
+        false,
+        false,
+        false,
+        true,
+        false,
+        None,
+        None,
+        None,
+    )?;
+    if let Some(pr_number) = pr_number {
+        println!(
+            "PR #{pr_number} still targets the old parent; run `git stack sync` to retarget it."
+        );
+    }
+    Ok(())
+}
+
+/// `git stack rebase-interactive`: `git rebase -i` `branch` over its own commits, then restack its
+/// descendants. Nothing is restacked if the rebase is aborted or stops partway.
+fn rebase_interactive(
+    git_repo: &GitRepo,
+    mut state: State,
+    repo: &str,
+    run_version: String,
+    branch: &str,
+    orig_branch: String,
+) -> Result<()> {
+    {
+        let _lock = git_repo.lock()?;
+        if git_repo.am_in_progress() || git_repo.rebase_in_progress() {
+            bail!("A rebase or `git am` is in progress; finish or abort it first.");
+        }
+        if !git::run_git_status_clean()? {
+            bail!(
+                "Your working tree has uncommitted changes; commit or stash them before rebasing."
+            );
+        }
+        let Some(parent) = state.get_parent_branch_of(repo, branch) else {
+            bail!("Branch {branch} is not in the git-stack tree (or is its root).");
+        };
+        let parent = parent.name.clone();
+        let Some(tree_branch) = state.get_tree_branch(repo, branch) else {
+            bail!("Branch {branch} not found in the git-stack tree.");
+        };
+        let base = match tree_branch.lkg_parent.clone() {
+            Some(lkg) => lkg,
+            None => git_repo.merge_base(&parent, branch)?,
+        };
+        let children: Vec<String> = tree_branch
+            .branches
+            .iter()
+            .map(|child| child.name.clone())
+            .collect();
+        let old_tip = git_repo.sha(branch)?;
+
+        // Pin each child's replay boundary to the old tip, so the restack below carries only the
+        // child's own commits over, not the ones this rebase rewrote.
+        for child in &children {
+            if git_repo.is_ancestor(&old_tip, child).unwrap_or(false)
+                && let Some(node) = state.get_tree_branch_mut(repo, child)
+            {
+                node.lkg_parent = Some(old_tip.clone());
+            }
+        }
+        state.save_state()?;
+
+        if orig_branch != branch {
+            run_git(&["checkout", "-q", branch])?;
+        }
+        // The todo list opens in an editor; don't hold up other git-stack processes meanwhile.
+        state::unlock_state();
+        let status = git::run_git_passthrough(&["rebase", "-i", &base])?;
+        if git_repo.rebase_in_progress() {
+            println!(
+                "The rebase of {} stopped partway; finish it with `git rebase --continue`.",
+                branch.yellow()
+            );
+            if !children.is_empty() {
+                println!("Then restack the branches stacked on it, starting with:");
+                for child in &children {
+                    println!("  git stack restack -b {child}");
+                }
+            }
+            bail!("rebase-interactive did not finish");
+        }
+        if !status.success() {
+            bail!("The rebase of {branch} was aborted; nothing was restacked.");
+        }
+        if git_repo.sha(branch)? == old_tip {
+            println!("{} is unchanged; nothing to restack.", branch.yellow());
+            return Ok(());
+        }
+        if children.is_empty() {
+            return Ok(());
+        }
+    }
+    state::lock_state()?;
+    restack(
+        git_repo,
+        state,
+        repo,
+        run_version,
+        Some(branch.to_string()),
+        orig_branch,
+        false,
+        false,
+        false,
+        false,
+        false,
+        true,
+        false,
+        None,
+        None,
+        None,
+    )
+}
+
+/// `git stack land`: merge the PR of the bottom branch of `current_branch`'s stack, then take that
+/// branch out of the tree (and git), point its children's PRs at trunk, and restack them.
+#[allow(clippy::too_many_arguments)]
+fn land(
+    git_repo: &GitRepo,
+    mut state: State,
+    repo: &str,
+    run_version: String,
+    current_branch: String,
+    force: bool,
+    method: Option<github::MergeMethod>,
+    push: bool,
+) -> Result<()> {
+    let method = method.unwrap_or_else(|| state.merge_method(repo));
+    let trunk = git::git_trunk(git_repo).ok_or_else(|| anyhow!("No remote configured"))?;
+    state.try_auto_mount(git_repo, repo, &current_branch)?;
+    let StackChain { links, .. } = stack_chain(&state, git_repo, repo, &current_branch)?;
+    let Some((_, landed)) = links.first().cloned() else {
+        bail!("{current_branch} is the trunk; there is nothing to land.");
+    };
+    if !force
+        && let Some(mark @ (BranchMark::Wip | BranchMark::Blocked)) = state
+            .get_tree_branch(repo, &landed)
+            .and_then(|branch| branch.mark)
+    {
+        bail!(
+            "{} is marked {mark}; run `git stack mark ready {landed}` first, or pass --force.",
+            landed.yellow()
+        );
+    }
+    if !git::run_git_status_clean()? {
+        bail!("Your working tree has uncommitted changes; commit or stash them before landing.");
+    }
+
+    let repo_id = github::get_repo_identifier(git_repo)?;
+    let client = provider::client_for(&repo_id)?;
+    let pr = match state
+        .get_tree_branch(repo, &landed)
+        .and_then(|b| b.pr_number)
+    {
+        Some(pr_number) => Some(client.get_pr(&repo_id, pr_number)?),
+        None => client.find_pr_for_branch(&repo_id, &landed)?,
+    };
+    let Some(pr) = pr else {
+        bail!(
+            "{} has no PR to land; create one with `git stack pr create`.",
+            landed.yellow()
+        );
+    };
+    if pr.state != github::PrState::Open || pr.merged {
+        bail!("PR #{} for {} is not open.", pr.number, landed.yellow());
+    }
+    if pr.draft {
+        bail!(
+            "PR #{} for {} is still a draft.",
+            pr.number,
+            landed.yellow()
+        );
+    }
+    if pr.base.ref_name != trunk.main_branch {
+        bail!(
+            "PR #{} for {} targets {}, not {}; run `git stack pr sync` first.",
+            pr.number,
+            landed.yellow(),
+            pr.base.ref_name.red(),
+            trunk.main_branch.green()
+        );
+    }
+
+    if let Some(warning) = merge_policy_mismatch(client.as_ref(), &repo_id, method) {
+        bail!("{warning} Pass --method, or set the default with `git stack init --merge-method`.");
+    }
+
+    let local_sha = git_repo.sha(&landed).ok();
+    if !force {
+        let readiness = client.pr_readiness(&repo_id, pr.number)?;
+        let blockers = land_blockers(
+            &readiness,
+            local_sha.as_deref() == Some(pr.head.sha.as_str()),
+        );
+        if !blockers.is_empty() {
+            for blocker in &blockers {
+                println!("  {} {blocker}", "✗".red());
+            }
+            bail!(
+                "Not landing PR #{} for {}; pass --force to land it anyway.",
+                pr.number,
+                landed
+            );
+        }
+    }
+
+    println!(
+        "Landing PR #{} for {} ({method})...",
+        pr.number.to_string().green(),
+        landed.yellow()
+    );
+    // Squash commits are titled like GitHub's own default, which GitLab doesn't follow.
+    let commit_title = format!("{} (#{})", pr.title, pr.number);
+    let commit_title = (method == github::MergeMethod::Squash).then_some(commit_title.as_str());
+    client
+        .merge_pr(&repo_id, pr.number, &pr.head.sha, method, commit_title)
+        .map_err(|e| anyhow!("Failed to merge PR #{}: {}", pr.number, e))?;
+    git::git_fetch(git_repo, FetchOptions::default())?;
+
+    let children: Vec<(String, Option<u64>)> = state
+        .get_tree_branch(repo, &landed)
+        .map(|b| {
+            b.branches
+                .iter()
+                .map(|child| (child.name.clone(), child.pr_number))
+                .collect()
+        })
+        .unwrap_or_default();
+    sync::unmount_branch_from_tree(git_repo, &mut state, repo, &landed, &trunk.main_branch)?;
+    for (child, pr_number) in &children {
+        let pr_number = match pr_number {
+            Some(pr_number) => Some(*pr_number),
+            None => client
+                .find_pr_for_branch(&repo_id, child)?
+                .map(|pr| pr.number),
+        };
+        let Some(pr_number) = pr_number else {
+            continue;
+        };
+        match client.update_pr(
+            &repo_id,
+            pr_number,
+            github::UpdatePrRequest {
+                base: Some(&trunk.main_branch),
+                title: None,
+                body: None,
+            },
+        ) {
+            Ok(_) => println!(
+                "Retargeted PR #{} base to '{}'.",
+                pr_number, trunk.main_branch
+            ),
+            Err(e) => tracing::warn!("Failed to retarget PR #{pr_number}: {e}"),
+        }
+    }
+
+    let orig_branch = if current_branch == landed {
+        let next = children
+            .first()
+            .map_or_else(|| trunk.main_branch.clone(), |(child, _)| child.clone());
+        git::checkout_tracked_branch(git_repo, &trunk.main_branch)?;
+        next
+    } else {
+        current_branch
+    };
+    if local_sha.as_deref() == Some(pr.head.sha.as_str()) {
+        run_git(&["branch", "-D", &landed])?;
+    } else if local_sha.is_some() {
+        eprintln!(
+            "{} Keeping local branch {}: it has commits that were not in PR #{}.",
+            "warning:".yellow().bold(),
+            landed,
+            pr.number
+        );
+    }
+    state.save_state()?;
+    println!(
+        "Landed {} into {}.",
+        landed.yellow(),
+        trunk.main_branch.green()
+    );
+
+    for (child, _) in children {
+        restack(
+            git_repo,
+            state,
+            repo,
+            run_version.clone(),
+            Some(child),
+            orig_branch.clone(),
+            false,
+            push,
+            false,
+            false,
+            false,
+            true,
+            false,
+            None,
+            None,
+            None,
+        )?;
+        state = State::load_state()?;
+    }
+    Ok(())
+}
+
+/// A warning when the repo's settings don't allow merging PRs with `method`, e.g. branch
+/// protection that requires squash while git-stack is set to make merge commits. `None` when
+/// `method` is allowed or the settings can't be read.
+fn merge_policy_mismatch(
+    client: &dyn provider::RemoteProvider,
+    repo_id: &github::RepoIdentifier,
+    method: github::MergeMethod,
+) -> Option<String> {
+    let allowed = client.allowed_merge_methods(repo_id).ok()?;
+    if allowed.is_empty() || allowed.contains(&method) {
+        return None;
+    }
+    let allowed: Vec<String> = allowed.iter().map(ToString::to_string).collect();
+    Some(format!(
+        "{} doesn't allow {method} merges (allowed: {}).",
+        repo_id.full_name(),
+        allowed.join(", ")
+    ))
+}
+
+/// Why `land` should hold off on a PR: unfinished or failing checks, a mergeable state GitHub
+/// doesn't call clean, or a local branch that has moved past the PR's head.
+fn land_blockers(readiness: &github::PrReadiness, local_matches_head: bool) -> Vec<String> {
+    let mut blockers = Vec::new();
+    if readiness.checks.failing > 0 {
+        blockers.push(format!("{} failing checks", readiness.checks.failing));
+    }
+    if readiness.checks.pending > 0 {
+        blockers.push(format!("{} pending checks", readiness.checks.pending));
+    }
+    match readiness.mergeable.as_deref() {
+        Some("clean" | "has_hooks" | "unstable" | "mergeable") => {}
+        Some(state) => blockers.push(format!("not mergeable ({state})")),
+        None => blockers.push("mergeability unknown".to_string()),
+    }
+    if !local_matches_head {
+        blockers.push("the local branch differs from the PR's head".to_string());
+    }
+    blockers
+}
+
+/// Report state-file integrity problems (`State::validate`) plus this repo's [`DoctorFix`]
+/// problems. With `fix`, repair the latter. Fails if anything was found and is still unfixed.
+fn doctor(git_repo: &GitRepo, mut state: State, repo: &str, fix: bool) -> Result<()> {
+    let unfixable = state.validate().err().unwrap_or_default();
+    let fixes = doctor_fixes(git_repo, &state, repo);
+
+    if unfixable.is_empty() && fixes.is_empty() {
+        println!("{}", "No problems found.".green());
+        return Ok(());
+    }
+    for problem in unfixable
+        .iter()
+        .chain(fixes.iter().map(|(problem, _)| problem))
+    {
+        println!("{} {}", "problem:".red().bold(), problem);
+    }
+    if !fix {
+        if !fixes.is_empty() {
+            println!("Run `git stack doctor --fix` to repair what it can.");
+        }
+        if !unfixable.is_empty() {
+            println!("Fix duplicate and empty branch names with `git stack edit`.");
+        }
+        bail!("{} problem(s) found", unfixable.len() + fixes.len());
+    }
+
+    if let Some(backup) = State::backup_state_file()? {
+        println!(
+            "Backed up the state file to {}; copy it back over state.yaml to undo.",
+            backup.display()
+        );
+    }
+    let mut unfixed = unfixable.len();
+    let fixes: Vec<DoctorFix> = fixes.into_iter().map(|(_, fix)| fix).collect();
+    if fixes.contains(&DoctorFix::SetRemoteHead) {
+        match run_git(&["remote", "set-head", DEFAULT_REMOTE, "--auto"]) {
+            Ok(_) => println!(
+                "{} {DEFAULT_REMOTE}/HEAD (undo with `git remote set-head {DEFAULT_REMOTE} -d`).",
+                "Set".green()
+            ),
+            Err(e) => {
+                println!("Couldn't set {DEFAULT_REMOTE}/HEAD: {e:#}");
+                unfixed += 1;
+            }
+        }
+    }
+    let removals = fixes
+        .iter()
+        .filter(|fix| matches!(fix, DoctorFix::Remove(_)))
+        .count();
+    if removals > 0 {
+        if std::io::stdin().is_terminal() && confirm_doctor_removals(removals) {
+            state.auto_cleanup_missing_branches(git_repo, repo)?;
+        } else {
+            println!("Kept the missing branches in the tree.");
+            unfixed += removals;
+        }
+    }
+    for fix in &fixes {
+        if let DoctorFix::ClearLkgParent(name) = fix
+            && let Some(branch) = state.get_tree_branch_mut(repo, name)
+        {
+            branch.lkg_parent = None;
+            println!("{} the LKG parent of {}.", "Cleared".green(), name.yellow());
+        }
+    }
+    state.save_state()?;
+    for fix in &fixes {
+        if let DoctorFix::Mount(name) = fix {
+            state.try_auto_mount(git_repo, repo, name)?;
+        }
+    }
+
+    if unfixed > 0 {
+        bail!("{unfixed} problem(s) left unfixed");
+    }
+    Ok(())
+}
+
+/// A problem in this repo that `doctor --fix` can repair.
+#[derive(Debug, PartialEq, Eq)]
+enum DoctorFix {
+    /// `origin/HEAD` isn't set, so the trunk can't be found: detect it from the remote.
+    SetRemoteHead,
+    /// A tracked branch exists neither locally nor on the remote: drop it from the tree, its
+    /// children moving up to its parent.
+    Remove(String),
+    /// A branch's LKG parent isn't a known commit: forget it.
+    ClearLkgParent(String),
+    /// A local branch is stacked on a tracked branch but isn't in the tree: mount it on the
+    /// deepest tracked branch it's based on.
+    Mount(String),
+}
+
+/// This repo's repairable problems, each with the line `doctor` reports for it.
+fn doctor_fixes(git_repo: &GitRepo, state: &State, repo: &str) -> Vec<(String, DoctorFix)> {
+    let mut fixes = Vec::new();
+    if git_trunk(git_repo).is_none()
+        && state.trunk_override(repo).is_none()
+        && git_repo.get_remote_url(DEFAULT_REMOTE).is_ok()
+    {
+        fixes.push((
+            format!("{repo}: {DEFAULT_REMOTE}/HEAD is not set, so the trunk can't be found"),
+            DoctorFix::SetRemoteHead,
+        ));
+    }
+    let Some(tree) = state.get_tree(repo) else {
+        return fixes;
+    };
+
+    let mut tracked = std::collections::HashSet::new();
+    for name in collect_all_branch_names(tree) {
+        // Empty and duplicate names were already reported by `validate`.
+        if name.trim().is_empty() || !tracked.insert(name.clone()) {
+            continue;
+        }
+        if git_repo.resolve_branch_ref(&name).is_none() {
+            fixes.push((
+                format!(
+                    "{repo}: branch '{name}' is tracked but exists neither locally nor on \
+                     {DEFAULT_REMOTE}"
+                ),
+                DoctorFix::Remove(name),
+            ));
+        }
+    }
+
+    for branch in tree.iter() {
+        if let Some(sha) = branch.lkg_parent.as_deref()
+            && !git_repo.ref_exists(sha)
+        {
+            fixes.push((
+                format!(
+                    "{repo}: branch '{}' has LKG parent {sha}, which isn't a known commit",
+                    branch.name
+                ),
+                DoctorFix::ClearLkgParent(branch.name.clone()),
+            ));
+        }
+    }
+
+    let mut untracked: Vec<String> = git_repo
+        .local_branch_names()
+        .into_iter()
+        .filter(|name| !tracked.contains(name))
+        .collect();
+    untracked.sort();
+    for name in untracked {
+        let sha = git_repo.sha(&name).ok();
+        let stacked_on = tree.iter().skip(1).find(|branch| {
+            git_repo.sha(&branch.name).ok() != sha
+                && git_repo.is_ancestor(&branch.name, &name).unwrap_or(false)
+        });
+        if let Some(parent) = stacked_on {
+            fixes.push((
+                format!(
+                    "{repo}: local branch '{name}' is stacked on '{}' but isn't in the tree",
+                    parent.name
+                ),
+                DoctorFix::Mount(name),
+            ));
+        }
+    }
+    fixes
+}
+
+fn confirm_doctor_removals(count: usize) -> bool {
+    print!("Remove {count} missing branch(es) from the git-stack tree? [y/N] ");
+    let _ = std::io::stdout().flush();
+    let mut input = String::new();
+    if std::io::stdin().read_line(&mut input).is_err() {
+        return false;
+    }
+    matches!(input.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Print the stack tree as a graph. PR states come from the local PR cache only (no network), so
+/// merged/closed styling reflects whatever the last `status`/`sync` observed.
+fn graph(git_repo: &GitRepo, state: &State, repo: &str, format: render::GraphFormat) -> Result<()> {
+    let tree = state
+        .get_tree(repo)
+        .ok_or_else(|| anyhow!("No stack tree found for repo {}", repo))?;
+    let pr_states = load_cached_pr_states(git_repo).unwrap_or_default();
+    print!("{}", render::render_graph(tree, format, &pr_states));
+    Ok(())
+}
+
+/// Read branch -> PR display state from the local redb PR cache. An open PR wins over a closed
+/// one for the same head ref (a reused branch name). Pure-local, no network.
+fn load_cached_pr_states(
+    git_repo: &GitRepo,
+) -> Option<std::collections::HashMap<String, github::PrDisplayState>> {
+    let repo_id = github::get_repo_identifier(git_repo).ok()?;
+    let cache = crate::pr_cache::PrCacheHandle::open().ok()?;
+    let repo_key = repo_id.full_name();
+    let mut states: std::collections::HashMap<String, github::PrDisplayState> = cache
+        .closed_prs_for_repo(&repo_key)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(branch, pr)| (branch, pr.display_state()))
+        .collect();
+    if let Ok(open) = cache.open_prs_for_repo(&repo_key) {
+        states.extend(
+            open.into_iter()
+                .map(|(branch, pr)| (branch, pr.display_state())),
+        );
+    }
+    Some(states)
+}
+
+fn show_log(state: State, repo: &str, branch: &str) -> Result<()> {
+    let parent_branch = state
+        .get_parent_branch_of(repo, branch)
+        .ok_or_else(|| anyhow!("No parent branch found for current branch: {}", branch))?;
+    tracing::debug!(
+        parent_branch = &parent_branch.name,
+        branch = branch,
+        "Log changes"
+    );
+    let status = git::run_git_passthrough(&[
+        "log",
+        "--graph",
+        "--oneline",
+        "-p",
+        "--decorate",
         &format!("{}..{}", &parent_branch.name, branch),
     ])?;
     if !status.success() {
@@ -612,6 +2180,99 @@ fn show_log(state: State, repo: &str, branch: &str) -> Result<()> {
     Ok(())
 }
 
+/// The branches `log --stack` covers.
+struct StackChain {
+    /// `(parent, branch)` pairs, trunk-side first.
+    links: Vec<(String, String)>,
+    /// Where the walk down stopped because a branch has several children.
+    fork: Option<String>,
+}
+
+/// `log --stack`: the chain from trunk through `branch`, continued down through single-child
+/// descendants to the stack's leaf (stopping where the stack forks).
+fn stack_chain(state: &State, git_repo: &GitRepo, repo: &str, branch: &str) -> Result<StackChain> {
+    let mut links: Vec<(String, String)> = state
+        .plan_restack(git_repo, repo, branch, true)?
+        .into_iter()
+        .map(|step| (step.parent, step.branch.name.clone()))
+        .collect();
+    let mut fork = None;
+    let mut tip = state
+        .get_tree_branch(repo, branch)
+        .ok_or_else(|| anyhow!("Branch {branch} not found in the git-stack tree."))?;
+    loop {
+        match tip.branches.as_slice() {
+            [only] => {
+                links.push((tip.name.clone(), only.name.clone()));
+                tip = only;
+            }
+            [] => break,
+            _ => {
+                fork = Some(tip.name.clone());
+                break;
+            }
+        }
+    }
+    Ok(StackChain { links, fork })
+}
+
+/// Print `git log --graph --oneline` for every branch in `branch`'s stack, leaf first, each
+/// under a header naming the branch and what it's stacked on.
+fn show_stack_log(state: &State, git_repo: &GitRepo, repo: &str, branch: &str) -> Result<()> {
+    let StackChain { links, fork } = stack_chain(state, git_repo, repo, branch)?;
+    if links.is_empty() {
+        bail!("{branch} is the trunk; it has no stack to show.");
+    }
+    for (parent, branch) in links.iter().rev() {
+        println!(
+            "{} {} {} {}",
+            "──".dimmed(),
+            branch.yellow().bold(),
+            "on".dimmed(),
+            parent.green()
+        );
+        std::io::stdout().flush()?;
+        let status = git::run_git_passthrough(&[
+            "log",
+            "--graph",
+            "--oneline",
+            "--decorate",
+            &format!("{parent}..{branch}"),
+        ])?;
+        if !status.success() {
+            bail!("git log failed");
+        }
+    }
+    if let Some(fork) = fork {
+        println!(
+            "{}",
+            format!("({fork} has several children; pass one of them to see further down.)")
+                .dimmed()
+        );
+    }
+    Ok(())
+}
+
+/// `note --all`: the first line of each note in `branch`'s stack, trunk-side first.
+fn show_stack_notes(state: &State, git_repo: &GitRepo, repo: &str, branch: &str) -> Result<()> {
+    let StackChain { links, .. } = stack_chain(state, git_repo, repo, branch)?;
+    if links.is_empty() {
+        bail!("{branch} is the trunk; it has no stack to show.");
+    }
+    for (_, name) in &links {
+        let first_line = state
+            .get_tree_branch(repo, name)
+            .and_then(|branch| branch.note.as_deref())
+            .and_then(|note| note.lines().next())
+            .filter(|line| !line.is_empty());
+        match first_line {
+            Some(line) => println!("{}: {}", name.yellow(), line),
+            None => println!("{}: {}", name.yellow(), "(no note)".dimmed()),
+        }
+    }
+    Ok(())
+}
+
 /// Open-PR fetch feeding the render's PR badges + `authors_filter` hiding (never deletion).
 ///
 /// Behavior is deterministic per command — no TTL, no staleness clock:
@@ -632,26 +2293,31 @@ fn show_log(state: State, repo: &str, branch: &str) -> Result<()> {
 /// Runs the same `fetch_pr_cache` → `add_closed_pr_authors` → `add_commit_authors` pipeline for
 /// both callers: `build_renderable_tree` (which also consumes the badge cache) and the `cleanup`
 /// prune path (authors only). Network-capable, so it lives in `main.rs` — `state.rs` stays
-/// network-free.
+/// network-free. `offline` (from `status --no-fetch-trunk`) keeps every step on the local caches.
 fn resolve_pr_authors(
     git_repo: &GitRepo,
     tree: &Branch,
     current_branch: &str,
     force_full: bool,
+    offline: bool,
 ) -> (
     std::collections::HashMap<String, String>,
     bool,
     Option<std::collections::HashMap<String, github::PullRequest>>,
 ) {
     let branch_names = collect_all_branch_names(tree);
-    let pr_result = fetch_pr_cache(git_repo, &branch_names, force_full);
+    let pr_result = fetch_pr_cache(git_repo, &branch_names, force_full, offline);
     let served_from_cache = pr_result.as_ref().is_some_and(|(_, cached)| *cached);
-    let open_authors = pr_result
+    let mut pr_authors = pr_result
         .as_ref()
         .map(|(r, _)| r.all_authors.clone())
         .unwrap_or_default();
-    let mut pr_authors = add_closed_pr_authors(git_repo, open_authors);
-    add_commit_authors(git_repo, tree, current_branch, &mut pr_authors);
+    if offline {
+        pr_authors.extend(load_closed_pr_authors(git_repo).unwrap_or_default());
+    } else {
+        pr_authors = add_closed_pr_authors(git_repo, pr_authors);
+        add_commit_authors(git_repo, tree, current_branch, &mut pr_authors);
+    }
     let pr_cache = pr_result.map(|(r, _)| r.prs);
     (pr_authors, served_from_cache, pr_cache)
 }
@@ -660,6 +2326,7 @@ fn fetch_pr_cache(
     git_repo: &GitRepo,
     branches: &[String],
     force_full: bool,
+    offline: bool,
 ) -> Option<(github::PrListResult, bool)> {
     use crate::pr_cache::PrCacheHandle;
     use github::CachedPullRequest;
@@ -667,9 +2334,13 @@ fn fetch_pr_cache(
     let repo_id = github::get_repo_identifier(git_repo).ok()?;
     let repo_key = repo_id.full_name();
     let cache = PrCacheHandle::open().ok();
-    let client = github::GitHubClient::from_env(&repo_id).ok();
+    let client = if offline {
+        None
+    } else {
+        provider::client_for(&repo_id).ok()
+    };
 
-    // No client (e.g. no token): serve entirely from cache if we have anything.
+    // No client (e.g. no token, or offline): serve entirely from cache if we have anything.
     let Some(client) = client else {
         let cached = cache?.open_prs_for_repo(&repo_key).ok()?;
         if cached.is_empty() {
@@ -756,15 +2427,7 @@ fn fetch_pr_cache(
 /// Collect the names of every branch in `tree` (trunk included — harmless, since `apply_pr_cache`
 /// looks up by tree branch name). Used to scope the default open-PR fetch to just the stack.
 fn collect_all_branch_names(tree: &Branch) -> Vec<String> {
-    let mut names = Vec::new();
-    fn walk(branch: &Branch, names: &mut Vec<String>) {
-        names.push(branch.name.clone());
-        for child in &branch.branches {
-            walk(child, names);
-        }
-    }
-    walk(tree, &mut names);
-    names
+    tree.iter().map(|branch| branch.name.clone()).collect()
 }
 
 /// Extend an open-PR author lookup (from `fetch_pr_cache`'s `all_authors`) with authors of
@@ -783,6 +2446,7 @@ fn eager_refresh_lkgs(
     state: &mut State,
     repo: &str,
     current_branch: &str,
+    force: bool,
 ) -> Result<()> {
     // Network-free by design: resolve the filter from cache only. A cold cache (or no GitHub
     // remote) yields `None`, which falls through to a full refresh — the historical behavior for
@@ -794,9 +2458,9 @@ fn eager_refresh_lkgs(
         Some(filter) if !filter.is_empty() => {
             let closed_pr_authors = load_closed_pr_authors(git_repo).unwrap_or_default();
             let scope = state.eager_lkg_scope(repo, current_branch, &filter, &closed_pr_authors);
-            state.refresh_lkgs_scoped(git_repo, repo, &scope)
+            state.refresh_lkgs_if_changed(git_repo, repo, Some(&scope), force)
         }
-        _ => state.refresh_lkgs(git_repo, repo),
+        _ => state.refresh_lkgs_if_changed(git_repo, repo, None, force),
     }
 }
 
@@ -811,6 +2475,26 @@ fn effective_authors_filter(git_repo: &GitRepo) -> Result<Vec<String>> {
     }
 }
 
+/// The author filter for `status`/`interactive`: the `--author` logins when given, otherwise the
+/// effective `authors_filter`. `offline` resolves it from the cached login only; with none cached
+/// it filters nothing rather than looking the login up.
+fn authors_filter_for(
+    git_repo: &GitRepo,
+    authors: &[String],
+    offline: bool,
+) -> Result<Vec<String>> {
+    if !authors.is_empty() {
+        Ok(authors.to_vec())
+    } else if offline {
+        Ok(github::get_repo_identifier(git_repo)
+            .ok()
+            .and_then(|repo_id| github::resolve_effective_authors_filter_cached(&repo_id))
+            .unwrap_or_default())
+    } else {
+        effective_authors_filter(git_repo)
+    }
+}
+
 /// Read branch -> login from the local redb closed-PR cache. Pure-local, no network.
 fn load_closed_pr_authors(git_repo: &GitRepo) -> Option<std::collections::HashMap<String, String>> {
     let repo_id = github::get_repo_identifier(git_repo).ok()?;
@@ -831,15 +2515,21 @@ fn add_closed_pr_authors(
     let Ok(repo_id) = github::get_repo_identifier(git_repo) else {
         return authors;
     };
-    let Ok(client) = github::GitHubClient::from_env(&repo_id) else {
+    let Ok(client) = provider::client_for(&repo_id) else {
         return authors;
     };
 
     let Ok(cache) = crate::pr_cache::PrCacheHandle::open() else {
         return authors;
     };
-    if let Ok(result) = client.list_closed_prs_with_cache(&repo_id, &cache, None) {
-        authors.extend(result.all_authors);
+    if let Ok(closed) = client.list_closed_prs_with_cache(
+        &repo_id,
+        &cache,
+        &github::ClosedPrLimits::default(),
+        github::ClosedPrCacheMode::Incremental,
+        None,
+    ) {
+        authors.extend(closed.result.all_authors);
     }
 
     authors
@@ -866,6 +2556,11 @@ fn add_commit_authors(
     let Ok(repo_id) = github::get_repo_identifier(git_repo) else {
         return;
     };
+    // Commit → account attribution is a GitHub-only API; other providers keep such branches
+    // visible ("can't tell ⇒ don't hide").
+    if provider::ProviderKind::for_host(&repo_id.host) != provider::ProviderKind::GitHub {
+        return;
+    }
     let Ok(client) = github::GitHubClient::from_env(&repo_id) else {
         return;
     };
@@ -997,7 +2692,7 @@ fn collect_branches_without_author(
 /// require the fetch to finish first (i.e. whenever authors_filter filtering isn't active).
 ///
 /// The default fetch is stack-scoped (see `fetch_pr_cache`); `force_full` (from `gs --fetch`)
-/// switches it to the authoritative whole-repo fetch. Returns the tree plus `served_from_cache`:
+/// switches it to the authoritative whole-repo fetch, and `offline` skips it for the cache. Returns the tree plus `served_from_cache`:
 /// true when any displayed PR badge came from the offline cache fallback (so the caller can warn).
 #[allow(clippy::too_many_arguments)]
 fn build_renderable_tree(
@@ -1009,6 +2704,9 @@ fn build_renderable_tree(
     show_all: bool,
     authors_filter: &[String],
     force_full: bool,
+    offline: bool,
+    max_depth: Option<usize>,
+    progress: bool,
 ) -> (render::RenderableTree, bool) {
     let hiding_active = !show_all && !authors_filter.is_empty();
     let branch_names = collect_all_branch_names(tree);
@@ -1017,7 +2715,7 @@ fn build_renderable_tree(
         // pr_authors depends on the fetch result plus further network calls below, so there's
         // no local work left to overlap the fetch with in this mode.
         let (pr_authors, served_from_cache, pr_cache) =
-            resolve_pr_authors(git_repo, tree, orig_branch, force_full);
+            resolve_pr_authors(git_repo, tree, orig_branch, force_full, offline);
 
         let renderable = render::compute_renderable_tree(
             git_repo,
@@ -1027,6 +2725,8 @@ fn build_renderable_tree(
             authors_filter,
             &pr_authors,
             show_all,
+            max_depth,
+            progress,
         );
         (renderable, pr_cache, served_from_cache)
     } else {
@@ -1037,11 +2737,12 @@ fn build_renderable_tree(
 
         let (renderable, pr_result, fetch_stats) = std::thread::scope(|scope| {
             let fetch_branches = branch_names.clone();
+            // Own GitRepo handle: git2::Repository is Send but not Sync, and the main thread is
+            // using `git_repo` concurrently below.
+            let fetch_repo = git_repo.init_repo_for_thread().ok();
             let fetch_handle = scope.spawn(move || {
-                // Own GitRepo handle: git2::Repository is Send but not Sync, and the main
-                // thread is using `git_repo` concurrently below.
-                let result = GitRepo::open(&repo_path).ok().and_then(|fresh_repo| {
-                    fetch_pr_cache(&fresh_repo, &fetch_branches, force_full)
+                let result = fetch_repo.and_then(|fetch_repo| {
+                    fetch_pr_cache(&fetch_repo, &fetch_branches, force_full, offline)
                 });
                 (result, crate::stats::get_stats())
             });
@@ -1054,6 +2755,8 @@ fn build_renderable_tree(
                 authors_filter,
                 &pr_authors,
                 show_all,
+                max_depth,
+                progress,
             );
 
             let (pr_result, fetch_stats) = fetch_handle
@@ -1070,17 +2773,25 @@ fn build_renderable_tree(
     (renderable, served_from_cache)
 }
 
+/// How old the last `sync` can get before `status` refreshes the whole PR cache.
+const STALE_SYNC_AGE: chrono::TimeDelta = chrono::TimeDelta::hours(24);
+
+#[allow(clippy::too_many_arguments)]
 fn status(
     git_repo: &GitRepo,
     mut state: State,
     repo: &str,
     orig_branch: &str,
     fetch: bool,
+    no_fetch_trunk: bool,
+    json: bool,
     verbose: bool,
     show_all: bool,
+    authors: &[String],
+    depth: Option<usize>,
 ) -> Result<()> {
     if fetch {
-        git_fetch()?;
+        git_fetch(git_repo, FetchOptions::default())?;
     }
     // ensure_trunk creates the tree if it doesn't exist (no-op if no remote)
     let _trunk = state.ensure_trunk(git_repo, repo);
@@ -1089,14 +2800,36 @@ fn status(
     state.auto_cleanup_missing_branches(git_repo, repo)?;
 
     let Some(tree) = state.get_tree(repo) else {
-        println!("No stack configured for this repository.");
+        if json {
+            // Keep stdout parseable: an empty tree, with the explanation on stderr.
+            eprintln!(
+                "No stack configured for this repository. Run `git stack init` to start one."
+            );
+            let empty = render::RenderableTree {
+                branches: Vec::new(),
+                current_branch_index: None,
+            };
+            println!("{}", serde_json::to_string_pretty(&empty)?);
+        } else {
+            println!("No stack configured for this repository. Run `git stack init` to start one.");
+        }
         return Ok(());
     };
 
     // Resolve the effective author filter (unset → your own login; hides branches whose PR author
     // isn't listed). Done after the "no stack" guard so a brand-new user in a stackless repo sees
     // that message rather than an identity-resolution error.
-    let authors_filter = effective_authors_filter(git_repo)?;
+    let authors_filter = authors_filter_for(git_repo, authors, no_fetch_trunk)?;
+
+    // PR states drift once a sync gets old; refresh the whole PR cache rather than just the stack's
+    // branches, as `--fetch` does. Such a refresh counts as fresh data for the next `status`.
+    let now = chrono::Utc::now();
+    let sync_age = state.last_sync(repo).map(|last_sync| now - last_sync);
+    let sync_is_stale = sync_age.is_some_and(|age| age > STALE_SYNC_AGE)
+        && state
+            .last_pr_refresh(repo)
+            .is_none_or(|refreshed| now - refreshed > STALE_SYNC_AGE);
+    let full_refresh = fetch || (sync_is_stale && !no_fetch_trunk);
 
     let (renderable, served_from_cache) = build_renderable_tree(
         git_repo,
@@ -1106,13 +2839,27 @@ fn status(
         verbose,
         show_all,
         &authors_filter,
-        fetch,
+        full_refresh,
+        no_fetch_trunk,
+        depth,
+        true,
     );
+    if full_refresh && !served_from_cache {
+        state.record_pr_refresh(repo);
+    }
 
-    // Render to CLI
-    render::render_cli(&renderable, verbose);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&renderable)?);
+    } else {
+        let theme = render::Theme::load();
+        if let Some(age) = sync_age {
+            render::render_sync_age(age, &theme);
+        }
+        render::render_cli(&renderable, verbose, &theme);
+    }
 
-    if served_from_cache {
+    // Cached data is what `--no-fetch-trunk` asked for, not a fallback worth a note.
+    if served_from_cache && !no_fetch_trunk {
         eprintln!(
             "{}",
             "note: showing cached PR data (a live fetch failed; run `git stack status --fetch` to \
@@ -1133,6 +2880,46 @@ fn status(
     Ok(())
 }
 
+/// `status --watch`: [`status`] on every change to the repo's refs, with freshly loaded state and
+/// the then-current branch. Only the first draw fetches.
+#[allow(clippy::too_many_arguments)]
+fn watch_status(
+    git_repo: &GitRepo,
+    state: State,
+    repo: &str,
+    fetch: bool,
+    no_fetch_trunk: bool,
+    verbose: bool,
+    show_all: bool,
+    authors: &[String],
+    depth: Option<usize>,
+) -> Result<()> {
+    let mut first = Some(state);
+    watch::watch(git_repo, || {
+        let fetch = fetch && first.is_some();
+        // The redraw may be for `origin/HEAD` itself moving.
+        git_repo.forget_trunk();
+        let state = match first.take() {
+            Some(state) => state,
+            None => State::load_state()?,
+        };
+        let current_branch = git_repo.current_branch()?;
+        status(
+            git_repo,
+            state,
+            repo,
+            &current_branch,
+            fetch,
+            no_fetch_trunk,
+            false,
+            verbose,
+            show_all,
+            authors,
+            depth,
+        )
+    })
+}
+
 fn interactive(
     git_repo: &GitRepo,
     mut state: State,
@@ -1140,6 +2927,7 @@ fn interactive(
     orig_branch: &str,
     verbose: bool,
     show_all: bool,
+    authors: &[String],
 ) -> Result<()> {
     // ensure_trunk creates the tree if it doesn't exist (no-op if no remote)
     let _trunk = state.ensure_trunk(git_repo, repo);
@@ -1148,14 +2936,14 @@ fn interactive(
     state.auto_cleanup_missing_branches(git_repo, repo)?;
 
     let Some(tree) = state.get_tree(repo) else {
-        println!("No stack configured for this repository.");
+        println!("No stack configured for this repository. Run `git stack init` to start one.");
         return Ok(());
     };
 
     // Resolve the effective author filter (unset → your own login; hides branches whose PR author
     // isn't listed). Done after the "no stack" guard so a brand-new user in a stackless repo sees
     // that message rather than an identity-resolution error.
-    let authors_filter = effective_authors_filter(git_repo)?;
+    let authors_filter = authors_filter_for(git_repo, authors, false)?;
 
     let (renderable, served_from_cache) = build_renderable_tree(
         git_repo,
@@ -1166,6 +2954,9 @@ fn interactive(
         show_all,
         &authors_filter,
         false,
+        false,
+        None,
+        true,
     );
 
     if served_from_cache {
@@ -1177,15 +2968,18 @@ fn interactive(
         );
     }
 
+    // Shared by the refresh and preview callbacks; refresh replaces it with a fresh load.
+    let state = RefCell::new(state);
     let branch_to_checkout = {
         let mut refresh = || {
-            state = State::load_state()?;
+            let mut state = state.borrow_mut();
+            *state = State::load_state()?;
             let _trunk = state.ensure_trunk(git_repo, repo);
             state.auto_cleanup_missing_branches(git_repo, repo)?;
             let tree = state
                 .get_tree(repo)
                 .ok_or_else(|| anyhow!("No stack configured for this repository."))?;
-            let authors_filter = effective_authors_filter(git_repo)?;
+            let authors_filter = authors_filter_for(git_repo, authors, false)?;
             Ok(build_renderable_tree(
                 git_repo,
                 repo,
@@ -1195,12 +2989,40 @@ fn interactive(
                 show_all,
                 &authors_filter,
                 false,
+                false,
+                None,
+                // The TUI owns the screen by now.
+                false,
             )
             .0)
         };
 
-        tui::run_tui(renderable, verbose, &mut refresh)?
+        // Diff against the LKG parent when recorded, like `git stack diff`.
+        let mut preview = |name: &str| {
+            let state = state.borrow();
+            let parent = state
+                .get_parent_branch_of(repo, name)
+                .ok_or_else(|| anyhow!("'{name}' is the trunk; there is nothing to diff."))?;
+            let base = state
+                .get_tree_branch(repo, name)
+                .and_then(|branch| branch.lkg_parent.clone())
+                .or_else(|| git_repo.resolve_branch_ref(&parent.name))
+                .unwrap_or_else(|| parent.name.clone());
+            let head = git_repo
+                .resolve_branch_ref(name)
+                .unwrap_or_else(|| name.to_string());
+            tui::BranchPreview::load(git_repo, &base, &head)
+        };
+
+        tui::run_tui(
+            renderable,
+            verbose,
+            render::Theme::load(),
+            &mut refresh,
+            &mut preview,
+        )?
     };
+    let state = state.into_inner();
 
     // Handle checkout if the user selected a branch.
     if let Some(branch_to_checkout) = branch_to_checkout {
@@ -1313,11 +3135,24 @@ fn print_restack_conflict_help(what: &str, skip_supported: bool) {
     eprintln!("  {}", "git stack restack --abort".yellow().bold());
 }
 
+/// Tell the user where an `--autostash` stash is while a restack is paused on a conflict.
+fn print_pending_autostash(stash: Option<&str>) {
+    if let Some(stash) = stash {
+        eprintln!();
+        eprintln!(
+            "Your local changes are stashed as {}; they are restored when the restack finishes or \
+             is aborted.",
+            stash.yellow()
+        );
+    }
+}
+
 /// Persist a pending-restack record for the conflicting branch, print guidance, and `exit(1)`.
 /// `original_sha` is the branch tip captured *before* the ref moved.
 #[allow(clippy::too_many_arguments)]
 fn record_restack_conflict(
     state: &mut State,
+    restack_log: &mut RestackLog,
     repo: &str,
     method: RestackMethod,
     branch_name: &str,
@@ -1326,6 +3161,15 @@ fn record_restack_conflict(
     resume: RestackResume,
     what: &str,
 ) -> ! {
+    restack_log.record(&RestackLogEntry {
+        branch: branch_name,
+        parent,
+        source_sha: original_sha,
+        method: Some(method),
+        outcome: RestackOutcome::Conflict,
+        result_sha: None,
+        pushed: false,
+    });
     state.set_pending_restack(
         repo,
         Some(PendingRestackOperation {
@@ -1344,20 +3188,32 @@ fn record_restack_conflict(
     // `--skip` only makes sense for the patch-replay mechanics (am/rebase).
     let skip_supported = matches!(method, RestackMethod::Am | RestackMethod::Rebase);
     print_restack_conflict_help(what, skip_supported);
+    print_pending_autostash(
+        state
+            .get_pending_restack(repo)
+            .and_then(|p| p.resume.autostash.as_deref()),
+    );
+    restack_log.announce();
     std::process::exit(1);
 }
 
-/// True if the working tree still has unmerged (conflicted) paths.
+/// True if the working tree still has unmerged (conflicted) paths: any of the porcelain
+/// `DD`, `AU`, `UD`, `UA`, `DU`, `AA`, `UU` states.
 fn has_unresolved_conflicts() -> Result<bool> {
     Ok(run_git(&["status", "--porcelain"])?
         .output()
-        .map(|s| {
-            s.lines()
-                .any(|l| l.starts_with("UU") || l.starts_with("AA"))
-        })
+        .map(|s| s.lines().any(is_unmerged_status_line))
         .unwrap_or(false))
 }
 
+fn is_unmerged_status_line(line: &str) -> bool {
+    let mut xy = line.chars();
+    match (xy.next(), xy.next()) {
+        (Some(x), Some(y)) => x == 'U' || y == 'U' || (x == y && (x == 'A' || x == 'D')),
+        _ => false,
+    }
+}
+
 /// Complete a squash operation (either after clean merge or after conflict resolution).
 fn complete_squash(
     git_repo: &GitRepo,
@@ -1401,6 +3257,7 @@ fn complete_squash(
 fn squash_branch(
     git_repo: &GitRepo,
     state: &mut State,
+    restack_log: &mut RestackLog,
     repo: &str,
     branch: &state::Branch,
     parent: &str,
@@ -1452,7 +3309,18 @@ fn squash_branch(
 
     if !merge_status.success() {
         // Conflict! The pending record is already persisted above; print guidance and exit.
+        restack_log.record(&RestackLogEntry {
+            branch: branch_name,
+            parent,
+            source_sha: &pending.original_sha,
+            method: Some(RestackMethod::Squash),
+            outcome: RestackOutcome::Conflict,
+            result_sha: None,
+            pushed: false,
+        });
         print_restack_conflict_help("Squash merge", false);
+        print_pending_autostash(pending.resume.autostash.as_deref());
+        restack_log.announce();
         std::process::exit(1);
     }
 
@@ -1556,8 +3424,14 @@ fn handle_restack_continue(
         r.orig_branch,
         false, // never re-fetch on resume
         r.push,
+        r.push_all,
         r.ancestors,
         r.squash,
+        r.descendants,
+        r.log,
+        r.log_path,
+        r.autostash,
+        r.onto,
     )
 }
 
@@ -1621,8 +3495,14 @@ fn handle_restack_skip(
         r.orig_branch,
         false, // never re-fetch on resume
         r.push,
+        r.push_all,
         r.ancestors,
         r.squash,
+        r.descendants,
+        r.log,
+        r.log_path,
+        r.autostash,
+        r.onto,
     )
 }
 
@@ -1661,11 +3541,74 @@ fn handle_restack_abort(git_repo: &GitRepo, mut state: State, repo: &str) -> Res
         "Restack aborted. Branch '{}' restored to original state.",
         pending.branch_name.yellow()
     );
+
+    // Like `git rebase --abort`, leave the user where the restack started.
+    let orig_branch = &pending.resume.orig_branch;
+    if *orig_branch != pending.branch_name && git_repo.branch_exists(orig_branch) {
+        checkout_tracked_branch(git_repo, orig_branch)?;
+        println!("Returned to '{}'.", orig_branch.yellow());
+    }
+    if let Some(stash) = pending.resume.autostash.as_deref() {
+        git::autostash_pop(stash)?;
+    }
     Ok(())
 }
 
+/// Run [`run_restack`], then restore the `--autostash` stash on the original branch. A conflict
+/// exits inside `run_restack` with the stash recorded in the pending restack, so `--continue`
+/// (which comes back through here) or `--abort` restores it. Any other failure leaves it stashed.
 #[allow(clippy::too_many_arguments)]
 fn restack(
+    git_repo: &GitRepo,
+    state: State,
+    repo: &str,
+    run_version: String,
+    restack_branch: Option<String>,
+    orig_branch: String,
+    fetch: bool,
+    push: bool,
+    push_all: bool,
+    ancestors: bool,
+    squash: bool,
+    descendants: bool,
+    log: bool,
+    log_path: Option<PathBuf>,
+    autostash: Option<String>,
+    onto: Option<String>,
+) -> Result<(), anyhow::Error> {
+    let result = run_restack(
+        git_repo,
+        state,
+        repo,
+        run_version,
+        restack_branch,
+        orig_branch,
+        fetch,
+        push,
+        push_all,
+        ancestors,
+        squash,
+        descendants,
+        log,
+        log_path,
+        autostash.clone(),
+        onto,
+    );
+    if let Some(stash) = autostash.as_deref() {
+        if result.is_ok() {
+            git::autostash_pop(stash)?;
+        } else {
+            println!(
+                "Your local changes are still stashed as {}; `git stash apply {stash}` restores them.",
+                stash.yellow()
+            );
+        }
+    }
+    result
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_restack(
     git_repo: &GitRepo,
     mut state: State,
     repo: &str,
@@ -1674,8 +3617,14 @@ fn restack(
     orig_branch: String,
     fetch: bool,
     push: bool,
+    push_all: bool,
     ancestors: bool,
     squash: bool,
+    descendants: bool,
+    log: bool,
+    log_path: Option<PathBuf>,
+    autostash: Option<String>,
+    onto: Option<String>,
 ) -> Result<(), anyhow::Error> {
     // Hold a repo-scoped advisory lock for the whole restack so a second
     // git-stack invocation can't race us on ref updates (e.g. the fetch below,
@@ -1684,14 +3633,28 @@ fn restack(
 
     let restack_branch = restack_branch.unwrap_or(orig_branch.clone());
 
+    if fetch {
+        git_fetch(git_repo, FetchOptions::default())?;
+    }
+
+    // Pinned to a SHA (after the fetch) so a resumed restack uses the same base.
+    let onto = onto.map(|onto| resolve_onto(git_repo, &onto)).transpose()?;
+
     // Captured once so a conflict at any exit site can persist enough to resume the remaining
     // plan via `--continue`.
-    let resume = RestackResume {
+    let mut resume = RestackResume {
         restack_branch: restack_branch.clone(),
         orig_branch: orig_branch.clone(),
         ancestors,
         push,
+        push_all,
         squash,
+        descendants,
+        log,
+        autostash,
+        onto: onto.clone(),
+        worktree: git_repo.root().ok(),
+        log_path,
     };
 
     // Read once for this run. Conflict recovery re-enters `restack`, so resumed plans pick up the
@@ -1701,10 +3664,6 @@ fn restack(
     // Track what changes occurred during restack (branch_name, status)
     let mut branch_results: Vec<(String, String)> = Vec::new();
 
-    if fetch {
-        git_fetch()?;
-    }
-
     // Check if user is trying to restack the trunk branch
     let trunk = git_trunk(git_repo).ok_or_else(|| anyhow!("No remote configured"))?;
     if restack_branch == trunk.main_branch {
@@ -1715,10 +3674,16 @@ fn restack(
         return Ok(());
     }
 
+    // Checking out and rewriting branches with local changes around can stop partway with a
+    // cryptic git error, so refuse up front.
+    if !git::run_git_status_clean()? {
+        bail!("Your working tree has uncommitted changes; commit or stash them before restacking.");
+    }
+
     // Ensure target branch exists locally (check it out from remote if needed)
     if !git_repo.branch_exists(&restack_branch) {
         let remote_ref = format!("{DEFAULT_REMOTE}/{restack_branch}");
-        if git_repo.ref_exists(&remote_ref) {
+        if git_repo.remote_branch_exists(DEFAULT_REMOTE, &restack_branch) {
             run_git(&["checkout", "-b", &restack_branch, &remote_ref])?;
             branch_results.push((restack_branch.clone(), "created".to_string()));
         } else {
@@ -1735,25 +3700,42 @@ fn restack(
     state.refresh_lkg_for_branch(git_repo, repo, &restack_branch)?;
 
     // Find starting_branch in the stacks of branches to determine which stack to use.
-    let plan = state.plan_restack(git_repo, repo, &restack_branch, ancestors)?;
+    let plan = if descendants {
+        state.plan_restack_subtree(git_repo, repo, &restack_branch)?
+    } else {
+        state.plan_restack(git_repo, repo, &restack_branch, ancestors)?
+    };
 
     // Collect plan into owned data to allow mutable access to state during the loop
-    let plan_owned: Vec<(String, state::Branch)> = plan
+    let mut plan_owned: Vec<(String, state::Branch)> = plan
         .into_iter()
         .map(|step| (step.parent, step.branch.clone()))
         .collect();
+    // `--onto`: the bottom branch moves onto the given base; the rest follow their parents.
+    let onto_bottom = match (onto, plan_owned.first_mut()) {
+        (Some(onto), Some((parent, branch))) => {
+            check_onto_bottom(&state, repo, &trunk.main_branch, &branch.name)?;
+            *parent = onto.clone();
+            Some((branch.name.clone(), onto))
+        }
+        _ => None,
+    };
 
     tracing::debug!("Restacking branches with plan. Checking out main...");
     git_checkout_main(git_repo, None)?;
 
     // Track pushed branches to record SHAs after the loop (avoids borrow issues with plan)
     let mut pushed_branches: Vec<String> = Vec::new();
+    // Branches `--push` left alone because their remote already matched.
+    let mut unchanged_branches: Vec<String> = Vec::new();
+    let mut restack_log = RestackLog::open(log, resume.log_path.as_deref());
+    resume.log_path = restack_log.path().map(Path::to_path_buf);
 
     for (parent, branch) in plan_owned {
         // Ensure the branch exists locally (check it out from remote if needed)
         if !git_repo.branch_exists(&branch.name) {
             let remote_ref = format!("{DEFAULT_REMOTE}/{}", branch.name);
-            if git_repo.ref_exists(&remote_ref) {
+            if git_repo.remote_branch_exists(DEFAULT_REMOTE, &branch.name) {
                 run_git(&["checkout", "-b", &branch.name, &remote_ref])?;
                 branch_results.push((branch.name.clone(), "created".to_string()));
             }
@@ -1792,25 +3774,54 @@ fn restack(
                     parent
                 );
                 let mut status = "no changes".to_string();
-                if push
-                    && !git_repo
-                        .shas_match(&format!("{DEFAULT_REMOTE}/{}", branch.name), &branch.name)
-                {
-                    restack_push(git_repo, &branch.name, true, push_no_verify)?;
+                let pushed =
+                    push && restack_push(git_repo, &branch.name, true, push_no_verify, push_all)?;
+                if pushed {
                     pushed_branches.push(branch.name.clone());
                     status = "no changes, pushed".to_string();
+                } else if push {
+                    unchanged_branches.push(branch.name.clone());
                 }
+                log_restack_step(
+                    &mut restack_log,
+                    git_repo,
+                    &branch.name,
+                    &parent,
+                    &source,
+                    None,
+                    RestackOutcome::AlreadyStacked,
+                    pushed,
+                );
                 branch_results.push((branch.name.clone(), status));
                 continue;
             }
-            squash_branch(git_repo, &mut state, repo, &branch, &parent, resume.clone())?;
-            let status = if push {
-                restack_push(git_repo, &branch.name, true, push_no_verify)?;
+            squash_branch(
+                git_repo,
+                &mut state,
+                &mut restack_log,
+                repo,
+                &branch,
+                &parent,
+                resume.clone(),
+            )?;
+            let pushed =
+                push && restack_push(git_repo, &branch.name, true, push_no_verify, push_all)?;
+            let status = if pushed {
                 pushed_branches.push(branch.name.clone());
                 "squashed, pushed"
             } else {
                 "squashed"
             };
+            log_restack_step(
+                &mut restack_log,
+                git_repo,
+                &branch.name,
+                &parent,
+                &source,
+                Some(RestackMethod::Squash),
+                RestackOutcome::Squashed,
+                pushed,
+            );
             branch_results.push((branch.name.clone(), status.to_string()));
             continue;
         }
@@ -1822,25 +3833,46 @@ fn restack(
         // re-triggers the replay-anchor cascade on the next `restack -afp`. When already stacked we
         // only push, and only when the branch is out of sync with origin — an in-sync branch is a
         // true no-op. See `git2_ops::tests::already_stacked_branch_is_skipped_not_rechurned`.
-        if git_repo.is_ancestor(&parent, &branch.name)? {
+        //
+        // `move` can re-parent a branch onto a parent that is already in its history (e.g. down
+        // onto trunk from a branch in between). Such a branch is only stacked once its replay
+        // boundary is in the new parent too; otherwise it still carries the old parent's commits.
+        let boundary_left_behind = descendants
+            && branch.name == restack_branch
+            && branch.lkg_parent.as_deref().is_some_and(|lkg_parent| {
+                !git_repo.is_ancestor(lkg_parent, &parent).unwrap_or(true)
+            });
+        if git_repo.is_ancestor(&parent, &branch.name)? && !boundary_left_behind {
             tracing::debug!(
                 "Branch '{}' is already stacked on '{}'.",
                 branch.name,
                 parent
             );
             let mut status = "no changes".to_string();
-            if push
-                && !git_repo.shas_match(&format!("{DEFAULT_REMOTE}/{}", branch.name), &branch.name)
-            {
-                restack_push(
+            let pushed = push
+                && restack_push(
                     git_repo,
                     &branch.name,
                     matches!(branch.stack_method, StackMethod::ApplyMerge),
                     push_no_verify,
+                    push_all,
                 )?;
+            if pushed {
                 pushed_branches.push(branch.name.clone());
                 status = "no changes, pushed".to_string();
+            } else if push {
+                unchanged_branches.push(branch.name.clone());
             }
+            log_restack_step(
+                &mut restack_log,
+                git_repo,
+                &branch.name,
+                &parent,
+                &source,
+                None,
+                RestackOutcome::AlreadyStacked,
+                pushed,
+            );
             branch_results.push((branch.name.clone(), status));
         } else {
             tracing::info!("Branch '{}' is not stacked on '{}'...", branch.name, parent);
@@ -1882,6 +3914,16 @@ fn restack(
                                 branch.name,
                                 parent
                             );
+                            log_restack_step(
+                                &mut restack_log,
+                                git_repo,
+                                &branch.name,
+                                &parent,
+                                &source,
+                                Some(RestackMethod::Am),
+                                RestackOutcome::FastForwarded,
+                                false,
+                            );
                             branch_results.push((branch.name.clone(), "restacked".to_string()));
                             continue;
                         };
@@ -1891,6 +3933,7 @@ fn restack(
                         if !rebased {
                             record_restack_conflict(
                                 &mut state,
+                                &mut restack_log,
                                 repo,
                                 RestackMethod::Am,
                                 &branch.name,
@@ -1900,13 +3943,30 @@ fn restack(
                                 "`git am`",
                             );
                         }
-                        let status = if push {
-                            restack_push(git_repo, &branch.name, true, push_no_verify)?;
+                        let pushed = push
+                            && restack_push(
+                                git_repo,
+                                &branch.name,
+                                true,
+                                push_no_verify,
+                                push_all,
+                            )?;
+                        let status = if pushed {
                             pushed_branches.push(branch.name.clone());
                             "restacked, pushed"
                         } else {
                             "restacked"
                         };
+                        log_restack_step(
+                            &mut restack_log,
+                            git_repo,
+                            &branch.name,
+                            &parent,
+                            &source,
+                            Some(RestackMethod::Am),
+                            RestackOutcome::Patched,
+                            pushed,
+                        );
                         branch_results.push((branch.name.clone(), status.to_string()));
                         continue;
                     }
@@ -1919,6 +3979,7 @@ fn restack(
                     if !rebased {
                         record_restack_conflict(
                             &mut state,
+                            &mut restack_log,
                             repo,
                             RestackMethod::Rebase,
                             &branch.name,
@@ -1928,13 +3989,24 @@ fn restack(
                             "Rebase",
                         );
                     }
-                    let status = if push {
-                        restack_push(git_repo, &branch.name, true, push_no_verify)?;
+                    let pushed = push
+                        && restack_push(git_repo, &branch.name, true, push_no_verify, push_all)?;
+                    let status = if pushed {
                         pushed_branches.push(branch.name.clone());
                         "restacked, pushed"
                     } else {
                         "restacked"
                     };
+                    log_restack_step(
+                        &mut restack_log,
+                        git_repo,
+                        &branch.name,
+                        &parent,
+                        &source,
+                        Some(RestackMethod::Rebase),
+                        RestackOutcome::Rebased,
+                        pushed,
+                    );
                     branch_results.push((branch.name.clone(), status.to_string()));
                     tracing::info!("Rebase completed successfully. Continuing...");
                 }
@@ -1942,8 +4014,16 @@ fn restack(
                     run_git(&["checkout", &branch.name])
                         .with_context(|| format!("checking out {}", branch.name))?;
                     if !run_git_status(&["merge", &parent], None)?.success() {
+                        // `git merge` also exits non-zero when it refuses to start (e.g. local
+                        // changes it would overwrite); only a real conflict is resumable.
+                        ensure!(
+                            has_unresolved_conflicts()?,
+                            "git merge {parent} into {} failed",
+                            branch.name
+                        );
                         record_restack_conflict(
                             &mut state,
+                            &mut restack_log,
                             repo,
                             RestackMethod::Merge,
                             &branch.name,
@@ -1953,6 +4033,16 @@ fn restack(
                             "Merge",
                         );
                     }
+                    log_restack_step(
+                        &mut restack_log,
+                        git_repo,
+                        &branch.name,
+                        &parent,
+                        &source,
+                        Some(RestackMethod::Merge),
+                        RestackOutcome::Merged,
+                        false,
+                    );
                     branch_results.push((branch.name.clone(), "restacked".to_string()));
                 }
             }
@@ -1960,8 +4050,8 @@ fn restack(
     }
 
     // Record pushed SHAs as seen on remote (for safe branch deletion)
-    for branch_name in pushed_branches {
-        if let Ok(sha) = git_repo.sha(&branch_name) {
+    for branch_name in &pushed_branches {
+        if let Ok(sha) = git_repo.sha(branch_name) {
             state.add_seen_sha(repo, sha);
         }
     }
@@ -1981,8 +4071,21 @@ fn restack(
             println!("{}: {}", branch.yellow(), status);
         }
     }
+    if push {
+        print_push_summary(&pushed_branches, &unchanged_branches);
+    }
+    restack_log.announce();
+
+    // The bottom branch now sits on the `--onto` base, so that's its replay boundary; the refresh
+    // below keeps it unless the tree parent's tip has moved past it.
+    if let Some((bottom, onto)) = onto_bottom
+        && let Some(branch) = state.get_tree_branch_mut(repo, &bottom)
+    {
+        branch.lkg_parent = Some(onto);
+        state.save_state()?;
+    }
 
-    eager_refresh_lkgs(git_repo, &mut state, repo, &restack_branch)?;
+    eager_refresh_lkgs(git_repo, &mut state, repo, &restack_branch, false)?;
 
     // Note: PR sync is now handled separately via `git stack sync`
     // Run `git stack sync` after `restack -p` to sync PR bases
@@ -1990,13 +4093,225 @@ fn restack(
     Ok(())
 }
 
+/// `--onto` leaves the bottom branch's tree parent alone, so it only makes sense for a branch stacked
+/// directly on trunk: a mid-stack branch would be replayed back onto its parent by the next restack.
+fn check_onto_bottom(state: &State, repo: &str, trunk: &str, bottom: &str) -> Result<()> {
+    if let Some(parent) = state.get_parent_branch_of(repo, bottom)
+        && parent.name != trunk
+    {
+        bail!(
+            "--onto only moves a branch stacked on {trunk}, and '{bottom}' is stacked on '{}'.\n\
+             Use -a to move its whole stack, or `git stack move` to re-parent it.",
+            parent.name
+        );
+    }
+    Ok(())
+}
+
+/// The SHA `restack --onto <onto>` restacks onto.
+fn resolve_onto(git_repo: &GitRepo, onto: &str) -> Result<String> {
+    git_repo
+        .sha(&format!("{onto}^{{commit}}"))
+        .map_err(|_| anyhow!("--onto {} doesn't name a commit.", onto.red()))
+}
+
+/// Record a finished restack step in the run's log, with the branch's new tip.
+#[allow(clippy::too_many_arguments)]
+fn log_restack_step(
+    restack_log: &mut RestackLog,
+    git_repo: &GitRepo,
+    branch: &str,
+    parent: &str,
+    source_sha: &str,
+    method: Option<RestackMethod>,
+    outcome: RestackOutcome,
+    pushed: bool,
+) {
+    restack_log.record(&RestackLogEntry {
+        branch,
+        parent,
+        source_sha,
+        method,
+        outcome,
+        result_sha: git_repo.sha(branch).ok(),
+        pushed,
+    });
+}
+
+/// End-of-restack summary of what `--push` did: the branches it pushed, and the ones it skipped
+/// because their remote already matched.
+fn print_push_summary(pushed: &[String], unchanged: &[String]) {
+    if !pushed.is_empty() {
+        println!("{} {}", "Pushed:".green(), pushed.join(", "));
+    }
+    if !unchanged.is_empty() {
+        println!(
+            "{} {}",
+            "Already up to date on origin:".dimmed(),
+            unchanged.join(", ")
+        );
+    }
+    if pushed.is_empty() && unchanged.is_empty() {
+        println!("Nothing was pushed.");
+    }
+}
+
+/// `git stack fetch`: fetch from the remote, then re-detect its default branch so `origin/HEAD`
+/// follows a trunk rename. A failed re-detection only warns; the fetch itself already succeeded.
+fn fetch(git_repo: &GitRepo, prune: bool, tags: bool) -> Result<()> {
+    git_fetch(git_repo, FetchOptions { prune, tags })?;
+    if let Err(e) = run_git(&["remote", "set-head", DEFAULT_REMOTE, "--auto"]) {
+        println!(
+            "{} couldn't refresh {DEFAULT_REMOTE}/HEAD: {e:#}",
+            "Warning:".yellow()
+        );
+    }
+    println!("Fetched from {}.", DEFAULT_REMOTE.yellow());
+    Ok(())
+}
+
+/// Print what `restack` would do with the same flags: for each step of the plan, whether the
+/// branch is already stacked or would be replayed (and by which mechanism), and whether it would be
+/// pushed. Mirrors the decisions in `restack` without checking out anything or touching refs.
+#[allow(clippy::too_many_arguments)]
+fn restack_dry_run(
+    git_repo: &GitRepo,
+    mut state: State,
+    repo: &str,
+    restack_branch: &str,
+    push: bool,
+    push_all: bool,
+    ancestors: bool,
+    squash: bool,
+    onto: Option<&str>,
+) -> Result<()> {
+    use std::collections::HashSet;
+
+    let trunk = git_trunk(git_repo).ok_or_else(|| anyhow!("No remote configured"))?;
+    if restack_branch == trunk.main_branch {
+        println!(
+            "You are on the trunk branch ({}). Nothing to restack.",
+            trunk.main_branch.yellow()
+        );
+        return Ok(());
+    }
+    let theme = render::Theme::load();
+    let Some(restack_ref) = git_repo.resolve_branch_ref(restack_branch) else {
+        bail!(
+            "Branch {} does not exist locally or on remote.",
+            restack_branch
+        );
+    };
+
+    // Same refresh `restack` does, so the am-vs-rebase prediction sees current LKG parents.
+    state.refresh_lkg_for_branch(git_repo, repo, restack_branch)?;
+    let mut plan = state.plan_restack(git_repo, repo, restack_branch, ancestors)?;
+    if let Some(onto) = onto {
+        resolve_onto(git_repo, onto)?;
+        if let Some(bottom) = plan.first_mut() {
+            check_onto_bottom(&state, repo, &trunk.main_branch, &bottom.branch.name)?;
+            bottom.parent = onto.to_string();
+        }
+    }
+
+    println!(
+        "Dry run: restacking '{}' would do the following (nothing was changed):",
+        restack_branch.yellow()
+    );
+    if plan.is_empty() {
+        println!("No branches to restack.");
+        return Ok(());
+    }
+    // Branches an earlier step would rewrite; their children no longer descend from them.
+    let mut rewritten: HashSet<String> = HashSet::new();
+    for RestackStep { parent, branch } in plan {
+        // A branch missing locally would be created from its remote-tracking ref first.
+        let branch_ref = if branch.name == restack_branch {
+            restack_ref.clone()
+        } else {
+            git_repo
+                .resolve_branch_ref(&branch.name)
+                .unwrap_or_else(|| branch.name.clone())
+        };
+        let out_of_sync = push_all
+            || !git_repo.shas_match(&format!("{DEFAULT_REMOTE}/{}", branch.name), &branch_ref);
+        let stacked = !rewritten.contains(&parent) && git_repo.is_ancestor(&parent, &branch_ref)?;
+
+        // (description, rewrites the branch, pushes, force-pushes)
+        let (action, rewrites, pushes, force) = if squash {
+            if stacked && git_repo.commits_ahead(&parent, &branch_ref)? <= 1 {
+                (
+                    format!("already squashed on {parent}"),
+                    false,
+                    push && out_of_sync,
+                    true,
+                )
+            } else {
+                (format!("would squash onto {parent}"), true, push, true)
+            }
+        } else if stacked {
+            (
+                format!("already stacked on {parent}"),
+                false,
+                push && out_of_sync,
+                matches!(branch.stack_method, StackMethod::ApplyMerge),
+            )
+        } else {
+            match branch.stack_method {
+                StackMethod::ApplyMerge => {
+                    let fast_path = match branch.lkg_parent.as_deref() {
+                        Some(lkg_parent) => git_repo.is_ancestor(lkg_parent, &branch_ref)?,
+                        None => false,
+                    };
+                    let mechanism = if fast_path {
+                        "format-patch/am"
+                    } else {
+                        "rebase, no usable LKG parent"
+                    };
+                    (
+                        format!("would restack onto {parent} via apply_merge ({mechanism})"),
+                        true,
+                        push,
+                        true,
+                    )
+                }
+                // `restack` never pushes merge-restacked branches.
+                StackMethod::Merge => (
+                    format!("would merge {parent} in via merge"),
+                    true,
+                    false,
+                    false,
+                ),
+            }
+        };
+
+        if rewrites {
+            rewritten.insert(branch.name.clone());
+        }
+        // Same coloring as `status`: green when stacked, yellow when it needs a restack.
+        let color = if rewrites { theme.yellow } else { theme.green };
+        let push_note = match (pushes, force) {
+            (false, _) => String::new(),
+            (true, true) => format!(", then {}", "force-push".red()),
+            (true, false) => ", then push".to_string(),
+        };
+        println!(
+            "  {}: {}{}",
+            theme.paint(&branch.name, color),
+            action,
+            push_note
+        );
+    }
+    Ok(())
+}
+
 /// Sync PR bases to match git-stack parents after restack (graceful degradation)
 /// Uses a bottom-up traversal (leaves first) so each parent is processed once.
 fn sync_pr_bases_after_restack(git_repo: &GitRepo, state: &State, repo: &str) -> Result<()> {
-    use github::{GitHubClient, UpdatePrRequest, get_repo_identifier};
+    use github::{UpdatePrRequest, get_repo_identifier};
 
     let repo_id = get_repo_identifier(git_repo)?;
-    let client = GitHubClient::from_env(&repo_id)?;
+    let client = provider::client_for(&repo_id)?;
 
     // Get the tree
     let tree = state
@@ -2006,7 +4321,7 @@ fn sync_pr_bases_after_restack(git_repo: &GitRepo, state: &State, repo: &str) ->
     let trunk = crate::git::git_trunk(git_repo).ok_or_else(|| anyhow!("No remote configured"))?;
 
     // Collect branches with depth for bottom-up processing
-    let branches_with_depth = collect_branches_with_depth(tree, &tree.name, 0);
+    let branches_with_depth = collect_branches_with_depth(tree);
 
     // Sort by depth descending (leaves first) for bottom-up processing
     let mut sorted_branches = branches_with_depth;
@@ -2029,7 +4344,7 @@ fn sync_pr_bases_after_restack(git_repo: &GitRepo, state: &State, repo: &str) ->
         if expected_base != trunk.main_branch && !processed_parents.contains(&expected_base) {
             ensure_branch_pr(
                 git_repo,
-                &client,
+                client.as_ref(),
                 &repo_id,
                 &mut all_prs,
                 &expected_base,
@@ -2073,7 +4388,7 @@ fn sync_pr_bases_after_restack(git_repo: &GitRepo, state: &State, repo: &str) ->
 #[allow(clippy::too_many_arguments)]
 fn ensure_branch_pr(
     git_repo: &GitRepo,
-    client: &github::GitHubClient,
+    client: &dyn provider::RemoteProvider,
     repo_id: &github::RepoIdentifier,
     all_prs: &mut std::collections::HashMap<String, github::PullRequest>,
     branch_name: &str,
@@ -2090,8 +4405,7 @@ fn ensure_branch_pr(
     }
 
     // Check if branch exists on remote
-    let remote_ref = format!("{DEFAULT_REMOTE}/{}", branch_name);
-    if !git_repo.ref_exists(&remote_ref) {
+    if !git_repo.remote_branch_exists(DEFAULT_REMOTE, branch_name) {
         if push_if_missing {
             // Push the branch
             println!(
@@ -2149,46 +4463,57 @@ fn ensure_branch_pr(
     .and_then(|r| r.output())
     .unwrap_or_else(|| branch_name.to_string());
 
-    let pr = client.create_pr(
+    let parent_pr = all_prs.get(&parent).map(|pr| pr.number);
+    let body = pr_body::build_pr_body(git_repo, state, repo, branch_name, parent_pr)
+        .unwrap_or_else(|e| {
+            tracing::warn!("Could not build a PR body for {branch_name}: {e:#}");
+            String::new()
+        });
+
+    let pr = match client.create_pr(
         repo_id,
         CreatePrRequest {
             title: &title,
-            body: "",
+            body: &body,
             head: branch_name,
             base: &parent,
             draft: Some(true),
         },
-    )?;
-
-    println!(
-        "Created PR #{} for '{}': {}",
-        pr.number.to_string().green(),
-        branch_name.yellow(),
-        pr.html_url.blue()
-    );
+    ) {
+        // Opened since the lookup above (e.g. by a run that failed partway); adopt it.
+        Err(github::GitHubError::PrAlreadyExists { pr_number }) => {
+            println!(
+                "PR #{} already exists for '{}'.",
+                pr_number.to_string().green(),
+                branch_name.yellow()
+            );
+            client.get_pr(repo_id, pr_number)?
+        }
+        result => {
+            let pr = result?;
+            println!(
+                "Created PR #{} for '{}': {}",
+                pr.number.to_string().green(),
+                branch_name.yellow(),
+                pr.html_url.blue()
+            );
+            pr
+        }
+    };
 
     all_prs.insert(branch_name.to_string(), pr);
     Ok(())
 }
 
 /// Collect branches with their parent and depth for bottom-up processing
-fn collect_branches_with_depth(
-    branch: &Branch,
-    parent_name: &str,
-    depth: usize,
-) -> Vec<(String, String, usize)> {
+fn collect_branches_with_depth(tree: &Branch) -> Vec<(String, String, usize)> {
     let mut result = Vec::new();
-
-    // Add this branch (unless it's the root/trunk)
-    if branch.name != parent_name {
-        result.push((branch.name.clone(), parent_name.to_string(), depth));
-    }
-
-    // Recurse into children
-    for child in &branch.branches {
-        result.extend(collect_branches_with_depth(child, &branch.name, depth + 1));
-    }
-
+    // Every branch but the root/trunk, which has no parent
+    tree.walk(&mut |branch, depth, parent| {
+        if let Some(parent) = parent {
+            result.push((branch.name.clone(), parent.to_string(), depth));
+        }
+    });
     result
 }
 
@@ -2205,26 +4530,94 @@ fn restack_push_args(branch: &str, force_with_lease: bool, no_verify: bool) -> V
     args
 }
 
-/// Push a restacked branch when its remote differs, preserving the restack path's force policy.
+/// Push a restacked branch when its remote differs (or always, for `--push-all`), preserving the
+/// restack path's force policy. Returns whether it pushed.
 fn restack_push(
     git_repo: &GitRepo,
     branch: &str,
     force_with_lease: bool,
     no_verify: bool,
-) -> Result<()> {
-    if !git_repo.shas_match(&format!("{DEFAULT_REMOTE}/{}", branch), branch) {
-        if force_with_lease {
-            tracing::debug!("Force-pushing (with lease) '{branch}' to {DEFAULT_REMOTE}...");
-        }
-        let args = restack_push_args(branch, force_with_lease, no_verify);
-        let args = args.iter().map(String::as_str).collect::<Vec<_>>();
-        run_git(&args)?;
+    always: bool,
+) -> Result<bool> {
+    if !always && git_repo.shas_match(&format!("{DEFAULT_REMOTE}/{}", branch), branch) {
+        return Ok(false);
     }
-    Ok(())
+    if force_with_lease {
+        tracing::debug!("Force-pushing (with lease) '{branch}' to {DEFAULT_REMOTE}...");
+    }
+    let args = restack_push_args(branch, force_with_lease, no_verify);
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    run_git(&args)?;
+    Ok(true)
 }
 
 // ============== GitHub PR Commands ==============
 
+/// One `pr status` line: the branch, its PR, and whether the PR is ready to merge.
+fn print_pr_readiness(branch: &str, pr_number: u64, readiness: &github::PrReadiness) {
+    let mergeable = match readiness.mergeable.as_deref() {
+        Some(state @ ("clean" | "mergeable")) => state.green(),
+        Some(state @ ("dirty" | "blocked" | "conflict" | "broken_status")) => state.red(),
+        Some(state) => state.yellow(),
+        None => "unknown".dimmed(),
+    };
+    let checks = checks_summary(&readiness.checks);
+    let checks = if readiness.checks.failing > 0 {
+        checks.red()
+    } else if readiness.checks.pending > 0 {
+        checks.yellow()
+    } else {
+        checks.green()
+    };
+    let review = readiness.review.to_string();
+    let review = match readiness.review {
+        github::ReviewDecision::Approved => review.green(),
+        github::ReviewDecision::ChangesRequested => review.red(),
+        github::ReviewDecision::ReviewRequired => review.yellow(),
+    };
+    println!(
+        "{}  #{pr_number}  {} {mergeable}  {} {checks}  {} {review}",
+        branch.yellow(),
+        "mergeable:".dimmed(),
+        "checks:".dimmed(),
+        "review:".dimmed(),
+    );
+}
+
+/// "3 passing, 1 failing, 2 pending", leaving out zero counts.
+fn checks_summary(checks: &github::CheckCounts) -> String {
+    let parts: Vec<String> = [
+        (checks.passing, "passing"),
+        (checks.failing, "failing"),
+        (checks.pending, "pending"),
+    ]
+    .into_iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, label)| format!("{count} {label}"))
+    .collect();
+    if parts.is_empty() {
+        "none".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// Ask whether to create a PR for `branch`, which has none yet (`pr view`/`pr open`).
+fn confirm_create_pr(branch: &str) -> bool {
+    print!(
+        "No PR found for branch '{}'. Create one? [y/N] ",
+        branch.yellow()
+    );
+    std::io::stdout().flush().unwrap();
+
+    let mut input = String::new();
+    if std::io::stdin().read_line(&mut input).is_err() {
+        return false;
+    }
+
+    matches!(input.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
 fn handle_pr_command(
     git_repo: &GitRepo,
     state: &mut State,
@@ -2233,14 +4626,15 @@ fn handle_pr_command(
     action: PrAction,
 ) -> Result<()> {
     use github::{
-        CreatePrRequest, GitHubClient, get_repo_identifier, has_github_token, login_interactive,
-        open_in_browser,
+        CreatePrRequest, get_repo_identifier, has_github_token, login_interactive, open_in_browser,
     };
 
     let repo_id = get_repo_identifier(git_repo)?;
 
-    // Ensure we have auth configured
-    if !has_github_token(&repo_id.host) {
+    // Ensure we have auth configured (GitHub's interactive login only applies to GitHub hosts)
+    if provider::ProviderKind::for_host(&repo_id.host) == provider::ProviderKind::GitHub
+        && !has_github_token(&repo_id.host)
+    {
         println!(
             "{}",
             "GitHub authentication required (needs the 'repo' scope).".yellow()
@@ -2248,7 +4642,7 @@ fn handle_pr_command(
         login_interactive()?;
     }
 
-    let client = GitHubClient::from_env(&repo_id)?;
+    let client = provider::client_for(&repo_id)?;
 
     match action {
         PrAction::Create {
@@ -2293,7 +4687,7 @@ fn handle_pr_command(
             for ancestor in &ancestor_chain {
                 ensure_branch_pr(
                     git_repo,
-                    &client,
+                    client.as_ref(),
                     &repo_id,
                     &mut all_prs,
                     ancestor,
@@ -2317,8 +4711,7 @@ fn handle_pr_command(
             let base_branch = parent.name.clone();
 
             // Check if branch exists on remote, push if not
-            let remote_ref = format!("{DEFAULT_REMOTE}/{}", branch_name);
-            if !git_repo.ref_exists(&remote_ref) {
+            if !git_repo.remote_branch_exists(DEFAULT_REMOTE, &branch_name) {
                 println!(
                     "Branch '{}' is not on remote. Pushing...",
                     branch_name.yellow()
@@ -2346,8 +4739,7 @@ fn handle_pr_command(
                 // Update stored PR number if not already set
                 if let Some(branch) = state.get_tree_branch(repo, &branch_name)
                     && branch.pr_number.is_none()
-                    && let Some(branch) =
-                        find_branch_by_name_mut(state.get_tree_mut(repo).unwrap(), &branch_name)
+                    && let Some(branch) = state.get_tree_mut(repo).unwrap().find_mut(&branch_name)
                 {
                     branch.pr_number = Some(existing_pr.number);
                     state.save_state()?;
@@ -2374,7 +4766,14 @@ fn handle_pr_command(
                 .unwrap_or_else(|| branch_name.clone())
             });
 
-            let body = body.unwrap_or_default();
+            let body = body.unwrap_or_else(|| {
+                let parent_pr = all_prs.get(&base_branch).map(|pr| pr.number);
+                pr_body::build_pr_body(git_repo, state, repo, &branch_name, parent_pr)
+                    .unwrap_or_else(|e| {
+                        tracing::warn!("Could not build a PR body for {branch_name}: {e:#}");
+                        String::new()
+                    })
+            });
 
             println!(
                 "Creating PR for '{}' with base '{}'...",
@@ -2382,7 +4781,7 @@ fn handle_pr_command(
                 base_branch.green()
             );
 
-            let pr = client.create_pr(
+            let pr = match client.create_pr(
                 &repo_id,
                 CreatePrRequest {
                     title: &title,
@@ -2391,18 +4790,39 @@ fn handle_pr_command(
                     base: &base_branch,
                     draft: if draft { Some(true) } else { None },
                 },
-            )?;
-
-            println!(
-                "Created PR #{}: {}",
-                pr.number.to_string().green(),
-                pr.html_url.blue()
-            );
+            ) {
+                // Someone opened it between our lookup and the create; record that PR instead.
+                Err(github::GitHubError::PrAlreadyExists { pr_number }) => {
+                    let pr = client.get_pr(&repo_id, pr_number)?;
+                    println!(
+                        "PR #{} already exists: {}",
+                        pr.number.to_string().green(),
+                        pr.html_url.blue()
+                    );
+                    pr
+                }
+                result => {
+                    let pr = result?;
+                    println!(
+                        "Created PR #{}: {}",
+                        pr.number.to_string().green(),
+                        pr.html_url.blue()
+                    );
+                    if let Some(warning) =
+                        merge_policy_mismatch(client.as_ref(), &repo_id, state.merge_method(repo))
+                    {
+                        eprintln!(
+                            "{} {warning} `git stack land` will need --method, or set the default \
+                             with `git stack init --merge-method`.",
+                            "warning:".yellow().bold()
+                        );
+                    }
+                    pr
+                }
+            };
 
             // Store PR number in state
-            if let Some(branch) =
-                find_branch_by_name_mut(state.get_tree_mut(repo).unwrap(), &branch_name)
-            {
+            if let Some(branch) = state.get_tree_mut(repo).unwrap().find_mut(&branch_name) {
                 branch.pr_number = Some(pr.number);
                 state.save_state()?;
             }
@@ -2423,17 +4843,71 @@ fn handle_pr_command(
 
             let pr = if let Some(pr_number) = pr_number {
                 client.get_pr(&repo_id, pr_number)?
+            } else if let Some(pr) = client.find_pr_for_branch(&repo_id, &branch_name)? {
+                pr
             } else {
-                // Try to find PR by branch name
-                client
-                    .find_pr_for_branch(&repo_id, &branch_name)?
-                    .ok_or_else(|| anyhow!("No PR found for branch '{}'", branch_name))?
+                if !std::io::stdin().is_terminal() {
+                    bail!(
+                        "No PR found for branch '{branch_name}'. Create one with `git stack pr \
+                         create --web`."
+                    );
+                }
+                if !confirm_create_pr(&branch_name) {
+                    return Ok(());
+                }
+                return handle_pr_command(
+                    git_repo,
+                    state,
+                    repo,
+                    current_branch,
+                    PrAction::Create {
+                        branch: Some(branch_name),
+                        title: None,
+                        body: None,
+                        draft: false,
+                        web: true,
+                    },
+                );
             };
 
             println!("Opening PR #{}: {}", pr.number, pr.html_url);
             open_in_browser(&pr.html_url)?;
             Ok(())
         }
+        PrAction::Status => {
+            state.try_auto_mount(git_repo, repo, current_branch)?;
+            let StackChain { links, fork } = stack_chain(state, git_repo, repo, current_branch)?;
+            if links.is_empty() {
+                bail!("{current_branch} is the trunk; it has no stack to show.");
+            }
+            for (_, branch_name) in &links {
+                let pr_number = match state
+                    .get_tree_branch(repo, branch_name)
+                    .and_then(|b| b.pr_number)
+                {
+                    Some(pr_number) => Some(pr_number),
+                    None => client
+                        .find_pr_for_branch(&repo_id, branch_name)?
+                        .map(|pr| pr.number),
+                };
+                let Some(pr_number) = pr_number else {
+                    println!("{}  {}", branch_name.yellow(), "no PR".dimmed());
+                    continue;
+                };
+                let readiness = client.pr_readiness(&repo_id, pr_number)?;
+                print_pr_readiness(branch_name, pr_number, &readiness);
+            }
+            if let Some(fork) = fork {
+                println!(
+                    "{}",
+                    format!(
+                        "({fork} has several children; check one of them out to see further down.)"
+                    )
+                    .dimmed()
+                );
+            }
+            Ok(())
+        }
         PrAction::Sync { all, dry_run } => {
             use github::UpdatePrRequest;
 
@@ -2445,7 +4919,7 @@ fn handle_pr_command(
                 let tree = state
                     .get_tree(repo)
                     .ok_or_else(|| anyhow!("No stack tree found for repo"))?;
-                collect_branches_with_depth(tree, &tree.name, 0)
+                collect_branches_with_depth(tree)
             } else {
                 let parent = state
                     .get_parent_branch_of(repo, current_branch)
@@ -2480,21 +4954,20 @@ fn handle_pr_command(
                 if expected_base != trunk.main_branch && !processed_parents.contains(&expected_base)
                 {
                     if dry_run {
-                        if !all_prs.contains_key(&expected_base) {
-                            let remote_ref = format!("{DEFAULT_REMOTE}/{}", expected_base);
-                            if git_repo.branch_exists(&remote_ref) {
-                                println!(
-                                    "[dry-run] Would create PR for parent '{}'",
-                                    expected_base.yellow()
-                                );
-                                created_count += 1;
-                            }
+                        if !all_prs.contains_key(&expected_base)
+                            && git_repo.remote_branch_exists(DEFAULT_REMOTE, &expected_base)
+                        {
+                            println!(
+                                "[dry-run] Would create PR for parent '{}'",
+                                expected_base.yellow()
+                            );
+                            created_count += 1;
                         }
                     } else {
                         let before_count = all_prs.len();
                         ensure_branch_pr(
                             git_repo,
-                            &client,
+                            client.as_ref(),
                             &repo_id,
                             &mut all_prs,
                             &expected_base,
@@ -2571,19 +5044,6 @@ fn handle_pr_command(
     }
 }
 
-fn find_branch_by_name_mut<'a>(tree: &'a mut Branch, name: &str) -> Option<&'a mut Branch> {
-    if tree.name == name {
-        Some(tree)
-    } else {
-        for child in &mut tree.branches {
-            if let Some(found) = find_branch_by_name_mut(child, name) {
-                return Some(found);
-            }
-        }
-        None
-    }
-}
-
 // ============== GitHub Auth Commands ==============
 
 fn handle_auth_command(git_repo: &GitRepo, action: AuthAction) -> Result<()> {
@@ -2621,26 +5081,7 @@ fn handle_auth_command(git_repo: &GitRepo, action: AuthAction) -> Result<()> {
 
             match find_auth_source(&host) {
                 Some(source) => {
-                    let desc = match &source {
-                        AuthSource::EnvGithubToken => {
-                            "GITHUB_TOKEN environment variable".to_string()
-                        }
-                        AuthSource::EnvGhToken => "GH_TOKEN environment variable".to_string(),
-                        AuthSource::GitConfig => "git config (github.token)".to_string(),
-                        AuthSource::ConfigHostToken => {
-                            "config file (host-specific token)".to_string()
-                        }
-                        AuthSource::ConfigDefaultToken => {
-                            "config file (personal access token)".to_string()
-                        }
-                        AuthSource::ConfigOauth { scope } => match scope {
-                            Some(s) => format!("config file (OAuth, scope: {s})"),
-                            None => "config file (OAuth)".to_string(),
-                        },
-                        AuthSource::GhCli => {
-                            format!("gh CLI (delegated credentials for {host})")
-                        }
-                    };
+                    let desc = describe_auth_source(&source, &host);
                     println!(
                         "{} Active method: {}.",
                         "GitHub token is configured.".green(),
@@ -2679,6 +5120,87 @@ fn handle_auth_command(git_repo: &GitRepo, action: AuthAction) -> Result<()> {
     }
 }
 
+/// Where a GitHub token came from, for `auth status` and `whoami`.
+fn describe_auth_source(source: &github::AuthSource, host: &str) -> String {
+    use github::AuthSource;
+    match source {
+        AuthSource::EnvGithubToken => "GITHUB_TOKEN environment variable".to_string(),
+        AuthSource::EnvGhToken => "GH_TOKEN environment variable".to_string(),
+        AuthSource::GitConfig => "git config (github.token)".to_string(),
+        AuthSource::ConfigHostToken => "config file (host-specific token)".to_string(),
+        AuthSource::ConfigDefaultToken => "config file (personal access token)".to_string(),
+        AuthSource::ConfigOauth { scope } => match scope {
+            Some(s) => format!("config file (OAuth, scope: {s})"),
+            None => "config file (OAuth)".to_string(),
+        },
+        AuthSource::GhCli => format!("gh CLI (delegated credentials for {host})"),
+    }
+}
+
+/// `git stack whoami`: the token's source, the login it authenticates as (`GET /user`), and the
+/// author and fork filters that decide which PRs `sync` and `status` keep. Fails when there is no
+/// token or the login can't be looked up, after printing the rest.
+fn whoami(git_repo: &GitRepo) -> Result<()> {
+    let repo_id = github::get_repo_identifier(git_repo)?;
+    let host = &repo_id.host;
+    println!("Host:           {host}");
+
+    let source = match provider::ProviderKind::for_host(host) {
+        provider::ProviderKind::GitHub => {
+            github::find_auth_source(host).map(|source| describe_auth_source(&source, host))
+        }
+        provider::ProviderKind::GitLab => {
+            gitlab::resolve_token(host).map(|(_, source)| match source {
+                gitlab::TokenSource::EnvGitlabToken => {
+                    "GITLAB_TOKEN environment variable".to_string()
+                }
+                gitlab::TokenSource::ConfigHostToken => {
+                    "config file (host-specific token)".to_string()
+                }
+            })
+        }
+    };
+    let Some(source) = source else {
+        println!("Token source:   {}", "none".yellow());
+        bail!(
+            "No token configured for {host}. Run `git stack auth login` to set up authentication."
+        );
+    };
+    println!("Token source:   {source}");
+
+    let login = provider::client_for(&repo_id)
+        .map_err(anyhow::Error::from)
+        .and_then(|client| Ok(client.whoami()?));
+    match &login {
+        Ok(login) => println!("Login:          {}", login.green()),
+        Err(_) => println!("Login:          {}", "unknown".red()),
+    }
+
+    let authors = match github::configured_authors_filter() {
+        github::ConfiguredAuthorsFilter::Default => match &login {
+            Ok(login) => format!("{login} (default: your own PRs)"),
+            Err(_) => "your own PRs (default)".to_string(),
+        },
+        github::ConfiguredAuthorsFilter::Explicit(list) if list.is_empty() => {
+            "everyone (authors_filter: [])".to_string()
+        }
+        github::ConfiguredAuthorsFilter::Explicit(list) => list.join(", "),
+    };
+    println!("Authors filter: {authors}");
+    println!(
+        "Fork PRs:       {}",
+        if github::include_forks() {
+            "kept"
+        } else {
+            "hidden (set include_forks: true or pass --include-forks to keep them)"
+        }
+    );
+
+    login
+        .map(|_| ())
+        .with_context(|| format!("Failed to look up the login for {host}"))
+}
+
 // ============== Cache Commands ==============
 
 fn handle_cache_command(
@@ -2711,6 +5233,15 @@ fn handle_cache_command(
     }
 }
 
+/// `git stack prune-cache`: drop the repo's PR cache only.
+fn prune_pr_cache(git_repo: &GitRepo) -> Result<()> {
+    let repo_id = github::get_repo_identifier(git_repo)?;
+    let repo_full_name = repo_id.full_name();
+    crate::pr_cache::clear_pr_cache(&repo_full_name)?;
+    println!("Cleared PR cache for {}.", repo_full_name);
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2722,6 +5253,7 @@ mod tests {
             note: None,
             lkg_parent: None,
             pr_number: None,
+            mark: None,
             branches,
         }
     }
@@ -2775,9 +5307,20 @@ mod tests {
     fn edit_parses_without_config_flag() {
         let args = Args::try_parse_from(["git-stack", "edit"]).expect("edit should parse");
         match args.command {
-            Some(Command::Edit { config }) => assert!(!config),
+            Some(Command::Edit { config, repo }) => assert!(!config && !repo),
+            _ => panic!("expected Command::Edit"),
+        }
+    }
+
+    #[test]
+    fn edit_parses_with_repo_flag() {
+        let args =
+            Args::try_parse_from(["git-stack", "edit", "--repo"]).expect("edit --repo parses");
+        match args.command {
+            Some(Command::Edit { config, repo }) => assert!(!config && repo),
             _ => panic!("expected Command::Edit"),
         }
+        assert!(Args::try_parse_from(["git-stack", "edit", "--repo", "--config"]).is_err());
     }
 
     #[test]
@@ -2785,7 +5328,7 @@ mod tests {
         let args =
             Args::try_parse_from(["git-stack", "edit", "--config"]).expect("edit --config parses");
         match args.command {
-            Some(Command::Edit { config }) => assert!(config),
+            Some(Command::Edit { config, .. }) => assert!(config),
             _ => panic!("expected Command::Edit"),
         }
     }
@@ -2828,6 +5371,417 @@ mod tests {
         }
     }
 
+    #[test]
+    fn move_requires_a_new_parent() {
+        let args = Args::try_parse_from(["git-stack", "move", "feature", "--onto", "main", "-p"])
+            .expect("move should parse");
+        match args.command {
+            Some(Command::Move { branch, onto, push }) => {
+                assert_eq!(branch.as_deref(), Some("feature"));
+                assert_eq!(onto, "main");
+                assert!(push);
+            }
+            _ => panic!("expected Command::Move"),
+        }
+        assert!(Args::try_parse_from(["git-stack", "move", "feature"]).is_err());
+    }
+
+    #[test]
+    fn sync_author_repeats() {
+        let args =
+            Args::try_parse_from(["git-stack", "sync", "--author", "alice", "--author", "bob"])
+                .expect("sync --author should parse");
+        match args.command {
+            Some(Command::Sync { authors, .. }) => assert_eq!(authors, ["alice", "bob"]),
+            _ => panic!("expected Command::Sync"),
+        }
+    }
+
+    #[test]
+    fn note_append_conflicts_with_edit() {
+        assert!(Args::try_parse_from(["git-stack", "note", "--append", "x"]).is_ok());
+        assert!(Args::try_parse_from(["git-stack", "note", "-e", "--append", "x"]).is_err());
+        assert!(Args::try_parse_from(["git-stack", "note", "--all", "--append", "x"]).is_err());
+    }
+
+    #[test]
+    fn sync_include_forks_parses() {
+        let args = Args::try_parse_from(["git-stack", "sync", "--include-forks"])
+            .expect("sync --include-forks should parse");
+        match args.command {
+            Some(Command::Sync { include_forks, .. }) => assert!(include_forks),
+            _ => panic!("expected Command::Sync"),
+        }
+    }
+
+    #[test]
+    fn sync_since_accepts_dates_and_rfc3339() {
+        assert_eq!(
+            parse_since("2024-03-01").as_deref(),
+            Ok("2024-03-01T00:00:00Z")
+        );
+        assert_eq!(
+            parse_since("2024-03-01T12:30:00+02:00").as_deref(),
+            Ok("2024-03-01T10:30:00Z")
+        );
+        assert!(parse_since("last week").is_err());
+    }
+
+    #[test]
+    fn land_method_is_optional() {
+        let args = Args::try_parse_from(["git-stack", "land"]).expect("land should parse");
+        match args.command {
+            Some(Command::Land {
+                force,
+                method,
+                push,
+            }) => {
+                assert!(!force);
+                assert_eq!(method, None);
+                assert!(!push);
+            }
+            _ => panic!("expected Command::Land"),
+        }
+        let args = Args::try_parse_from(["git-stack", "land", "--method", "squash", "--force"])
+            .expect("land --method should parse");
+        assert!(matches!(
+            args.command,
+            Some(Command::Land {
+                force: true,
+                method: Some(github::MergeMethod::Squash),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn mount_onto_remote_excludes_an_explicit_parent() {
+        let args = Args::try_parse_from(["git-stack", "mount", "--onto-remote"])
+            .expect("mount --onto-remote should parse");
+        assert!(matches!(
+            args.command,
+            Some(Command::Mount {
+                parent_branch: None,
+                onto_remote: true,
+            })
+        ));
+        assert!(Args::try_parse_from(["git-stack", "mount", "main", "--onto-remote"]).is_err());
+    }
+
+    #[test]
+    fn no_color_is_a_global_flag() {
+        let args = Args::try_parse_from(["git-stack", "status", "--no-color"])
+            .expect("--no-color should parse after a subcommand");
+        assert!(args.no_color);
+        assert!(
+            !Args::try_parse_from(["git-stack", "status"])
+                .unwrap()
+                .no_color
+        );
+    }
+
+    #[test]
+    fn restack_dry_run_conflicts_with_recovery_flags() {
+        let args = Args::try_parse_from(["git-stack", "restack", "--dry-run", "-a"])
+            .expect("restack --dry-run should parse");
+        match args.command {
+            Some(Command::Restack {
+                dry_run, ancestors, ..
+            }) => {
+                assert!(dry_run);
+                assert!(ancestors);
+            }
+            _ => panic!("expected Command::Restack"),
+        }
+        assert!(Args::try_parse_from(["git-stack", "restack", "--dry-run", "--abort"]).is_err());
+    }
+
+    #[test]
+    fn fetch_prunes_unless_told_not_to() {
+        let args = Args::try_parse_from(["git-stack", "fetch"]).expect("fetch should parse");
+        assert!(matches!(
+            args.command,
+            Some(Command::Fetch {
+                prune: true,
+                tags: false
+            })
+        ));
+        let args = Args::try_parse_from(["git-stack", "fetch", "--no-prune", "--tags"])
+            .expect("fetch --no-prune --tags should parse");
+        assert!(matches!(
+            args.command,
+            Some(Command::Fetch {
+                prune: false,
+                tags: true
+            })
+        ));
+    }
+
+    #[test]
+    fn restack_push_all_parses() {
+        let args = Args::try_parse_from(["git-stack", "restack", "--push-all"])
+            .expect("restack --push-all should parse");
+        match args.command {
+            Some(Command::Restack { push, push_all, .. }) => {
+                assert!(!push, "--push-all implies --push at dispatch, not in clap");
+                assert!(push_all);
+            }
+            _ => panic!("expected Command::Restack"),
+        }
+    }
+
+    #[test]
+    fn graph_defaults_to_dot_format() {
+        let args = Args::try_parse_from(["git-stack", "graph"]).expect("graph should parse");
+        match args.command {
+            Some(Command::Graph { format }) => assert_eq!(format, render::GraphFormat::Dot),
+            _ => panic!("expected Command::Graph"),
+        }
+    }
+
+    #[test]
+    fn graph_parses_mermaid_format() {
+        let args = Args::try_parse_from(["git-stack", "graph", "--format", "mermaid"])
+            .expect("graph --format mermaid should parse");
+        match args.command {
+            Some(Command::Graph { format }) => assert_eq!(format, render::GraphFormat::Mermaid),
+            _ => panic!("expected Command::Graph"),
+        }
+    }
+
+    #[test]
+    fn status_json_flag_parses() {
+        let args = Args::try_parse_from(["git-stack", "status", "--json"])
+            .expect("status --json should parse");
+        match args.command {
+            Some(Command::Status {
+                json,
+                fetch,
+                no_fetch_trunk,
+                authors,
+                depth,
+                watch,
+            }) => {
+                assert!(json);
+                assert!(!fetch);
+                assert!(!no_fetch_trunk);
+                assert!(authors.is_empty());
+                assert_eq!(depth, None);
+                assert!(!watch);
+            }
+            _ => panic!("expected Command::Status"),
+        }
+    }
+
+    #[test]
+    fn status_no_fetch_trunk_conflicts_with_fetch() {
+        for flag in ["--no-fetch-trunk", "--no-pr-refresh"] {
+            let args = Args::try_parse_from(["git-stack", "status", flag])
+                .unwrap_or_else(|e| panic!("status {flag} should parse: {e}"));
+            assert!(matches!(
+                args.command,
+                Some(Command::Status {
+                    no_fetch_trunk: true,
+                    ..
+                })
+            ));
+            assert!(Args::try_parse_from(["git-stack", "status", flag, "--fetch"]).is_err());
+        }
+    }
+
+    #[test]
+    fn status_watch_parses_and_conflicts_with_json() {
+        let args = Args::try_parse_from(["git-stack", "status", "--watch"])
+            .expect("status --watch should parse");
+        match args.command {
+            Some(Command::Status { watch, .. }) => assert!(watch),
+            _ => panic!("expected Command::Status"),
+        }
+        assert!(Args::try_parse_from(["git-stack", "status", "--watch", "--json"]).is_err());
+    }
+
+    #[test]
+    fn status_depth_parses() {
+        let args = Args::try_parse_from(["git-stack", "status", "--depth", "2"])
+            .expect("status --depth should parse");
+        match args.command {
+            Some(Command::Status { depth, .. }) => assert_eq!(depth, Some(2)),
+            _ => panic!("expected Command::Status"),
+        }
+    }
+
+    #[test]
+    fn checks_summary_skips_zero_counts() {
+        let counts = |passing, failing, pending| github::CheckCounts {
+            passing,
+            failing,
+            pending,
+        };
+        assert_eq!(checks_summary(&counts(0, 0, 0)), "none");
+        assert_eq!(checks_summary(&counts(3, 0, 0)), "3 passing");
+        assert_eq!(
+            checks_summary(&counts(3, 1, 2)),
+            "3 passing, 1 failing, 2 pending"
+        );
+    }
+
+    #[test]
+    fn land_blockers_name_everything_force_would_skip() {
+        let readiness = |mergeable: Option<&str>, failing, pending| github::PrReadiness {
+            mergeable: mergeable.map(str::to_string),
+            checks: github::CheckCounts {
+                passing: 1,
+                failing,
+                pending,
+            },
+            review: github::ReviewDecision::Approved,
+        };
+        assert!(land_blockers(&readiness(Some("clean"), 0, 0), true).is_empty());
+        assert!(land_blockers(&readiness(Some("has_hooks"), 0, 0), true).is_empty());
+        assert_eq!(
+            land_blockers(&readiness(Some("blocked"), 1, 2), false),
+            [
+                "1 failing checks",
+                "2 pending checks",
+                "not mergeable (blocked)",
+                "the local branch differs from the PR's head",
+            ]
+        );
+        assert_eq!(
+            land_blockers(&readiness(None, 0, 0), true),
+            ["mergeability unknown"]
+        );
+    }
+
+    #[test]
+    fn pr_open_is_an_alias_of_pr_view() {
+        let args = Args::try_parse_from(["git-stack", "pr", "open", "feature"])
+            .expect("pr open should parse");
+        match args.command {
+            Some(Command::Pr {
+                action: PrAction::View { branch },
+            }) => assert_eq!(branch.as_deref(), Some("feature")),
+            _ => panic!("expected pr view"),
+        }
+    }
+
+    #[test]
+    fn stats_flags_alias_the_benchmark_flags() {
+        let args =
+            Args::try_parse_from(["git-stack", "status", "--stats"]).expect("--stats should parse");
+        assert!(args.benchmark);
+        assert!(!args.json && !args.stats_json);
+        let args = Args::try_parse_from(["git-stack", "restack", "--stats-json"])
+            .expect("--stats-json should parse");
+        assert!(args.stats_json);
+        let args =
+            Args::try_parse_from(["git-stack", "--json", "restack"]).expect("--json should parse");
+        assert!(args.json);
+    }
+
+    #[test]
+    fn status_json_leaves_the_stats_flags_alone() {
+        let args = Args::try_parse_from(["git-stack", "status", "--json"])
+            .expect("status --json should parse");
+        assert!(!args.json && !args.stats_json);
+        assert!(matches!(
+            args.command,
+            Some(Command::Status { json: true, .. })
+        ));
+    }
+
+    #[test]
+    fn author_flag_repeats_on_status_and_interactive() {
+        let args = Args::try_parse_from([
+            "git-stack",
+            "status",
+            "--author",
+            "octocat",
+            "--author",
+            "hubber",
+        ])
+        .expect("status --author should parse");
+        match args.command {
+            Some(Command::Status { authors, .. }) => assert_eq!(authors, ["octocat", "hubber"]),
+            _ => panic!("expected Command::Status"),
+        }
+        let args = Args::try_parse_from(["git-stack", "interactive", "--author", "octocat"])
+            .expect("interactive --author should parse");
+        match args.command {
+            Some(Command::Interactive { authors }) => assert_eq!(authors, ["octocat"]),
+            _ => panic!("expected Command::Interactive"),
+        }
+    }
+
+    #[test]
+    fn diff_parses_stat_and_pathspec() {
+        let args = Args::try_parse_from(["git-stack", "diff", "feature", "--stat", "--", "src/"])
+            .expect("diff --stat -- <path> should parse");
+        match args.command {
+            Some(Command::Diff {
+                branch,
+                stat,
+                name_only,
+                paths,
+            }) => {
+                assert_eq!(branch.as_deref(), Some("feature"));
+                assert!(stat);
+                assert!(!name_only);
+                assert_eq!(paths, vec!["src/".to_string()]);
+            }
+            _ => panic!("expected Command::Diff"),
+        }
+        assert!(
+            Args::try_parse_from(["git-stack", "diff", "--stat", "--name-only"]).is_err(),
+            "--stat and --name-only conflict"
+        );
+    }
+
+    #[test]
+    fn checkout_remote_flag_parses() {
+        let args = Args::try_parse_from(["git-stack", "checkout", "--remote", "feature"])
+            .expect("checkout --remote should parse");
+        match args.command {
+            Some(Command::Checkout {
+                branch_name,
+                remote,
+                parent,
+                slug,
+                autostash,
+                track,
+            }) => {
+                assert_eq!(branch_name, "feature");
+                assert!(remote);
+                assert_eq!(parent, None);
+                assert!(!slug);
+                assert!(!autostash);
+                assert!(!track);
+            }
+            _ => panic!("expected Command::Checkout"),
+        }
+    }
+
+    #[test]
+    fn checkout_parent_parses_and_conflicts_with_remote() {
+        let args = Args::try_parse_from(["git-stack", "checkout", "feature", "--parent", "base"])
+            .expect("checkout --parent should parse");
+        match args.command {
+            Some(Command::Checkout { parent, .. }) => assert_eq!(parent.as_deref(), Some("base")),
+            _ => panic!("expected Command::Checkout"),
+        }
+        assert!(
+            Args::try_parse_from([
+                "git-stack",
+                "checkout",
+                "feature",
+                "--remote",
+                "--parent",
+                "base"
+            ])
+            .is_err()
+        );
+    }
+
     #[test]
     fn collect_branches_without_author_skips_the_root_and_known_authors() {
         // main (root, always excluded)
@@ -2903,4 +5857,14 @@ mod tests {
         );
         assert_eq!(skipped, vec!["b3".to_string(), "b4".to_string()]);
     }
+
+    #[test]
+    fn unmerged_status_lines_cover_every_conflict_state() {
+        for line in ["UU a", "AA a", "DD a", "AU a", "UA a", "DU a", "UD a"] {
+            assert!(is_unmerged_status_line(line), "{line}");
+        }
+        for line in ["M  a", " M a", "A  a", "D  a", "?? a", "AM a", ""] {
+            assert!(!is_unmerged_status_line(line), "{line}");
+        }
+    }
 }
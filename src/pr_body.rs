@@ -0,0 +1,208 @@
+//! Default PR descriptions.
+//!
+//! `pr create` (without `--body`) and the ancestor PRs it creates along the way get a body built
+//! from the branch's own commits, plus a "Stacked on #N" line pointing reviewers at the parent
+//! branch's PR. A repo can supply its own layout in `.git-stack/pr_template.md`, using these
+//! placeholders:
+//!
+//! - `{{commits}}`: one bullet per commit (subject, then its body indented beneath it)
+//! - `{{stacked_on}}`: `Stacked on #N` when the parent branch has a PR, otherwise empty
+//! - `{{branch}}` / `{{parent}}`: the branch and its parent branch names
+
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::{git::run_git, git2_ops::GitRepo, state::State};
+
+/// Repo-relative path of the PR body template.
+pub(crate) const TEMPLATE_PATH: &str = ".git-stack/pr_template.md";
+
+const DEFAULT_TEMPLATE: &str = "{{stacked_on}}\n\n{{commits}}\n";
+
+/// Separates fields / records in the `git log` format below; neither can appear in a message.
+const FIELD_SEP: char = '\x1f';
+const RECORD_SEP: char = '\x1e';
+
+#[derive(Debug, PartialEq, Eq)]
+struct CommitMessage {
+    subject: String,
+    body: String,
+}
+
+/// Build the body for `branch`'s new PR. The commits are those since its `lkg_parent` (or the
+/// merge-base with its parent when that isn't usable), oldest first. `parent_pr` is the parent's
+/// PR if the caller already knows it (e.g. it was just created); otherwise the parent's recorded
+/// `pr_number` is used.
+pub(crate) fn build_pr_body(
+    git_repo: &GitRepo,
+    state: &State,
+    repo: &str,
+    branch: &str,
+    parent_pr: Option<u64>,
+) -> Result<String> {
+    let parent = state.get_parent_branch_of(repo, branch);
+    let parent_name = parent.map(|p| p.name.as_str()).unwrap_or_default();
+    let parent_pr = parent_pr.or_else(|| parent.and_then(|p| p.pr_number));
+
+    let lkg_parent = state
+        .get_tree_branch(repo, branch)
+        .and_then(|b| b.lkg_parent.clone())
+        .filter(|sha| git_repo.is_ancestor(sha, branch).unwrap_or(false));
+    let base = match lkg_parent {
+        Some(sha) => sha,
+        None => git_repo.merge_base(parent_name, branch)?,
+    };
+
+    let log = run_git(&[
+        "log",
+        "--reverse",
+        "--no-show-signature",
+        &format!("--format=%s{FIELD_SEP}%b{RECORD_SEP}"),
+        &format!("{base}..{branch}"),
+    ])?
+    .output()
+    .unwrap_or_default();
+
+    let template_path = Path::new(repo).join(TEMPLATE_PATH);
+    let template = match std::fs::read_to_string(&template_path) {
+        Ok(template) => template,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => DEFAULT_TEMPLATE.to_string(),
+        Err(e) => {
+            tracing::warn!(
+                "Ignoring {}: {e}; using the default PR body.",
+                template_path.display()
+            );
+            DEFAULT_TEMPLATE.to_string()
+        }
+    };
+
+    Ok(render(
+        &template,
+        &parse_log(&log),
+        branch,
+        parent_name,
+        parent_pr,
+    ))
+}
+
+fn parse_log(log: &str) -> Vec<CommitMessage> {
+    log.split(RECORD_SEP)
+        .filter_map(|record| {
+            let (subject, body) = record.split_once(FIELD_SEP)?;
+            Some(CommitMessage {
+                subject: subject.trim().to_string(),
+                body: body.trim().to_string(),
+            })
+        })
+        .filter(|c| !c.subject.is_empty())
+        .collect()
+}
+
+fn render(
+    template: &str,
+    commits: &[CommitMessage],
+    branch: &str,
+    parent: &str,
+    parent_pr: Option<u64>,
+) -> String {
+    let commit_list = commits
+        .iter()
+        .map(|c| {
+            let mut item = format!("- {}", c.subject);
+            for line in c.body.lines() {
+                item.push('\n');
+                if !line.trim().is_empty() {
+                    item.push_str("  ");
+                    item.push_str(line);
+                }
+            }
+            item
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let stacked_on = parent_pr
+        .map(|n| format!("Stacked on #{n}"))
+        .unwrap_or_default();
+
+    let rendered = template
+        .replace("{{commits}}", &commit_list)
+        .replace("{{stacked_on}}", &stacked_on)
+        .replace("{{branch}}", branch)
+        .replace("{{parent}}", parent);
+
+    // Empty placeholders leave blank runs behind; collapse them to single blank lines.
+    let mut out = String::new();
+    let mut blank_run = 0;
+    for line in rendered.trim().lines() {
+        if line.trim().is_empty() {
+            blank_run += 1;
+            if blank_run > 1 {
+                continue;
+            }
+        } else {
+            blank_run = 0;
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(subject: &str, body: &str) -> CommitMessage {
+        CommitMessage {
+            subject: subject.to_string(),
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn parse_log_splits_subjects_and_bodies() {
+        let log = format!(
+            "Add parser{FIELD_SEP}Handles nested lists.\n\nAnd tables.\n{RECORD_SEP}\nFix typo{FIELD_SEP}{RECORD_SEP}"
+        );
+        assert_eq!(
+            parse_log(&log),
+            vec![
+                commit("Add parser", "Handles nested lists.\n\nAnd tables."),
+                commit("Fix typo", ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn default_template_bullets_commits_under_the_stacked_on_line() {
+        let commits = [
+            commit("Add parser", "Handles nested lists.\n\nAnd tables."),
+            commit("Fix typo", ""),
+        ];
+        assert_eq!(
+            render(DEFAULT_TEMPLATE, &commits, "parser", "lexer", Some(41)),
+            "Stacked on #41\n\n- Add parser\n  Handles nested lists.\n\n  And tables.\n- Fix typo\n"
+        );
+        // No parent PR: no dangling blank line at the top.
+        assert_eq!(
+            render(DEFAULT_TEMPLATE, &commits[1..], "parser", "main", None),
+            "- Fix typo\n"
+        );
+    }
+
+    #[test]
+    fn custom_template_fills_every_placeholder() {
+        let template = "## {{branch}} (onto {{parent}})\n\n{{stacked_on}}\n\n### Commits\n{{commits}}\n\n### Testing\n";
+        assert_eq!(
+            render(
+                template,
+                &[commit("Add parser", "")],
+                "parser",
+                "lexer",
+                None
+            ),
+            "## parser (onto lexer)\n\n### Commits\n- Add parser\n\n### Testing\n"
+        );
+    }
+}
@@ -8,7 +8,7 @@
 use std::{collections::HashMap, path::Path};
 
 use anyhow::{Context, Result};
-use redb::{ReadableDatabase, ReadableTable, TableDefinition, TableError};
+use redb::{ReadableDatabase, ReadableTable, TableDefinition, TableError, TableHandle};
 
 use crate::github::CachedPullRequest;
 
@@ -271,8 +271,12 @@ impl PrCacheHandle {
         Ok(())
     }
 
-    /// Remove all cached data (closed PRs and watermark) for `repo`.
-    pub fn clear_repo(&self, repo: &str) -> Result<()> {
+    /// Drop `branches`' cached closed PRs for `repo`, leaving the watermark alone. No-ops if
+    /// `branches` is empty.
+    pub fn remove_closed_prs(&self, repo: &str, branches: &[&str]) -> Result<()> {
+        if branches.is_empty() {
+            return Ok(());
+        }
         let write_txn = self
             .db
             .begin_write()
@@ -281,64 +285,111 @@ impl PrCacheHandle {
             let mut table = write_txn
                 .open_table(CLOSED_PRS_TABLE)
                 .context("opening closed PRs table")?;
-            let branches: Vec<String> = {
-                let mut branches = Vec::new();
-                for entry in table
-                    .range((repo, "")..)
-                    .context("scanning closed PRs table")?
-                {
-                    let (key, _) = entry.context("reading closed PR cache entry")?;
-                    let (key_repo, key_branch) = key.value();
-                    if key_repo != repo {
-                        break;
-                    }
-                    branches.push(key_branch.to_string());
-                }
-                branches
-            };
             for branch in branches {
                 table
-                    .remove((repo, branch.as_str()))
+                    .remove((repo, *branch))
                     .context("removing cached PR")?;
             }
         }
+        write_txn.commit().context("committing PR cache eviction")?;
+        Ok(())
+    }
+
+    /// Remove all cached data (closed PRs and watermark) for `repo`.
+    pub fn clear_repo(&self, repo: &str) -> Result<()> {
+        let write_txn = self
+            .db
+            .begin_write()
+            .context("opening PR cache write transaction")?;
+        remove_repo_rows(&write_txn, CLOSED_PRS_TABLE, repo)?;
+        remove_repo_rows(&write_txn, OPEN_PRS_TABLE, repo)?;
         {
             let mut table = write_txn
-                .open_table(OPEN_PRS_TABLE)
-                .context("opening open PRs table")?;
-            let branches: Vec<String> = {
-                let mut branches = Vec::new();
-                for entry in table
-                    .range((repo, "")..)
-                    .context("scanning open PRs table")?
-                {
-                    let (key, _) = entry.context("reading open PR cache entry")?;
-                    let (key_repo, key_branch) = key.value();
-                    if key_repo != repo {
-                        break;
-                    }
-                    branches.push(key_branch.to_string());
-                }
-                branches
-            };
-            for branch in branches {
+                .open_table(WATERMARKS_TABLE)
+                .context("opening watermarks table")?;
+            table.remove(repo).context("removing watermark")?;
+        }
+        write_txn.commit().context("committing PR cache clear")?;
+        Ok(())
+    }
+
+    /// Authoritative full replace of `repo`'s closed PRs and watermark with a fetch that ignored
+    /// the watermark (`sync --no-cache`), in one write transaction. A `None` watermark removes it.
+    /// Cached open PRs are kept.
+    pub fn replace_closed_prs<'a>(
+        &self,
+        repo: &str,
+        fresh: impl Iterator<Item = (&'a str, &'a CachedPullRequest)>,
+        watermark: Option<&str>,
+    ) -> Result<()> {
+        let write_txn = self
+            .db
+            .begin_write()
+            .context("opening PR cache write transaction")?;
+        remove_repo_rows(&write_txn, CLOSED_PRS_TABLE, repo)?;
+        {
+            let mut table = write_txn
+                .open_table(CLOSED_PRS_TABLE)
+                .context("opening closed PRs table")?;
+            for (branch, pr) in fresh {
+                let value = serde_json::to_vec(pr).context("serializing cached PR")?;
                 table
-                    .remove((repo, branch.as_str()))
-                    .context("removing cached open PR")?;
+                    .insert((repo, branch), value.as_slice())
+                    .context("inserting cached PR")?;
             }
         }
         {
             let mut table = write_txn
                 .open_table(WATERMARKS_TABLE)
                 .context("opening watermarks table")?;
-            table.remove(repo).context("removing watermark")?;
+            match watermark {
+                Some(watermark) => table
+                    .insert(repo, watermark)
+                    .context("updating watermark")?,
+                None => table.remove(repo).context("removing watermark")?,
+            };
         }
-        write_txn.commit().context("committing PR cache clear")?;
+        write_txn
+            .commit()
+            .context("committing closed PR cache replace")?;
         Ok(())
     }
 }
 
-/// Clear PR cache for a specific repo (used by `git stack cache clear`).
+/// Delete every `(repo, branch)` row of `definition` for `repo`.
+fn remove_repo_rows(
+    write_txn: &redb::WriteTransaction,
+    definition: TableDefinition<(&str, &str), &[u8]>,
+    repo: &str,
+) -> Result<()> {
+    let mut table = write_txn
+        .open_table(definition)
+        .with_context(|| format!("opening {} table", definition.name()))?;
+    let branches: Vec<String> = {
+        let mut branches = Vec::new();
+        for entry in table
+            .range((repo, "")..)
+            .with_context(|| format!("scanning {} table", definition.name()))?
+        {
+            let (key, _) = entry.context("reading PR cache entry")?;
+            let (key_repo, key_branch) = key.value();
+            if key_repo != repo {
+                break;
+            }
+            branches.push(key_branch.to_string());
+        }
+        branches
+    };
+    for branch in branches {
+        table
+            .remove((repo, branch.as_str()))
+            .context("removing cached PR")?;
+    }
+    Ok(())
+}
+
+/// Clear PR cache for a specific repo (used by `git stack cache clear` and
+/// `git stack prune-cache`).
 pub fn clear_pr_cache(repo_full_name: &str) -> Result<()> {
     PrCacheHandle::open()?.clear_repo(repo_full_name)
 }
@@ -384,11 +435,13 @@ mod tests {
                 ref_name: "main".to_string(),
                 sha: "basesha".to_string(),
                 repo: None,
+                label: None,
             },
             head: CachedPrBranchRef {
                 ref_name: branch.to_string(),
                 sha: "headsha".to_string(),
                 repo: None,
+                label: None,
             },
             user: CachedPrUser {
                 login: "octocat".to_string(),
@@ -693,6 +746,47 @@ mod tests {
         assert_eq!(sibling.get("b1").unwrap().number, 2);
     }
 
+    #[test]
+    fn replace_closed_prs_drops_stale_and_keeps_open_prs() {
+        let dir = tempfile::tempdir().unwrap();
+        let handle = open_test_handle(&dir);
+
+        let closed = sample_pr("old", 1);
+        let open = sample_open_pr("new", 2);
+        handle
+            .commit_fresh_prs(
+                "acme/app",
+                vec![("old", &closed)].into_iter(),
+                Some("2024-01-01"),
+            )
+            .unwrap();
+        handle
+            .replace_open_prs("acme/app", &[("new", &open)])
+            .unwrap();
+
+        let refetched = sample_pr("kept", 3);
+        handle
+            .replace_closed_prs(
+                "acme/app",
+                vec![("kept", &refetched)].into_iter(),
+                Some("2024-02-01"),
+            )
+            .unwrap();
+        let closed = handle.closed_prs_for_repo("acme/app").unwrap();
+        assert_eq!(closed.keys().collect::<Vec<_>>(), ["kept"]);
+        assert_eq!(
+            handle.watermark("acme/app").unwrap().as_deref(),
+            Some("2024-02-01")
+        );
+
+        handle
+            .replace_closed_prs("acme/app", std::iter::empty(), None)
+            .unwrap();
+        assert!(handle.closed_prs_for_repo("acme/app").unwrap().is_empty());
+        assert_eq!(handle.watermark("acme/app").unwrap(), None);
+        assert_eq!(handle.open_prs_for_repo("acme/app").unwrap().len(), 1);
+    }
+
     #[test]
     fn open_and_closed_tables_are_independent() {
         let dir = tempfile::tempdir().unwrap();
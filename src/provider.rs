@@ -0,0 +1,234 @@
+//! Hosting-provider abstraction for PR/MR operations.
+//!
+//! `sync` and the `pr` commands only need a handful of pull-request operations. They're expressed
+//! here as the `RemoteProvider` trait so the same code drives GitHub pull requests
+//! (`crate::github::GitHubClient`) and GitLab merge requests (`crate::gitlab::GitLabClient`).
+//! GitLab merge requests are mapped onto the GitHub-shaped `PullRequest` type, so everything
+//! downstream (caches, rendering, the sync planner) is provider-agnostic.
+
+use crate::{
+    github::{
+        ClosedPrCacheMode, ClosedPrLimits, ClosedPrList, CommentUpsert, CreatePrRequest,
+        GitHubClient, GitHubError, MergeMethod, PrListResult, PrReadiness, PullRequest,
+        RepoIdentifier, ScopedOpenPrs, UpdatePrRequest,
+    },
+    gitlab::GitLabClient,
+};
+
+/// Which hosting API a remote speaks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderKind {
+    GitHub,
+    GitLab,
+}
+
+impl ProviderKind {
+    /// Pick the provider for a remote host: `gitlab.com` and any `gitlab.*` host (the usual
+    /// self-managed naming) are GitLab; everything else is GitHub (github.com or GHE).
+    pub fn for_host(host: &str) -> Self {
+        let host = host.to_ascii_lowercase();
+        if host == "gitlab.com" || host.starts_with("gitlab.") {
+            Self::GitLab
+        } else {
+            Self::GitHub
+        }
+    }
+}
+
+/// Build the provider client for `repo_id`'s host, resolving its token the same way for both
+/// providers (see `github::find_auth_source`).
+pub fn client_for(repo_id: &RepoIdentifier) -> Result<Box<dyn RemoteProvider>, GitHubError> {
+    match ProviderKind::for_host(&repo_id.host) {
+        ProviderKind::GitHub => Ok(Box::new(GitHubClient::from_env(repo_id)?)),
+        ProviderKind::GitLab => Ok(Box::new(GitLabClient::from_env(repo_id)?)),
+    }
+}
+
+/// Pull-request operations git-stack needs from a hosting provider. Implementations must be
+/// `Sync`: `list_open_prs_for_branches` fans lookups out across threads.
+pub trait RemoteProvider: Send + Sync {
+    /// Login/username of the authenticated user.
+    fn whoami(&self) -> Result<String, GitHubError>;
+
+    /// Get a PR by number.
+    fn get_pr(&self, repo: &RepoIdentifier, pr_number: u64) -> Result<PullRequest, GitHubError>;
+
+    /// Find the open PR whose head is `branch` (None if there is none).
+    fn find_pr_for_branch(
+        &self,
+        repo: &RepoIdentifier,
+        branch: &str,
+    ) -> Result<Option<PullRequest>, GitHubError>;
+
+    /// Create a new PR.
+    fn create_pr(
+        &self,
+        repo: &RepoIdentifier,
+        request: CreatePrRequest,
+    ) -> Result<PullRequest, GitHubError>;
+
+    /// Update a PR (e.g. to retarget its base).
+    fn update_pr(
+        &self,
+        repo: &RepoIdentifier,
+        pr_number: u64,
+        request: UpdatePrRequest,
+    ) -> Result<PullRequest, GitHubError>;
+
+    /// List every open PR in the repo, keyed by head branch (forks filtered out).
+    fn list_open_prs(
+        &self,
+        repo: &RepoIdentifier,
+        on_progress: Option<&dyn Fn(usize, usize)>,
+    ) -> Result<PrListResult, GitHubError>;
+
+    /// List closed/merged PRs through the on-disk watermark cache (or around it, per `mode`),
+    /// fetching no further back than `limits` allows.
+    fn list_closed_prs_with_cache(
+        &self,
+        repo: &RepoIdentifier,
+        cache: &crate::pr_cache::PrCacheHandle,
+        limits: &ClosedPrLimits,
+        mode: ClosedPrCacheMode,
+        on_progress: Option<&dyn Fn(usize, usize)>,
+    ) -> Result<ClosedPrList, GitHubError>;
+
+    /// Enumerate the open PRs authored by any of `authors`, fork PRs excluded. Empty `authors`
+    /// yields `Ok(vec![])` with no request.
+    fn list_open_prs_by_authors(
+        &self,
+        repo: &RepoIdentifier,
+        authors: &[String],
+    ) -> Result<Vec<PullRequest>, GitHubError>;
+
+    /// Post `body` on PR `pr_number`, or edit git-stack's earlier comment there (the first one
+    /// containing `marker`), so the PR carries a single up-to-date copy.
+    fn create_or_update_comment(
+        &self,
+        repo: &RepoIdentifier,
+        pr_number: u64,
+        marker: &str,
+        body: &str,
+    ) -> Result<CommentUpsert, GitHubError>;
+
+    /// Mergeability, CI check results, and review state of PR `pr_number`.
+    fn pr_readiness(
+        &self,
+        repo: &RepoIdentifier,
+        pr_number: u64,
+    ) -> Result<PrReadiness, GitHubError>;
+
+    /// Merge PR `pr_number` with `method`, provided its head is still `head_sha`. `commit_title`
+    /// overrides the provider's default merge or squash commit title.
+    fn merge_pr(
+        &self,
+        repo: &RepoIdentifier,
+        pr_number: u64,
+        head_sha: &str,
+        method: MergeMethod,
+        commit_title: Option<&str>,
+    ) -> Result<(), GitHubError>;
+
+    /// Delete `branch` from the repo (`sync --prune-remote`, for a merged PR's head branch).
+    fn delete_branch(&self, repo: &RepoIdentifier, branch: &str) -> Result<(), GitHubError>;
+
+    /// The merge methods the repo's settings allow.
+    fn allowed_merge_methods(&self, repo: &RepoIdentifier)
+    -> Result<Vec<MergeMethod>, GitHubError>;
+
+    /// Fetch open PRs for exactly `branches` (the stack's branches) with bounded parallelism,
+    /// scaling with stack size rather than total repo PR activity. Each branch is looked up with
+    /// `find_pr_for_branch`. Best-effort: never returns `Result` — a per-branch error omits that
+    /// branch from both outcome lists so the caller keeps its cached (last-known-good) entry.
+    fn list_open_prs_for_branches(
+        &self,
+        repo: &RepoIdentifier,
+        branches: &[String],
+    ) -> ScopedOpenPrs {
+        if branches.is_empty() {
+            return ScopedOpenPrs::default();
+        }
+
+        // branches is non-empty (early return above), so this is always >= 1.
+        let worker_count = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .min(branches.len())
+            .min(8);
+
+        // Partition branches round-robin across workers.
+        let mut buckets: Vec<Vec<&String>> = (0..worker_count).map(|_| Vec::new()).collect();
+        for (i, branch) in branches.iter().enumerate() {
+            buckets[i % worker_count].push(branch);
+        }
+
+        let mut result = ScopedOpenPrs::default();
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = buckets
+                .into_iter()
+                .map(|bucket| {
+                    scope.spawn(move || {
+                        let mut found: Vec<(String, PullRequest)> = Vec::new();
+                        let mut absent: Vec<String> = Vec::new();
+                        for branch in bucket {
+                            match self.find_pr_for_branch(repo, branch) {
+                                Ok(Some(pr)) => found.push((branch.clone(), pr)),
+                                Ok(None) => absent.push(branch.clone()),
+                                Err(e) => {
+                                    tracing::debug!(
+                                        "Scoped open-PR fetch failed for branch {}: {}",
+                                        branch,
+                                        e
+                                    );
+                                }
+                            }
+                        }
+                        // `GitBenchmark` records into thread-local stats, so hand this worker's
+                        // `find-pr` spans back for merging into the caller's thread.
+                        (found, absent, crate::stats::get_stats())
+                    })
+                })
+                .collect();
+
+            for handle in handles {
+                if let Ok((found, absent, stats)) = handle.join() {
+                    for (branch, pr) in found {
+                        result.found.insert(branch, pr);
+                    }
+                    result.confirmed_absent.extend(absent);
+                    crate::stats::merge_into_current(&stats);
+                }
+            }
+        });
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gitlab_hosts_select_gitlab() {
+        assert_eq!(ProviderKind::for_host("gitlab.com"), ProviderKind::GitLab);
+        assert_eq!(
+            ProviderKind::for_host("gitlab.example.com"),
+            ProviderKind::GitLab
+        );
+        assert_eq!(ProviderKind::for_host("GitLab.com"), ProviderKind::GitLab);
+    }
+
+    #[test]
+    fn other_hosts_select_github() {
+        assert_eq!(ProviderKind::for_host("github.com"), ProviderKind::GitHub);
+        assert_eq!(
+            ProviderKind::for_host("github.example.com"),
+            ProviderKind::GitHub
+        );
+        assert_eq!(
+            ProviderKind::for_host("code.example.com"),
+            ProviderKind::GitHub
+        );
+    }
+}
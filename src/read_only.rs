@@ -0,0 +1,154 @@
+//! The global `--read-only` guard. Once enabled, every git invocation that could change refs, the
+//! index, the working tree, or the remote, and every GitHub/GitLab write request, fails instead of
+//! running. Unlike a command's `--dry-run`, this holds on every code path, including ones that
+//! don't implement a dry run.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{Result, bail};
+
+/// Set at startup by `--read-only` for the whole process.
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn enable() {
+    READ_ONLY.store(true, Ordering::Relaxed);
+}
+
+pub(crate) fn enabled() -> bool {
+    READ_ONLY.load(Ordering::Relaxed)
+}
+
+/// Refuse `git <args>` when read-only mode is on and the invocation can mutate anything.
+pub(crate) fn check_git(args: &[&str]) -> Result<()> {
+    if enabled() && is_mutating_git(args) {
+        bail!(
+            "Refusing to run `git {}`: git-stack is running with --read-only.",
+            args.join(" ")
+        );
+    }
+    Ok(())
+}
+
+/// The error message for a blocked provider write (`POST`/`PATCH`/`PUT`/`DELETE` to `url`), or
+/// `None` when the request may go ahead.
+pub(crate) fn blocked_request(method: &str, url: &str) -> Option<String> {
+    enabled()
+        .then(|| format!("Refusing to send {method} {url}: git-stack is running with --read-only."))
+}
+
+/// Whether `git <args>` can change refs, the index, the working tree, config, or the remote.
+/// Object-only plumbing (`write-tree`, `commit-tree`, `hash-object`) counts as read-only: it adds
+/// unreferenced objects but changes nothing a user can see.
+fn is_mutating_git(args: &[&str]) -> bool {
+    // Skip global options; `-c`/`-C` take a value.
+    let mut rest = args;
+    while let Some((first, tail)) = rest.split_first() {
+        match *first {
+            "-c" | "-C" => rest = tail.get(1..).unwrap_or_default(),
+            option if option.starts_with('-') => rest = tail,
+            _ => break,
+        }
+    }
+    let Some((subcommand, rest)) = rest.split_first() else {
+        return false;
+    };
+    let has = |flags: &[&str]| rest.iter().any(|arg| flags.contains(arg));
+    let positionals = rest.iter().filter(|arg| !arg.starts_with('-')).count();
+    match *subcommand {
+        "push" | "fetch" | "pull" | "checkout" | "switch" | "commit" | "merge" | "rebase"
+        | "am" | "cherry-pick" | "revert" | "reset" | "restore" | "update-ref" | "read-tree"
+        | "add" | "rm" | "mv" | "clean" | "gc" | "prune" | "notes" => true,
+        "apply" => !has(&["--check", "--stat", "--numstat", "--summary"]),
+        "stash" => !matches!(rest.first(), Some(&"list" | &"show")),
+        "worktree" => !matches!(rest.first(), Some(&"list")),
+        "branch" => {
+            // Bare `git branch` and the listing/query forms are read-only.
+            !(rest.is_empty()
+                || has(&[
+                    "--list",
+                    "-l",
+                    "-r",
+                    "-a",
+                    "--all",
+                    "--remotes",
+                    "--show-current",
+                    "--contains",
+                    "--no-contains",
+                    "--merged",
+                    "--no-merged",
+                    "--points-at",
+                    "-v",
+                    "-vv",
+                ])
+                || rest.iter().any(|arg| arg.starts_with("--format")))
+        }
+        "tag" => {
+            !(rest.is_empty() || has(&["-l", "--list", "--contains", "--points-at", "--merged"]))
+        }
+        "remote" => !matches!(
+            rest.first(),
+            None | Some(&"-v" | &"--verbose" | &"get-url" | &"show")
+        ),
+        "config" => !has(&[
+            "--get",
+            "--get-all",
+            "--get-regexp",
+            "--get-urlmatch",
+            "--list",
+            "-l",
+        ]),
+        "symbolic-ref" => has(&["-d", "--delete"]) || positionals >= 2,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_the_git_calls_git_stack_makes() {
+        for args in [
+            &["push", "-u", "origin", "a:a"][..],
+            &["fetch", "--prune", "origin"],
+            &["checkout", "-B", "a", "main"],
+            &["branch", "-D", "a"],
+            &["branch", "--set-upstream-to", "origin/a", "a"],
+            &["branch", "new"],
+            &["am", "--3way"],
+            &["stash", "push", "--include-untracked"],
+            &["worktree", "add", "-q", "../wt"],
+            &["update-ref", "refs/heads/a", "HEAD"],
+            &["remote", "set-head", "origin", "--auto"],
+            &["config", "branch.a.merge", "refs/heads/a"],
+            &[
+                "symbolic-ref",
+                "refs/remotes/origin/HEAD",
+                "refs/remotes/origin/main",
+            ],
+            &["-c", "core.editor=true", "rebase", "--continue"],
+        ] {
+            assert!(is_mutating_git(args), "{args:?}");
+        }
+        for args in [
+            &["rev-parse", "HEAD"][..],
+            &["log", "--format=%s", "-1", "a"],
+            &["status", "--porcelain"],
+            &["diff", "main...a"],
+            &["branch", "--show-current"],
+            &["branch", "-r", "--list", "origin/a"],
+            &["branch"],
+            &["stash", "list"],
+            &["worktree", "list", "--porcelain"],
+            &["config", "--get", "github.token"],
+            &["symbolic-ref", "refs/remotes/origin/HEAD"],
+            &["apply", "--check"],
+            &["write-tree"],
+            &["commit-tree", "abc", "-p", "def", "-m", "msg"],
+            &["-C", "/repo", "rev-parse", "HEAD"],
+            &[],
+        ] {
+            assert!(!is_mutating_git(args), "{args:?}");
+        }
+    }
+}
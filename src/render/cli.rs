@@ -3,7 +3,7 @@
 use colored::Colorize;
 
 use super::{
-    colors::{ThemeColor, string_to_color, theme},
+    colors::{Theme, ThemeColor, string_to_color},
     tree_data::{RenderableBranch, RenderableTree},
 };
 use crate::github::PrDisplayState;
@@ -20,73 +20,121 @@ fn selection_marker() -> &'static str {
 }
 
 /// Apply color to a string using the colored crate.
-fn apply_color(s: &str, color: ThemeColor) -> colored::ColoredString {
-    let (r, g, b) = color.rgb();
-    s.truecolor(r, g, b)
+fn apply_color(theme: &Theme, s: &str, color: ThemeColor) -> colored::ColoredString {
+    theme.paint(s, color)
+}
+
+/// Print the status header line: how long ago `sync` last ran, e.g. `(synced 2h ago)`.
+pub fn render_sync_age(age: chrono::TimeDelta, theme: &Theme) {
+    let header = format!("(synced {})", humanize_age(age));
+    println!("{}", apply_color(theme, &header, theme.muted));
+}
+
+/// A coarse, compact age: `just now`, `5m ago`, `2h ago`, `3d ago`.
+fn humanize_age(age: chrono::TimeDelta) -> String {
+    let minutes = age.num_minutes();
+    if minutes < 1 {
+        "just now".to_string()
+    } else if minutes < 60 {
+        format!("{minutes}m ago")
+    } else if age.num_hours() < 24 {
+        format!("{}h ago", age.num_hours())
+    } else {
+        format!("{}d ago", age.num_days())
+    }
 }
 
 /// Render the tree to the CLI.
-pub fn render_cli(tree: &RenderableTree, verbose: bool) {
+pub fn render_cli(tree: &RenderableTree, verbose: bool, theme: &Theme) {
     for branch in &tree.branches {
-        render_branch(branch, verbose);
+        render_branch(branch, verbose, theme);
+        if branch.elided_below > 0 {
+            print!("  ");
+            for _ in 0..=branch.depth {
+                print!("{}", apply_color(theme, "┃ ", theme.tree));
+            }
+            println!(
+                "{}",
+                apply_color(
+                    theme,
+                    &format!("… ({} more)", branch.elided_below),
+                    theme.gray
+                )
+            );
+        }
     }
 }
 
-fn render_branch(branch: &RenderableBranch, verbose: bool) {
+fn render_branch(branch: &RenderableBranch, verbose: bool, theme: &Theme) {
     let dim = if branch.is_dimmed { DIM_FACTOR } else { 1.0 };
 
     // Selection marker
     if branch.is_current {
-        print!("{} ", selection_marker().bright_purple().bold());
+        let marker = if theme.monochrome {
+            selection_marker().bold()
+        } else {
+            selection_marker().bright_purple().bold()
+        };
+        print!("{marker} ");
     } else {
         print!("  ");
     }
 
     // Tree indentation
     for _ in 0..branch.depth {
-        print!("{}", apply_color("┃ ", theme::TREE));
+        print!("{}", apply_color(theme, "┃ ", theme.tree));
     }
 
     // Handle remote-only branches without status
     if branch.is_remote_only && branch.status.is_none() {
-        let branch_color = theme::GRAY.apply_dim(dim);
-        println!("{}", apply_color(&branch.name, branch_color));
+        let branch_color = theme.gray.apply_dim(dim);
+        println!("{}", apply_color(theme, &branch.name, branch_color));
         return;
     }
 
     // Branch name with status-based coloring
     let branch_color = if let Some(ref status) = branch.status {
         if status.is_descendent {
-            theme::GREEN.apply_dim(dim)
+            theme.green.apply_dim(dim)
         } else {
-            theme::YELLOW.apply_dim(dim)
+            theme.yellow.apply_dim(dim)
         }
     } else {
-        theme::GRAY.apply_dim(dim)
+        theme.gray.apply_dim(dim)
     };
 
     let branch_name = if branch.is_current {
-        apply_color(&branch.name, branch_color).bold()
+        apply_color(theme, &branch.name, branch_color).bold()
     } else {
-        apply_color(&branch.name, branch_color)
+        apply_color(theme, &branch.name, branch_color)
     };
 
+    // Mark tag, right after the name
+    let mark = branch
+        .mark
+        .map(|mark| {
+            let color = theme.mark(mark).apply_dim(dim);
+            format!(" {}", apply_color(theme, &format!("({mark})"), color))
+        })
+        .unwrap_or_default();
+
     // Diff stats
     let diff_stats = branch
         .diff_stats
         .as_ref()
         .map(|ds| {
-            let green = theme::GREEN.apply_dim(dim);
-            let red = theme::RED.apply_dim(dim);
+            let green = theme.green.apply_dim(dim);
+            let red = theme.red.apply_dim(dim);
             let prefix = if ds.reliable { "" } else { "~ " };
             format!(
                 " [{}{}{}]",
                 prefix,
-                apply_color(&format!("+{}", ds.additions), green),
-                apply_color(&format!(" -{}", ds.deletions), red)
+                apply_color(theme, &format!("+{}", ds.additions), green),
+                apply_color(theme, &format!(" -{}", ds.deletions), red)
             )
         })
         .unwrap_or_default();
+    let diff_stats = format!("{mark}{diff_stats}");
 
     // Local status
     let local_status = branch
@@ -94,26 +142,44 @@ fn render_branch(branch: &RenderableBranch, verbose: bool) {
         .as_ref()
         .map(|ls| {
             let mut parts = Vec::new();
-            let green = theme::GREEN.apply_dim(dim);
-            let yellow = theme::YELLOW.apply_dim(dim);
-            let gray = theme::GRAY.apply_dim(dim);
+            let green = theme.green.apply_dim(dim);
+            let yellow = theme.yellow.apply_dim(dim);
+            let gray = theme.gray.apply_dim(dim);
             if ls.staged > 0 {
-                parts.push(apply_color(&format!("+{}", ls.staged), green).to_string());
+                parts.push(apply_color(theme, &format!("+{}", ls.staged), green).to_string());
             }
             if ls.unstaged > 0 {
-                parts.push(apply_color(&format!("~{}", ls.unstaged), yellow).to_string());
+                parts.push(apply_color(theme, &format!("~{}", ls.unstaged), yellow).to_string());
             }
             if ls.untracked > 0 {
-                parts.push(apply_color(&format!("?{}", ls.untracked), gray).to_string());
+                parts.push(apply_color(theme, &format!("?{}", ls.untracked), gray).to_string());
             }
             format!(" [{}]", parts.join(" "))
         })
         .unwrap_or_default();
 
     if verbose {
-        render_verbose_line(branch, &branch_name, &diff_stats, &local_status, dim);
+        // Commit count, after the line counts
+        let commits = branch
+            .diff_stats
+            .as_ref()
+            .map(|ds| {
+                let noun = if ds.commit_count == 1 {
+                    "commit"
+                } else {
+                    "commits"
+                };
+                let gray = theme.gray.apply_dim(dim);
+                format!(
+                    " {}",
+                    apply_color(theme, &format!("({} {noun})", ds.commit_count), gray)
+                )
+            })
+            .unwrap_or_default();
+        let diff_stats = format!("{diff_stats}{commits}");
+        render_verbose_line(branch, &branch_name, &diff_stats, &local_status, dim, theme);
     } else {
-        render_simple_line(branch, &branch_name, &diff_stats, &local_status, dim);
+        render_simple_line(branch, &branch_name, &diff_stats, &local_status, dim, theme);
     }
 }
 
@@ -123,34 +189,35 @@ fn render_simple_line(
     diff_stats: &str,
     local_status: &str,
     dim: f32,
+    theme: &Theme,
 ) {
     // PR info
     let pr_info = branch
         .pr_info
         .as_ref()
         .map(|pr| {
-            let gray = theme::GRAY.apply_dim(dim);
-            let green = theme::GREEN.apply_dim(dim);
-            let purple = theme::PURPLE.apply_dim(dim);
-            let red = theme::RED.apply_dim(dim);
+            let gray = theme.gray.apply_dim(dim);
+            let green = theme.green.apply_dim(dim);
+            let purple = theme.purple.apply_dim(dim);
+            let red = theme.red.apply_dim(dim);
 
             let state_colored = match pr.state {
-                PrDisplayState::Draft => apply_color(&format!("[{}]", pr.state), gray),
-                PrDisplayState::Open => apply_color(&format!("[{}]", pr.state), green),
-                PrDisplayState::Merged => apply_color(&format!("[{}]", pr.state), purple),
-                PrDisplayState::Closed => apply_color(&format!("[{}]", pr.state), red),
+                PrDisplayState::Draft => apply_color(theme, &format!("[{}]", pr.state), gray),
+                PrDisplayState::Open => apply_color(theme, &format!("[{}]", pr.state), green),
+                PrDisplayState::Merged => apply_color(theme, &format!("[{}]", pr.state), purple),
+                PrDisplayState::Closed => apply_color(theme, &format!("[{}]", pr.state), red),
             };
 
             let author_color = string_to_color(&pr.author).apply_dim(dim);
-            let author_colored = apply_color(&format!("@{}", pr.author), author_color);
+            let author_colored = apply_color(theme, &format!("@{}", pr.author), author_color);
 
-            let pr_num = theme::PR_NUMBER.apply_dim(dim);
-            let number_colored = apply_color(&format!("#{}", pr.number), pr_num);
+            let pr_num = theme.pr_number.apply_dim(dim);
+            let number_colored = apply_color(theme, &format!("#{}", pr.number), pr_num);
 
-            let arrow = theme::PR_ARROW.apply_dim(dim);
+            let arrow = theme.pr_arrow.apply_dim(dim);
             format!(
                 " {} {} {} {}",
-                apply_color("", arrow),
+                apply_color(theme, "", arrow),
                 author_colored,
                 number_colored,
                 state_colored
@@ -167,24 +234,25 @@ fn render_verbose_line(
     diff_stats: &str,
     local_status: &str,
     dim: f32,
+    theme: &Theme,
 ) {
     let Some(ref status) = branch.status else {
         println!("{}", branch_name);
         return;
     };
 
-    let gold = theme::GOLD.apply_dim(dim);
-    let stacked_on = theme::STACKED_ON.apply_dim(dim);
-    let yellow = theme::YELLOW.apply_dim(dim);
-    let red = theme::RED.apply_dim(dim);
-    let green = theme::GREEN.apply_dim(dim);
-    let upstream_color = theme::UPSTREAM.apply_dim(dim);
+    let gold = theme.gold.apply_dim(dim);
+    let stacked_on = theme.stacked_on.apply_dim(dim);
+    let yellow = theme.yellow.apply_dim(dim);
+    let red = theme.red.apply_dim(dim);
+    let green = theme.green.apply_dim(dim);
+    let upstream_color = theme.upstream.apply_dim(dim);
 
     // SHA
     let sha_display = if status.sha.len() >= 8 {
-        apply_color(&status.sha[..8], gold)
+        apply_color(theme, &status.sha[..8], gold)
     } else {
-        apply_color(&status.sha, gold)
+        apply_color(theme, &status.sha, gold)
     };
 
     // Status details
@@ -192,35 +260,37 @@ fn render_verbose_line(
         if status.is_descendent {
             format!(
                 "{} {}",
-                apply_color("is stacked on", stacked_on),
-                apply_color(&status.parent_branch, yellow)
+                apply_color(theme, "is stacked on", stacked_on),
+                apply_color(theme, &status.parent_branch, yellow)
             )
         } else {
             format!(
                 "{} {}",
-                apply_color("diverges from", red),
-                apply_color(&status.parent_branch, yellow)
+                apply_color(theme, "diverges from", red),
+                apply_color(theme, &status.parent_branch, yellow)
             )
         }
     } else {
-        apply_color("does not exist!", red).to_string()
+        apply_color(theme, "does not exist!", red).to_string()
     };
 
     // Upstream status
     let upstream_info = if let Some(ref verbose) = branch.verbose {
         if let Some((ref name, synced)) = verbose.upstream_status {
             let synced_str = if synced {
-                apply_color("synced", green)
+                apply_color(theme, "synced", green)
+            } else if let Some(label) = status.ahead_behind_label() {
+                apply_color(theme, &format!("not synced, {label}"), red)
             } else {
-                apply_color("not synced", red)
+                apply_color(theme, "not synced", red)
             };
             format!(
                 " (upstream {} is {})",
-                apply_color(name, upstream_color),
+                apply_color(theme, name, upstream_color),
                 synced_str
             )
         } else {
-            format!(" ({})", apply_color("no upstream", gold))
+            format!(" ({})", apply_color(theme, "no upstream", gold))
         }
     } else {
         String::new()
@@ -231,7 +301,7 @@ fn render_verbose_line(
         .verbose
         .as_ref()
         .and_then(|v| v.lkg_parent.as_ref())
-        .map(|lkg| format!(" (lkg parent {})", apply_color(lkg, gold)))
+        .map(|lkg| format!(" (lkg parent {})", apply_color(theme, lkg, gold)))
         .unwrap_or_default();
 
     // Stack method
@@ -239,8 +309,8 @@ fn render_verbose_line(
         .verbose
         .as_ref()
         .map(|v| {
-            let method_color = theme::GREEN.apply_dim(dim);
-            format!(" ({})", apply_color(&v.stack_method, method_color))
+            let method_color = theme.green.apply_dim(dim);
+            format!(" ({})", apply_color(theme, &v.stack_method, method_color))
         })
         .unwrap_or_default();
 
@@ -260,13 +330,31 @@ fn render_verbose_line(
     if let Some(ref note) = branch.note_preview {
         print!("  ");
         for _ in 0..branch.depth {
-            print!("{}", apply_color("┃ ", theme::TREE));
+            print!("{}", apply_color(theme, "┃ ", theme.tree));
         }
-        let note_display = if branch.is_current {
-            note.bright_blue().bold()
-        } else {
-            note.blue()
+        let note_display = match (theme.monochrome, branch.is_current) {
+            (true, true) => note.bold(),
+            (true, false) => note.normal(),
+            (false, true) => note.bright_blue().bold(),
+            (false, false) => note.blue(),
         };
-        println!("  {} {}", apply_color("›", theme::TREE), note_display);
+        println!("  {} {}", apply_color(theme, "›", theme.tree), note_display);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::*;
+
+    #[test]
+    fn humanize_age_picks_the_largest_whole_unit() {
+        assert_eq!(humanize_age(TimeDelta::seconds(30)), "just now");
+        assert_eq!(humanize_age(TimeDelta::seconds(-5)), "just now");
+        assert_eq!(humanize_age(TimeDelta::minutes(59)), "59m ago");
+        assert_eq!(humanize_age(TimeDelta::minutes(150)), "2h ago");
+        assert_eq!(humanize_age(TimeDelta::hours(24)), "1d ago");
+        assert_eq!(humanize_age(TimeDelta::days(9)), "9d ago");
     }
 }
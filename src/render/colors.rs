@@ -1,7 +1,43 @@
 //! Unified color definitions for CLI and TUI rendering.
+//!
+//! Colors come from a [`Theme`]: one of the built-in presets, optionally adjusted by
+//! `~/.config/git-stack/theme.yaml`:
+//!
+//! ```yaml
+//! preset: light        # gruvbox (default), light, or nocolor
+//! green: "#3c8f3c"     # any color below, as "#rrggbb" or [r, g, b]
+//! tree: [200, 200, 195]
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::state::BranchMark;
+
+/// Set at startup when colors are turned off for the whole process.
+static COLORS_DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Whether `NO_COLOR` is set to a non-empty value (<https://no-color.org>).
+pub fn no_color_env() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+/// Turn colors off for the rest of the process: the colored crate stops emitting escapes, and
+/// [`Theme::load`] returns a monochrome theme so the TUI draws without colors too.
+pub fn disable_colors() {
+    COLORS_DISABLED.store(true, Ordering::Relaxed);
+    colored::control::set_override(false);
+}
 
 /// RGB color that can be converted to both colored crate and ratatui formats.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "ColorRepr", into = "ColorRepr")]
 pub struct ThemeColor(pub u8, pub u8, pub u8);
 
 impl ThemeColor {
@@ -20,23 +56,197 @@ impl ThemeColor {
     }
 }
 
-// Color constants for consistent theming
-pub mod theme {
-    use super::ThemeColor;
-
-    pub const GREEN: ThemeColor = ThemeColor(142, 192, 124);
-    pub const RED: ThemeColor = ThemeColor(204, 36, 29);
-    pub const GRAY: ThemeColor = ThemeColor(128, 128, 128);
-    pub const GOLD: ThemeColor = ThemeColor(215, 153, 33);
-    pub const TREE: ThemeColor = ThemeColor(55, 55, 50);
-    pub const YELLOW: ThemeColor = ThemeColor(250, 189, 47);
-    pub const PURPLE: ThemeColor = ThemeColor(180, 142, 173);
-    pub const MUTED: ThemeColor = ThemeColor(90, 90, 90);
-    pub const PR_NUMBER: ThemeColor = ThemeColor(90, 78, 98);
-    pub const PR_ARROW: ThemeColor = ThemeColor(100, 105, 105);
-    pub const UPSTREAM: ThemeColor = ThemeColor(88, 88, 88);
-    pub const STACKED_ON: ThemeColor = ThemeColor(90, 120, 87);
-    pub const BLUE: ThemeColor = ThemeColor(131, 165, 152);
+/// How a color is spelled in `theme.yaml`.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ColorRepr {
+    Hex(String),
+    Rgb([u8; 3]),
+}
+
+impl TryFrom<ColorRepr> for ThemeColor {
+    type Error = String;
+
+    fn try_from(repr: ColorRepr) -> Result<Self, Self::Error> {
+        match repr {
+            ColorRepr::Rgb([r, g, b]) => Ok(ThemeColor(r, g, b)),
+            ColorRepr::Hex(hex) => {
+                let digits = hex.strip_prefix('#').unwrap_or(&hex);
+                let channel = |i: usize| {
+                    digits
+                        .get(i..i + 2)
+                        .and_then(|c| u8::from_str_radix(c, 16).ok())
+                };
+                match (digits.len(), channel(0), channel(2), channel(4)) {
+                    (6, Some(r), Some(g), Some(b)) => Ok(ThemeColor(r, g, b)),
+                    _ => Err(format!("invalid color {hex:?}; expected \"#rrggbb\"")),
+                }
+            }
+        }
+    }
+}
+
+impl From<ThemeColor> for ColorRepr {
+    fn from(color: ThemeColor) -> Self {
+        ColorRepr::Hex(format!("#{:02x}{:02x}{:02x}", color.0, color.1, color.2))
+    }
+}
+
+/// The colors used by the CLI tree, the TUI, and other colored output.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Theme {
+    /// Print without any colors (the tree-drawing characters remain).
+    #[serde(default)]
+    pub monochrome: bool,
+    pub green: ThemeColor,
+    pub red: ThemeColor,
+    pub gray: ThemeColor,
+    pub gold: ThemeColor,
+    pub tree: ThemeColor,
+    pub yellow: ThemeColor,
+    pub purple: ThemeColor,
+    pub muted: ThemeColor,
+    pub pr_number: ThemeColor,
+    pub pr_arrow: ThemeColor,
+    pub upstream: ThemeColor,
+    pub stacked_on: ThemeColor,
+    pub blue: ThemeColor,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::GRUVBOX
+    }
+}
+
+impl Theme {
+    /// The color of a branch's `git stack mark` tag.
+    pub fn mark(&self, mark: BranchMark) -> ThemeColor {
+        match mark {
+            BranchMark::Wip => self.yellow,
+            BranchMark::Ready => self.green,
+            BranchMark::Blocked => self.red,
+        }
+    }
+
+    /// The default palette, tuned for dark terminals.
+    pub const GRUVBOX: Theme = Theme {
+        monochrome: false,
+        green: ThemeColor(142, 192, 124),
+        red: ThemeColor(204, 36, 29),
+        gray: ThemeColor(128, 128, 128),
+        gold: ThemeColor(215, 153, 33),
+        tree: ThemeColor(55, 55, 50),
+        yellow: ThemeColor(250, 189, 47),
+        purple: ThemeColor(180, 142, 173),
+        muted: ThemeColor(90, 90, 90),
+        pr_number: ThemeColor(90, 78, 98),
+        pr_arrow: ThemeColor(100, 105, 105),
+        upstream: ThemeColor(88, 88, 88),
+        stacked_on: ThemeColor(90, 120, 87),
+        blue: ThemeColor(131, 165, 152),
+    };
+
+    /// Darker, more saturated colors that stay readable on light backgrounds.
+    pub const LIGHT: Theme = Theme {
+        monochrome: false,
+        green: ThemeColor(66, 123, 88),
+        red: ThemeColor(157, 0, 6),
+        gray: ThemeColor(102, 92, 84),
+        gold: ThemeColor(181, 118, 20),
+        tree: ThemeColor(189, 174, 147),
+        yellow: ThemeColor(175, 58, 3),
+        purple: ThemeColor(143, 63, 113),
+        muted: ThemeColor(124, 111, 100),
+        pr_number: ThemeColor(102, 92, 84),
+        pr_arrow: ThemeColor(124, 111, 100),
+        upstream: ThemeColor(124, 111, 100),
+        stacked_on: ThemeColor(66, 123, 88),
+        blue: ThemeColor(7, 102, 120),
+    };
+
+    /// No colors at all.
+    pub const NOCOLOR: Theme = Theme {
+        monochrome: true,
+        ..Theme::GRUVBOX
+    };
+
+    /// Color `s` for the terminal with the colored crate; plain for a monochrome theme.
+    pub fn paint(&self, s: &str, color: ThemeColor) -> colored::ColoredString {
+        use colored::Colorize;
+
+        if self.monochrome {
+            return s.normal();
+        }
+        let (r, g, b) = color.rgb();
+        s.truecolor(r, g, b)
+    }
+
+    /// Look up a built-in preset by name.
+    pub fn preset(name: &str) -> Option<Theme> {
+        match name {
+            "gruvbox" => Some(Self::GRUVBOX),
+            "light" => Some(Self::LIGHT),
+            "nocolor" => Some(Self::NOCOLOR),
+            _ => None,
+        }
+    }
+
+    /// The user's theme from `~/.config/git-stack/theme.yaml`, or the default theme if there is
+    /// no such file. A file that can't be read or parsed is reported and ignored. Monochrome
+    /// whenever colors are disabled.
+    pub fn load() -> Theme {
+        let theme = Self::load_configured();
+        if COLORS_DISABLED.load(Ordering::Relaxed) {
+            Theme {
+                monochrome: true,
+                ..theme
+            }
+        } else {
+            theme
+        }
+    }
+
+    fn load_configured() -> Theme {
+        let Some(path) =
+            xdg::BaseDirectories::with_prefix("git-stack").find_config_file("theme.yaml")
+        else {
+            return Theme::default();
+        };
+        Self::load_from(&path).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring {}: {e:#}", path.display());
+            Theme::default()
+        })
+    }
+
+    fn load_from(path: &Path) -> Result<Theme> {
+        let contents = std::fs::read_to_string(path).context("reading theme file")?;
+        Self::parse(&contents)
+    }
+
+    /// Parse a theme file: an optional `preset` name plus any colors to override on top of it.
+    fn parse(contents: &str) -> Result<Theme> {
+        let mut overrides: BTreeMap<String, serde_yaml::Value> = if contents.trim().is_empty() {
+            BTreeMap::new()
+        } else {
+            serde_yaml::from_str(contents).context("parsing theme file")?
+        };
+        let base = match overrides.remove("preset") {
+            None => Theme::default(),
+            Some(serde_yaml::Value::String(name)) => Theme::preset(&name).ok_or_else(|| {
+                anyhow!("unknown preset {name:?}; expected gruvbox, light, or nocolor")
+            })?,
+            Some(other) => bail!("preset must be a name, got {other:?}"),
+        };
+        let serde_yaml::Value::Mapping(mut merged) = serde_yaml::to_value(base)? else {
+            unreachable!("a Theme serializes to a mapping");
+        };
+        for (key, value) in overrides {
+            merged.insert(serde_yaml::Value::String(key), value);
+        }
+        serde_yaml::from_value(serde_yaml::Value::Mapping(merged)).context("parsing theme file")
+    }
 }
 
 /// Compute a deterministic RGB color from a string using its hash.
@@ -75,3 +285,29 @@ fn hsv_to_rgb(h: f32, s: f32, v: f32) -> (u8, u8, u8) {
         ((b + m) * 255.0) as u8,
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn theme_file_overrides_colors_on_top_of_a_preset() {
+        let theme = Theme::parse("preset: light\ngreen: \"#102030\"\ntree: [1, 2, 3]\n").unwrap();
+        assert_eq!(theme.green, ThemeColor(0x10, 0x20, 0x30));
+        assert_eq!(theme.tree, ThemeColor(1, 2, 3));
+        assert_eq!(theme.red, Theme::LIGHT.red);
+
+        assert_eq!(Theme::parse("").unwrap(), Theme::GRUVBOX);
+        assert!(Theme::parse("preset: nocolor\n").unwrap().monochrome);
+    }
+
+    #[test]
+    fn theme_file_errors_name_the_problem() {
+        let err = Theme::parse("preset: solarized\n").unwrap_err();
+        assert!(format!("{err:#}").contains("unknown preset"), "{err:#}");
+        let err = Theme::parse("green: \"#12345\"\n").unwrap_err();
+        assert!(format!("{err:#}").contains("invalid color"), "{err:#}");
+        let err = Theme::parse("grene: \"#123456\"\n").unwrap_err();
+        assert!(format!("{err:#}").contains("grene"), "{err:#}");
+    }
+}
@@ -0,0 +1,200 @@
+//! Machine-readable export of the stack tree (Graphviz DOT / Mermaid).
+
+use std::{collections::HashMap, fmt::Write as _};
+
+use crate::{github::PrDisplayState, state::Branch};
+
+/// Output format for `git stack graph`.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GraphFormat {
+    /// Graphviz DOT (`dot -Tsvg`).
+    #[default]
+    Dot,
+    /// Mermaid flowchart (renders inline in GitHub markdown).
+    Mermaid,
+}
+
+/// Render `tree` as a graph with one node per branch (labeled with its name and PR number) and one
+/// parent→child edge per stack relationship. `pr_states` maps branch name to its PR's display
+/// state; merged/closed/draft PRs get distinct node styling, and branches absent from it render
+/// plain.
+pub fn render_graph(
+    tree: &Branch,
+    format: GraphFormat,
+    pr_states: &HashMap<String, PrDisplayState>,
+) -> String {
+    let mut nodes: Vec<&Branch> = Vec::new();
+    let mut edges: Vec<(usize, usize)> = Vec::new();
+    collect_graph(tree, None, &mut nodes, &mut edges);
+
+    match format {
+        GraphFormat::Dot => render_dot(&nodes, &edges, pr_states),
+        GraphFormat::Mermaid => render_mermaid(&nodes, &edges, pr_states),
+    }
+}
+
+/// Pre-order walk assigning each branch a stable index; edges are (parent, child) indices.
+fn collect_graph<'a>(
+    branch: &'a Branch,
+    parent: Option<usize>,
+    nodes: &mut Vec<&'a Branch>,
+    edges: &mut Vec<(usize, usize)>,
+) {
+    let index = nodes.len();
+    nodes.push(branch);
+    if let Some(parent) = parent {
+        edges.push((parent, index));
+    }
+    for child in &branch.branches {
+        collect_graph(child, Some(index), nodes, edges);
+    }
+}
+
+fn node_label(branch: &Branch, newline: &str) -> String {
+    match branch.pr_number {
+        Some(number) => format!("{}{newline}#{number}", branch.name),
+        None => branch.name.clone(),
+    }
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn render_dot(
+    nodes: &[&Branch],
+    edges: &[(usize, usize)],
+    pr_states: &HashMap<String, PrDisplayState>,
+) -> String {
+    let mut out = String::new();
+    out.push_str("digraph stack {\n");
+    out.push_str("  rankdir=TB;\n");
+    out.push_str("  node [shape=box];\n");
+    for (index, branch) in nodes.iter().enumerate() {
+        let label = dot_escape(&node_label(branch, "\n")).replace('\n', "\\n");
+        let style = match pr_states.get(&branch.name) {
+            Some(PrDisplayState::Merged) => ", style=\"filled,dashed\", fillcolor=\"#e6dcf5\"",
+            Some(PrDisplayState::Closed) => ", style=\"filled,dashed\", fillcolor=\"#f5dcdc\"",
+            Some(PrDisplayState::Draft) => ", style=dotted",
+            Some(PrDisplayState::Open) | None => "",
+        };
+        let _ = writeln!(out, "  n{index} [label=\"{label}\"{style}];");
+    }
+    for (parent, child) in edges {
+        let _ = writeln!(out, "  n{parent} -> n{child};");
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn mermaid_escape(s: &str) -> String {
+    s.replace('"', "#quot;")
+}
+
+fn render_mermaid(
+    nodes: &[&Branch],
+    edges: &[(usize, usize)],
+    pr_states: &HashMap<String, PrDisplayState>,
+) -> String {
+    let mut out = String::new();
+    out.push_str("flowchart TD\n");
+    let mut classes: Vec<(usize, &str)> = Vec::new();
+    for (index, branch) in nodes.iter().enumerate() {
+        let label = mermaid_escape(&node_label(branch, "<br/>"));
+        let _ = writeln!(out, "  n{index}[\"{label}\"]");
+        match pr_states.get(&branch.name) {
+            Some(PrDisplayState::Merged) => classes.push((index, "merged")),
+            Some(PrDisplayState::Closed) => classes.push((index, "closed")),
+            Some(PrDisplayState::Draft) => classes.push((index, "draft")),
+            Some(PrDisplayState::Open) | None => {}
+        }
+    }
+    for (parent, child) in edges {
+        let _ = writeln!(out, "  n{parent} --> n{child}");
+    }
+    if !classes.is_empty() {
+        out.push_str("  classDef merged fill:#e6dcf5,stroke-dasharray:5 5\n");
+        out.push_str("  classDef closed fill:#f5dcdc,stroke-dasharray:5 5\n");
+        out.push_str("  classDef draft stroke-dasharray:2 2\n");
+        for (index, class) in classes {
+            let _ = writeln!(out, "  class n{index} {class}");
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::StackMethod;
+
+    fn branch(name: &str, pr_number: Option<u64>, branches: Vec<Branch>) -> Branch {
+        Branch {
+            name: name.to_string(),
+            stack_method: StackMethod::ApplyMerge,
+            note: None,
+            lkg_parent: None,
+            pr_number,
+            mark: None,
+            branches,
+        }
+    }
+
+    fn sample_tree() -> Branch {
+        branch(
+            "main",
+            None,
+            vec![branch(
+                "feature-a",
+                Some(42),
+                vec![branch("feature-b", None, vec![])],
+            )],
+        )
+    }
+
+    #[test]
+    fn dot_emits_labeled_nodes_and_parent_child_edges() {
+        let out = render_graph(&sample_tree(), GraphFormat::Dot, &HashMap::new());
+        assert_eq!(
+            out,
+            "digraph stack {\n  rankdir=TB;\n  node [shape=box];\n  n0 [label=\"main\"];\n  \
+             n1 [label=\"feature-a\\n#42\"];\n  n2 [label=\"feature-b\"];\n  n0 -> n1;\n  \
+             n1 -> n2;\n}\n"
+        );
+    }
+
+    #[test]
+    fn mermaid_emits_flowchart_without_classes_when_no_pr_states() {
+        let out = render_graph(&sample_tree(), GraphFormat::Mermaid, &HashMap::new());
+        assert_eq!(
+            out,
+            "flowchart TD\n  n0[\"main\"]\n  n1[\"feature-a<br/>#42\"]\n  n2[\"feature-b\"]\n  \
+             n0 --> n1\n  n1 --> n2\n"
+        );
+    }
+
+    #[test]
+    fn merged_and_closed_prs_are_styled() {
+        let states = HashMap::from([
+            ("feature-a".to_string(), PrDisplayState::Merged),
+            ("feature-b".to_string(), PrDisplayState::Closed),
+        ]);
+        let dot = render_graph(&sample_tree(), GraphFormat::Dot, &states);
+        assert!(dot.contains("n1 [label=\"feature-a\\n#42\", style=\"filled,dashed\""));
+        assert!(dot.contains("n2 [label=\"feature-b\", style=\"filled,dashed\""));
+
+        let mermaid = render_graph(&sample_tree(), GraphFormat::Mermaid, &states);
+        assert!(mermaid.contains("  class n1 merged\n"));
+        assert!(mermaid.contains("  class n2 closed\n"));
+        assert!(!mermaid.contains("class n0"));
+    }
+
+    #[test]
+    fn quotes_in_branch_names_are_escaped() {
+        let tree = branch("we\"ird", None, vec![]);
+        let dot = render_graph(&tree, GraphFormat::Dot, &HashMap::new());
+        assert!(dot.contains("label=\"we\\\"ird\""));
+        let mermaid = render_graph(&tree, GraphFormat::Mermaid, &HashMap::new());
+        assert!(mermaid.contains("n0[\"we#quot;ird\"]"));
+    }
+}
@@ -2,10 +2,12 @@
 
 pub mod cli;
 pub mod colors;
+pub mod graph;
 pub mod tree_data;
 
-pub use cli::render_cli;
-pub use colors::ThemeColor;
+pub use cli::{render_cli, render_sync_age};
+pub use colors::{Theme, ThemeColor};
+pub use graph::{GraphFormat, render_graph};
 pub use tree_data::{
     BranchRenderStatus, PrRenderInfo, RenderableBranch, RenderableTree, apply_pr_cache,
     compute_protected_branches, compute_renderable_tree,
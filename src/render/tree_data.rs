@@ -1,12 +1,18 @@
 //! Tree data computation and flattening for rendering.
 
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    io::IsTerminal,
+};
+
+use indicatif::{ProgressBar, ProgressFinish, ProgressStyle};
+use serde::Serialize;
 
 use crate::{
     git::get_local_status,
     git2_ops::GitRepo,
     github::{PrDisplayState, PullRequest},
-    state::Branch,
+    state::{Branch, BranchMark},
 };
 
 /// Memoization of diff-stat results within a single render walk, keyed by
@@ -15,10 +21,10 @@ use crate::{
 /// pair isn't retried per branch). The `reliable` flag is intentionally *not* keyed —
 /// it depends on how the base was chosen (LKG vs merge-base), not on the sha pair — so
 /// it stays computed per-branch outside the cache.
-type DiffStatsCache = HashMap<(String, String), Option<(usize, usize)>>;
+type DiffStatsCache = HashMap<(String, String), Option<(usize, usize, usize)>>;
 
 /// Status information for a branch's relationship to its parent.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct BranchRenderStatus {
     pub exists: bool,
     pub is_descendent: bool,
@@ -26,10 +32,39 @@ pub struct BranchRenderStatus {
     pub parent_branch: String,
     pub upstream_synced: Option<bool>,
     pub upstream_name: Option<String>,
+    /// Commits the branch has that its upstream doesn't (`None` without an upstream).
+    pub upstream_ahead: Option<usize>,
+    /// Commits the upstream has that the branch doesn't (`None` without an upstream).
+    pub upstream_behind: Option<usize>,
+}
+
+impl BranchRenderStatus {
+    /// `↑2 ↓1`-style counts against the upstream, leaving out zero sides; `None` when there's no
+    /// upstream or the branch matches it.
+    pub fn ahead_behind_label(&self) -> Option<String> {
+        let ahead = self
+            .upstream_ahead
+            .filter(|&n| n > 0)
+            .map(|n| format!("↑{n}"));
+        let behind = self
+            .upstream_behind
+            .filter(|&n| n > 0)
+            .map(|n| format!("↓{n}"));
+        match (ahead, behind) {
+            (None, None) => None,
+            (ahead, behind) => Some(
+                [ahead, behind]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+        }
+    }
 }
 
 /// PR information for rendering.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct PrRenderInfo {
     pub number: u64,
     pub state: PrDisplayState,
@@ -37,17 +72,19 @@ pub struct PrRenderInfo {
     pub html_url: String,
 }
 
-/// Diff statistics (additions, deletions).
-#[derive(Debug, Clone, Default)]
+/// Diff statistics (additions, deletions, commits).
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct DiffStats {
     pub additions: usize,
     pub deletions: usize,
+    /// Commits the branch adds over the same base the line counts use.
+    pub commit_count: usize,
     /// Whether the diff stats are from a reliable source (LKG parent vs merge-base guess)
     pub reliable: bool,
 }
 
 /// Local working tree status (for current branch only).
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct LocalStatus {
     pub staged: usize,
     pub unstaged: usize,
@@ -61,7 +98,7 @@ impl LocalStatus {
 }
 
 /// Verbose details for a branch (shown in verbose mode).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct VerboseDetails {
     pub stacked_on: String,
     pub is_diverged: bool,
@@ -71,7 +108,7 @@ pub struct VerboseDetails {
 }
 
 /// A flattened branch entry for rendering (shared by CLI and TUI).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct RenderableBranch {
     /// The branch name.
     pub name: String,
@@ -93,14 +130,18 @@ pub struct RenderableBranch {
     pub pr_info: Option<PrRenderInfo>,
     /// First line of branch note (if any).
     pub note_preview: Option<String>,
+    /// The branch's `git stack mark`, if any.
+    pub mark: Option<BranchMark>,
     /// Verbose details (populated when verbose mode is requested).
     pub verbose: Option<VerboseDetails>,
+    /// How many descendants `status --depth` left out below this branch.
+    pub elided_below: usize,
     /// Index in the flattened list (for TUI cursor navigation).
     pub index: usize,
 }
 
 /// A flattened tree ready for rendering.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct RenderableTree {
     /// Flattened list of branches in display order.
     pub branches: Vec<RenderableBranch>,
@@ -116,22 +157,13 @@ fn subtree_contains(
     authors_filter: &[String],
     pr_authors: &HashMap<String, String>,
 ) -> (bool, bool) {
-    let is_target = branch.name == target_branch;
-    let has_author = pr_authors
-        .get(&branch.name)
-        .map(|author| crate::github::author_in_filter(authors_filter, author))
-        .unwrap_or(false);
-
-    let (child_has_target, child_has_author) = branch
-        .branches
-        .iter()
-        .map(|b| subtree_contains(b, target_branch, authors_filter, pr_authors))
-        .fold((false, false), |(t1, a1), (t2, a2)| (t1 || t2, a1 || a2));
-
-    (
-        is_target || child_has_target,
-        has_author || child_has_author,
-    )
+    let has_target = branch.iter().any(|b| b.name == target_branch);
+    let has_author = branch.iter().any(|b| {
+        pr_authors
+            .get(&b.name)
+            .is_some_and(|author| crate::github::author_in_filter(authors_filter, author))
+    });
+    (has_target, has_author)
 }
 
 /// Marks `branch` and every ancestor of `target` (inclusive of `target` itself) in `path`.
@@ -203,10 +235,54 @@ fn mark_hidden(
     }
 }
 
+/// Branches left out by `status --depth`: those more than `max_depth` levels deeper than the
+/// current branch (than the root, when the current branch isn't in the tree). The current branch's
+/// ancestors are never deeper than it, so they always stay.
+fn compute_depth_elided(
+    tree: &Branch,
+    current_branch: &str,
+    max_depth: Option<usize>,
+) -> HashSet<String> {
+    let mut elided = HashSet::new();
+    let Some(max_depth) = max_depth else {
+        return elided;
+    };
+    let mut ancestry = HashSet::new();
+    let current_depth = if mark_ancestor_path(tree, current_branch, &mut ancestry) {
+        ancestry.len() - 1
+    } else {
+        0
+    };
+    tree.walk(&mut |branch, depth, _| {
+        if depth > current_depth + max_depth {
+            elided.insert(branch.name.clone());
+        }
+    });
+    elided
+}
+
+/// A `len`-step progress bar labeled `message` for a per-branch git walk, cleared from the screen
+/// when dropped, or `None` when stderr isn't a terminal.
+pub(crate) fn branch_progress(len: usize, message: &'static str) -> Option<ProgressBar> {
+    if !std::io::stderr().is_terminal() {
+        return None;
+    }
+    let pb = ProgressBar::new(len as u64).with_finish(ProgressFinish::AndClear);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{msg} [{bar:30.cyan/blue}] {pos}/{len}")
+            .expect("valid template")
+            .progress_chars("=> "),
+    );
+    pb.set_message(message);
+    Some(pb)
+}
+
 /// Compute a renderable tree from the branch tree. PR badge info (`pr_info`) is not populated
 /// here — call `apply_pr_cache` afterward. This split lets callers overlap the PR fetch (network)
 /// with this local git walk when `pr_authors` doesn't depend on the fetch (see
-/// `build_renderable_tree` in main.rs).
+/// `build_renderable_tree` in main.rs). With `progress`, a progress bar on stderr counts off the
+/// branches' git status as it's computed.
 #[allow(clippy::too_many_arguments)]
 pub fn compute_renderable_tree(
     git_repo: &GitRepo,
@@ -216,26 +292,33 @@ pub fn compute_renderable_tree(
     authors_filter: &[String],
     pr_authors: &HashMap<String, String>,
     show_all: bool,
+    max_depth: Option<usize>,
+    progress: bool,
 ) -> RenderableTree {
     let mut branches = Vec::new();
     let mut current_branch_index = None;
     let hidden =
         compute_hidden_branches(tree, current_branch, authors_filter, pr_authors, show_all);
-    let mut diff_cache = DiffStatsCache::new();
+    let elided = compute_depth_elided(tree, current_branch, max_depth);
+    let mut git_info = compute_branch_git_info(
+        git_repo,
+        tree,
+        &hidden.union(&elided).cloned().collect(),
+        progress,
+    );
 
     flatten_tree(
-        git_repo,
         tree,
-        None,
         0,
         current_branch,
         verbose,
         authors_filter,
         pr_authors,
         &hidden,
+        &elided,
+        &mut git_info,
         &mut branches,
         &mut current_branch_index,
-        &mut diff_cache,
     );
 
     RenderableTree {
@@ -260,23 +343,224 @@ pub fn apply_pr_cache(tree: &mut RenderableTree, pr_cache: Option<&HashMap<Strin
     }
 }
 
+/// Git-derived facts for one branch. Computed up front by `compute_branch_git_info` (possibly on
+/// worker threads) and looked up by name during the flatten walk.
+#[derive(Debug, Default)]
+struct BranchGitInfo {
+    is_remote_only: bool,
+    status: Option<BranchRenderStatus>,
+    diff_stats: Option<DiffStats>,
+}
+
+/// A parent ref resolved once and shared by all of its children's status checks.
+struct StatusParent {
+    name: String,
+    sha: Option<String>,
+}
+
+fn branch_git_info(
+    git_repo: &GitRepo,
+    branch: &Branch,
+    parent: &StatusParent,
+    cache: &mut DiffStatsCache,
+) -> BranchGitInfo {
+    // Check if branch is remote-only (not local)
+    let is_remote_only = !git_repo.branch_exists(&branch.name);
+
+    // Get branch status
+    let status = git_repo
+        .branch_status_with_parent_sha(&parent.name, parent.sha.as_deref(), &branch.name)
+        .ok()
+        .map(|bs| BranchRenderStatus {
+            exists: bs.exists,
+            is_descendent: bs.is_descendent,
+            sha: bs.sha,
+            parent_branch: bs.parent_branch,
+            upstream_synced: bs.upstream_status.as_ref().map(|us| us.synced),
+            upstream_ahead: bs.upstream_status.as_ref().map(|us| us.ahead),
+            upstream_behind: bs.upstream_status.as_ref().map(|us| us.behind),
+            upstream_name: bs.upstream_status.map(|us| us.symbolic_name),
+        });
+
+    // Compute diff stats
+    let diff_stats = if let Some(ref status) = status {
+        compute_diff_stats(git_repo, branch, status, cache)
+    } else {
+        None
+    };
+
+    BranchGitInfo {
+        is_remote_only,
+        status,
+        diff_stats,
+    }
+}
+
+/// Collect `(parent, branch)` for every visible branch in `branch`'s subtree. Hidden branches
+/// still pass their real name down as the git parent of their children.
+fn collect_status_jobs<'a>(
+    branch: &'a Branch,
+    parent_branch: Option<&'a str>,
+    hidden: &HashSet<String>,
+    jobs: &mut Vec<(Option<&'a str>, &'a Branch)>,
+) {
+    if !hidden.contains(&branch.name) {
+        jobs.push((parent_branch, branch));
+    }
+    for child in &branch.branches {
+        collect_status_jobs(child, Some(&branch.name), hidden, jobs);
+    }
+}
+
+/// Assign job groups (by size) to at most `worker_count` buckets, largest group first onto the
+/// least-loaded bucket, so one deep stack doesn't serialize behind a round-robin split. Returns
+/// group indices per bucket; empty buckets are dropped.
+fn balance_groups(sizes: &[usize], worker_count: usize) -> Vec<Vec<usize>> {
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(sizes[i]));
+    let mut buckets: Vec<(usize, Vec<usize>)> = vec![(0, Vec::new()); worker_count.max(1)];
+    for i in order {
+        let (load, groups) = buckets
+            .iter_mut()
+            .min_by_key(|(load, _)| *load)
+            .expect("at least one bucket");
+        *load += sizes[i];
+        groups.push(i);
+    }
+    buckets
+        .into_iter()
+        .map(|(_, groups)| groups)
+        .filter(|groups| !groups.is_empty())
+        .collect()
+}
+
+/// Compute `BranchGitInfo` for every visible branch. The root's child subtrees are independent, so
+/// they're spread across up to 8 threads, each on its own `GitRepo` handle (git2 handles aren't
+/// `Sync`). The first bucket runs on the calling thread with `git_repo`; a bucket whose handle
+/// fails to open falls back to it too. With `progress`, each finished branch ticks a progress bar.
+fn compute_branch_git_info(
+    git_repo: &GitRepo,
+    tree: &Branch,
+    hidden: &HashSet<String>,
+    progress: bool,
+) -> HashMap<String, BranchGitInfo> {
+    let _wall = crate::stats::WallClock::start("render:branch-status");
+
+    let mut groups: Vec<Vec<(Option<&str>, &Branch)>> = Vec::new();
+    if !hidden.contains(&tree.name) {
+        groups.push(vec![(None, tree)]);
+    }
+    for child in &tree.branches {
+        let mut jobs = Vec::new();
+        collect_status_jobs(child, Some(&tree.name), hidden, &mut jobs);
+        if !jobs.is_empty() {
+            groups.push(jobs);
+        }
+    }
+
+    let progress = if progress {
+        branch_progress(groups.iter().map(Vec::len).sum(), "Reading branch status")
+    } else {
+        None
+    };
+    let progress = progress.as_ref();
+    let run = |repo: &GitRepo, jobs: Vec<(Option<&str>, &Branch)>| {
+        let mut cache = DiffStatsCache::new();
+        let mut parents: HashMap<Option<&str>, StatusParent> = HashMap::new();
+        jobs.into_iter()
+            .map(|(parent, branch)| {
+                let parent = parents.entry(parent).or_insert_with(|| {
+                    let name = repo.status_parent_ref(parent, &branch.name);
+                    let sha = repo.sha(&name).ok();
+                    StatusParent { name, sha }
+                });
+                let info = branch_git_info(repo, branch, parent, &mut cache);
+                if let Some(pb) = progress {
+                    pb.inc(1);
+                }
+                (branch.name.clone(), info)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let worker_count = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(groups.len())
+        .min(8);
+    let sizes: Vec<usize> = groups.iter().map(Vec::len).collect();
+    let mut groups: Vec<Option<Vec<_>>> = groups.into_iter().map(Some).collect();
+    let mut buckets: Vec<Vec<(Option<&str>, &Branch)>> = balance_groups(&sizes, worker_count)
+        .into_iter()
+        .map(|bucket| {
+            bucket
+                .into_iter()
+                .flat_map(|i| groups[i].take().unwrap_or_default())
+                .collect()
+        })
+        .collect();
+
+    let mut info = HashMap::new();
+    if buckets.len() <= 1 {
+        info.extend(run(git_repo, buckets.pop().unwrap_or_default()));
+        return info;
+    }
+
+    let local = buckets.remove(0);
+    std::thread::scope(|scope| {
+        let mut handles = Vec::new();
+        let mut fallback = Vec::new();
+        for bucket in buckets {
+            match git_repo.init_repo_for_thread() {
+                Ok(worker_repo) => handles.push(scope.spawn(move || {
+                    // `GitBenchmark` records into thread-local stats, so hand this worker's
+                    // git2 spans back for merging into the caller's thread.
+                    (run(&worker_repo, bucket), crate::stats::get_stats())
+                })),
+                Err(e) => {
+                    tracing::debug!("Failed to open worker repo, computing inline: {e:#}");
+                    fallback.extend(bucket);
+                }
+            }
+        }
+
+        info.extend(run(git_repo, local));
+        info.extend(run(git_repo, fallback));
+        for handle in handles {
+            if let Ok((computed, stats)) = handle.join() {
+                info.extend(computed);
+                crate::stats::merge_into_current(&stats);
+            }
+        }
+    });
+    info
+}
+
 #[allow(clippy::too_many_arguments)]
 fn flatten_tree(
-    git_repo: &GitRepo,
     branch: &Branch,
-    parent_branch: Option<&str>,
     depth: usize,
     current_branch: &str,
     verbose: bool,
     authors_filter: &[String],
     pr_authors: &HashMap<String, String>,
     hidden: &HashSet<String>,
+    elided: &HashSet<String>,
+    git_info: &mut HashMap<String, BranchGitInfo>,
     result: &mut Vec<RenderableBranch>,
     current_branch_index: &mut Option<usize>,
-    cache: &mut DiffStatsCache,
 ) {
     let is_current = branch.name == current_branch;
     let is_hidden = hidden.contains(&branch.name);
+    // A child is elided with its whole subtree, so the marker counts all of it.
+    let (elided_children, children): (Vec<&Branch>, Vec<&Branch>) = branch
+        .branches
+        .iter()
+        .partition(|child| elided.contains(&child.name));
+    let elided_below = elided_children
+        .iter()
+        .map(|child| child.iter().count())
+        .sum();
 
     if !is_hidden {
         let index = result.len();
@@ -293,28 +577,12 @@ fn flatten_tree(
             pr_author.is_some_and(|author| !crate::github::author_in_filter(authors_filter, author))
         };
 
-        // Check if branch is remote-only (not local)
-        let is_remote_only = !git_repo.branch_exists(&branch.name);
-
-        // Get branch status
-        let status = git_repo
-            .branch_status(parent_branch, &branch.name)
-            .ok()
-            .map(|bs| BranchRenderStatus {
-                exists: bs.exists,
-                is_descendent: bs.is_descendent,
-                sha: bs.sha,
-                parent_branch: bs.parent_branch,
-                upstream_synced: bs.upstream_status.as_ref().map(|us| us.synced),
-                upstream_name: bs.upstream_status.map(|us| us.symbolic_name),
-            });
-
-        // Compute diff stats
-        let diff_stats = if let Some(ref status) = status {
-            compute_diff_stats(git_repo, branch, status, cache)
-        } else {
-            None
-        };
+        // Git facts were computed up front by `compute_branch_git_info`.
+        let BranchGitInfo {
+            is_remote_only,
+            status,
+            diff_stats,
+        } = git_info.remove(&branch.name).unwrap_or_default();
 
         // Get local status (only for current branch)
         let local_status = if is_current {
@@ -370,13 +638,15 @@ fn flatten_tree(
             local_status,
             pr_info,
             note_preview,
+            mark: branch.mark,
             verbose: verbose_details,
+            elided_below,
             index,
         });
     }
 
     // Sort children: current subtree first, authors_filter second, alphabetical third
-    let mut children: Vec<&Branch> = branch.branches.iter().collect();
+    let mut children = children;
 
     // Pre-compute subtree properties for sorting
     let subtree_cache: HashMap<&str, (bool, bool)> = children
@@ -417,23 +687,22 @@ fn flatten_tree(
 
     // Recursively process children. Hidden branches pass their own depth through unchanged, so
     // a visible descendant renders at the depth it would have if attached directly to the
-    // nearest visible ancestor (display-only reparenting; git ancestry via `parent_branch` above
+    // nearest visible ancestor (display-only reparenting; git ancestry in `collect_status_jobs`
     // is untouched).
     let child_depth = if is_hidden { depth } else { depth + 1 };
     for child in children {
         flatten_tree(
-            git_repo,
             child,
-            Some(&branch.name),
             child_depth,
             current_branch,
             verbose,
             authors_filter,
             pr_authors,
             hidden,
+            elided,
+            git_info,
             result,
             current_branch_index,
-            cache,
         );
     }
 }
@@ -444,8 +713,8 @@ fn memoized_diff_stats(
     cache: &mut DiffStatsCache,
     base: &str,
     head: &str,
-    compute: impl FnOnce() -> Option<(usize, usize)>,
-) -> Option<(usize, usize)> {
+    compute: impl FnOnce() -> Option<(usize, usize, usize)>,
+) -> Option<(usize, usize, usize)> {
     if let Some(&cached) = cache.get(&(base.to_string(), head.to_string())) {
         return cached;
     }
@@ -471,11 +740,14 @@ fn compute_diff_stats(
 
     base_ref.and_then(|base| {
         memoized_diff_stats(cache, &base, &status.sha, || {
-            git_repo.diff_stats(&base, &status.sha).ok()
+            let (adds, dels) = git_repo.diff_stats(&base, &status.sha).ok()?;
+            let commits = git_repo.commits_ahead(&base, &status.sha).ok()?;
+            Some((adds, dels, commits))
         })
-        .map(|(adds, dels)| DiffStats {
+        .map(|(adds, dels, commits)| DiffStats {
             additions: adds,
             deletions: dels,
+            commit_count: commits,
             reliable: is_reliable,
         })
     })
@@ -493,6 +765,7 @@ mod tests {
             note: None,
             lkg_parent: None,
             pr_number: None,
+            mark: None,
             branches,
         }
     }
@@ -538,6 +811,26 @@ mod tests {
         authors
     }
 
+    #[test]
+    fn depth_elides_branches_below_the_current_branch_depth() {
+        let tree = fixture_tree();
+        let elided = |current, max_depth| {
+            let mut names: Vec<String> = compute_depth_elided(&tree, current, max_depth)
+                .into_iter()
+                .collect();
+            names.sort();
+            names
+        };
+        assert!(elided("bob-1", None).is_empty());
+        assert_eq!(elided("bob-1", Some(1)), ["carol-1-child"]);
+        assert_eq!(elided("bob-1", Some(0)), ["carol-1", "carol-1-child"]);
+        // An untracked current branch counts from the root.
+        assert_eq!(
+            elided("untracked", Some(1)),
+            ["bob-1", "carol-1", "carol-1-child", "eve-1"]
+        );
+    }
+
     #[test]
     fn compute_protected_branches_returns_current_ancestors_and_root() {
         let tree = fixture_tree();
@@ -700,6 +993,35 @@ mod tests {
         assert!(!hidden.contains("scoped-miss"));
     }
 
+    #[test]
+    fn status_jobs_skip_hidden_branches_but_keep_their_git_parent() {
+        let tree = fixture_tree();
+        let hidden = HashSet::from(["carol-1".to_string()]);
+        let mut jobs = Vec::new();
+        collect_status_jobs(&tree.branches[0], Some("main"), &hidden, &mut jobs);
+        let jobs: Vec<(Option<&str>, &str)> = jobs
+            .into_iter()
+            .map(|(parent, b)| (parent, b.name.as_str()))
+            .collect();
+        assert_eq!(
+            jobs,
+            vec![
+                (Some("main"), "alice-1"),
+                (Some("alice-1"), "bob-1"),
+                (Some("carol-1"), "carol-1-child"),
+            ]
+        );
+    }
+
+    #[test]
+    fn balance_groups_puts_largest_subtrees_on_separate_workers() {
+        let buckets = balance_groups(&[1, 5, 2, 4], 2);
+        assert_eq!(buckets, vec![vec![1, 0], vec![3, 2]]);
+        // More workers than groups: empty buckets are dropped.
+        assert_eq!(balance_groups(&[3], 4), vec![vec![0]]);
+        assert!(balance_groups(&[], 4).is_empty());
+    }
+
     fn sample_pr(number: u64, login: &str) -> PullRequest {
         use crate::github::{PrBranchRef, PrState, PrUser};
 
@@ -712,11 +1034,13 @@ mod tests {
                 ref_name: "main".to_string(),
                 sha: "deadbeef".to_string(),
                 repo: None,
+                label: None,
             },
             head: PrBranchRef {
                 ref_name: "feature".to_string(),
                 sha: "cafebabe".to_string(),
                 repo: None,
+                label: None,
             },
             user: PrUser {
                 login: login.to_string(),
@@ -740,7 +1064,9 @@ mod tests {
             local_status: None,
             pr_info: None,
             note_preview: None,
+            mark: None,
             verbose: None,
+            elided_below: 0,
             index,
         }
     }
@@ -769,6 +1095,70 @@ mod tests {
         assert!(tree.branches[1].pr_info.is_none());
     }
 
+    #[test]
+    fn renderable_tree_serializes_status_diff_and_pr_fields() {
+        let mut branch = sample_renderable_branch("alice-1", 0);
+        branch.is_current = true;
+        branch.status = Some(BranchRenderStatus {
+            exists: true,
+            is_descendent: false,
+            sha: "abc123".to_string(),
+            parent_branch: "main".to_string(),
+            upstream_synced: None,
+            upstream_name: None,
+            upstream_ahead: None,
+            upstream_behind: None,
+        });
+        branch.diff_stats = Some(DiffStats {
+            additions: 3,
+            deletions: 1,
+            commit_count: 2,
+            reliable: true,
+        });
+        let mut tree = RenderableTree {
+            branches: vec![branch],
+            current_branch_index: Some(0),
+        };
+        let pr_cache = HashMap::from([("alice-1".to_string(), sample_pr(42, "alice"))]);
+        apply_pr_cache(&mut tree, Some(&pr_cache));
+
+        let json = serde_json::to_value(&tree).unwrap();
+        let branch = &json["branches"][0];
+        assert_eq!(json["current_branch_index"], 0);
+        assert_eq!(branch["name"], "alice-1");
+        assert_eq!(branch["depth"], 0);
+        assert_eq!(branch["is_current"], true);
+        assert_eq!(branch["status"]["is_descendent"], false);
+        assert_eq!(branch["diff_stats"]["additions"], 3);
+        assert_eq!(branch["diff_stats"]["commit_count"], 2);
+        assert_eq!(branch["pr_info"]["number"], 42);
+        assert_eq!(branch["pr_info"]["state"], "open");
+    }
+
+    #[test]
+    fn ahead_behind_label_skips_zero_sides_and_missing_upstreams() {
+        let status = |ahead, behind| BranchRenderStatus {
+            exists: true,
+            is_descendent: true,
+            sha: "abc123".to_string(),
+            parent_branch: "main".to_string(),
+            upstream_synced: Some(false),
+            upstream_name: Some("origin/feature".to_string()),
+            upstream_ahead: ahead,
+            upstream_behind: behind,
+        };
+        assert_eq!(
+            status(Some(2), Some(1)).ahead_behind_label().as_deref(),
+            Some("↑2 ↓1")
+        );
+        assert_eq!(
+            status(Some(0), Some(3)).ahead_behind_label().as_deref(),
+            Some("↓3")
+        );
+        assert_eq!(status(Some(0), Some(0)).ahead_behind_label(), None);
+        assert_eq!(status(None, None).ahead_behind_label(), None);
+    }
+
     #[test]
     fn memoized_diff_stats_computes_once_per_key() {
         use std::cell::Cell;
@@ -777,15 +1167,15 @@ mod tests {
         let mut run = |base, head| {
             memoized_diff_stats(&mut cache, base, head, || {
                 calls.set(calls.get() + 1);
-                Some((1, 2))
+                Some((1, 2, 3))
             })
         };
 
-        assert_eq!(run("a", "b"), Some((1, 2)));
-        assert_eq!(run("a", "b"), Some((1, 2))); // cache hit
+        assert_eq!(run("a", "b"), Some((1, 2, 3)));
+        assert_eq!(run("a", "b"), Some((1, 2, 3))); // cache hit
         assert_eq!(calls.get(), 1);
 
-        assert_eq!(run("a", "c"), Some((1, 2))); // distinct head -> recompute
+        assert_eq!(run("a", "c"), Some((1, 2, 3))); // distinct head -> recompute
         assert_eq!(calls.get(), 2);
     }
 
@@ -0,0 +1,223 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::state::RestackMethod;
+
+/// What one restack step did to its branch.
+#[derive(Clone, Copy, Debug, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RestackOutcome {
+    /// The parent was already an ancestor; nothing was replayed.
+    AlreadyStacked,
+    /// The branch had no work of its own and was moved onto the parent.
+    FastForwarded,
+    /// The `format-patch` + `git am --3way` fast path applied cleanly.
+    Patched,
+    /// The `git rebase` fallback completed.
+    Rebased,
+    /// `git merge` of the parent completed.
+    Merged,
+    /// The branch was squashed into one commit on the parent.
+    Squashed,
+    /// The step stopped on a conflict; the restack is paused.
+    Conflict,
+}
+
+/// One line of the restack log.
+#[derive(Debug, Serialize)]
+pub(crate) struct RestackLogEntry<'a> {
+    pub(crate) branch: &'a str,
+    pub(crate) parent: &'a str,
+    /// The branch tip before this step touched it.
+    pub(crate) source_sha: &'a str,
+    /// The mechanic that ran, or `None` when the branch was already stacked.
+    pub(crate) method: Option<RestackMethod>,
+    pub(crate) outcome: RestackOutcome,
+    /// The branch tip after the step, when it finished.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) result_sha: Option<String>,
+    pub(crate) pushed: bool,
+}
+
+/// How many restack logs to keep; starting a new one deletes the oldest beyond this.
+const KEPT_LOGS: usize = 50;
+
+/// Per-run JSON-lines log of restack steps under the XDG state dir, so a restack that stops halfway
+/// leaves a record of exactly what it did. A log that can't be created degrades to no log; it never
+/// fails the restack.
+pub(crate) struct RestackLog {
+    file: Option<(PathBuf, File)>,
+    /// Print the log path when the run ends (`restack --log`).
+    announce: bool,
+}
+
+impl RestackLog {
+    /// Open the log for this run. A restack resumed after a conflict passes the log its first run
+    /// wrote (`resume_path`) and appends to it; otherwise a new log is created.
+    pub(crate) fn open(announce: bool, resume_path: Option<&Path>) -> Self {
+        let file = match resume_path {
+            Some(path) => OpenOptions::new()
+                .append(true)
+                .create(true)
+                .open(path)
+                .with_context(|| format!("reopening restack log {}", path.display()))
+                .map(|file| (path.to_path_buf(), file)),
+            None => restack_log_path().and_then(|path| {
+                let file = File::create(&path)
+                    .with_context(|| format!("creating restack log {}", path.display()))?;
+                if let Some(dir) = path.parent() {
+                    prune_logs(dir, KEPT_LOGS);
+                }
+                Ok((path, file))
+            }),
+        };
+        match file {
+            Ok(file) => Self {
+                file: Some(file),
+                announce,
+            },
+            Err(e) => {
+                tracing::warn!("Not writing a restack log: {e:#}");
+                Self {
+                    file: None,
+                    announce,
+                }
+            }
+        }
+    }
+
+    /// Append `entry`, stamped with the current time, as one JSON line. Write failures are only
+    /// traced.
+    pub(crate) fn record(&mut self, entry: &RestackLogEntry<'_>) {
+        let Some((path, file)) = self.file.as_mut() else {
+            return;
+        };
+        let line = log_line(
+            &chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            entry,
+        );
+        if let Err(e) = writeln!(file, "{line}") {
+            tracing::warn!("Failed to write restack log {}: {e}", path.display());
+        }
+    }
+
+    pub(crate) fn path(&self) -> Option<&Path> {
+        self.file.as_ref().map(|(path, _)| path.as_path())
+    }
+
+    /// Print where the log went, if `--log` asked for it.
+    pub(crate) fn announce(&self) {
+        if !self.announce {
+            return;
+        }
+        match self.path() {
+            Some(path) => println!("Restack log: {}", path.display().to_string().cyan()),
+            None => println!("No restack log was written for this run."),
+        }
+    }
+}
+
+/// `<state dir>/restack-logs/<UTC timestamp>-<pid>.jsonl`; the pid keeps two runs in the same
+/// second apart.
+fn restack_log_path() -> Result<PathBuf> {
+    let base_dirs = xdg::BaseDirectories::with_prefix(env!("CARGO_PKG_NAME"));
+    let name = format!(
+        "restack-logs/{}-{}.jsonl",
+        chrono::Utc::now().format("%Y%m%dT%H%M%SZ"),
+        std::process::id()
+    );
+    base_dirs
+        .place_state_file(name)
+        .context("Failed to determine restack log path")
+}
+
+/// Delete all but the newest `keep` logs in `dir`. Log names start with their UTC time, so they
+/// sort oldest first. Failures are only traced.
+fn prune_logs(dir: &Path, keep: usize) {
+    let mut logs: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+            .collect(),
+        Err(e) => {
+            tracing::warn!("Failed to list restack logs in {}: {e}", dir.display());
+            return;
+        }
+    };
+    logs.sort();
+    let excess = logs.len().saturating_sub(keep);
+    for path in &logs[..excess] {
+        if let Err(e) = fs::remove_file(path) {
+            tracing::warn!("Failed to delete old restack log {}: {e}", path.display());
+        }
+    }
+}
+
+fn log_line(time: &str, entry: &RestackLogEntry<'_>) -> String {
+    #[derive(Serialize)]
+    struct Line<'a> {
+        time: &'a str,
+        #[serde(flatten)]
+        entry: &'a RestackLogEntry<'a>,
+    }
+    serde_json::to_string(&Line { time, entry }).expect("restack log entries serialize")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_serialize_as_one_json_line() {
+        let entry = RestackLogEntry {
+            branch: "feature",
+            parent: "main",
+            source_sha: "abc",
+            method: Some(RestackMethod::Am),
+            outcome: RestackOutcome::Conflict,
+            result_sha: None,
+            pushed: false,
+        };
+        assert_eq!(
+            log_line("2026-01-02T03:04:05Z", &entry),
+            r#"{"time":"2026-01-02T03:04:05Z","branch":"feature","parent":"main","source_sha":"abc","method":"am","outcome":"conflict","pushed":false}"#
+        );
+    }
+
+    #[test]
+    fn pruning_keeps_the_newest_logs() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "20260101T000000Z-1.jsonl",
+            "20260102T000000Z-1.jsonl",
+            "20260103T000000Z-1.jsonl",
+            "20260104T000000Z-1.jsonl",
+            "notes.txt",
+        ] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+
+        prune_logs(dir.path(), 2);
+
+        let mut left: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        left.sort();
+        assert_eq!(
+            left,
+            [
+                "20260103T000000Z-1.jsonl",
+                "20260104T000000Z-1.jsonl",
+                "notes.txt"
+            ]
+        );
+    }
+}
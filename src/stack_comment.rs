@@ -0,0 +1,191 @@
+//! The stack overview comment `sync --stack-comments` keeps on each PR.
+//!
+//! Every open PR in a stack gets one comment listing the whole stack (each branch with its open
+//! PR's link, the PR being viewed in bold). The comment starts with a hidden
+//! HTML marker so later syncs edit it in place rather than posting another.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::{
+    git2_ops::GitRepo,
+    github::{CommentUpsert, PrDisplayState, RepoIdentifier},
+    provider::RemoteProvider,
+    render::{RenderableBranch, RenderableTree, apply_pr_cache, compute_renderable_tree},
+    state::Branch,
+};
+
+/// Identifies git-stack's comment among a PR's comments.
+pub(crate) const MARKER: &str = "<!-- git-stack:stack-comment -->";
+
+/// Split the flattened tree into stacks: each child of the trunk root starts one, and the
+/// branches below it (deeper entries up to the next depth-1 entry) belong to it.
+fn stacks(tree: &RenderableTree) -> Vec<&[RenderableBranch]> {
+    let branches = &tree.branches;
+    let starts: Vec<usize> = branches
+        .iter()
+        .enumerate()
+        .filter(|(_, b)| b.depth == 1)
+        .map(|(i, _)| i)
+        .collect();
+    starts
+        .iter()
+        .enumerate()
+        .map(|(n, &start)| {
+            let end = starts.get(n + 1).copied().unwrap_or(branches.len());
+            &branches[start..end]
+        })
+        .collect()
+}
+
+/// Render the comment for the PR on `current` within `stack`.
+fn render_comment(stack: &[RenderableBranch], current: &str) -> String {
+    let mut body = format!("{MARKER}\nThis PR is part of a stack:\n\n");
+    for branch in stack {
+        let indent = "  ".repeat(branch.depth.saturating_sub(1));
+        let label = match &branch.pr_info {
+            Some(pr) => format!("[#{}]({}) `{}`", pr.number, pr.html_url, branch.name),
+            None => format!("`{}`", branch.name),
+        };
+        if branch.name == current {
+            body.push_str(&format!("{indent}- **{label}** 👈\n"));
+        } else {
+            body.push_str(&format!("{indent}- {label}\n"));
+        }
+    }
+    body.push_str("\n<sub>Maintained by git-stack; edits will be overwritten.</sub>\n");
+    body
+}
+
+/// Upsert the stack comment on every open PR whose stack has more than one branch. Branch PRs are
+/// looked up fresh, so bases retargeted earlier in the same sync are reflected. Failures on one
+/// PR are reported and skipped.
+pub(crate) fn post_stack_comments(
+    git_repo: &GitRepo,
+    tree: &Branch,
+    client: &dyn RemoteProvider,
+    repo_id: &RepoIdentifier,
+) -> Result<()> {
+    let mut renderable = compute_renderable_tree(
+        git_repo,
+        tree,
+        "",
+        false,
+        &[],
+        &HashMap::new(),
+        true,
+        None,
+        true,
+    );
+    let branch_names: Vec<String> = renderable
+        .branches
+        .iter()
+        .filter(|b| b.depth > 0)
+        .map(|b| b.name.clone())
+        .collect();
+    let open_prs = client.list_open_prs_for_branches(repo_id, &branch_names);
+    apply_pr_cache(&mut renderable, Some(&open_prs.found));
+
+    println!("Updating stack comments...");
+    for stack in stacks(&renderable) {
+        if stack.len() < 2 {
+            continue;
+        }
+        for branch in stack {
+            let Some(pr) = &branch.pr_info else { continue };
+            if !matches!(pr.state, PrDisplayState::Open | PrDisplayState::Draft) {
+                continue;
+            }
+            let body = render_comment(stack, &branch.name);
+            match client.create_or_update_comment(repo_id, pr.number, MARKER, &body) {
+                Ok(CommentUpsert::Unchanged) => {}
+                Ok(outcome) => println!(
+                    "  {} stack comment on PR #{} ('{}')",
+                    if outcome == CommentUpsert::Created {
+                        "Posted"
+                    } else {
+                        "Updated"
+                    },
+                    pr.number.to_string().green(),
+                    branch.name.yellow()
+                ),
+                Err(e) => tracing::warn!(
+                    "Failed to update the stack comment on PR #{}: {}",
+                    pr.number,
+                    e
+                ),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::PrRenderInfo;
+
+    fn branch(name: &str, depth: usize, pr: Option<(u64, PrDisplayState)>) -> RenderableBranch {
+        RenderableBranch {
+            name: name.to_string(),
+            depth,
+            is_current: false,
+            is_dimmed: false,
+            is_remote_only: false,
+            status: None,
+            diff_stats: None,
+            local_status: None,
+            pr_info: pr.map(|(number, state)| PrRenderInfo {
+                number,
+                state,
+                author: "me".to_string(),
+                html_url: format!("https://github.com/o/r/pull/{number}"),
+            }),
+            note_preview: None,
+            mark: None,
+            verbose: None,
+            elided_below: 0,
+            index: 0,
+        }
+    }
+
+    #[test]
+    fn stacks_split_at_each_child_of_trunk() {
+        let tree = RenderableTree {
+            branches: vec![
+                branch("main", 0, None),
+                branch("a", 1, None),
+                branch("b", 2, None),
+                branch("c", 3, None),
+                branch("x", 1, None),
+            ],
+            current_branch_index: None,
+        };
+        let names: Vec<Vec<&str>> = stacks(&tree)
+            .iter()
+            .map(|s| s.iter().map(|b| b.name.as_str()).collect())
+            .collect();
+        assert_eq!(names, vec![vec!["a", "b", "c"], vec!["x"]]);
+    }
+
+    #[test]
+    fn comment_bolds_the_current_pr() {
+        let stack = [
+            branch("auth", 1, Some((10, PrDisplayState::Open))),
+            branch("login", 2, Some((11, PrDisplayState::Open))),
+            branch("logout", 3, None),
+        ];
+        assert_eq!(
+            render_comment(&stack, "login"),
+            format!(
+                "{MARKER}\nThis PR is part of a stack:\n\n\
+                 - [#10](https://github.com/o/r/pull/10) `auth`\n  \
+                 - **[#11](https://github.com/o/r/pull/11) `login`** 👈\n    \
+                 - `logout`\n\
+                 \n<sub>Maintained by git-stack; edits will be overwritten.</sub>\n"
+            )
+        );
+    }
+}
@@ -6,6 +6,7 @@ use std::{
     path::{Path, PathBuf},
     process::Command,
     rc::Rc,
+    sync::Mutex,
 };
 
 use anyhow::{Context, Result, anyhow, bail, ensure};
@@ -13,30 +14,106 @@ use colored::Colorize;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    git::{GitTrunk, after_text, checkout_tracked_branch, git_branch_exists, git_trunk},
+    branch_name,
+    git::{
+        GitTrunk, after_text, checkout_tracked_branch, git_branch_exists, git_trunk,
+        guarded_checkout,
+    },
     git2_ops::{DEFAULT_REMOTE, GitRepo},
+    github::{MergeMethod, get_repo_identifier},
+    lock::StateLock,
+    provider::client_for,
     run_git,
 };
 
-/// Write a file with secure permissions (0600 on Unix).
-/// This ensures sensitive config and state files are only readable by the owner.
+/// Held by a command that changes the stack, from before it loads the state until it exits (or
+/// hands the terminal to an editor), so its read-modify-save can't interleave with another
+/// git-stack process's. Read-only commands never take it; a save they make locks just for the
+/// write.
+static STATE_LOCK: Mutex<Option<StateLock>> = Mutex::new(None);
+
+/// `state.yaml` as this process last read or wrote it. A save whose YAML matches is skipped, so
+/// commands that change nothing (`status`, `log`, `note`, ...) never rewrite the file.
+static STATE_ON_DISK: Mutex<Option<(PathBuf, String)>> = Mutex::new(None);
+
+/// How many `state.yaml.<timestamp>.bak` copies [`State::backup_state_file`] keeps.
+const KEPT_BACKUPS: usize = 5;
+
+/// Take the state lock for the rest of the command, unless this process already holds it.
+pub(crate) fn lock_state() -> Result<()> {
+    let mut held = STATE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if held.is_none() {
+        *held = Some(acquire_state_lock(&get_xdg_path()?)?);
+    }
+    Ok(())
+}
+
+/// Let go of the state lock, e.g. before waiting on an editor. Later saves lock for the write and
+/// still refuse to overwrite a change another process made meanwhile.
+pub(crate) fn unlock_state() {
+    STATE_LOCK.lock().unwrap_or_else(|e| e.into_inner()).take();
+}
+
+fn acquire_state_lock(state_path: &Path) -> Result<StateLock> {
+    if let Some(dir) = state_path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("creating state directory {}", dir.display()))?;
+    }
+    StateLock::try_acquire(state_path)
+}
+
+/// Restrict `path` to owner read/write (0600 on Unix).
 #[cfg(unix)]
-pub fn write_file_secure(path: &Path, contents: &str) -> std::io::Result<()> {
+fn set_secure_permissions(path: &Path) -> std::io::Result<()> {
     use std::os::unix::fs::PermissionsExt;
-    fs::write(path, contents)?;
     let mut perms = fs::metadata(path)?.permissions();
     perms.set_mode(0o600);
-    fs::set_permissions(path, perms)?;
-    Ok(())
+    fs::set_permissions(path, perms)
 }
 
-/// Write a file (non-Unix platforms don't have the same permission model).
+/// Non-Unix platforms don't have the same permission model.
 #[cfg(not(unix))]
+fn set_secure_permissions(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Write a file with secure permissions (0600 on Unix).
+/// This ensures sensitive config and state files are only readable by the owner.
 pub fn write_file_secure(path: &Path, contents: &str) -> std::io::Result<()> {
-    fs::write(path, contents)
+    fs::write(path, contents)?;
+    set_secure_permissions(path)
 }
 
-#[derive(Clone, Copy, Default, Debug, Serialize, Deserialize, Eq, PartialEq)]
+/// Replace `path` with whatever `write` produces, without ever exposing a partial file. The
+/// contents go to a sibling temp file (0600 before the first byte lands) that is renamed over
+/// `path` only once `write` succeeds and the data is synced; an error or panic in `write` drops
+/// the temp file and leaves the existing `path` untouched.
+pub fn write_file_secure_atomic(
+    path: &Path,
+    write: impl FnOnce(&mut dyn Write) -> Result<()>,
+) -> Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut temp = tempfile::Builder::new()
+        .prefix(".git-stack-")
+        .suffix(".tmp")
+        .tempfile_in(dir)
+        .with_context(|| format!("creating temp file in {}", dir.display()))?;
+    set_secure_permissions(temp.path())?;
+    {
+        let mut writer = io::BufWriter::new(temp.as_file_mut());
+        write(&mut writer)?;
+        writer.flush()?;
+    }
+    temp.as_file().sync_all()?;
+    temp.persist(path)
+        .with_context(|| format!("replacing {}", path.display()))?;
+    Ok(())
+}
+
+#[derive(Clone, Copy, Default, Debug, Serialize, Deserialize, Eq, PartialEq, clap::ValueEnum)]
 #[serde(rename_all = "snake_case")]
 pub enum StackMethod {
     /// Uses `git format-patch` and `git am` to restack branches.
@@ -46,6 +123,29 @@ pub enum StackMethod {
     Merge,
 }
 
+/// A team's workflow signal on a branch, set with `git stack mark`, independent of the PR's draft
+/// state. `land` won't merge a branch marked `Wip` or `Blocked` without `--force`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BranchMark {
+    /// Still being worked on.
+    Wip,
+    /// Done and ready to merge.
+    Ready,
+    /// Waiting on something else.
+    Blocked,
+}
+
+impl std::fmt::Display for BranchMark {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            BranchMark::Wip => "wip",
+            BranchMark::Ready => "ready",
+            BranchMark::Blocked => "blocked",
+        })
+    }
+}
+
 /// Which restack mechanic was in progress when a conflict interrupted it. Determines the
 /// `--abort`/`--continue` mechanics the handlers run.
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq)]
@@ -72,8 +172,30 @@ pub struct RestackResume {
     pub ancestors: bool,
     /// Whether the original invocation pushed after each branch.
     pub push: bool,
+    /// Whether the original invocation pushed even branches whose remote already matched.
+    #[serde(default)]
+    pub push_all: bool,
     /// Whether the original invocation was a squash restack.
     pub squash: bool,
+    /// Whether the original invocation (a `move`) also restacked the branch's descendants.
+    #[serde(default)]
+    pub descendants: bool,
+    /// Whether the original invocation asked for the restack log path (`--log`).
+    #[serde(default)]
+    pub log: bool,
+    /// The `--autostash` stash commit to restore once the restack finishes or is aborted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub autostash: Option<String>,
+    /// The `--onto` commit the bottom branch is restacked onto instead of its parent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub onto: Option<String>,
+    /// The worktree the restack runs in. Every worktree of a repo shares its state entry, so the
+    /// paused restack may only be continued, skipped, or aborted from this one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worktree: Option<String>,
+    /// The restack log the run writes to; a resumed run appends to it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_path: Option<PathBuf>,
 }
 
 /// A restack operation interrupted by a conflict, awaiting `--continue`/`--abort`.
@@ -114,6 +236,9 @@ pub struct Branch {
     /// The GitHub PR number associated with this branch, if any.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pr_number: Option<u64>,
+    /// The branch's workflow mark, if one was set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mark: Option<BranchMark>,
     /// The upstream branch reference.
     pub branches: Vec<Branch>,
 }
@@ -126,9 +251,77 @@ impl Branch {
             stack_method: StackMethod::default(),
             lkg_parent,
             pr_number: None,
+            mark: None,
             branches: vec![],
         }
     }
+
+    /// The branch named `name` in this subtree (including this branch itself).
+    pub(crate) fn find(&self, name: &str) -> Option<&Branch> {
+        find_branch(self, &|branch| branch.name == name)
+    }
+
+    /// Mutable [`Branch::find`].
+    pub(crate) fn find_mut(&mut self, name: &str) -> Option<&mut Branch> {
+        find_branch_mut(self, &|branch| branch.name == name)
+    }
+
+    /// Every branch in this subtree, this one first, then each child's subtree in order.
+    pub(crate) fn iter(&self) -> BranchIter<'_> {
+        BranchIter { stack: vec![self] }
+    }
+
+    /// Visit every branch in this subtree in [`Branch::iter`] order, with its depth below this
+    /// branch (0 for this one) and its parent's name (`None` for this one).
+    pub(crate) fn walk(&self, f: &mut impl FnMut(&Branch, usize, Option<&str>)) {
+        self.walk_from(0, None, f);
+    }
+
+    fn walk_from(
+        &self,
+        depth: usize,
+        parent: Option<&str>,
+        f: &mut impl FnMut(&Branch, usize, Option<&str>),
+    ) {
+        f(self, depth, parent);
+        for child in &self.branches {
+            child.walk_from(depth + 1, Some(&self.name), f);
+        }
+    }
+
+    /// Mutable [`Branch::walk`]. A branch is visited before its children, so changes `f` makes to
+    /// `branches` (e.g. sorting them) decide what is visited next.
+    pub(crate) fn walk_mut(&mut self, f: &mut impl FnMut(&mut Branch, usize, Option<&str>)) {
+        self.walk_mut_from(0, None, f);
+    }
+
+    fn walk_mut_from(
+        &mut self,
+        depth: usize,
+        parent: Option<&str>,
+        f: &mut impl FnMut(&mut Branch, usize, Option<&str>),
+    ) {
+        f(self, depth, parent);
+        let Branch { name, branches, .. } = self;
+        for child in branches {
+            child.walk_mut_from(depth + 1, Some(name), f);
+        }
+    }
+}
+
+/// Pre-order iterator over a branch subtree; see [`Branch::iter`].
+pub(crate) struct BranchIter<'a> {
+    stack: Vec<&'a Branch>,
+}
+
+impl<'a> Iterator for BranchIter<'a> {
+    type Item = &'a Branch;
+
+    fn next(&mut self) -> Option<&'a Branch> {
+        let branch = self.stack.pop()?;
+        self.stack.extend(branch.branches.iter().rev());
+        Some(branch)
+    }
 }
 
 /// Per-repository state including the branch tree and seen remote SHAs.
@@ -144,6 +337,40 @@ pub struct RepoState {
     /// Pending restack operation that needs to be resumed/aborted after conflict resolution.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub pending_restack: Option<PendingRestackOperation>,
+    /// Trunk branch set with `--trunk`, used instead of resolving `origin/HEAD` for repos where
+    /// that ref is missing or points at the wrong branch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trunk: Option<String>,
+    /// When `sync` last completed for this repo (ISO 8601, UTC).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_sync: Option<String>,
+    /// When `status` last refreshed the whole PR cache itself (ISO 8601, UTC), so a stale sync
+    /// costs one slow `status` rather than every one until the next `sync`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_pr_refresh: Option<String>,
+    /// Stable identity of the repo (`host/owner/repo` of its `origin` remote), used to find this
+    /// entry again after the repo is moved or re-cloned to another path.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<String>,
+    /// Stack method for branches newly added to this repo's tree, set with `init
+    /// --stack-method`. Unset means [`StackMethod::default`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_stack_method: Option<StackMethod>,
+    /// How `land` merges PRs in this repo, set with `init --merge-method`. Unset means
+    /// [`MergeMethod::default`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merge_method: Option<MergeMethod>,
+    /// Prefix (e.g. `me/`) put in front of new branch names from `checkout`, set with `init
+    /// --branch-prefix`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch_prefix: Option<String>,
+    /// What the last eager LKG refresh saw (see [`State::lkg_fingerprint`]); a run that sees the
+    /// same skips the refresh.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lkg_fingerprint: Option<String>,
+    /// The tracked branch `HEAD` was last moved off of, which `checkout -` goes back to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_branch: Option<String>,
 }
 
 impl RepoState {
@@ -152,6 +379,15 @@ impl RepoState {
             tree,
             seen_remote_shas: HashSet::new(),
             pending_restack: None,
+            trunk: None,
+            last_sync: None,
+            last_pr_refresh: None,
+            identity: None,
+            default_stack_method: None,
+            merge_method: None,
+            branch_prefix: None,
+            lkg_fingerprint: None,
+            last_branch: None,
         }
     }
 }
@@ -162,8 +398,21 @@ enum LkgParentPolicy {
     Preserve,
 }
 
+/// The `state.yaml` schema version this build reads and writes.
+///
+/// - 0 (no `version` key): a repo's value is either its tree or, in the oldest files, a list of
+///   stacks, each a list of branch names from trunk up.
+/// - 1: every repo's value is its tree.
+/// - 2: each repo records its `identity`, stamped on upgrade for every repo still checked out at
+///   its path.
+pub const STATE_VERSION: u32 = 2;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct State {
+    /// Schema version of the file this was loaded from; always [`STATE_VERSION`] once loaded, so
+    /// saves write the current version.
+    #[serde(default)]
+    pub version: u32,
     /// The directory name is the key, and the value is the repo state.
     #[serde(flatten, default)]
     pub repos: BTreeMap<String, RepoState>,
@@ -186,26 +435,151 @@ impl State {
                 "".to_string()
             }
         };
-        let state: Self = serde_yaml::from_str(&data)
+        let (state, upgraded) = Self::parse_upgrading(&data)
             .with_context(|| format!("parsing state file: {:?}", state_path))?;
+        *STATE_ON_DISK.lock().unwrap_or_else(|e| e.into_inner()) =
+            used_existing_state.then(|| (state_path.clone(), data));
         fs::create_dir_all(state_path.parent().unwrap())
             .inspect_err(|error| tracing::warn!("Failed to create config directory: {}", error))?;
+        if let Err(problems) = state.validate() {
+            for problem in &problems {
+                tracing::warn!("{}: {}", state_path.display(), problem);
+            }
+            tracing::warn!("Run `git stack doctor` for details, or `git stack edit` to fix.");
+        }
         if !used_existing_state {
             tracing::info!("No existing config file found, creating a new one.");
             state
                 .save_state()
                 .inspect_err(|error| tracing::warn!("Failed to save config file: {}", error))?;
+        } else if upgraded {
+            // Upgrade once, so the identity stamping isn't redone on every run.
+            state.save_state()?;
         }
         Ok(state)
     }
 
+    /// Parse `state.yaml`, upgrading older schema versions to [`STATE_VERSION`]. Fails on a file
+    /// from a newer git-stack rather than dropping fields it doesn't know.
+    fn parse(data: &str) -> Result<Self> {
+        Ok(Self::parse_upgrading(data)?.0)
+    }
+
+    /// [`Self::parse`], also returning whether the file was from an older schema version.
+    fn parse_upgrading(data: &str) -> Result<(Self, bool)> {
+        let mut doc: serde_yaml::Value = serde_yaml::from_str(data)?;
+        if doc.is_null() {
+            doc = serde_yaml::Value::Mapping(serde_yaml::Mapping::new());
+        }
+        let serde_yaml::Value::Mapping(repos) = &mut doc else {
+            bail!("expected a map of repo paths to stack trees");
+        };
+        let version = match repos.get("version") {
+            None => 0,
+            Some(value) => value
+                .as_u64()
+                .and_then(|v| u32::try_from(v).ok())
+                .ok_or_else(|| anyhow!("invalid version {value:?}"))?,
+        };
+        if version > STATE_VERSION {
+            bail!(
+                "the state file has schema version {version}, but this git-stack only reads up \
+                 to version {STATE_VERSION}; upgrade git-stack"
+            );
+        }
+        if version < 1 {
+            migrate_legacy_stacks(repos)?;
+        }
+        let mut state: Self = serde_yaml::from_value(doc)?;
+        if version < 2 {
+            state.stamp_identities();
+        }
+        state.version = STATE_VERSION;
+        Ok((state, version < STATE_VERSION))
+    }
+
+    /// Record the identity of every repo that is still checked out at its path, so it can be found
+    /// again if it moves later. A repo already gone from its path is left to the fallback in
+    /// [`Self::relocate_moved_repo`].
+    fn stamp_identities(&mut self) {
+        for (path, repo_state) in &mut self.repos {
+            if repo_state.identity.is_none()
+                && Path::new(path).join(".git").exists()
+                && let Ok(git_repo) = GitRepo::open_uncached(path)
+            {
+                repo_state.identity = repo_identity(&git_repo);
+            }
+        }
+    }
+
+    /// Check each repo's tree for integrity problems a hand edit can introduce: empty branch names,
+    /// and names that appear more than once (the `find_branch` helpers would silently use the
+    /// first). Returns one message per problem.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+        for (repo, repo_state) in &self.repos {
+            problems.extend(tree_problems(repo, &repo_state.tree));
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
+    /// Copy the state file to `state.yaml.<UTC timestamp>.bak` beside it, so a command about to
+    /// make several repairs at once can be undone by copying it back over `state.yaml`. Only the
+    /// newest [`KEPT_BACKUPS`] copies are kept. Returns the copy's path, or `None` when there is
+    /// no state file yet.
+    pub(crate) fn backup_state_file() -> Result<Option<PathBuf>> {
+        let state_path = get_xdg_path()?;
+        let Ok(contents) = fs::read(&state_path) else {
+            return Ok(None);
+        };
+        let backup = state_path.with_extension(format!(
+            "yaml.{}.bak",
+            chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ")
+        ));
+        write_file_secure_atomic(&backup, |out| Ok(out.write_all(&contents)?))?;
+        if let Some(dir) = state_path.parent() {
+            prune_backups(dir, KEPT_BACKUPS);
+        }
+        Ok(Some(backup))
+    }
+
+    /// Write the state file, unless it already holds exactly this state (as far as this process
+    /// last read or wrote it). Fails rather than overwrite a file another process saved since.
     pub fn save_state(&self) -> Result<()> {
         let state_path = get_xdg_path()?;
+        let yaml = serde_yaml::to_string(self)?;
+        let mut on_disk = STATE_ON_DISK.lock().unwrap_or_else(|e| e.into_inner());
+        if on_disk
+            .as_ref()
+            .is_some_and(|(path, data)| *path == state_path && *data == yaml)
+        {
+            tracing::trace!(?state_path, "State unchanged; not saving");
+            return Ok(());
+        }
+        let held = STATE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let _write_lock = match &*held {
+            Some(_) => None,
+            None => Some(acquire_state_lock(&state_path)?),
+        };
+        let expected = on_disk
+            .as_ref()
+            .filter(|(path, _)| *path == state_path)
+            .map(|(_, data)| data.as_str());
+        if fs::read_to_string(&state_path).ok().as_deref() != expected {
+            bail!(
+                "{} changed since this git-stack process read it (another git-stack process \
+                 saved it meanwhile); run the command again.",
+                state_path.display()
+            );
+        }
         tracing::trace!(?self, ?state_path, "Saving state to config file");
-        Ok(write_file_secure(
-            &state_path,
-            &serde_yaml::to_string(&self)?,
-        )?)
+        write_file_secure_atomic(&state_path, |out| Ok(out.write_all(yaml.as_bytes())?))?;
+        *on_disk = Some((state_path, yaml));
+        Ok(())
     }
 
     pub fn get_tree(&self, repo: &str) -> Option<&Branch> {
@@ -230,6 +604,45 @@ impl State {
     pub fn get_seen_shas(&self, repo: &str) -> Option<&HashSet<String>> {
         self.repos.get(repo).map(|r| &r.seen_remote_shas)
     }
+    /// When `sync` last completed for `repo`, if it ever has (and the stored time parses).
+    pub fn last_sync(&self, repo: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+        parse_utc(self.repos.get(repo)?.last_sync.as_deref()?)
+    }
+
+    /// Record that `sync` just completed for `repo`. Saved with the next `save_state`.
+    pub fn record_sync(&mut self, repo: &str) {
+        if let Some(repo_state) = self.repos.get_mut(repo) {
+            repo_state.last_sync = Some(utc_now());
+        }
+    }
+
+    /// When `status` last refreshed `repo`'s whole PR cache, if it ever has.
+    pub fn last_pr_refresh(&self, repo: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+        parse_utc(self.repos.get(repo)?.last_pr_refresh.as_deref()?)
+    }
+
+    /// Record that `status` just refreshed `repo`'s whole PR cache. Saved with the next
+    /// `save_state`.
+    pub fn record_pr_refresh(&mut self, repo: &str) {
+        if let Some(repo_state) = self.repos.get_mut(repo) {
+            repo_state.last_pr_refresh = Some(utc_now());
+        }
+    }
+
+    /// Record, for `checkout -`, the newest of `moved_off` (the branches a command moved `HEAD` off
+    /// of, newest first) that the stack tree tracks and that isn't `current_branch`. Records
+    /// nothing without one. Saved with the next `save_state`.
+    pub fn record_last_branch(&mut self, repo: &str, current_branch: &str, moved_off: &[String]) {
+        let Some(repo_state) = self.repos.get_mut(repo) else {
+            return;
+        };
+        if let Some(branch) = moved_off.iter().find(|branch| {
+            *branch != current_branch && is_branch_mentioned_in_tree(branch, &repo_state.tree)
+        }) {
+            repo_state.last_branch = Some(branch.clone());
+        }
+    }
+
     /// Clear all seen SHAs for a repo.
     pub fn clear_seen_shas(&mut self, repo: &str) {
         if let Some(repo_state) = self.repos.get_mut(repo) {
@@ -262,6 +675,10 @@ impl State {
     ///
     /// For branches tracked in git-stack but not existing locally, this will create the local
     /// branch from the remote ref (origin/branch_name) on-demand.
+    ///
+    /// With `slug`, `branch_name` is free text turned into a name first. A name that isn't an
+    /// existing branch gets the repo's branch prefix and must be a valid git branch name.
+    #[allow(clippy::too_many_arguments)]
     pub fn checkout(
         &mut self,
         git_repo: &GitRepo,
@@ -269,7 +686,19 @@ impl State {
         current_branch: String,
         current_upstream: Option<String>,
         branch_name: String,
+        remote: bool,
+        parent: Option<String>,
+        slug: bool,
+        autostash: bool,
+        track: bool,
     ) -> Result<()> {
+        if branch_name == "-" {
+            if remote || parent.is_some() || slug || track {
+                bail!("`checkout -` doesn't take --remote, --parent, --slug, or --track.");
+            }
+            return self.checkout_previous(git_repo, repo, &current_branch, autostash);
+        }
+
         // Ensure the main branch is in the git-stack tree for this repo if we haven't
         // added it yet (only if we have a remote configured).
         if let Some(trunk) = git_trunk(git_repo) {
@@ -279,9 +708,26 @@ impl State {
             self.save_state()?;
         }
 
+        let branch_name = self.new_branch_name(git_repo, repo, &branch_name, remote, slug)?;
         let branch_exists_in_tree = self.branch_exists_in_tree(repo, &branch_name);
         let branch_exists_locally = git_branch_exists(git_repo, &branch_name);
 
+        // `--parent` only says where a new branch goes.
+        if let Some(parent) = parent.as_deref() {
+            if branch_exists_locally || branch_exists_in_tree {
+                bail!(
+                    "Branch {branch_name} already exists; --parent only applies to a new branch.",
+                    branch_name = branch_name.red()
+                );
+            }
+            if self.get_tree_branch(repo, parent).is_none() {
+                bail!(
+                    "Branch {parent} is not being tracked in the git-stack tree.",
+                    parent = parent.red()
+                );
+            }
+        }
+
         // Case 1: Branch exists locally - just check it out
         if branch_exists_locally {
             if !branch_exists_in_tree {
@@ -291,16 +737,20 @@ impl State {
                     <parent-branch>` to stack {branch_name} on top of the parent branch.",
                 );
             }
-            checkout_tracked_branch(git_repo, &branch_name)?;
+            guarded_checkout(git_repo, &branch_name, &branch_name, autostash, || {
+                checkout_tracked_branch(git_repo, &branch_name)
+            })?;
             return Ok(());
         }
 
         // Case 2: Branch is in tree but doesn't exist locally - create from remote
         if branch_exists_in_tree {
             let remote_ref = format!("origin/{}", branch_name);
-            if git_repo.ref_exists(&remote_ref) {
+            if git_repo.remote_branch_exists(DEFAULT_REMOTE, &branch_name) {
                 // Create local branch from remote ref
-                run_git(&["checkout", "-b", &branch_name, &remote_ref])?;
+                guarded_checkout(git_repo, &remote_ref, &branch_name, autostash, || {
+                    run_git(&["checkout", "-b", &branch_name, &remote_ref]).map(drop)
+                })?;
                 println!(
                     "Branch {branch_name} created from remote and checked out.",
                     branch_name = branch_name.yellow()
@@ -314,22 +764,16 @@ impl State {
             }
         }
 
-        // Case 3: Branch doesn't exist anywhere - create a new branch from current
-        let branch = self
-            .get_tree_branch_mut(repo, &current_branch)
-            .ok_or_else(|| {
-                anyhow::anyhow!(
-                    "Branch '{current_branch}' is not being tracked in the git-stack tree."
-                )
-            })?;
-
-        branch.branches.push(Branch::new(
-            branch_name.clone(),
-            git_repo.sha(&current_branch).ok(),
-        ));
+        // Case 3: Branch isn't ours yet but has a PR - fetch it and mount it under the PR's base
+        if remote {
+            return self.checkout_from_pr(git_repo, repo, &branch_name, autostash);
+        }
 
-        // Actually create the git branch.
-        run_git(&["checkout", "-b", &branch_name, &current_branch])?;
+        // Case 4: Branch doesn't exist anywhere - create a new branch from `--parent` or current
+        let parent = parent.unwrap_or(current_branch);
+        guarded_checkout(git_repo, &parent, &branch_name, autostash, || {
+            self.create_child_branch(git_repo, repo, &parent, &branch_name)
+        })?;
 
         println!(
             "Branch {branch_name} created and checked out.",
@@ -339,6 +783,235 @@ impl State {
         // Save the state after modifying it.
         self.save_state()?;
 
+        if track {
+            run_git(&[
+                "push",
+                "-u",
+                DEFAULT_REMOTE,
+                &format!("{branch_name}:{branch_name}"),
+            ])?;
+            println!(
+                "Pushed {branch_name}; it tracks {DEFAULT_REMOTE}/{branch_name}.",
+                branch_name = branch_name.yellow()
+            );
+        }
+
+        Ok(())
+    }
+
+    /// `checkout -`: go back to the tracked branch `HEAD` was last moved off of. Without one (or if
+    /// it's gone, or already checked out), fall back to git's own `git checkout -`.
+    fn checkout_previous(
+        &self,
+        git_repo: &GitRepo,
+        repo: &str,
+        current_branch: &str,
+        autostash: bool,
+    ) -> Result<()> {
+        let last_branch = self
+            .repos
+            .get(repo)
+            .and_then(|repo_state| repo_state.last_branch.as_deref())
+            .filter(|last| *last != current_branch && git_branch_exists(git_repo, last));
+        match last_branch {
+            Some(last) => guarded_checkout(git_repo, last, last, autostash, || {
+                checkout_tracked_branch(git_repo, last)
+            }),
+            None => guarded_checkout(git_repo, "@{-1}", "the previous branch", autostash, || {
+                run_git(&["checkout", "-"])?;
+                Ok(())
+            }),
+        }
+    }
+
+    /// The branch `checkout` should act on for `name`: slugified with `slug`, then, unless that
+    /// already names a local or tracked branch, prefixed with the repo's branch prefix (not for
+    /// `--remote`, which looks up someone's PR branch by its exact name) and validated.
+    fn new_branch_name(
+        &self,
+        git_repo: &GitRepo,
+        repo: &str,
+        name: &str,
+        remote: bool,
+        slug: bool,
+    ) -> Result<String> {
+        let name = if slug {
+            let slugged = branch_name::slugify(name);
+            if slugged.is_empty() {
+                bail!(
+                    "{name} has no letters or digits to make a branch name from.",
+                    name = format!("'{name}'").red()
+                );
+            }
+            slugged
+        } else {
+            name.to_string()
+        };
+        if git_branch_exists(git_repo, &name) || self.branch_exists_in_tree(repo, &name) {
+            return Ok(name);
+        }
+        let name = if remote {
+            name
+        } else {
+            let prefix = self
+                .repos
+                .get(repo)
+                .and_then(|r| r.branch_prefix.as_deref());
+            branch_name::with_prefix(prefix, &name)
+        };
+        if let Err(e) = branch_name::validate(&name) {
+            if slug {
+                return Err(e);
+            }
+            bail!("{e} Pass --slug to make a branch name from it.");
+        }
+        Ok(name)
+    }
+
+    /// Track `branch_name` as a new child of `parent` and create it in git at `parent`'s tip,
+    /// checked out. Doesn't save.
+    fn create_child_branch(
+        &mut self,
+        git_repo: &GitRepo,
+        repo: &str,
+        parent: &str,
+        branch_name: &str,
+    ) -> Result<()> {
+        let stack_method = self.new_branch_stack_method(repo);
+        let branch = self.get_tree_branch_mut(repo, parent).ok_or_else(|| {
+            anyhow::anyhow!("Branch '{parent}' is not being tracked in the git-stack tree.")
+        })?;
+
+        branch.branches.push(Branch {
+            stack_method,
+            ..Branch::new(branch_name.to_string(), git_repo.sha(parent).ok())
+        });
+
+        // Actually create the git branch.
+        run_git(&["checkout", "-b", branch_name, parent])?;
+        Ok(())
+    }
+
+    /// `git stack create-stack`: create `branch_names` as a linear stack on top of
+    /// `current_branch`, each at its parent's tip, leaving the last one checked out. Nothing is
+    /// created if any name is already taken; if creating one fails partway, the branches made so
+    /// far are kept and listed.
+    pub(crate) fn create_stack(
+        &mut self,
+        git_repo: &GitRepo,
+        repo: &str,
+        current_branch: &str,
+        branch_names: &[String],
+    ) -> Result<()> {
+        self.ensure_trunk(git_repo, repo);
+        if !self.branch_exists_in_tree(repo, current_branch) {
+            bail!(
+                "Branch {current_branch} is not being tracked in the git-stack tree. Run `git \
+                 stack mount <parent>` first.",
+                current_branch = current_branch.red()
+            );
+        }
+        let mut seen = HashSet::new();
+        for name in branch_names {
+            if !seen.insert(name.as_str()) {
+                bail!("Branch {name} is listed more than once.", name = name.red());
+            }
+            branch_name::validate(name)?;
+            if git_branch_exists(git_repo, name)
+                || self.branch_exists_in_tree(repo, name)
+                || git_repo.remote_branch_exists(DEFAULT_REMOTE, name)
+            {
+                bail!(
+                    "Branch {name} already exists; no branches were created.",
+                    name = name.red()
+                );
+            }
+        }
+
+        let mut parent = current_branch;
+        let mut created: Vec<&str> = Vec::new();
+        for name in branch_names {
+            if let Err(e) = self.create_child_branch(git_repo, repo, parent, name) {
+                self.save_state()?;
+                let created = if created.is_empty() {
+                    "none".to_string()
+                } else {
+                    created.join(", ")
+                };
+                return Err(e.context(format!(
+                    "Stopped at {name}; branches created so far: {created}"
+                )));
+            }
+            created.push(name);
+            parent = name;
+        }
+        self.save_state()?;
+
+        println!(
+            "Created {} on top of {current_branch}; {last} is checked out.",
+            created.join(" -> "),
+            current_branch = current_branch.yellow(),
+            last = parent.yellow()
+        );
+        Ok(())
+    }
+
+    /// `checkout --remote`: look up the open PR whose head is `branch_name`, create a local
+    /// branch tracking `origin/<branch_name>`, and mount it under the PR's base branch. The base
+    /// must already be in the tree, so a teammate's stack is pulled in bottom-up.
+    fn checkout_from_pr(
+        &mut self,
+        git_repo: &GitRepo,
+        repo: &str,
+        branch_name: &str,
+        autostash: bool,
+    ) -> Result<()> {
+        let repo_id = get_repo_identifier(git_repo)?;
+        let client = client_for(&repo_id)?;
+        let Some(pr) = client.find_pr_for_branch(&repo_id, branch_name)? else {
+            bail!(
+                "Branch {branch_name} doesn't exist locally and has no open PR on {repo}.",
+                branch_name = branch_name.red(),
+                repo = repo_id.full_name(),
+            );
+        };
+        let base = pr.base.ref_name;
+        if !self.branch_exists_in_tree(repo, &base) {
+            bail!(
+                "PR #{number} for {branch_name} targets {base}, which git-stack isn't tracking. \
+                 Run `git stack checkout --remote {base}` first.",
+                number = pr.number,
+                branch_name = branch_name.yellow(),
+                base = base.red(),
+            );
+        }
+
+        let remote_ref = format!("{DEFAULT_REMOTE}/{branch_name}");
+        run_git(&[
+            "fetch",
+            DEFAULT_REMOTE,
+            &format!("+refs/heads/{branch_name}:refs/remotes/{remote_ref}"),
+        ])
+        .with_context(|| format!("fetching {branch_name} from {DEFAULT_REMOTE}"))?;
+        guarded_checkout(git_repo, &remote_ref, branch_name, autostash, || {
+            run_git(&["checkout", "-b", branch_name, "--track", &remote_ref]).map(drop)
+        })?;
+
+        self.mount(git_repo, repo, branch_name, Some(base.clone()))?;
+        // `mount` records the base's current tip, but the PR branch was cut from wherever the base
+        // was back then; replay from their actual fork point.
+        let lkg_parent = git_repo.merge_base(&base, branch_name).ok();
+        if let Some(branch) = self.get_tree_branch_mut(repo, branch_name) {
+            branch.lkg_parent = lkg_parent;
+            branch.pr_number = Some(pr.number);
+        }
+        self.save_state()?;
+
+        println!(
+            "Branch {branch_name} checked out from PR #{number}.",
+            branch_name = branch_name.yellow(),
+            number = pr.number,
+        );
         Ok(())
     }
 
@@ -350,19 +1023,17 @@ impl State {
     }
 
     pub fn get_tree_branch<'a>(&'a self, repo: &str, branch_name: &str) -> Option<&'a Branch> {
-        self.repos
-            .get(repo)
-            .and_then(|r| find_branch_by_name(&r.tree, branch_name))
+        self.repos.get(repo).and_then(|r| r.tree.find(branch_name))
     }
 
-    fn get_tree_branch_mut<'a>(
+    pub(crate) fn get_tree_branch_mut<'a>(
         &'a mut self,
         repo: &str,
         branch_name: &str,
     ) -> Option<&'a mut Branch> {
         self.repos
             .get_mut(repo)
-            .and_then(|r| find_branch_by_name_mut(&mut r.tree, branch_name))
+            .and_then(|r| r.tree.find_mut(branch_name))
     }
 
     pub(crate) fn plan_restack(
@@ -416,6 +1087,30 @@ impl State {
             .collect::<Vec<_>>())
     }
 
+    /// Plan for `move`: the branch onto its parent, then each of its descendants onto theirs,
+    /// parents before children.
+    pub(crate) fn plan_restack_subtree(
+        &'_ self,
+        git_repo: &GitRepo,
+        repo: &str,
+        starting_branch: &str,
+    ) -> Result<Vec<RestackStep<'_>>> {
+        fn push_descendants<'a>(parent: &'a Branch, plan: &mut Vec<RestackStep<'a>>) {
+            for child in &parent.branches {
+                plan.push(RestackStep {
+                    parent: parent.name.clone(),
+                    branch: child,
+                });
+                push_descendants(child, plan);
+            }
+        }
+
+        let mut plan = self.plan_restack(git_repo, repo, starting_branch, false)?;
+        push_descendants(plan[0].branch, &mut plan);
+        Ok(plan)
+    }
+
+    /// Remove `branch_name` from `repo`'s tree, re-stacking its children on its parent.
     pub(crate) fn delete_branch(&mut self, repo: &str, branch_name: &str) -> Result<()> {
         let Some(parent) = self
             .repos
@@ -424,7 +1119,21 @@ impl State {
         else {
             bail!("Branch {branch_name} not found in the git-stack tree.");
         };
-        parent.branches.retain(|branch| branch.name != branch_name);
+        // Children take the deleted branch's place under its parent, keeping their LKG parents.
+        let index = parent
+            .branches
+            .iter()
+            .position(|branch| branch.name == branch_name)
+            .expect("find_parent_of_branch_mut found it");
+        let removed = parent.branches.remove(index);
+        for child in &removed.branches {
+            println!(
+                "    Repointing '{}' → '{}'",
+                child.name.yellow(),
+                parent.name.green()
+            );
+        }
+        parent.branches.splice(index..index, removed.branches);
         println!(
             "Branch {branch_name} removed from git-stack tree.",
             branch_name = branch_name.yellow()
@@ -653,12 +1362,13 @@ impl State {
             std::env::set_current_dir(repo_path)?;
 
             // Open a GitRepo for this specific repository
-            let Ok(repo_git) = GitRepo::open(repo_path) else {
+            let Ok(mut repo_git) = GitRepo::open(repo_path) else {
                 println!("{}: {}", repo_path.yellow(), "failed to open repo".red());
                 std::env::set_current_dir(original_dir)?;
                 repos_to_remove.push(repo_path.clone());
                 continue;
             };
+            repo_git.set_trunk_override(self.trunk_override(repo_path));
 
             let repo_state = self.repos.get_mut(repo_path).unwrap();
             let mut removed_branches = Vec::new();
@@ -716,6 +1426,182 @@ impl State {
         Ok(())
     }
 
+    /// Keep `repo`'s entry findable when the repo moves. State is keyed by path, so each entry also
+    /// records the repo's identity (its `origin` remote). When there is no entry for `repo` but
+    /// exactly one entry with the same identity sits at a path that is no longer a git checkout,
+    /// that entry is moved to `repo`. Failing that, an entry with no identity (one that moved
+    /// before its identity was ever recorded) is moved when it is the only such orphan whose
+    /// branches all exist here, locally or on `origin`. A second clone still present at its old
+    /// path keeps its own entry. Repos without an `origin` remote are tracked by path alone.
+    /// Returns whether anything changed, i.e. whether the state needs saving.
+    pub(crate) fn relocate_moved_repo(&mut self, git_repo: &GitRepo, repo: &str) -> bool {
+        let Some(identity) = repo_identity(git_repo) else {
+            return false;
+        };
+
+        if let Some(repo_state) = self.repos.get_mut(repo) {
+            if repo_state.identity.as_deref() == Some(identity.as_str()) {
+                return false;
+            }
+            repo_state.identity = Some(identity);
+            return true;
+        }
+
+        let orphans = || {
+            self.repos
+                .iter()
+                .filter(|(path, _)| !Path::new(path).join(".git").exists())
+        };
+        let mut moved_from: Vec<&String> = orphans()
+            .filter(|(_, repo_state)| repo_state.identity.as_deref() == Some(identity.as_str()))
+            .map(|(path, _)| path)
+            .collect();
+        if moved_from.is_empty() {
+            moved_from = orphans()
+                .filter(|(_, repo_state)| {
+                    repo_state.identity.is_none()
+                        && !repo_state.tree.branches.is_empty()
+                        && repo_state.tree.iter().skip(1).all(|branch| {
+                            git_repo.branch_exists(&branch.name)
+                                || git_repo.remote_branch_exists(DEFAULT_REMOTE, &branch.name)
+                        })
+                })
+                .map(|(path, _)| path)
+                .collect();
+        }
+        let [old_path] = moved_from.as_slice() else {
+            return false;
+        };
+        let old_path = old_path.to_string();
+        let mut repo_state = self
+            .repos
+            .remove(&old_path)
+            .expect("moved_from lists existing entries");
+        // On stderr: this runs before every command, including ones whose stdout is data.
+        eprintln!(
+            "Moving the stack for {} from {old_path} (no longer a git checkout) to {repo}.",
+            identity.yellow()
+        );
+        repo_state.identity = Some(identity);
+        self.repos.insert(repo.to_string(), repo_state);
+        true
+    }
+
+    /// The `--trunk` override recorded for `repo`, if any.
+    pub fn trunk_override(&self, repo: &str) -> Option<String> {
+        self.repos.get(repo).and_then(|r| r.trunk.clone())
+    }
+
+    /// Record `trunk` as `repo`'s trunk branch (`--trunk`). The tree's root is the trunk, so an
+    /// existing tree rooted at a different branch (usually a wrong `origin/HEAD` guess) is
+    /// re-rooted on `trunk`, keeping its stacks.
+    pub fn set_trunk_override(&mut self, repo: &str, trunk: &str) -> Result<()> {
+        let repo_state = self
+            .repos
+            .entry(repo.to_string())
+            .or_insert_with(|| RepoState::new(Branch::new(trunk.to_string(), None)));
+        if repo_state.trunk.as_deref() == Some(trunk) && repo_state.tree.name == trunk {
+            return Ok(());
+        }
+        if repo_state.tree.name != trunk {
+            println!(
+                "Re-rooting the stack tree from {old} to {trunk}.",
+                old = repo_state.tree.name.yellow(),
+                trunk = trunk.yellow()
+            );
+            repo_state.tree.name = trunk.to_string();
+        }
+        repo_state.trunk = Some(trunk.to_string());
+        self.save_state()
+    }
+
+    /// `git stack init`: start tracking `repo` with its trunk as the root of the tree, optionally
+    /// recording the stack method for branches added later, how `land` merges PRs, and the prefix
+    /// for new branch names (an empty prefix clears it). Safe to run again.
+    pub(crate) fn init(
+        &mut self,
+        git_repo: &GitRepo,
+        repo: &str,
+        stack_method: Option<StackMethod>,
+        merge_method: Option<MergeMethod>,
+        branch_prefix: Option<String>,
+    ) -> Result<()> {
+        if let Some(prefix) = branch_prefix.as_deref().filter(|prefix| !prefix.is_empty()) {
+            // The prefix has to work in front of any valid name.
+            branch_name::validate(&format!("{prefix}x"))
+                .with_context(|| format!("Invalid branch prefix '{prefix}'"))?;
+        }
+        let already_tracked = self.repos.contains_key(repo);
+        let Some(trunk) = self.ensure_trunk(git_repo, repo) else {
+            bail!(
+                "Could not determine the trunk branch for {repo}. Pass `--trunk <branch>` (e.g. \
+                 `git stack init --trunk main`), or run `git remote set-head {DEFAULT_REMOTE} -a`."
+            );
+        };
+        let repo_state = self
+            .repos
+            .get_mut(repo)
+            .expect("ensure_trunk added the repo");
+        if let Some(stack_method) = stack_method {
+            repo_state.default_stack_method = Some(stack_method);
+        }
+        if let Some(merge_method) = merge_method {
+            repo_state.merge_method = Some(merge_method);
+        }
+        if let Some(prefix) = branch_prefix {
+            repo_state.branch_prefix = Some(prefix).filter(|prefix| !prefix.is_empty());
+        }
+        let stack_method = repo_state.default_stack_method.unwrap_or_default();
+        let merge_method = repo_state.merge_method.unwrap_or_default();
+        let branch_prefix = repo_state.branch_prefix.clone();
+        self.save_state()?;
+
+        if already_tracked {
+            println!(
+                "Already tracking {repo}, with trunk {trunk}.",
+                trunk = trunk.main_branch.yellow()
+            );
+        } else {
+            println!(
+                "Tracking {repo}, with trunk {trunk}.",
+                trunk = trunk.main_branch.yellow()
+            );
+        }
+        let stack_method = match stack_method {
+            StackMethod::ApplyMerge => "apply_merge",
+            StackMethod::Merge => "merge",
+        };
+        println!("New branches will use the {stack_method} stack method.");
+        println!("`git stack land` will {merge_method} PRs.");
+        if let Some(prefix) = branch_prefix {
+            println!(
+                "New branches from `git stack checkout` will be named {}.",
+                format!("{prefix}<name>").yellow()
+            );
+        }
+        println!(
+            "Next: `git stack checkout <name>` creates a branch on top of the current one, and \
+             `git stack mount [parent]` tracks an existing branch."
+        );
+        Ok(())
+    }
+
+    /// How `land` merges PRs in `repo` when no `--method` is given.
+    pub(crate) fn merge_method(&self, repo: &str) -> MergeMethod {
+        self.repos
+            .get(repo)
+            .and_then(|r| r.merge_method)
+            .unwrap_or_default()
+    }
+
+    /// The stack method for a branch newly added to `repo`'s tree.
+    fn new_branch_stack_method(&self, repo: &str) -> StackMethod {
+        self.repos
+            .get(repo)
+            .and_then(|r| r.default_stack_method)
+            .unwrap_or_default()
+    }
+
     pub(crate) fn ensure_trunk(&mut self, git_repo: &GitRepo, repo: &str) -> Option<GitTrunk> {
         let trunk = git_trunk(git_repo)?;
         // The branch might not exist in git, let's create it, and add it to the tree.
@@ -743,6 +1629,53 @@ impl State {
         )
     }
 
+    /// `mount --onto-remote`: mount `branch_name` on the base branch of its open PR, adding the base
+    /// to the tree (on trunk) if git-stack isn't tracking it yet. Without an open PR, the branch
+    /// is mounted on trunk.
+    pub(crate) fn mount_onto_remote(
+        &mut self,
+        git_repo: &GitRepo,
+        repo: &str,
+        branch_name: &str,
+    ) -> Result<()> {
+        let repo_id = get_repo_identifier(git_repo)?;
+        let client = client_for(&repo_id)?;
+        let Some(pr) = client.find_pr_for_branch(&repo_id, branch_name)? else {
+            println!(
+                "Branch {branch_name} has no open PR; mounting it on trunk.",
+                branch_name = branch_name.yellow()
+            );
+            return self.mount(git_repo, repo, branch_name, None);
+        };
+        let base = pr.base.ref_name;
+        println!(
+            "PR #{number} for {branch_name} targets {base}.",
+            number = pr.number,
+            branch_name = branch_name.yellow(),
+            base = base.yellow(),
+        );
+
+        let trunk = self.ensure_trunk(git_repo, repo);
+        let base_is_trunk = trunk.is_some_and(|t| t.main_branch == base);
+        if !base_is_trunk && !self.branch_exists_in_tree(repo, &base) {
+            self.mount(git_repo, repo, &base, None)?;
+            // `mount` records trunk's current tip; replay the base from where it forked instead.
+            let trunk_ref = self
+                .get_parent_branch_of(repo, &base)
+                .map(|parent| parent.name.clone());
+            let lkg_parent = trunk_ref.and_then(|t| git_repo.merge_base(&t, &base).ok());
+            if let Some(node) = self.get_tree_branch_mut(repo, &base) {
+                node.lkg_parent = lkg_parent;
+            }
+        }
+
+        self.mount(git_repo, repo, branch_name, Some(base))?;
+        if let Some(branch) = self.get_tree_branch_mut(repo, branch_name) {
+            branch.pr_number = Some(pr.number);
+        }
+        self.save_state()
+    }
+
     /// Move an existing subtree for a topology-only change without changing the branch's replay
     /// boundary. Unlike an ordinary mount, this preserves `lkg_parent` verbatim, including `None`.
     pub(crate) fn reparent_preserving_lkg(
@@ -822,7 +1755,10 @@ impl State {
             None if matches!(lkg_parent_policy, LkgParentPolicy::Preserve) => {
                 bail!("Branch {branch_name} not found in the git-stack tree.")
             }
-            None => Branch::new(branch_name.to_string(), git_repo.sha(&parent_branch).ok()),
+            None => Branch {
+                stack_method: self.new_branch_stack_method(repo),
+                ..Branch::new(branch_name.to_string(), git_repo.sha(&parent_branch).ok())
+            },
         };
 
         if matches!(lkg_parent_policy, LkgParentPolicy::RecordSelectedParent) {
@@ -845,6 +1781,35 @@ impl State {
         self.save_state()?;
         Ok(())
     }
+    /// Insert `new_branch` between `branch_name` and its parent, as `git stack split` does. The new
+    /// node replays from `base` (the original branch's old boundary) and inherits its stack
+    /// method; the original branch is re-mounted on it, which records the new branch's tip (the
+    /// split point) as its `lkg_parent`.
+    pub(crate) fn split_branch(
+        &mut self,
+        git_repo: &GitRepo,
+        repo: &str,
+        branch_name: &str,
+        new_branch: &str,
+        base: String,
+    ) -> Result<()> {
+        let Some(parent) = self.get_parent_branch_of(repo, branch_name) else {
+            bail!("Branch {branch_name} not found in the git-stack tree (or is its root).");
+        };
+        let parent = parent.name.clone();
+        let stack_method = self
+            .get_tree_branch(repo, branch_name)
+            .map(|b| b.stack_method)
+            .unwrap_or_default();
+
+        self.mount(git_repo, repo, new_branch, Some(parent))?;
+        if let Some(node) = self.get_tree_branch_mut(repo, new_branch) {
+            node.lkg_parent = Some(base);
+            node.stack_method = stack_method;
+        }
+        self.mount(git_repo, repo, branch_name, Some(new_branch.to_string()))
+    }
+
     pub fn get_parent_branch_of(&self, repo: &str, branch_name: &str) -> Option<&Branch> {
         self.repos
             .get(repo)
@@ -899,7 +1864,12 @@ impl State {
                     let tree_branch = self.get_tree_branch(repo, &branch).unwrap();
                     let valid_lkg_parent =
                         if let Some(lkg_parent) = tree_branch.lkg_parent.as_deref() {
-                            if git_repo.is_ancestor(lkg_parent, &branch_ref)? {
+                            // A SHA this clone doesn't have (gc'd, or from another clone) is just
+                            // as stale as one that's no longer an ancestor.
+                            if git_repo
+                                .is_ancestor(lkg_parent, &branch_ref)
+                                .unwrap_or(false)
+                            {
                                 parent_lkgs
                                     .insert(tree_branch.name.clone(), Some(lkg_parent.to_string()));
                                 Some(lkg_parent)
@@ -995,6 +1965,75 @@ impl State {
         Ok(())
     }
 
+    /// The refresh run at the start of every command: [`State::refresh_lkgs`], or
+    /// [`State::refresh_lkgs_scoped`] with `scope`, skipped (along with its save) when nothing it
+    /// reads has changed since the last time it ran, unless `force`.
+    pub(crate) fn refresh_lkgs_if_changed(
+        &mut self,
+        git_repo: &GitRepo,
+        repo: &str,
+        scope: Option<&HashSet<String>>,
+        force: bool,
+    ) -> Result<()> {
+        let fingerprint = self.lkg_fingerprint(git_repo, repo, scope);
+        let stored = self
+            .repos
+            .get(repo)
+            .and_then(|r| r.lkg_fingerprint.as_deref());
+        if !force && fingerprint.is_some() && fingerprint.as_deref() == stored {
+            tracing::debug!("No branch moved since the last LKG refresh; skipping it");
+            return Ok(());
+        }
+
+        let _bench = crate::stats::GitBenchmark::start("state:refresh-lkgs");
+        let parent_lkgs = self.compute_lkg_updates(git_repo, repo, scope)?;
+        let mut changed = self.apply_lkg_updates(repo, parent_lkgs)?;
+        // Taken again, since the refresh may have just changed some `lkg_parent`s.
+        let fingerprint = self.lkg_fingerprint(git_repo, repo, scope);
+        if let Some(repo_state) = self.repos.get_mut(repo)
+            && repo_state.lkg_fingerprint != fingerprint
+        {
+            repo_state.lkg_fingerprint = fingerprint;
+            changed = true;
+        }
+        if changed {
+            self.save_state()?;
+        }
+        Ok(())
+    }
+
+    /// A digest of everything the LKG refresh reads: the trunk, and for every tracked branch its
+    /// parent, the SHA it resolves to (local, else `origin/`), and its current `lkg_parent`, plus
+    /// the refresh's scope. `None` when there's no trunk, so nothing to refresh against.
+    fn lkg_fingerprint(
+        &self,
+        git_repo: &GitRepo,
+        repo: &str,
+        scope: Option<&HashSet<String>>,
+    ) -> Option<String> {
+        let trunk = git_trunk(git_repo)?;
+        let tree = self.get_tree(repo)?;
+        let mut input = format!("trunk {}\n", trunk.main_branch);
+        tree.walk(&mut |branch, _, parent| {
+            let sha = git_repo
+                .resolve_branch_ref(&branch.name)
+                .and_then(|branch_ref| git_repo.sha(&branch_ref).ok());
+            input.push_str(&format!(
+                "{} {} {} {}\n",
+                parent.unwrap_or("-"),
+                branch.name,
+                sha.as_deref().unwrap_or("-"),
+                branch.lkg_parent.as_deref().unwrap_or("-"),
+            ));
+        });
+        if let Some(scope) = scope {
+            let mut scope: Vec<&str> = scope.iter().map(String::as_str).collect();
+            scope.sort_unstable();
+            input.push_str(&format!("scope {}\n", scope.join(" ")));
+        }
+        Some(format!("{:x}", md5::compute(input)))
+    }
+
     /// Refresh `lkg_parent` for only the branches in `scope`. Out-of-scope branches keep their
     /// existing (possibly stale/missing) values; the consumers all tolerate that via merge-base
     /// fallbacks, and `refresh_lkg_for_branch` refreshes on demand right before consumption.
@@ -1100,27 +2139,268 @@ impl State {
         Ok(())
     }
 
+    /// `note --append`: add `text` as a new last line of the branch's note.
+    pub(crate) fn append_note(&mut self, repo: &str, branch: &str, text: &str) -> Result<()> {
+        let Some(branch) = self.get_tree_branch_mut(repo, branch) else {
+            bail!("Branch {branch} not found in the git-stack tree.");
+        };
+        branch.note = Some(appended_note(branch.note.as_deref(), text));
+        self.save_state()?;
+        Ok(())
+    }
+
+    /// `git stack mark`: set (or with `None`, clear) the branch's workflow mark.
+    pub(crate) fn set_mark(
+        &mut self,
+        repo: &str,
+        branch: &str,
+        mark: Option<BranchMark>,
+    ) -> Result<()> {
+        let Some(tree_branch) = self.get_tree_branch_mut(repo, branch) else {
+            bail!("Branch {branch} not found in the git-stack tree.");
+        };
+        tree_branch.mark = mark;
+        self.save_state()?;
+        match mark {
+            Some(mark) => println!("Marked {} as {mark}.", branch.yellow()),
+            None => println!("Cleared the mark on {}.", branch.yellow()),
+        }
+        Ok(())
+    }
+
     pub(crate) fn show_note(&self, repo: &str, branch: &str) -> Result<()> {
         let Some(branch) = self.get_tree_branch(repo, branch) else {
             bail!("Branch {branch} not found in the git-stack tree.");
         };
 
-        let note = branch
-            .note
-            .as_deref()
-            .unwrap_or(&format!(
-                "No note set for branch '{branch}'.",
-                branch = branch.name.as_str().yellow()
-            ))
-            .to_string();
-        print!("{}{}", note, if !note.ends_with("\n") { "\n" } else { "" });
+        match branch.note.as_deref() {
+            Some(note) => print!("{}{}", note, if !note.ends_with('\n') { "\n" } else { "" }),
+            // `yellow()` follows the process-wide color switch set from `--no-color`/`NO_COLOR`.
+            None => println!(
+                "No note set for branch '{}'.",
+                branch.name.as_str().yellow()
+            ),
+        }
+        Ok(())
+    }
+
+    pub(crate) fn edit_state(&self) -> Result<()> {
+        let _ = launch_editor(&get_xdg_path()?)?;
         Ok(())
     }
 
-    pub(crate) fn edit_state(&self) -> Result<()> {
-        // TODO: edit only the state for the current repo.
-        let _ = launch_editor(&get_xdg_path()?)?;
-        Ok(())
+    /// `git stack edit --repo`: edit only `repo`'s tree, as YAML in a temp file, and put it back
+    /// in the state if it parses and validates. Otherwise nothing changes, and the temp file is
+    /// kept so the edits aren't lost.
+    pub(crate) fn edit_repo_tree(&mut self, repo: &str) -> Result<()> {
+        let Some(tree) = self.get_tree(repo) else {
+            bail!("No stack configured for this repository. Run `git stack init` to start one.");
+        };
+        let original = serde_yaml::to_string(tree)?;
+        let mut temp_file = tempfile::Builder::new()
+            .prefix("git-stack-tree-")
+            .suffix(".yaml")
+            .tempfile()?;
+        write!(
+            temp_file,
+            "# The git-stack tree for {repo}. Other repos' stacks are unaffected.\n{original}"
+        )?;
+
+        if !launch_editor(temp_file.path())?.success() {
+            println!("The editor failed; the stack tree was not changed.");
+            return Ok(());
+        }
+        let edited = fs::read_to_string(temp_file.path())?;
+        let result = serde_yaml::from_str::<Branch>(&edited)
+            .map_err(anyhow::Error::from)
+            .and_then(|tree| {
+                let problems = tree_problems(repo, &tree);
+                ensure!(problems.is_empty(), "{}", problems.join("\n"));
+                Ok(tree)
+            });
+        let tree = match result {
+            Ok(tree) => tree,
+            Err(e) => {
+                let (_, path) = temp_file.keep()?;
+                bail!(
+                    "The edited stack tree is not valid, so the state was not changed:\n{e}\nYour \
+                     edits are saved in {}.",
+                    path.display()
+                );
+            }
+        };
+        if serde_yaml::to_string(&tree)? == original {
+            println!("No changes.");
+            return Ok(());
+        }
+        self.repos.get_mut(repo).expect("checked above").tree = tree;
+        self.save_state()?;
+        println!("Saved the stack tree for {repo}.");
+        Ok(())
+    }
+
+    /// `git stack export`: write `repo`'s stack tree (names, stack methods, notes, LKG parents, PR
+    /// numbers) to `out`, or to stdout. JSON when `out` ends in `.json`, YAML otherwise.
+    pub(crate) fn export_tree(&self, repo: &str, out: Option<&Path>) -> Result<()> {
+        let tree = self
+            .get_tree(repo)
+            .ok_or_else(|| anyhow!("No stack tree found for repo {repo}"))?;
+        let Some(out) = out else {
+            print!("{}", serde_yaml::to_string(tree)?);
+            return Ok(());
+        };
+        let contents = if out.extension().is_some_and(|ext| ext == "json") {
+            serde_json::to_string_pretty(tree)? + "\n"
+        } else {
+            serde_yaml::to_string(tree)?
+        };
+        fs::write(out, contents).with_context(|| format!("writing {}", out.display()))?;
+        println!("Exported the stack tree for {repo} to {}.", out.display());
+        Ok(())
+    }
+
+    /// `git stack import`: make the tree in `file` (from `export`) `repo`'s stack tree. The root
+    /// is renamed to this repo's trunk, branches that exist neither locally nor on the remote are
+    /// dropped (their children move up), and LKG parents this repo doesn't have are cleared.
+    /// Replacing a tree that already has branches needs `force`.
+    pub(crate) fn import_tree(
+        &mut self,
+        git_repo: &GitRepo,
+        repo: &str,
+        file: &Path,
+        force: bool,
+    ) -> Result<()> {
+        let contents =
+            fs::read_to_string(file).with_context(|| format!("reading {}", file.display()))?;
+        // YAML is a superset of JSON, so this reads either export format.
+        let mut tree: Branch = serde_yaml::from_str(&contents)
+            .with_context(|| format!("parsing stack tree from {}", file.display()))?;
+
+        let problems = tree_problems(repo, &tree);
+        if !problems.is_empty() {
+            bail!(
+                "{} is not a valid stack tree:\n  {}",
+                file.display(),
+                problems.join("\n  ")
+            );
+        }
+
+        if let Some(trunk) = self.ensure_trunk(git_repo, repo)
+            && tree.name != trunk.main_branch
+        {
+            println!(
+                "Rooting the imported tree at {trunk} instead of {old}.",
+                trunk = trunk.main_branch.yellow(),
+                old = tree.name.yellow()
+            );
+            tree.name = trunk.main_branch;
+        }
+        if let Some(existing) = self.get_tree(repo)
+            && !existing.branches.is_empty()
+            && !force
+        {
+            bail!("{repo} already has a stack tree. Pass --force to replace it.");
+        }
+
+        let mut dropped = Vec::new();
+        let mut remounted = Vec::new();
+        cleanup_tree_recursive(git_repo, &mut tree, &mut dropped, &mut remounted);
+        for name in &dropped {
+            println!(
+                "Skipping {name}: no such branch locally or on {DEFAULT_REMOTE}.",
+                name = name.yellow()
+            );
+        }
+        clear_unknown_lkg_parents(git_repo, &mut tree);
+
+        let mut names = Vec::new();
+        collect_all_branches(&tree, &mut names);
+        match self.repos.get_mut(repo) {
+            Some(repo_state) => repo_state.tree = tree,
+            None => {
+                self.repos.insert(repo.to_string(), RepoState::new(tree));
+            }
+        }
+        self.save_state()?;
+        println!(
+            "Imported {} branches for {repo} from {}.",
+            names.len() - 1,
+            file.display()
+        );
+        Ok(())
+    }
+
+    /// `git stack tidy`: one maintenance pass over `repo`'s tree. Drops branches that no longer
+    /// exist (also those left only on the remote with `prune_remote_only`), forgets LKG parents
+    /// that are unknown or no longer ancestors of their branch, refreshes LKGs, and sorts children
+    /// by name so the state file is written in a canonical order. Nothing is saved with `dry_run`.
+    pub(crate) fn tidy(
+        &mut self,
+        git_repo: &GitRepo,
+        repo: &str,
+        prune_remote_only: bool,
+        dry_run: bool,
+    ) -> Result<()> {
+        let Some(repo_state) = self.repos.get_mut(repo) else {
+            println!("No stack tree found for repo {}", repo.yellow());
+            return Ok(());
+        };
+        let before = serde_yaml::to_string(&repo_state.tree)?;
+
+        let mut missing = HashSet::new();
+        collect_missing_branches(git_repo, &repo_state.tree, prune_remote_only, &mut missing);
+        missing.remove(&repo_state.tree.name);
+        let (removed, remounted) = apply_prune(&mut repo_state.tree, &missing);
+
+        let mut cleared = Vec::new();
+        clear_stale_lkg_parents(git_repo, &mut repo_state.tree, &mut cleared);
+
+        let parent_lkgs = self.compute_lkg_updates(git_repo, repo, None)?;
+        let mut refreshed: Vec<&String> = parent_lkgs
+            .iter()
+            .filter(|(branch, lkg_parent)| {
+                self.get_tree_branch(repo, branch)
+                    .is_some_and(|node| node.lkg_parent != **lkg_parent)
+            })
+            .map(|(branch, _)| branch)
+            .collect();
+        refreshed.sort();
+        let refreshed: Vec<String> = refreshed.into_iter().cloned().collect();
+        self.apply_lkg_updates(repo, parent_lkgs)?;
+
+        let tree = self
+            .get_tree_mut(repo)
+            .ok_or_else(|| anyhow!("No stack tree found for repo {repo}"))?;
+        sort_children(tree);
+        let after = serde_yaml::to_string(tree)?;
+        if before == after {
+            println!("The stack tree for {} is already tidy.", repo.yellow());
+            return Ok(());
+        }
+
+        let prefix = if dry_run { "[dry-run] " } else { "" };
+        println!("{prefix}Tidy summary for {}:", repo.yellow());
+        for name in &removed {
+            println!("  Removed {} (branch no longer exists)", name.red());
+        }
+        for (name, new_parent) in &remounted {
+            println!("  Remounted {} onto {}", name.yellow(), new_parent.green());
+        }
+        for name in &cleared {
+            println!("  Cleared the stale LKG parent of {}", name.yellow());
+        }
+        for name in &refreshed {
+            println!("  Refreshed the LKG parent of {}", name.yellow());
+        }
+        if removed.is_empty() && remounted.is_empty() && cleared.is_empty() && refreshed.is_empty()
+        {
+            println!("  Sorted branches by name");
+        }
+        if dry_run {
+            println!("Run without --dry-run to apply these changes.");
+            return Ok(());
+        }
+        self.save_state()
     }
 
     pub(crate) fn edit_github_config(&self) -> Result<()> {
@@ -1199,7 +2479,7 @@ impl State {
             let mut max_depth = 0;
 
             for ancestor in &ancestor_branches {
-                if let Some(depth) = get_branch_depth(tree, ancestor, 0)
+                if let Some(depth) = get_branch_depth(tree, ancestor)
                     && depth >= max_depth
                 {
                     max_depth = depth;
@@ -1225,6 +2505,63 @@ impl State {
     }
 }
 
+/// The identity recorded in [`RepoState::identity`]: `host/owner/repo` of the `origin` remote, or
+/// `None` without one.
+fn repo_identity(git_repo: &GitRepo) -> Option<String> {
+    let repo_id = get_repo_identifier(git_repo).ok()?;
+    Some(format!("{}/{}", repo_id.host, repo_id.full_name()))
+}
+
+/// [`State::validate`]'s checks for one repo's tree.
+fn tree_problems(repo: &str, tree: &Branch) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    validate_branch(repo, tree, &mut Vec::new(), &mut seen, &mut problems);
+    let mut duplicates: Vec<(&str, usize)> =
+        seen.into_iter().filter(|(_, count)| *count > 1).collect();
+    duplicates.sort();
+    for (name, count) in duplicates {
+        problems.push(format!(
+            "{repo}: branch '{name}' appears {count} times in the tree"
+        ));
+    }
+    problems
+}
+
+/// Recursive helper for `State::validate`: count every name into `seen` and report empty names
+/// and names that repeat one of their own ancestors (a cycle once resolved by name).
+fn validate_branch<'a>(
+    repo: &str,
+    branch: &'a Branch,
+    ancestors: &mut Vec<&'a str>,
+    seen: &mut HashMap<&'a str, usize>,
+    problems: &mut Vec<String>,
+) {
+    if branch.name.trim().is_empty() {
+        match ancestors.last() {
+            Some(parent) => {
+                problems.push(format!("{repo}: a child of '{parent}' has an empty name"))
+            }
+            None => problems.push(format!("{repo}: the root branch has an empty name")),
+        }
+    } else {
+        if ancestors.contains(&branch.name.as_str()) {
+            problems.push(format!(
+                "{repo}: branch '{}' is nested under itself ({} -> {})",
+                branch.name,
+                ancestors.join(" -> "),
+                branch.name
+            ));
+        }
+        *seen.entry(branch.name.as_str()).or_default() += 1;
+    }
+    ancestors.push(&branch.name);
+    for child in &branch.branches {
+        validate_branch(repo, child, ancestors, seen, problems);
+    }
+    ancestors.pop();
+}
+
 fn get_path<'a>(branch: &'a Branch, target_branch: &str, path: &mut Vec<&'a Branch>) -> bool {
     if branch.name == target_branch {
         path.insert(0, branch);
@@ -1245,14 +2582,6 @@ pub(crate) struct RestackStep<'a> {
     pub(crate) branch: &'a Branch,
 }
 
-fn find_branch_by_name<'a>(tree: &'a Branch, name: &str) -> Option<&'a Branch> {
-    find_branch(tree, &|branch| branch.name == name)
-}
-
-fn find_branch_by_name_mut<'a>(tree: &'a mut Branch, name: &str) -> Option<&'a mut Branch> {
-    find_branch_mut(tree, &|branch| branch.name == name)
-}
-
 fn find_parent_of_branch_mut<'a>(tree: &'a mut Branch, name: &str) -> Option<&'a mut Branch> {
     find_branch_mut(tree, &|branch| {
         branch.branches.iter().any(|branch| branch.name == name)
@@ -1269,17 +2598,7 @@ fn find_branch<'a, F>(tree: &'a Branch, pred: &F) -> Option<&'a Branch>
 where
     F: Fn(&Branch) -> bool,
 {
-    if pred(tree) {
-        Some(tree)
-    } else {
-        for child_branch in tree.branches.iter() {
-            let result = find_branch(child_branch, pred);
-            if result.is_some() {
-                return result;
-            }
-        }
-        None
-    }
+    tree.iter().find(|branch| pred(branch))
 }
 
 fn find_branch_mut<'a, F>(tree: &'a mut Branch, pred: &F) -> Option<&'a mut Branch>
@@ -1300,17 +2619,8 @@ where
 }
 
 // Linear walk through the tree to find the branch.
-fn is_branch_mentioned_in_tree(branch_name: &str, branch: &Branch) -> bool {
-    if branch.name == branch_name {
-        return true;
-    }
-
-    for child_branch in &branch.branches {
-        if is_branch_mentioned_in_tree(branch_name, child_branch) {
-            return true;
-        }
-    }
-    false
+pub(crate) fn is_branch_mentioned_in_tree(branch_name: &str, branch: &Branch) -> bool {
+    branch.iter().any(|branch| branch.name == branch_name)
 }
 
 /// Recursively cleans up missing branches from the tree.
@@ -1331,8 +2641,9 @@ fn cleanup_tree_recursive(
     let mut indices_to_remove = Vec::new();
 
     for (index, child) in branch.branches.iter().enumerate() {
-        let remote_ref = format!("origin/{}", child.name);
-        if !git_branch_exists(git_repo, &child.name) && !git_repo.ref_exists(&remote_ref) {
+        if !git_branch_exists(git_repo, &child.name)
+            && !git_repo.remote_branch_exists(DEFAULT_REMOTE, &child.name)
+        {
             // This branch doesn't exist locally or on remote, mark it for removal
             removed_branches.push(child.name.clone());
 
@@ -1355,6 +2666,60 @@ fn cleanup_tree_recursive(
     branch.branches.extend(branches_to_adopt);
 }
 
+/// Forget LKG parents that don't resolve in this clone (e.g. an imported tree whose commits were
+/// never fetched here), so restack falls back to replaying from the merge base.
+fn clear_unknown_lkg_parents(git_repo: &GitRepo, tree: &mut Branch) {
+    tree.walk_mut(&mut |branch, _, _| {
+        if branch
+            .lkg_parent
+            .as_deref()
+            .is_some_and(|sha| !git_repo.ref_exists(sha))
+        {
+            branch.lkg_parent = None;
+        }
+    });
+}
+
+/// Collect the names of branches in the tree that don't exist locally, and — unless
+/// `include_remote_only` — aren't on the remote either.
+fn collect_missing_branches(
+    git_repo: &GitRepo,
+    tree: &Branch,
+    include_remote_only: bool,
+    missing: &mut HashSet<String>,
+) {
+    missing.extend(
+        tree.iter()
+            .filter(|branch| {
+                !git_branch_exists(git_repo, &branch.name)
+                    && (include_remote_only
+                        || !git_repo.remote_branch_exists(DEFAULT_REMOTE, &branch.name))
+            })
+            .map(|branch| branch.name.clone()),
+    );
+}
+
+/// Forget LKG parents that don't resolve, or that are no longer ancestors of their branch (local
+/// or, failing that, remote-tracking), recording each branch whose value was cleared.
+fn clear_stale_lkg_parents(git_repo: &GitRepo, tree: &mut Branch, cleared: &mut Vec<String>) {
+    tree.walk_mut(&mut |branch, _, _| {
+        if let Some(sha) = branch.lkg_parent.as_deref() {
+            let branch_ref = git_repo
+                .resolve_branch_ref(&branch.name)
+                .unwrap_or_else(|| branch.name.clone());
+            if !git_repo.is_ancestor(sha, &branch_ref).unwrap_or(false) {
+                branch.lkg_parent = None;
+                cleared.push(branch.name.clone());
+            }
+        }
+    });
+}
+
+/// Sort every node's children by name, giving the state file a canonical order.
+fn sort_children(tree: &mut Branch) {
+    tree.walk_mut(&mut |branch, _, _| branch.branches.sort_by(|a, b| a.name.cmp(&b.name)));
+}
+
 /// Splice every branch named in `to_remove` out of the tree, adopting each removed node's kept
 /// children into its parent (mirroring `cleanup_tree_recursive`'s remount behavior, but keyed on
 /// an explicit name set rather than git existence). Bottom-up so a removed branch whose parent is
@@ -1418,6 +2783,43 @@ fn confirm_prune() -> bool {
     matches!(input.trim().to_lowercase().as_str(), "y" | "yes")
 }
 
+/// Version 0 → 1: turn each repo stored as a list of stacks (branch names from trunk up) into a
+/// tree rooted at the trunk. Branches shared by several stacks are merged; the first stack to name
+/// a branch decides its parent.
+fn migrate_legacy_stacks(repos: &mut serde_yaml::Mapping) -> Result<()> {
+    for (repo, value) in repos.iter_mut() {
+        if !value.is_sequence() {
+            continue;
+        }
+        let repo = repo.as_str().unwrap_or_default();
+        let stacks: Vec<Vec<String>> = serde_yaml::from_value(value.clone())
+            .with_context(|| format!("reading the legacy stacks for {repo}"))?;
+        let Some(root) = stacks.iter().find_map(|stack| stack.first()).cloned() else {
+            bail!("{repo}: the legacy stacks name no branches");
+        };
+        let mut tree = Branch::new(root.clone(), None);
+        for stack in stacks {
+            let mut parent = root.clone();
+            let names = match stack.first() {
+                Some(first) if *first == root => &stack[1..],
+                _ => &stack[..],
+            };
+            for name in names {
+                if tree.find(name).is_none() {
+                    tree.find_mut(&parent)
+                        .expect("parent was added to the tree")
+                        .branches
+                        .push(Branch::new(name.clone(), None));
+                }
+                parent = name.clone();
+            }
+        }
+        tracing::info!("Migrated the legacy stacks for {repo} to a stack tree.");
+        *value = serde_yaml::to_value(RepoState::new(tree))?;
+    }
+    Ok(())
+}
+
 fn find_stack_with_branch<'a>(
     stacks: &'a mut [Vec<String>],
     current_branch: &str,
@@ -1434,24 +2836,55 @@ fn find_stack_with_branch<'a>(
 }
 
 /// Collect all branch names from the tree recursively.
-fn collect_all_branches(branch: &Branch, branches: &mut Vec<String>) {
-    branches.push(branch.name.clone());
-    for child in &branch.branches {
-        collect_all_branches(child, branches);
-    }
+fn collect_all_branches(tree: &Branch, branches: &mut Vec<String>) {
+    branches.extend(tree.iter().map(|branch| branch.name.clone()));
 }
 
 /// Calculate the depth of a branch in the tree. Returns None if the branch is not found.
-fn get_branch_depth(tree: &Branch, target: &str, current_depth: usize) -> Option<usize> {
-    if tree.name == target {
-        return Some(current_depth);
-    }
-    for child in &tree.branches {
-        if let Some(depth) = get_branch_depth(child, target, current_depth + 1) {
-            return Some(depth);
+fn get_branch_depth(tree: &Branch, target: &str) -> Option<usize> {
+    let mut found = None;
+    tree.walk(&mut |branch, depth, _| {
+        if found.is_none() && branch.name == target {
+            found = Some(depth);
+        }
+    });
+    found
+}
+
+fn utc_now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+fn parse_utc(timestamp: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|t| t.with_timezone(&chrono::Utc))
+}
+
+/// Delete all but the newest `keep` state backups in `dir`. The timestamp in their names sorts
+/// oldest first.
+fn prune_backups(dir: &Path, keep: usize) {
+    let mut backups: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("state.yaml.") && name.ends_with(".bak"))
+            })
+            .collect(),
+        Err(e) => {
+            tracing::warn!("Failed to list state backups in {}: {e}", dir.display());
+            return;
+        }
+    };
+    backups.sort();
+    let excess = backups.len().saturating_sub(keep);
+    for path in &backups[..excess] {
+        if let Err(e) = fs::remove_file(path) {
+            tracing::warn!("Failed to delete old state backup {}: {e}", path.display());
         }
     }
-    None
 }
 
 fn get_xdg_path() -> anyhow::Result<PathBuf> {
@@ -1463,10 +2896,20 @@ fn get_xdg_path() -> anyhow::Result<PathBuf> {
 
 /// Launch the user's `$EDITOR` (falling back to `vi`) on `path`.
 fn launch_editor(path: &Path) -> Result<std::process::ExitStatus> {
+    // Don't hold up other git-stack processes for as long as the editor stays open.
+    unlock_state();
     let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
     Ok(Command::new(editor).arg(path).status()?)
 }
 
+/// `note` with `text` added as its last line; an empty or missing note becomes just `text`.
+fn appended_note(note: Option<&str>, text: &str) -> String {
+    match note.map(str::trim_end).filter(|note| !note.is_empty()) {
+        Some(note) => format!("{note}\n{text}"),
+        None => text.to_string(),
+    }
+}
+
 fn edit_until_valid(
     path: &Path,
     mut edit: impl FnMut(&Path) -> Result<()>,
@@ -1495,6 +2938,7 @@ mod tests {
     #[test]
     fn test_state_write() {
         let state = State {
+            version: STATE_VERSION,
             repos: vec![(
                 "/tmp/foo".to_string(),
                 RepoState::new(Branch {
@@ -1503,6 +2947,7 @@ mod tests {
                     note: None,
                     lkg_parent: None,
                     pr_number: None,
+                    mark: None,
                     branches: vec![],
                 }),
             )]
@@ -1512,9 +2957,294 @@ mod tests {
         let serialized = serde_yaml::to_string(&state).unwrap();
         assert_eq!(
             serialized,
-            "/tmp/foo:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches: []\n",
+            "version: 2\n/tmp/foo:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches: []\n",
+        );
+    }
+
+    #[test]
+    fn pruning_keeps_the_newest_state_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        for time in [
+            "20260101T000000.000Z",
+            "20260102T000000.000Z",
+            "20260103T000000.000Z",
+        ] {
+            fs::write(dir.path().join(format!("state.yaml.{time}.bak")), "").unwrap();
+        }
+        fs::write(dir.path().join("state.yaml"), "").unwrap();
+
+        prune_backups(dir.path(), 2);
+
+        let mut left: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        left.sort();
+        assert_eq!(
+            left,
+            [
+                "state.yaml",
+                "state.yaml.20260102T000000.000Z.bak",
+                "state.yaml.20260103T000000.000Z.bak"
+            ]
+        );
+    }
+
+    #[test]
+    fn branch_iter_and_find_cover_the_whole_subtree() {
+        let mut a = Branch::new("a".to_string(), None);
+        a.branches.push(Branch::new("b".to_string(), None));
+        a.branches.push(Branch::new("c".to_string(), None));
+        let mut main_branch = Branch::new("main".to_string(), None);
+        main_branch.branches.push(a);
+        main_branch
+            .branches
+            .push(Branch::new("d".to_string(), None));
+
+        let names: Vec<&str> = main_branch.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, ["main", "a", "b", "c", "d"]);
+        assert_eq!(main_branch.find("c").map(|b| b.name.as_str()), Some("c"));
+        assert!(main_branch.branches[1].find("b").is_none());
+
+        main_branch.find_mut("b").unwrap().note = Some("found".to_string());
+        assert_eq!(
+            main_branch.branches[0].branches[0].note.as_deref(),
+            Some("found")
+        );
+    }
+
+    /// main -> {a -> {c, b -> {e}}, d}, with `a`'s children out of name order.
+    fn multi_level_tree() -> Branch {
+        let mut b = Branch::new("b".to_string(), None);
+        b.branches.push(Branch::new("e".to_string(), None));
+        let mut a = Branch::new("a".to_string(), None);
+        a.branches.push(Branch::new("c".to_string(), None));
+        a.branches.push(b);
+        let mut main_branch = Branch::new("main".to_string(), None);
+        main_branch.branches.push(a);
+        main_branch
+            .branches
+            .push(Branch::new("d".to_string(), None));
+        main_branch
+    }
+
+    #[test]
+    fn walk_reports_depth_and_parent() {
+        let tree = multi_level_tree();
+        let mut visited = Vec::new();
+        tree.walk(&mut |branch, depth, parent| {
+            visited.push((branch.name.clone(), depth, parent.map(str::to_string)));
+        });
+        let expected = [
+            ("main", 0, None),
+            ("a", 1, Some("main")),
+            ("c", 2, Some("a")),
+            ("b", 2, Some("a")),
+            ("e", 3, Some("b")),
+            ("d", 1, Some("main")),
+        ]
+        .map(|(name, depth, parent)| (name.to_string(), depth, parent.map(str::to_string)));
+        assert_eq!(visited, expected);
+
+        // A subtree walks from depth 0 with no parent.
+        let mut subtree = Vec::new();
+        tree.branches[0].branches[1].walk(&mut |branch, depth, parent| {
+            subtree.push((branch.name.clone(), depth, parent.is_some()));
+        });
+        assert_eq!(
+            subtree,
+            [("b".to_string(), 0, false), ("e".to_string(), 1, true)]
+        );
+
+        assert_eq!(get_branch_depth(&tree, "e"), Some(3));
+        assert_eq!(get_branch_depth(&tree, "missing"), None);
+        assert!(is_branch_mentioned_in_tree("e", &tree));
+        assert!(!is_branch_mentioned_in_tree("main", &tree.branches[1]));
+    }
+
+    #[test]
+    fn walk_mut_visits_children_after_their_parent_changes_them() {
+        let mut tree = multi_level_tree();
+        let mut order = Vec::new();
+        tree.walk_mut(&mut |branch, depth, parent| {
+            branch.branches.sort_by(|a, b| a.name.cmp(&b.name));
+            if let Some(parent) = parent {
+                branch.note = Some(format!("{parent}@{depth}"));
+            }
+            order.push(branch.name.clone());
+        });
+        assert_eq!(order, ["main", "a", "b", "e", "c", "d"]);
+        assert_eq!(tree.find("e").unwrap().note.as_deref(), Some("b@3"));
+        assert_eq!(tree.note, None);
+    }
+
+    #[test]
+    fn parse_migrates_legacy_stacks_to_a_tree() {
+        let state = State::parse(
+            "/tmp/old:\n- [main, a, b]\n- [main, a, c]\n- [d]\n/tmp/new:\n  name: main\n  stack_method: merge\n  lkg_parent: null\n  branches: []\n",
+        )
+        .unwrap();
+        assert_eq!(state.version, STATE_VERSION);
+
+        let old = state.get_tree("/tmp/old").unwrap();
+        assert_eq!(old.name, "main");
+        let names = |b: &Branch| {
+            b.branches
+                .iter()
+                .map(|c| c.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(old), ["a", "d"]);
+        assert_eq!(names(&old.branches[0]), ["b", "c"]);
+        assert_eq!(
+            state.get_tree("/tmp/new").unwrap().stack_method,
+            StackMethod::Merge
+        );
+
+        let saved = serde_yaml::to_string(&state).unwrap();
+        assert!(
+            saved.starts_with(&format!("version: {STATE_VERSION}\n")),
+            "{saved}"
+        );
+    }
+
+    #[test]
+    fn parse_refuses_a_newer_schema() {
+        let err = State::parse("version: 99\n").unwrap_err();
+        assert!(format!("{err:#}").contains("upgrade git-stack"), "{err:#}");
+        assert!(State::parse("").unwrap().repos.is_empty());
+    }
+    #[test]
+    fn validate_accepts_a_well_formed_tree() {
+        let state: State = serde_yaml::from_str(
+            "/tmp/foo:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: a\n    stack_method: apply_merge\n    lkg_parent: null\n    branches:\n    - name: b\n      stack_method: apply_merge\n      lkg_parent: null\n      branches: []\n",
+        )
+        .unwrap();
+        assert_eq!(state.validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_reports_duplicates_self_nesting_and_empty_names() {
+        let state: State = serde_yaml::from_str(
+            "/tmp/foo:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: a\n    stack_method: apply_merge\n    lkg_parent: null\n    branches:\n    - name: a\n      stack_method: apply_merge\n      lkg_parent: null\n      branches: []\n  - name: ''\n    stack_method: apply_merge\n    lkg_parent: null\n    branches: []\n",
+        )
+        .unwrap();
+        let problems = state.validate().unwrap_err();
+        assert_eq!(
+            problems,
+            vec![
+                "/tmp/foo: branch 'a' is nested under itself (main -> a -> a)".to_string(),
+                "/tmp/foo: a child of 'main' has an empty name".to_string(),
+                "/tmp/foo: branch 'a' appears 2 times in the tree".to_string(),
+            ]
+        );
+    }
+
+    /// Two concurrent writers that go through `StateLock` never interleave: whichever holds the
+    /// lock writes a complete document, the other backs off, and the file always parses.
+    #[test]
+    fn concurrent_locked_writers_keep_state_valid_yaml() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_path = dir.path().join("state.yaml");
+        let writer = |tag: &'static str| {
+            let state_path = state_path.clone();
+            std::thread::spawn(move || {
+                let mut written = 0;
+                while written < 25 {
+                    let Ok(_guard) = StateLock::try_acquire(&state_path) else {
+                        std::thread::yield_now();
+                        continue;
+                    };
+                    let branches = (0..200)
+                        .map(|i| Branch::new(format!("{tag}-{i}"), None))
+                        .collect();
+                    let mut tree = Branch::new("main".to_string(), None);
+                    tree.branches = branches;
+                    let state = State {
+                        version: STATE_VERSION,
+                        repos: [(format!("/tmp/{tag}"), RepoState::new(tree))]
+                            .into_iter()
+                            .collect(),
+                    };
+                    write_file_secure(&state_path, &serde_yaml::to_string(&state).unwrap())
+                        .unwrap();
+                    let reread: State =
+                        serde_yaml::from_str(&fs::read_to_string(&state_path).unwrap()).unwrap();
+                    assert!(reread.repos.contains_key(&format!("/tmp/{tag}")));
+                    written += 1;
+                }
+            })
+        };
+        let (a, b) = (writer("a"), writer("b"));
+        a.join().unwrap();
+        b.join().unwrap();
+
+        let state: State = serde_yaml::from_str(&fs::read_to_string(&state_path).unwrap()).unwrap();
+        assert_eq!(state.repos.len(), 1);
+    }
+
+    /// A serializer that dies after emitting half the document must not cost the user their
+    /// existing `state.yaml`: the rename never happens and the temp file is cleaned up.
+    #[test]
+    fn atomic_write_keeps_old_file_when_serialization_panics() {
+        struct PanicsPartway;
+        impl Serialize for PanicsPartway {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeMap;
+                let mut map = serializer.serialize_map(None)?;
+                map.serialize_entry("/tmp/half", &"written")?;
+                panic!("serializer failed partway");
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let state_path = dir.path().join("state.yaml");
+        let original = "/tmp/foo:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches: []\n";
+        fs::write(&state_path, original).unwrap();
+
+        let result = std::panic::catch_unwind(|| {
+            write_file_secure_atomic(&state_path, |out| {
+                Ok(serde_yaml::to_writer(out, &PanicsPartway)?)
+            })
+        });
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&state_path).unwrap(), original);
+        let leftovers: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(leftovers, vec![std::ffi::OsString::from("state.yaml")]);
+
+        write_file_secure_atomic(&state_path, |out| {
+            Ok(out.write_all(b"/tmp/bar: replaced\n")?)
+        })
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(&state_path).unwrap(),
+            "/tmp/bar: replaced\n"
         );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&state_path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn trunk_override_round_trips_and_is_omitted_when_unset() {
+        let yaml = "version: 1\n/tmp/foo:\n  name: develop\n  stack_method: apply_merge\n  lkg_parent: null\n  branches: []\n  trunk: develop\n";
+        let state: State = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(state.trunk_override("/tmp/foo").as_deref(), Some("develop"));
+        assert_eq!(serde_yaml::to_string(&state).unwrap(), yaml);
+
+        let state: State = serde_yaml::from_str(
+            "/tmp/foo:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches: []\n",
+        )
+        .unwrap();
+        assert_eq!(state.trunk_override("/tmp/foo"), None);
     }
+
     #[test]
     fn test_state_read() {
         let state = "/tmp/foo:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches: []\n";
@@ -1566,7 +3296,14 @@ mod tests {
             orig_branch: "feature-b".to_string(),
             ancestors: true,
             push: false,
+            push_all: false,
             squash: false,
+            descendants: false,
+            log: false,
+            autostash: None,
+            onto: None,
+            worktree: Some("/home/user/repo".to_string()),
+            log_path: None,
         }
     }
 
@@ -1725,6 +3462,7 @@ mod tests {
         let mut main_branch = Branch::new("main".to_string(), None);
         main_branch.branches.push(removed_parent);
         let mut state = State {
+            version: STATE_VERSION,
             repos: [(repo.clone(), RepoState::new(main_branch))]
                 .into_iter()
                 .collect(),
@@ -1794,6 +3532,7 @@ mod tests {
         let mut main_branch = Branch::new("main".to_string(), None);
         main_branch.branches = vec![parent_a, parent_b];
         let mut state = State {
+            version: STATE_VERSION,
             repos: [(repo.clone(), RepoState::new(main_branch))]
                 .into_iter()
                 .collect(),
@@ -1845,6 +3584,7 @@ mod tests {
             .branches
             .push(Branch::new("feature".to_string(), None));
         let state = State {
+            version: STATE_VERSION,
             repos: [(repo.clone(), RepoState::new(main_branch))]
                 .into_iter()
                 .collect(),
@@ -1880,6 +3620,7 @@ mod tests {
             .branches
             .push(Branch::new("feature".to_string(), Some(sha_a.clone())));
         let state = State {
+            version: STATE_VERSION,
             repos: [(repo.clone(), RepoState::new(main_branch))]
                 .into_iter()
                 .collect(),
@@ -1920,6 +3661,7 @@ mod tests {
             Some(old_parent_tip.clone()),
         ));
         let state = State {
+            version: STATE_VERSION,
             repos: [(repo.clone(), RepoState::new(main))].into_iter().collect(),
         };
 
@@ -1954,6 +3696,7 @@ mod tests {
         main.branches
             .push(Branch::new("child".to_string(), Some(old_lkg)));
         let state = State {
+            version: STATE_VERSION,
             repos: [(repo.clone(), RepoState::new(main))].into_iter().collect(),
         };
 
@@ -2003,6 +3746,7 @@ mod tests {
             .push(Branch::new("env".to_string(), Some(old_p01_tip.clone())));
         main_branch.branches.push(p01);
         let state = State {
+            version: STATE_VERSION,
             repos: [(repo.clone(), RepoState::new(main_branch))]
                 .into_iter()
                 .collect(),
@@ -2039,6 +3783,7 @@ mod tests {
             .branches
             .push(Branch::new("feature".to_string(), None));
         let state = State {
+            version: STATE_VERSION,
             repos: [(repo.clone(), RepoState::new(main_branch))]
                 .into_iter()
                 .collect(),
@@ -2048,6 +3793,29 @@ mod tests {
         assert!(matches!(updates.get("feature"), None | Some(None)));
     }
 
+    #[test]
+    fn record_sync_is_read_back_by_last_sync() {
+        let main_branch = Branch::new("main".to_string(), None);
+        let mut state = State {
+            version: STATE_VERSION,
+            repos: [("repo".to_string(), RepoState::new(main_branch))]
+                .into_iter()
+                .collect(),
+        };
+        assert!(state.last_sync("repo").is_none());
+
+        state.record_sync("repo");
+        let age = chrono::Utc::now() - state.last_sync("repo").unwrap();
+        assert!(age < chrono::TimeDelta::minutes(1), "{age}");
+
+        state.repos.get_mut("repo").unwrap().last_sync = Some("yesterday".to_string());
+        assert!(state.last_sync("repo").is_none());
+
+        assert!(state.last_pr_refresh("repo").is_none());
+        state.record_pr_refresh("repo");
+        assert!(state.last_pr_refresh("repo").is_some());
+    }
+
     #[test]
     fn apply_lkg_updates_reports_no_change_when_values_match() {
         let mut main_branch = Branch::new("main".to_string(), None);
@@ -2056,6 +3824,7 @@ mod tests {
             Some("abc123".to_string()),
         ));
         let mut state = State {
+            version: STATE_VERSION,
             repos: [("repo".to_string(), RepoState::new(main_branch))]
                 .into_iter()
                 .collect(),
@@ -2080,6 +3849,7 @@ mod tests {
             Some("abc123".to_string()),
         ));
         let mut state = State {
+            version: STATE_VERSION,
             repos: [("repo".to_string(), RepoState::new(main_branch))]
                 .into_iter()
                 .collect(),
@@ -2125,6 +3895,7 @@ mod tests {
         a_branch.branches.push(Branch::new("b".to_string(), None));
         main_branch.branches.push(a_branch);
         let state = State {
+            version: STATE_VERSION,
             repos: [(repo.clone(), RepoState::new(main_branch))]
                 .into_iter()
                 .collect(),
@@ -2159,6 +3930,7 @@ mod tests {
         let mut main_branch = Branch::new("main".to_string(), None);
         main_branch.branches.push(mine);
         State {
+            version: STATE_VERSION,
             repos: [(repo.to_string(), RepoState::new(main_branch))]
                 .into_iter()
                 .collect(),
@@ -2198,7 +3970,7 @@ mod tests {
         assert!(!is_branch_mentioned_in_tree("theirs", tree));
 
         // `mychild` is now a direct child of `mine`.
-        let mine = find_branch_by_name(tree, "mine").unwrap();
+        let mine = tree.find("mine").unwrap();
         assert!(mine.branches.iter().any(|b| b.name == "mychild"));
     }
 
@@ -2317,6 +4089,7 @@ mod tests {
             .branches
             .push(Branch::new("feature".to_string(), None));
         let mut state = State {
+            version: STATE_VERSION,
             repos: [(repo.clone(), RepoState::new(main_branch))]
                 .into_iter()
                 .collect(),
@@ -2337,4 +4110,91 @@ mod tests {
             Some(sha_a)
         );
     }
+
+    #[test]
+    fn refresh_lkgs_if_changed_skips_until_a_branch_moves() {
+        // Redirect the XDG state file so save_state() can't clobber the real user state.
+        let state_home = tempfile::tempdir().unwrap();
+        fs::create_dir_all(state_home.path().join(env!("CARGO_PKG_NAME"))).unwrap();
+        unsafe { std::env::set_var("XDG_STATE_HOME", state_home.path()) };
+
+        let dir = tempfile::tempdir().unwrap();
+        init_test_repo(dir.path());
+        git_run(dir.path(), &["checkout", "-q", "-b", "feature"]);
+        git_run(dir.path(), &["commit", "--allow-empty", "-q", "-m", "f"]);
+        git_run(dir.path(), &["checkout", "-q", "main"]);
+        let git_repo =
+            GitRepo::open_with_cache_at(dir.path(), &dir.path().join("mb_cache.redb")).unwrap();
+        let repo = repo_key(dir.path());
+
+        let mut main_branch = Branch::new("main".to_string(), None);
+        main_branch
+            .branches
+            .push(Branch::new("feature".to_string(), None));
+        let mut state = State {
+            version: STATE_VERSION,
+            repos: [(repo.clone(), RepoState::new(main_branch))]
+                .into_iter()
+                .collect(),
+        };
+        let lkg = |state: &State| {
+            state
+                .get_tree_branch(&repo, "feature")
+                .unwrap()
+                .lkg_parent
+                .clone()
+        };
+
+        state
+            .refresh_lkgs_if_changed(&git_repo, &repo, None, false)
+            .unwrap();
+        let main_sha = git_rev_parse(dir.path(), "main");
+        assert_eq!(lkg(&state), Some(main_sha.clone()));
+        assert!(state.repos[&repo].lkg_fingerprint.is_some());
+
+        // Pretend the last refresh had left no LKG: with nothing moved, the refresh is skipped...
+        state
+            .get_tree_branch_mut(&repo, "feature")
+            .unwrap()
+            .lkg_parent = None;
+        let fingerprint = state.lkg_fingerprint(&git_repo, &repo, None);
+        state.get_repo_state_mut(&repo).unwrap().lkg_fingerprint = fingerprint;
+        state
+            .refresh_lkgs_if_changed(&git_repo, &repo, None, false)
+            .unwrap();
+        assert_eq!(lkg(&state), None);
+
+        // ...unless forced,
+        state
+            .refresh_lkgs_if_changed(&git_repo, &repo, None, true)
+            .unwrap();
+        assert_eq!(lkg(&state), Some(main_sha.clone()));
+
+        // or a branch moves.
+        state
+            .get_tree_branch_mut(&repo, "feature")
+            .unwrap()
+            .lkg_parent = None;
+        let fingerprint = state.lkg_fingerprint(&git_repo, &repo, None);
+        state.get_repo_state_mut(&repo).unwrap().lkg_fingerprint = fingerprint;
+        git_run(dir.path(), &["checkout", "-q", "feature"]);
+        git_run(dir.path(), &["commit", "--allow-empty", "-q", "-m", "f2"]);
+        state
+            .refresh_lkgs_if_changed(&git_repo, &repo, None, false)
+            .unwrap();
+        assert_eq!(lkg(&state), Some(main_sha));
+    }
+
+    #[test]
+    fn appended_note_adds_a_line() {
+        assert_eq!(appended_note(None, "blocked on #123"), "blocked on #123");
+        assert_eq!(
+            appended_note(Some(""), "blocked on #123"),
+            "blocked on #123"
+        );
+        assert_eq!(
+            appended_note(Some("first\n"), "blocked on #123"),
+            "first\nblocked on #123"
+        );
+    }
 }
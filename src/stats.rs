@@ -28,6 +28,37 @@ impl Drop for GitBenchmark {
     }
 }
 
+/// RAII guard for the wall-clock time of a phase that fans work out across threads. Recorded
+/// separately from command spans (and excluded from the TOTAL row), so the summary can set the
+/// phase's elapsed time against the summed per-command time spent inside it.
+pub struct WallClock {
+    phase: &'static str,
+    start: Instant,
+}
+
+impl WallClock {
+    pub fn start(phase: &'static str) -> Self {
+        Self {
+            phase,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for WallClock {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        GIT_STATS.with(|stats| {
+            stats
+                .borrow_mut()
+                .wall_clock
+                .entry(self.phase.to_string())
+                .or_default()
+                .record(elapsed);
+        });
+    }
+}
+
 fn record_git_command_internal(command: &str, duration: Duration) {
     GIT_STATS.with(|stats| {
         stats.borrow_mut().record(command, duration);
@@ -67,6 +98,8 @@ pub struct GitStats {
     pub by_command: HashMap<String, CommandStats>,
     /// Overall stats
     pub total: CommandStats,
+    /// Wall-clock time of parallel phases (see `WallClock`), not included in `total`
+    pub wall_clock: HashMap<String, CommandStats>,
 }
 
 impl GitStats {
@@ -100,6 +133,7 @@ pub fn get_stats() -> GitStats {
         GitStats {
             by_command: borrowed.by_command.clone(),
             total: borrowed.total.clone(),
+            wall_clock: borrowed.wall_clock.clone(),
         }
     })
 }
@@ -123,6 +157,12 @@ pub fn merge_into_current(other: &GitStats) {
             entry.total_duration += cmd_stats.total_duration;
             entry.max_duration = entry.max_duration.max(cmd_stats.max_duration);
         }
+        for (phase, phase_stats) in &other.wall_clock {
+            let entry = stats.wall_clock.entry(phase.clone()).or_default();
+            entry.count += phase_stats.count;
+            entry.total_duration += phase_stats.total_duration;
+            entry.max_duration = entry.max_duration.max(phase_stats.max_duration);
+        }
         stats.total.count += other.total.count;
         stats.total.total_duration += other.total.total_duration;
         stats.total.max_duration = stats.total.max_duration.max(other.total.max_duration);
@@ -182,6 +222,13 @@ pub fn print_summary() {
             .map(|c| c.add_attribute(Attribute::Bold)),
     );
 
+    // Parallel phases: elapsed time, to compare against the summed command time above.
+    let mut phases: Vec<_> = stats.wall_clock.iter().collect();
+    phases.sort_by(|a, b| a.0.cmp(b.0));
+    for (phase, phase_stats) in phases {
+        table.add_row(row(Cell::new(format!("wall:{phase}")), phase_stats));
+    }
+
     eprintln!("{table}");
     eprintln!();
 }
@@ -212,6 +259,59 @@ pub fn print_json() {
             comma
         );
     }
+    eprintln!("  }},");
+
+    eprintln!("  \"wall_clock\": {{");
+    let phases: Vec<_> = stats.wall_clock.iter().collect();
+    for (i, (phase, phase_stats)) in phases.iter().enumerate() {
+        let comma = if i < phases.len() - 1 { "," } else { "" };
+        eprintln!(
+            "    \"{}\": {{ \"count\": {}, \"total_ms\": {:.3} }}{}",
+            phase,
+            phase_stats.count,
+            phase_stats.total_duration.as_secs_f64() * 1000.0,
+            comma
+        );
+    }
     eprintln!("  }}");
     eprintln!("}}");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wall_clock_is_kept_out_of_the_command_total() {
+        reset_stats();
+        {
+            let _wall = WallClock::start("scan");
+            let _bench = GitBenchmark::start("git2:rev-parse");
+        }
+        let stats = get_stats();
+        assert_eq!(stats.total.count, 1);
+        assert_eq!(stats.wall_clock["scan"].count, 1);
+        assert!(!stats.by_command.contains_key("scan"));
+    }
+
+    #[test]
+    fn merge_into_current_folds_wall_clock() {
+        reset_stats();
+        let mut other = GitStats::default();
+        other.record("git2:merge-base", Duration::from_millis(3));
+        other
+            .wall_clock
+            .entry("scan".to_string())
+            .or_default()
+            .record(Duration::from_millis(5));
+        merge_into_current(&other);
+        merge_into_current(&other);
+        let stats = get_stats();
+        assert_eq!(stats.by_command["git2:merge-base"].count, 2);
+        assert_eq!(stats.wall_clock["scan"].count, 2);
+        assert_eq!(
+            stats.wall_clock["scan"].total_duration,
+            Duration::from_millis(10)
+        );
+    }
+}
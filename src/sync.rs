@@ -15,17 +15,19 @@ use std::{
 
 use anyhow::{Context, Result, anyhow, bail};
 use colored::Colorize;
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{ProgressBar, ProgressFinish, ProgressStyle};
 use rand::seq::SliceRandom;
 
 use crate::{
-    git::{fetch_with_recovery, git_trunk, run_git},
+    git::{FetchOptions, git_fetch, git_trunk, run_git},
     git2_ops::{DEFAULT_REMOTE, GitRepo},
     github::{
-        CachedPullRequest, GitHubClient, PrState, PullRequest, RepoIdentifier, UpdatePrRequest,
-        get_repo_identifier,
+        CachedPullRequest, ClosedPrCacheMode, ClosedPrLimits, PrState, PullRequest, RepoIdentifier,
+        ScopedOpenPrs, UpdatePrRequest, get_repo_identifier, include_forks,
     },
-    state::{Branch, State},
+    provider::{RemoteProvider, client_for},
+    render::tree_data::branch_progress,
+    state::{Branch, StackMethod, State},
 };
 
 // ============== Stage 1: State Types ==============
@@ -48,6 +50,8 @@ pub struct LocalBranch {
     pub pr_number: Option<u64>,
     /// Whether the branch has been pushed to remote
     pub pushed_to_remote: bool,
+    /// The local branch's tip, if the branch exists locally
+    pub sha: Option<String>,
 }
 
 /// Remote state gathered from GitHub API
@@ -69,6 +73,8 @@ pub struct RemotePr {
     pub state: RemotePrState,
     pub title: String,
     pub html_url: String,
+    /// The commit the PR currently shows as its head
+    pub head_sha: String,
 }
 
 impl From<&PullRequest> for RemotePr {
@@ -87,6 +93,7 @@ impl From<&PullRequest> for RemotePr {
             },
             title: pr.title.clone(),
             html_url: pr.html_url.clone(),
+            head_sha: pr.head.sha.clone(),
         }
     }
 }
@@ -164,10 +171,25 @@ pub enum RemoteChange {
     },
     /// Push a branch to remote (used before retargeting to it)
     PushBranch { branch: String },
+    /// Push local commits (e.g. a restack) to a branch already on the remote. `force` pushes with
+    /// `--force-with-lease`, for `apply_merge` branches whose history a restack rewrites.
+    Push { branch: String, force: bool },
+    /// Delete a merged PR's head branch from the remote (`--prune-remote`). `retargets` are the
+    /// open PRs based on `branch`; the deletion is only safe once each of them is retargeted.
+    DeleteRemoteBranch { branch: String, retargets: Vec<u64> },
 }
 
 // ============== Stage 4: Sync Plan ==============
 
+/// A pushed branch whose local tip isn't what its open PR shows, e.g. restacked but not pushed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StalePrHead {
+    pub branch: String,
+    pub pr_number: u64,
+    pub local_sha: String,
+    pub pr_sha: String,
+}
+
 /// Complete sync plan with all changes
 #[derive(Debug)]
 pub struct SyncPlan {
@@ -178,6 +200,8 @@ pub struct SyncPlan {
     pub branches_to_unmount: Vec<String>,
     /// Branches safe to delete locally (work preserved on remote)
     pub branches_to_delete: Vec<String>,
+    /// Branches whose local tip differs from their open PR's head
+    pub stale_pr_heads: Vec<StalePrHead>,
 }
 
 impl SyncPlan {
@@ -192,7 +216,7 @@ impl SyncPlan {
 
 // ============== Sync Options ==============
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct SyncOptions {
     /// Only push local changes to remote (no pull)
     pub push_only: bool,
@@ -200,6 +224,19 @@ pub struct SyncOptions {
     pub pull_only: bool,
     /// Show plan without applying
     pub dry_run: bool,
+    /// Prompt before applying each change
+    pub interactive: bool,
+    /// After applying, upsert the stack overview comment on each open PR in the stack
+    pub stack_comments: bool,
+    /// How far back to fetch closed PRs that aren't cached yet
+    pub closed_pr_limits: ClosedPrLimits,
+    /// Use these authors instead of the configured `authors_filter` for this run (`--author`;
+    /// empty under `--show-all`)
+    pub authors_override: Option<Vec<String>>,
+    /// Delete the remote branches of merged PRs (`--prune-remote`)
+    pub prune_remote: bool,
+    /// Start the closed-PR cache over instead of fetching only past its watermark (`--no-cache`)
+    pub no_cache: bool,
 }
 
 // ============== Implementation ==============
@@ -210,13 +247,23 @@ pub fn sync(git_repo: &GitRepo, state: &mut State, repo: &str, options: SyncOpti
     // invocation can't race us on ref updates (e.g. concurrent fetch --prune).
     let _lock = git_repo.lock()?;
 
+    if options.interactive && !options.dry_run && !std::io::stdin().is_terminal() {
+        bail!("--interactive requires a terminal to prompt on; use --dry-run to preview instead.");
+    }
+
     // Get repo identifier for GitHub API
     let repo_id = get_repo_identifier(git_repo)?;
-    let client = GitHubClient::from_env(&repo_id)?;
+    let client = client_for(&repo_id)?;
 
     // Fetch with prune to ensure remote tracking refs are up-to-date
     println!("Fetching from remote...");
-    fetch_with_recovery(&["fetch", "--tags", "-f", "--prune", DEFAULT_REMOTE])?;
+    git_fetch(
+        git_repo,
+        FetchOptions {
+            prune: true,
+            tags: true,
+        },
+    )?;
 
     // Stage 1: Read current state
     println!("Reading local state...");
@@ -227,9 +274,12 @@ pub fn sync(git_repo: &GitRepo, state: &mut State, repo: &str, options: SyncOpti
     let current_branch = git_repo.current_branch().unwrap_or_default();
     // sync is always online with a live client, so refresh the identity cache here (an unset
     // filter resolves to your own login; explicit config passes through).
-    let authors_filter = crate::github::resolve_effective_authors_filter(&repo_id, Some(&client))?;
+    let authors_filter = match &options.authors_override {
+        Some(authors) => authors.clone(),
+        None => crate::github::resolve_effective_authors_filter(&repo_id, Some(client.as_ref()))?,
+    };
     let scope_vec = compute_scope_branches(
-        &client,
+        client.as_ref(),
         &repo_id,
         &local_state,
         &current_branch,
@@ -255,7 +305,19 @@ pub fn sync(git_repo: &GitRepo, state: &mut State, repo: &str, options: SyncOpti
     };
 
     println!("Reading remote state...");
-    let (mut remote_state, mut seen_shas) = read_remote_state(&client, &repo_id, &scope_vec)?;
+    let (mut remote_state, mut seen_shas) = read_remote_state(
+        client.as_ref(),
+        &repo_id,
+        &scope_vec,
+        &options.closed_pr_limits,
+        if options.no_cache {
+            ClosedPrCacheMode::Rebuild {
+                persist: !options.dry_run,
+            }
+        } else {
+            ClosedPrCacheMode::Incremental
+        },
+    )?;
     merge_discovered_prs(
         &discovered_prs,
         &mut scope,
@@ -329,7 +391,7 @@ pub fn sync(git_repo: &GitRepo, state: &mut State, repo: &str, options: SyncOpti
     );
 
     // Stage 3: Compute diffs
-    let plan = compute_sync_plan(
+    let mut plan = compute_sync_plan(
         git_repo,
         state,
         repo,
@@ -340,22 +402,38 @@ pub fn sync(git_repo: &GitRepo, state: &mut State, repo: &str, options: SyncOpti
     );
 
     // Stage 4: Validate
-    validate_plan(&plan)?;
+    validate_plan(
+        git_repo,
+        &mut plan,
+        &local_state,
+        &remote_state,
+        state.get_seen_shas(repo),
+    )?;
 
     // Print plan
     print_plan(&plan, options.dry_run);
 
+    // Stage 5: Apply (if not dry-run)
     if plan.is_empty() {
         println!("\n{}", "Everything is in sync!".green());
-        return Ok(());
-    }
-
-    // Stage 5: Apply (if not dry-run)
-    if options.dry_run {
+    } else if options.dry_run {
         println!(
             "\n{}",
             "Dry run mode: no changes applied.".bright_blue().bold()
         );
+    } else if options.interactive {
+        // Each change is confirmed individually, so no up-front remote-changes prompt.
+        println!("\nApplying changes...");
+        apply_plan(
+            git_repo,
+            state,
+            repo,
+            client.as_ref(),
+            &repo_id,
+            &plan,
+            true,
+        )?;
+        println!("\n{}", "Sync complete!".green().bold());
     } else if plan.has_remote_changes() {
         // Prompt for confirmation before applying remote changes
         if !std::io::stdin().is_terminal() {
@@ -365,21 +443,123 @@ pub fn sync(git_repo: &GitRepo, state: &mut State, repo: &str, options: SyncOpti
         }
         if confirm_remote_changes() {
             println!("\nApplying changes...");
-            apply_plan(git_repo, state, repo, &client, &repo_id, &plan)?;
+            apply_plan(
+                git_repo,
+                state,
+                repo,
+                client.as_ref(),
+                &repo_id,
+                &plan,
+                false,
+            )?;
             println!("\n{}", "Sync complete!".green().bold());
         } else {
             println!("\n{}", "Aborted.".yellow());
+            return Ok(());
         }
     } else {
         // Only local changes - apply without confirmation
         println!("\nApplying changes...");
-        apply_plan(git_repo, state, repo, &client, &repo_id, &plan)?;
+        apply_plan(
+            git_repo,
+            state,
+            repo,
+            client.as_ref(),
+            &repo_id,
+            &plan,
+            false,
+        )?;
         println!("\n{}", "Sync complete!".green().bold());
     }
 
+    if !options.dry_run {
+        state.record_sync(repo);
+        state.save_state()?;
+    }
+
+    // Stage 6: Stack comments (opt-in; reflects the tree and PR bases as applied above)
+    if options.stack_comments {
+        if options.dry_run {
+            println!("Dry run mode: stack comments not updated.");
+        } else if let Some(tree) = state.get_tree(repo) {
+            crate::stack_comment::post_stack_comments(git_repo, tree, client.as_ref(), &repo_id)?;
+        }
+    }
+
     Ok(())
 }
 
+/// `cleanup --merged`: delete tracked local branches whose work has landed on trunk, with the same
+/// checks `sync` uses (see `merged_branch_deletions`) but without contacting the remote. Merged
+/// PRs come from the closed-PR cache the last `sync` filled in and remote branches from the
+/// current remote-tracking refs, so fetch first for an up-to-date answer.
+pub(crate) fn cleanup_merged(
+    git_repo: &GitRepo,
+    state: &mut State,
+    repo: &str,
+    dry_run: bool,
+) -> Result<()> {
+    let _lock = git_repo.lock()?;
+
+    let local = read_local_state(git_repo, state, repo)?;
+    let keep_forks = include_forks();
+    let closed_prs: HashMap<String, RemotePr> = get_repo_identifier(git_repo)
+        .ok()
+        .and_then(|repo_id| {
+            let cache = crate::pr_cache::PrCacheHandle::open().ok()?;
+            cache.closed_prs_for_repo(&repo_id.full_name()).ok()
+        })
+        .unwrap_or_default()
+        .iter()
+        .map(|(branch, cached)| (branch.clone(), PullRequest::from(cached)))
+        .filter(|(_, pr)| keep_forks || !pr.is_from_fork())
+        .map(|(branch, pr)| (branch, RemotePr::from(&pr)))
+        .collect();
+    let current_branch = git_repo.current_branch().unwrap_or_default();
+    let (local_changes, _) = merged_branch_deletions(
+        git_repo,
+        &local,
+        &closed_prs,
+        state.get_seen_shas(repo),
+        &current_branch,
+        &HashSet::new(),
+    );
+
+    let mut plan = SyncPlan {
+        local_changes,
+        remote_changes: Vec::new(),
+        warnings: Vec::new(),
+        branches_to_unmount: Vec::new(),
+        branches_to_delete: Vec::new(),
+        stale_pr_heads: Vec::new(),
+    };
+    let remote = RemoteState {
+        prs: HashMap::new(),
+        closed_prs,
+        authors: HashMap::new(),
+    };
+    validate_plan(
+        git_repo,
+        &mut plan,
+        &local,
+        &remote,
+        state.get_seen_shas(repo),
+    )?;
+
+    if plan.is_empty() && plan.warnings.is_empty() {
+        println!("No merged branches to clean up.");
+        return Ok(());
+    }
+    print_plan(&plan, dry_run);
+    if dry_run {
+        return Ok(());
+    }
+    for change in &plan.local_changes {
+        apply_local_change(git_repo, state, repo, change)?;
+    }
+    state.save_state()
+}
+
 /// Prompt user to confirm remote changes
 fn confirm_remote_changes() -> bool {
     use std::io::{self, Write};
@@ -395,12 +575,93 @@ fn confirm_remote_changes() -> bool {
     matches!(input.trim().to_lowercase().as_str(), "y" | "yes")
 }
 
+/// Answer to a per-change `--interactive` prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChangeConfirm {
+    /// Apply this change.
+    Yes,
+    /// Skip this change.
+    No,
+    /// Apply this and every remaining change without asking.
+    All,
+    /// Skip this and every remaining change.
+    Quit,
+}
+
+fn parse_change_confirm(input: &str) -> Option<ChangeConfirm> {
+    match input.trim().to_lowercase().as_str() {
+        "y" | "yes" => Some(ChangeConfirm::Yes),
+        "n" | "no" => Some(ChangeConfirm::No),
+        "a" | "all" => Some(ChangeConfirm::All),
+        "q" | "quit" => Some(ChangeConfirm::Quit),
+        _ => None,
+    }
+}
+
+/// Per-change confirmation state for `apply_plan`. With `interactive` off every change is
+/// approved; otherwise the user is asked until they answer `a` (approve the rest) or `q`
+/// (decline the rest).
+struct ChangeConfirmer {
+    interactive: bool,
+    apply_all: bool,
+    quit: bool,
+}
+
+impl ChangeConfirmer {
+    fn new(interactive: bool) -> Self {
+        Self {
+            interactive,
+            apply_all: false,
+            quit: false,
+        }
+    }
+
+    /// Ask whether to apply the change described by `description` (formatted as in `print_plan`).
+    fn confirm(&mut self, description: &str) -> bool {
+        use std::io::{self, Write};
+
+        if self.quit {
+            return false;
+        }
+        if !self.interactive || self.apply_all {
+            return true;
+        }
+        loop {
+            print!("  {}? [y/n/a/q] ", description);
+            io::stdout().flush().unwrap();
+
+            let mut input = String::new();
+            // EOF or a read error: stop rather than guess.
+            if io::stdin().read_line(&mut input).unwrap_or(0) == 0 {
+                self.quit = true;
+                return false;
+            }
+            match parse_change_confirm(&input) {
+                Some(ChangeConfirm::Yes) => return true,
+                Some(ChangeConfirm::No) => return false,
+                Some(ChangeConfirm::All) => {
+                    self.apply_all = true;
+                    return true;
+                }
+                Some(ChangeConfirm::Quit) => {
+                    self.quit = true;
+                    return false;
+                }
+                None => println!("  Please answer y (yes), n (no), a (all), or q (quit)."),
+            }
+        }
+    }
+}
+
 // ============== Stage 1: Read Functions ==============
 
 /// Garbage collect seen SHAs that are no longer needed.
 /// Prunes SHAs that are:
 /// - Ancestors of origin/trunk (already merged)
 /// - Not reachable from any tracked branch
+///
+/// A tracked branch's current `origin/<branch>` tip is always kept: after a local restack it is no
+/// longer reachable, and [`push_needed`] only force-pushes over remote tips it has seen.
 fn gc_seen_shas(git_repo: &GitRepo, state: &mut State, repo: &str, trunk: &str) {
     const MAX_GC_DURATION: Duration = Duration::from_millis(100);
 
@@ -410,6 +671,15 @@ fn gc_seen_shas(git_repo: &GitRepo, state: &mut State, repo: &str, trunk: &str)
 
     // Collect all tracked branch HEADs
     let tracked_shas: Vec<String> = collect_tracked_branch_shas(git_repo, &repo_state.tree);
+    let remote_tips: HashSet<String> = repo_state
+        .tree
+        .iter()
+        .filter_map(|branch| {
+            git_repo
+                .sha(&format!("{DEFAULT_REMOTE}/{}", branch.name))
+                .ok()
+        })
+        .collect();
     let origin_trunk = format!("{}/{}", DEFAULT_REMOTE, trunk);
 
     // Copy SHAs into a Vec and shuffle for stochastic traversal
@@ -431,8 +701,10 @@ fn gc_seen_shas(git_repo: &GitRepo, state: &mut State, repo: &str, trunk: &str)
 
         // Check if this SHA should be pruned
         let should_keep = {
+            if remote_tips.contains(&sha) {
+                true
             // Prune if merged to main
-            if git_repo.is_ancestor(&sha, &origin_trunk).unwrap_or(false) {
+            } else if git_repo.is_ancestor(&sha, &origin_trunk).unwrap_or(false) {
                 false
             } else {
                 // Keep if reachable from any tracked branch
@@ -478,19 +750,10 @@ fn get_merged_branches(trunk: &str) -> Result<HashSet<String>> {
 
 /// Collect SHAs of all tracked branch HEADs
 fn collect_tracked_branch_shas(git_repo: &GitRepo, branch: &Branch) -> Vec<String> {
-    let mut shas = Vec::new();
-
-    // Add this branch's SHA if it exists
-    if let Ok(sha) = git_repo.sha(&branch.name) {
-        shas.push(sha);
-    }
-
-    // Recurse into children
-    for child in &branch.branches {
-        shas.extend(collect_tracked_branch_shas(git_repo, child));
-    }
-
-    shas
+    branch
+        .iter()
+        .filter_map(|branch| git_repo.sha(&branch.name).ok())
+        .collect()
 }
 
 /// Read current local state from git-stack and git
@@ -506,8 +769,17 @@ fn read_local_state(git_repo: &GitRepo, state: &State, repo: &str) -> Result<Loc
         });
     };
 
-    // Walk the tree and collect branch info
-    collect_local_branches(git_repo, tree, None, &mut branches);
+    // Walk the tree and collect branch info; each branch is recorded under its parent's visit.
+    let progress = branch_progress(tree.iter().skip(1).count(), "Reading local branches");
+    insert_local_branch(git_repo, tree, None, &mut branches);
+    for parent in tree.iter() {
+        for child in &parent.branches {
+            insert_local_branch(git_repo, child, Some(&parent.name), &mut branches);
+            if let Some(pb) = &progress {
+                pb.inc(1);
+            }
+        }
+    }
 
     Ok(LocalState {
         branches,
@@ -515,15 +787,14 @@ fn read_local_state(git_repo: &GitRepo, state: &State, repo: &str) -> Result<Loc
     })
 }
 
-/// Recursively collect branch info from git-stack tree
-fn collect_local_branches(
+/// Record one tree branch's local info
+fn insert_local_branch(
     git_repo: &GitRepo,
     branch: &Branch,
     parent: Option<&str>,
     branches: &mut HashMap<String, LocalBranch>,
 ) {
-    let remote_ref = format!("{}/{}", DEFAULT_REMOTE, branch.name);
-    let pushed_to_remote = git_repo.ref_exists(&remote_ref);
+    let pushed_to_remote = git_repo.remote_branch_exists(DEFAULT_REMOTE, &branch.name);
 
     branches.insert(
         branch.name.clone(),
@@ -531,25 +802,39 @@ fn collect_local_branches(
             parent: parent.map(|s| s.to_string()),
             pr_number: branch.pr_number,
             pushed_to_remote,
+            sha: git_repo.sha(&branch.name).ok(),
         },
     );
+}
 
-    for child in &branch.branches {
-        collect_local_branches(git_repo, child, Some(&branch.name), branches);
+/// `--limit 500, --since 2024-01-01T00:00:00Z`, naming whichever bounds are set.
+fn describe_closed_pr_limits(limits: &ClosedPrLimits) -> String {
+    let mut bounds = Vec::new();
+    if let Some(limit) = limits.limit {
+        bounds.push(format!("--limit {limit}"));
+    }
+    if let Some(since) = &limits.since {
+        bounds.push(format!("--since {since}"));
     }
+    bounds.join(", ")
 }
 
 /// Read current remote state from GitHub, fetching open PRs only for the `scope` branches
-/// (the user's stack) rather than enumerating every open PR in the repo.
+/// (the user's stack) rather than enumerating every open PR in the repo. Closed PRs not yet
+/// cached are fetched newest first, as far back as `limits` allows, unless `closed_mode` rebuilds
+/// the closed-PR cache instead.
 /// Returns (RemoteState, seen_shas)
 fn read_remote_state(
-    client: &GitHubClient,
+    client: &dyn RemoteProvider,
     repo_id: &RepoIdentifier,
     scope: &[String],
+    limits: &ClosedPrLimits,
+    closed_mode: ClosedPrCacheMode,
 ) -> Result<(RemoteState, HashSet<String>)> {
-    // Only show spinner if stderr is a TTY
+    // Only show spinner if stderr is a TTY. It clears itself when dropped, so a failed fetch
+    // (e.g. a timeout) leaves no spinning line above the error.
     let spinner = if std::io::stderr().is_terminal() {
-        let pb = ProgressBar::new_spinner();
+        let pb = ProgressBar::new_spinner().with_finish(ProgressFinish::AndClear);
         pb.set_style(
             ProgressStyle::default_spinner()
                 .template("{spinner:.cyan} {msg}")
@@ -561,12 +846,58 @@ fn read_remote_state(
         None
     };
 
-    // Fetch open PRs, scoped to the stack's branches (cost scales with stack size, not repo
-    // PR count). `list_open_prs_for_branches` early-returns on an empty scope.
+    // Open and closed PRs are independent requests, so fetch them concurrently: open PRs on a
+    // worker thread (the provider is `Sync`), closed PRs here so their progress drives the spinner.
+    let _wall = crate::stats::WallClock::start("sync:fetch-prs");
     if let Some(s) = &spinner {
-        s.set_message("Fetching open PRs...");
+        s.set_message("Fetching open and closed PRs...");
+    }
+    let closed_progress = |_page: usize, count: usize| {
+        if let Some(s) = &spinner {
+            s.set_message(format!(
+                "Fetching open and closed PRs... ({count} closed loaded)"
+            ));
+        }
+    };
+
+    let cache = crate::pr_cache::PrCacheHandle::open().context("Failed to open PR cache")?;
+
+    let (scoped, closed_result) = std::thread::scope(|thread_scope| {
+        // Open PRs, scoped to the stack's branches (cost scales with stack size, not repo PR
+        // count). `list_open_prs_for_branches` early-returns on an empty scope.
+        let open_handle = thread_scope.spawn(|| {
+            let scoped = client.list_open_prs_for_branches(repo_id, scope);
+            // `GitBenchmark` records into thread-local stats; hand them back for merging.
+            (scoped, crate::stats::get_stats())
+        });
+        let closed_result = client.list_closed_prs_with_cache(
+            repo_id,
+            &cache,
+            limits,
+            closed_mode,
+            Some(&closed_progress),
+        );
+        let scoped = match open_handle.join() {
+            Ok((scoped, stats)) => {
+                crate::stats::merge_into_current(&stats);
+                scoped
+            }
+            Err(_) => ScopedOpenPrs::default(),
+        };
+        (scoped, closed_result)
+    });
+    let mut closed_result = closed_result.map_err(|e| anyhow!("{}", e))?;
+
+    // A closed PR that has been reopened stays in the closed-PR cache until its next update
+    // (which the watermark may never fetch), where it would read as merged.
+    let reopened = evict_reopened_prs(&scoped.found, &mut closed_result.result.prs);
+    if !reopened.is_empty() {
+        tracing::debug!("Evicting reopened PRs from the closed-PR cache: {reopened:?}");
+        let reopened: Vec<&str> = reopened.iter().map(String::as_str).collect();
+        if let Err(e) = cache.remove_closed_prs(&repo_id.full_name(), &reopened) {
+            tracing::warn!("Failed to evict reopened PRs from the PR cache: {e}");
+        }
     }
-    let scoped = client.list_open_prs_for_branches(repo_id, scope);
 
     let prs: HashMap<String, RemotePr> = scoped
         .found
@@ -579,23 +910,8 @@ fn read_remote_state(
         .map(|(branch, pr)| (branch.clone(), pr.user.login.clone()))
         .collect();
 
-    // Fetch closed PRs (includes merged) with caching
-    if let Some(s) = &spinner {
-        s.set_message("Fetching closed PRs...");
-    }
-    let closed_progress = |_page: usize, count: usize| {
-        if let Some(s) = &spinner {
-            s.set_message(format!("Fetching closed PRs... ({count} loaded)"));
-        }
-    };
-
-    let cache = crate::pr_cache::PrCacheHandle::open().context("Failed to open PR cache")?;
-
-    let closed_result = client
-        .list_closed_prs_with_cache(repo_id, &cache, Some(&closed_progress))
-        .map_err(|e| anyhow!("{}", e))?;
-
     let closed_prs: HashMap<String, RemotePr> = closed_result
+        .result
         .prs
         .iter()
         .map(|(branch, pr)| (branch.clone(), RemotePr::from(pr)))
@@ -604,6 +920,13 @@ fn read_remote_state(
     if let Some(s) = spinner {
         s.finish_and_clear();
     }
+    if closed_result.truncated {
+        println!(
+            "Fetched {} closed PRs; older ones were skipped ({}).",
+            closed_result.fetched,
+            describe_closed_pr_limits(limits)
+        );
+    }
 
     // Collect all PR head SHAs for seen tracking
     let mut seen_shas: HashSet<String> = scoped
@@ -611,7 +934,13 @@ fn read_remote_state(
         .values()
         .map(|pr| pr.head.sha.clone())
         .collect();
-    seen_shas.extend(closed_result.prs.values().map(|pr| pr.head.sha.clone()));
+    seen_shas.extend(
+        closed_result
+            .result
+            .prs
+            .values()
+            .map(|pr| pr.head.sha.clone()),
+    );
 
     Ok((
         RemoteState {
@@ -623,6 +952,27 @@ fn read_remote_state(
     ))
 }
 
+/// Drop from `closed` every branch whose closed PR is open again in `open` (same PR number), and
+/// return those branches. A branch reused for a new PR keeps its old closed entry.
+fn evict_reopened_prs(
+    open: &HashMap<String, PullRequest>,
+    closed: &mut HashMap<String, PullRequest>,
+) -> Vec<String> {
+    let mut reopened: Vec<String> = closed
+        .iter()
+        .filter(|(branch, pr)| {
+            open.get(*branch)
+                .is_some_and(|open| open.number == pr.number)
+        })
+        .map(|(branch, _)| branch.clone())
+        .collect();
+    reopened.sort();
+    for branch in &reopened {
+        closed.remove(branch);
+    }
+    reopened
+}
+
 /// Walk a PR base chain starting from `start`, returning the branch names visited (including
 /// `start`). `lookup_base(branch)` yields the branch's open-PR base ref, or `None` when the
 /// branch has no open PR. The walk stops at `trunk`, at the first branch with no PR, or on a
@@ -654,7 +1004,7 @@ fn walk_pr_base_chain(
 /// with an empty tree) the reachable stack reconstructed by walking the current branch's PR base
 /// chain. Never enumerates the whole repo.
 fn compute_scope_branches(
-    client: &GitHubClient,
+    client: &dyn RemoteProvider,
     repo_id: &RepoIdentifier,
     local: &LocalState,
     current_branch: &str,
@@ -848,8 +1198,7 @@ fn build_target_state(
     for (branch_name, pr_base, pr_number) in
         remote_only_branches_to_inject(local, remote, scope, authors_filter)
     {
-        let remote_ref = format!("{}/{}", DEFAULT_REMOTE, branch_name);
-        let pushed_to_remote = git_repo.ref_exists(&remote_ref);
+        let pushed_to_remote = git_repo.remote_branch_exists(DEFAULT_REMOTE, &branch_name);
 
         branches.insert(
             branch_name,
@@ -1006,8 +1355,7 @@ fn compute_sync_plan(
                     continue;
                 }
                 // Check if parent exists as remote tracking branch
-                let remote_ref = format!("{}/{}", DEFAULT_REMOTE, parent);
-                if git_repo.ref_exists(&remote_ref) {
+                if git_repo.remote_branch_exists(DEFAULT_REMOTE, parent) {
                     // Mount missing parent on trunk
                     parents_to_add.push((parent.clone(), local.trunk.clone()));
                 }
@@ -1089,7 +1437,7 @@ fn compute_sync_plan(
         } else {
             // Closed but not merged - check if remote has our work
             let remote_ref = format!("{}/{}", DEFAULT_REMOTE, branch_name);
-            git_repo.ref_exists(&remote_ref)
+            git_repo.remote_branch_exists(DEFAULT_REMOTE, branch_name)
                 && git_repo
                     .is_ancestor(branch_name, &remote_ref)
                     .unwrap_or(false)
@@ -1117,8 +1465,7 @@ fn compute_sync_plan(
                         // PR's old base should be the unmounted branch, new base is repoint_to
                         if pr.base == *branch_name {
                             // Check if the new base branch is pushed to remote
-                            let new_base_remote_ref = format!("{}/{}", DEFAULT_REMOTE, repoint_to);
-                            if !git_repo.ref_exists(&new_base_remote_ref) {
+                            if !git_repo.remote_branch_exists(DEFAULT_REMOTE, repoint_to) {
                                 // Need to push the intermediate branch first
                                 remote_changes.push(RemoteChange::PushBranch {
                                     branch: repoint_to.clone(),
@@ -1168,14 +1515,20 @@ fn compute_sync_plan(
                 continue;
             }
 
+            if let Some(force) = push_needed(git_repo, state, repo, branch_name, &mut warnings) {
+                remote_changes.push(RemoteChange::Push {
+                    branch: branch_name.clone(),
+                    force,
+                });
+            }
+
             let remote_pr = remote.prs.get(branch_name);
 
             match (remote_pr, &target_branch.expected_pr_base) {
                 // PR exists, check if base matches
                 (Some(pr), Some(expected_base)) if pr.base != *expected_base => {
                     // Check if the new base branch is pushed to remote
-                    let new_base_remote_ref = format!("{}/{}", DEFAULT_REMOTE, expected_base);
-                    if !git_repo.ref_exists(&new_base_remote_ref) {
+                    if !git_repo.remote_branch_exists(DEFAULT_REMOTE, expected_base) {
                         // Need to push the intermediate branch first
                         remote_changes.push(RemoteChange::PushBranch {
                             branch: expected_base.clone(),
@@ -1195,81 +1548,35 @@ fn compute_sync_plan(
         }
     }
 
-    // Compute branch deletions (both strategies)
-    if !options.push_only {
-        let current_branch = git_repo.current_branch().unwrap_or_default();
-        let seen_shas = state.get_seen_shas(repo);
-
-        // Get branches fully merged into origin/trunk (Strategy B)
-        let merged_into_main = get_merged_branches(&local.trunk).unwrap_or_default();
-
-        // Track which branches we're already deleting to avoid duplicates
-        let mut branches_to_delete: HashSet<String> = HashSet::new();
-
-        // Strategy A: PR-based deletion with seen SHA verification
-        // For squash/rebase merged PRs where the branch tip won't be an ancestor of main
-        for branch_name in local.branches.keys() {
-            // Skip trunk
-            if branch_name == &local.trunk {
-                continue;
-            }
-
-            // Skip if currently checked out
-            if branch_name == &current_branch {
-                continue;
-            }
-
-            // Check if this branch has a merged PR
-            if let Some(closed_pr) = remote.closed_prs.get(branch_name)
-                && closed_pr.state == RemotePrState::Merged
-            {
-                // Check if remote branch is deleted (fetch --prune already ran)
-                let remote_ref = format!("{}/{}", DEFAULT_REMOTE, branch_name);
-                if !git_repo.ref_exists(&remote_ref) {
-                    // Check if local HEAD SHA is in seen set
-                    if let Ok(local_sha) = git_repo.sha(branch_name)
-                        && let Some(seen) = seen_shas
-                        && seen.contains(&local_sha)
-                    {
-                        branches_to_delete.insert(branch_name.clone());
-                        local_changes.push(LocalChange::DeleteLocalBranch {
-                            name: branch_name.clone(),
-                            reason: DeleteReason::SeenOnRemote {
-                                verified_sha: local_sha,
-                            },
-                        });
-                    }
-                }
+    // Last, so any retarget off a pruned branch goes first.
+    let mut pruned: HashSet<String> = HashSet::new();
+    if options.prune_remote && !options.pull_only {
+        for change in remote_branch_prunes(
+            git_repo,
+            local,
+            remote,
+            state.get_seen_shas(repo),
+            &remote_changes,
+        ) {
+            if let RemoteChange::DeleteRemoteBranch { branch, .. } = &change {
+                pruned.insert(branch.clone());
             }
+            remote_changes.push(change);
         }
+    }
 
-        // Strategy B: Git merge-based deletion
-        // For merge-commit merges where branch tip IS an ancestor of main
-        for branch_name in &merged_into_main {
-            // Skip trunk
-            if branch_name == &local.trunk {
-                continue;
-            }
-
-            // Skip if currently checked out
-            if branch_name == &current_branch {
-                continue;
-            }
-
-            // Skip if already marked for deletion by Strategy A
-            if branches_to_delete.contains(branch_name) {
-                continue;
-            }
-
-            // Only delete if it's a tracked branch (in our local state)
-            if local.branches.contains_key(branch_name) {
-                branches_to_delete.insert(branch_name.clone());
-                local_changes.push(LocalChange::DeleteLocalBranch {
-                    name: branch_name.clone(),
-                    reason: DeleteReason::MergedIntoMain,
-                });
-            }
-        }
+    // Compute branch deletions (both strategies)
+    if !options.push_only {
+        let current_branch = git_repo.current_branch().unwrap_or_default();
+        let (deletions, branches_to_delete) = merged_branch_deletions(
+            git_repo,
+            local,
+            &remote.closed_prs,
+            state.get_seen_shas(repo),
+            &current_branch,
+            &pruned,
+        );
+        local_changes.extend(deletions);
 
         // Strategy C: Local branch is ancestor of origin/<branch>
         // All local work has been pushed, safe to delete local branch
@@ -1291,7 +1598,7 @@ fn compute_sync_plan(
 
             // Check if local branch is ancestor of origin/<branch>
             let remote_ref = format!("{}/{}", DEFAULT_REMOTE, branch_name);
-            if git_repo.ref_exists(&remote_ref)
+            if git_repo.remote_branch_exists(DEFAULT_REMOTE, branch_name)
                 && let Ok(true) = git_repo.is_ancestor(branch_name, &remote_ref)
             {
                 local_changes.push(LocalChange::DeleteLocalBranch {
@@ -1302,6 +1609,19 @@ fn compute_sync_plan(
         }
     }
 
+    // A branch the plan pushes won't be stale once it's applied.
+    let pushing: HashSet<&str> = remote_changes
+        .iter()
+        .filter_map(|change| match change {
+            RemoteChange::Push { branch, .. } => Some(branch.as_str()),
+            _ => None,
+        })
+        .collect();
+    let stale_pr_heads = find_stale_pr_heads(git_repo, local, remote)
+        .into_iter()
+        .filter(|head| !pushing.contains(head.branch.as_str()))
+        .collect();
+
     SyncPlan {
         local_changes,
         remote_changes,
@@ -1311,76 +1631,444 @@ fn compute_sync_plan(
             .map(|(name, _)| name.clone())
             .collect(),
         branches_to_delete,
+        stale_pr_heads,
     }
 }
 
-/// Topologically sort branches so parents come before children.
-/// Uses Kahn's algorithm for topological sorting.
-fn topological_sort_branches(branches: &[(String, String)], trunk: &str) -> Vec<(String, String)> {
-    if branches.is_empty() {
-        return Vec::new();
+/// Whether sync should push `branch`'s local commits to its remote-tracking branch, and if so
+/// whether to force: `Some(false)` fast-forwards, `Some(true)` force-pushes (with lease) an
+/// `apply_merge` branch whose restack rewrote it. `None` when the remote already matches, when the
+/// local branch is only behind (pushing would drop the remote's commits), or when a `merge` branch
+/// has diverged, since its history is never rewritten and a force push would lose work.
+///
+/// A diverged `apply_merge` branch is only forced when its remote tip is in `seen_remote_shas`
+/// (git-stack pushed or synced it). Otherwise someone else pushed it, so this adds a warning and
+/// skips the push instead.
+fn push_needed(
+    git_repo: &GitRepo,
+    state: &State,
+    repo: &str,
+    branch: &str,
+    warnings: &mut Vec<String>,
+) -> Option<bool> {
+    let remote_ref = format!("{DEFAULT_REMOTE}/{branch}");
+    if !git_repo.branch_exists(branch)
+        || !git_repo.remote_branch_exists(DEFAULT_REMOTE, branch)
+        || git_repo.shas_match(&remote_ref, branch)
+    {
+        return None;
     }
-
-    // Build a map of branch -> parent
-    let branch_to_parent: HashMap<&str, &str> = branches
-        .iter()
-        .map(|(b, p)| (b.as_str(), p.as_str()))
-        .collect();
-
-    // Build set of all branches we're mounting
-    let branches_set: HashSet<&str> = branches.iter().map(|(b, _)| b.as_str()).collect();
-
-    // Compute in-degree for each branch (how many branches depend on it being mounted first)
-    // A branch has in-degree > 0 if its parent is also in the set of branches to mount
-    let mut in_degree: HashMap<&str, usize> = HashMap::new();
-    for (branch, parent) in branches {
-        in_degree.entry(branch.as_str()).or_insert(0);
-        if branches_set.contains(parent.as_str()) {
-            *in_degree.entry(branch.as_str()).or_insert(0) += 1;
-        }
+    if git_repo.is_ancestor(branch, &remote_ref).unwrap_or(true) {
+        return None;
+    }
+    if git_repo.is_ancestor(&remote_ref, branch).unwrap_or(false) {
+        return Some(false);
+    }
+    let stack_method = state
+        .get_tree_branch(repo, branch)
+        .map(|b| b.stack_method)
+        .unwrap_or_default();
+    if !matches!(stack_method, StackMethod::ApplyMerge) {
+        return None;
+    }
+    let remote_sha = git_repo.sha(&remote_ref).ok()?;
+    if !state
+        .get_seen_shas(repo)
+        .is_some_and(|seen| seen.contains(&remote_sha))
+    {
+        warnings.push(format!(
+            "Not force-pushing '{branch}': {remote_ref} has commits git-stack hasn't seen \
+             (pushed from elsewhere?). Bring them into '{branch}', or push it yourself with \
+             `git push --force-with-lease`."
+        ));
+        return None;
     }
+    Some(true)
+}
 
-    // Start with branches whose parent is trunk or already mounted (in-degree 0)
-    let mut queue: Vec<&str> = in_degree
+/// Pushed branches whose local tip is neither their open PR's head nor `origin/<branch>`: local
+/// commits (typically a restack) the PR doesn't show yet. Checking the just-fetched remote ref too
+/// keeps a stale cached PR head from raising a false alarm.
+fn find_stale_pr_heads(
+    git_repo: &GitRepo,
+    local: &LocalState,
+    remote: &RemoteState,
+) -> Vec<StalePrHead> {
+    let mut stale: Vec<StalePrHead> = local
+        .branches
         .iter()
-        .filter(|(_, deg)| **deg == 0)
-        .map(|(b, _)| *b)
+        .filter(|(name, branch)| *name != &local.trunk && branch.pushed_to_remote)
+        .filter_map(|(name, branch)| {
+            let local_sha = branch.sha.as_ref()?;
+            let pr = remote.prs.get(name)?;
+            if pr.head_sha == *local_sha
+                || git_repo.shas_match(&format!("{DEFAULT_REMOTE}/{name}"), name)
+            {
+                return None;
+            }
+            Some(StalePrHead {
+                branch: name.clone(),
+                pr_number: pr.number,
+                local_sha: local_sha.clone(),
+                pr_sha: pr.head_sha.clone(),
+            })
+        })
         .collect();
+    stale.sort_by(|a, b| a.branch.cmp(&b.branch));
+    stale
+}
 
-    // Sort for deterministic output
-    queue.sort();
+/// Local branches whose work has landed on trunk, as deletions (plus their names). Shared by
+/// `sync` and `cleanup --merged`; never selects trunk or `current_branch`.
+///
+/// - Strategy A (squash/rebase merges, where the tip isn't an ancestor of trunk): the branch has
+///   a merged PR in `closed_prs`, its remote branch is gone (or in `pruned`, which this sync
+///   deletes), and its tip is a seen remote SHA.
+/// - Strategy B (merge commits): the branch is tracked and in `git branch --merged origin/<trunk>`.
+pub(crate) fn merged_branch_deletions(
+    git_repo: &GitRepo,
+    local: &LocalState,
+    closed_prs: &HashMap<String, RemotePr>,
+    seen_shas: Option<&HashSet<String>>,
+    current_branch: &str,
+    pruned: &HashSet<String>,
+) -> (Vec<LocalChange>, HashSet<String>) {
+    // Get branches fully merged into origin/trunk (Strategy B)
+    let merged_into_main = get_merged_branches(&local.trunk).unwrap_or_default();
 
-    let mut result = Vec::new();
+    // Track which branches we're already deleting to avoid duplicates
+    let mut deletions = Vec::new();
+    let mut branches_to_delete: HashSet<String> = HashSet::new();
 
-    while let Some(branch) = queue.pop() {
-        // Add this branch to result
-        if let Some(&parent) = branch_to_parent.get(branch) {
-            result.push((branch.to_string(), parent.to_string()));
+    // Strategy A: PR-based deletion with seen SHA verification
+    // For squash/rebase merged PRs where the branch tip won't be an ancestor of main
+    for branch_name in local.branches.keys() {
+        // Skip trunk
+        if branch_name == &local.trunk {
+            continue;
         }
 
-        // Decrease in-degree for branches that depend on this one
-        for (child, parent) in branches {
-            if parent == branch
-                && let Some(deg) = in_degree.get_mut(child.as_str())
+        // Skip if currently checked out
+        if branch_name == current_branch {
+            continue;
+        }
+
+        // Check if this branch has a merged PR
+        if let Some(closed_pr) = closed_prs.get(branch_name)
+            && closed_pr.state == RemotePrState::Merged
+        {
+            // Check if remote branch is deleted (fetch --prune already ran)
+            if pruned.contains(branch_name)
+                || !git_repo.remote_branch_exists(DEFAULT_REMOTE, branch_name)
             {
-                *deg -= 1;
-                if *deg == 0 {
-                    queue.push(child.as_str());
-                    queue.sort(); // Keep sorted for determinism
+                // Check if local HEAD SHA is in seen set
+                if let Ok(local_sha) = git_repo.sha(branch_name)
+                    && let Some(seen) = seen_shas
+                    && seen.contains(&local_sha)
+                {
+                    branches_to_delete.insert(branch_name.clone());
+                    deletions.push(LocalChange::DeleteLocalBranch {
+                        name: branch_name.clone(),
+                        reason: DeleteReason::SeenOnRemote {
+                            verified_sha: local_sha,
+                        },
+                    });
                 }
             }
         }
     }
 
-    result
+    // Strategy B: Git merge-based deletion
+    // For merge-commit merges where branch tip IS an ancestor of main
+    for branch_name in &merged_into_main {
+        // Skip trunk
+        if branch_name == &local.trunk {
+            continue;
+        }
+
+        // Skip if currently checked out
+        if branch_name == current_branch {
+            continue;
+        }
+
+        // Skip if already marked for deletion by Strategy A
+        if branches_to_delete.contains(branch_name) {
+            continue;
+        }
+
+        // Only delete if it's a tracked branch (in our local state)
+        if local.branches.contains_key(branch_name) {
+            branches_to_delete.insert(branch_name.clone());
+            deletions.push(LocalChange::DeleteLocalBranch {
+                name: branch_name.clone(),
+                reason: DeleteReason::MergedIntoMain,
+            });
+        }
+    }
+
+    (deletions, branches_to_delete)
+}
+
+/// `sync --prune-remote`: delete the remote branch of each tracked branch whose PR was merged,
+/// given the evidence Strategy A of [`merged_branch_deletions`] wants for a local deletion: the
+/// remote branch is still exactly the merged PR's head, and that head is a seen SHA. A branch that
+/// is the base of an open PR is kept unless `planned` retargets that PR, and the deletion then
+/// depends on that retarget.
+fn remote_branch_prunes(
+    git_repo: &GitRepo,
+    local: &LocalState,
+    remote: &RemoteState,
+    seen_shas: Option<&HashSet<String>>,
+    planned: &[RemoteChange],
+) -> Vec<RemoteChange> {
+    let retargeted: HashSet<u64> = planned
+        .iter()
+        .filter_map(|change| match change {
+            RemoteChange::RetargetPr { number, .. } => Some(*number),
+            _ => None,
+        })
+        .collect();
+    let mut branches: Vec<&String> = local
+        .branches
+        .keys()
+        .filter(|name| **name != local.trunk && !remote.prs.contains_key(*name))
+        .filter(|name| {
+            let Some(pr) = remote.closed_prs.get(*name) else {
+                return false;
+            };
+            pr.state == RemotePrState::Merged
+                && git_repo.remote_branch_exists(DEFAULT_REMOTE, name)
+                && git_repo
+                    .sha(&format!("refs/remotes/{DEFAULT_REMOTE}/{name}"))
+                    .is_ok_and(|sha| {
+                        sha == pr.head_sha && seen_shas.is_some_and(|seen| seen.contains(&sha))
+                    })
+                && !remote
+                    .prs
+                    .values()
+                    .any(|open| open.base == **name && !retargeted.contains(&open.number))
+        })
+        .collect();
+    branches.sort();
+    branches
+        .into_iter()
+        .map(|branch| {
+            let mut retargets: Vec<u64> = remote
+                .prs
+                .values()
+                .filter(|open| open.base == *branch)
+                .map(|open| open.number)
+                .collect();
+            retargets.sort();
+            RemoteChange::DeleteRemoteBranch {
+                branch: branch.clone(),
+                retargets,
+            }
+        })
+        .collect()
+}
+
+/// Topologically sort branches so parents come before children.
+/// Uses Kahn's algorithm for topological sorting.
+fn topological_sort_branches(branches: &[(String, String)], trunk: &str) -> Vec<(String, String)> {
+    if branches.is_empty() {
+        return Vec::new();
+    }
+
+    // Build a map of branch -> parent
+    let branch_to_parent: HashMap<&str, &str> = branches
+        .iter()
+        .map(|(b, p)| (b.as_str(), p.as_str()))
+        .collect();
+
+    // Build set of all branches we're mounting
+    let branches_set: HashSet<&str> = branches.iter().map(|(b, _)| b.as_str()).collect();
+
+    // Compute in-degree for each branch (how many branches depend on it being mounted first)
+    // A branch has in-degree > 0 if its parent is also in the set of branches to mount
+    let mut in_degree: HashMap<&str, usize> = HashMap::new();
+    for (branch, parent) in branches {
+        in_degree.entry(branch.as_str()).or_insert(0);
+        if branches_set.contains(parent.as_str()) {
+            *in_degree.entry(branch.as_str()).or_insert(0) += 1;
+        }
+    }
+
+    // Start with branches whose parent is trunk or already mounted (in-degree 0)
+    let mut queue: Vec<&str> = in_degree
+        .iter()
+        .filter(|(_, deg)| **deg == 0)
+        .map(|(b, _)| *b)
+        .collect();
+
+    // Sort for deterministic output
+    queue.sort();
+
+    let mut result = Vec::new();
+
+    while let Some(branch) = queue.pop() {
+        // Add this branch to result
+        if let Some(&parent) = branch_to_parent.get(branch) {
+            result.push((branch.to_string(), parent.to_string()));
+        }
+
+        // Decrease in-degree for branches that depend on this one
+        for (child, parent) in branches {
+            if parent == branch
+                && let Some(deg) = in_degree.get_mut(child.as_str())
+            {
+                *deg -= 1;
+                if *deg == 0 {
+                    queue.push(child.as_str());
+                    queue.sort(); // Keep sorted for determinism
+                }
+            }
+        }
+    }
+
+    result
 }
 
 // ============== Stage 4: Validate Functions ==============
 
-/// Validate the sync plan for safety
-fn validate_plan(plan: &SyncPlan) -> Result<()> {
-    // Currently we don't have any validations that would fail
-    // Future: check for potential data loss scenarios
+/// Whether `branch`'s local tip is preserved somewhere other than the local ref, so deleting the
+/// local branch loses no work: reachable from origin/trunk, recorded in the seen-SHA set (a PR head
+/// GitHub has seen), or an ancestor of its own remote-tracking branch. A branch that doesn't exist
+/// locally has nothing to lose.
+fn local_tip_is_preserved(
+    git_repo: &GitRepo,
+    branch: &str,
+    origin_trunk: &str,
+    seen_shas: Option<&HashSet<String>>,
+) -> bool {
+    let Ok(sha) = git_repo.sha(branch) else {
+        return true;
+    };
+    if seen_shas.is_some_and(|seen| seen.contains(&sha)) {
+        return true;
+    }
+    if git_repo.is_ancestor(&sha, origin_trunk).unwrap_or(false) {
+        return true;
+    }
+    let remote_ref = format!("{}/{}", DEFAULT_REMOTE, branch);
+    git_repo.remote_branch_exists(DEFAULT_REMOTE, branch)
+        && git_repo.is_ancestor(&sha, &remote_ref).unwrap_or(false)
+}
+
+/// Validate the sync plan for safety. Individually unsafe changes are dropped from the plan and
+/// recorded in `plan.warnings` (so `--dry-run` shows them); a plan whose topology would be left
+/// inconsistent is rejected outright.
+///
+/// - A local branch deletion is dropped unless its tip is preserved (see
+///   `local_tip_is_preserved`).
+/// - A PR retarget is dropped unless its new base exists on the remote or an earlier change in the
+///   plan pushes it.
+/// - A remote branch deletion is dropped unless every retarget it depends on survives.
+/// - Unmounting a branch while a tracked child's open PR is still based on it, with no retarget
+///   for that PR, is an error.
+fn validate_plan(
+    git_repo: &GitRepo,
+    plan: &mut SyncPlan,
+    local: &LocalState,
+    remote: &RemoteState,
+    seen_shas: Option<&HashSet<String>>,
+) -> Result<()> {
+    let origin_trunk = format!("{}/{}", DEFAULT_REMOTE, local.trunk);
+
+    // Lossy deletions: refuse to emit them, keep the rest of the plan.
+    plan.local_changes.retain(|change| match change {
+        LocalChange::DeleteLocalBranch { name, .. }
+            if !local_tip_is_preserved(git_repo, name, &origin_trunk, seen_shas) =>
+        {
+            plan.warnings.push(format!(
+                "Not deleting local branch '{}': its tip is not on trunk, the remote, or any seen PR head",
+                name
+            ));
+            false
+        }
+        _ => true,
+    });
+    plan.branches_to_delete.retain(|name| {
+        let preserved = local_tip_is_preserved(git_repo, name, &origin_trunk, seen_shas);
+        if !preserved {
+            plan.warnings.push(format!(
+                "Not deleting local branch '{}': it has commits not found on trunk, the remote, or any seen PR head",
+                name
+            ));
+        }
+        preserved
+    });
+
+    // Retargets onto a base the remote doesn't have (and that nothing in the plan pushes first)
+    // would just fail against the API mid-apply.
+    let mut pushed: HashSet<String> = HashSet::new();
+    let mut retargeted: HashSet<u64> = HashSet::new();
+    plan.remote_changes.retain(|change| match change {
+        RemoteChange::PushBranch { branch } | RemoteChange::Push { branch, .. } => {
+            pushed.insert(branch.clone());
+            true
+        }
+        RemoteChange::RetargetPr {
+            number, new_base, ..
+        } => {
+            let available = pushed.contains(new_base)
+                || git_repo.remote_branch_exists(DEFAULT_REMOTE, new_base);
+            if !available {
+                plan.warnings.push(format!(
+                    "Not retargeting PR #{}: base '{}' does not exist on {}",
+                    number, new_base, DEFAULT_REMOTE
+                ));
+            } else {
+                retargeted.insert(*number);
+            }
+            available
+        }
+        // Deleting a base whose PRs weren't retargeted off it would close them.
+        RemoteChange::DeleteRemoteBranch { branch, retargets } => {
+            let unblocked = retargets.iter().all(|number| retargeted.contains(number));
+            if !unblocked {
+                plan.warnings.push(format!(
+                    "Not deleting '{}' from {}: an open PR is still based on it",
+                    branch, DEFAULT_REMOTE
+                ));
+            }
+            unblocked
+        }
+    });
+
+    // An unmount must not strand a surviving child's open PR on the removed base.
+    let unmounted: HashSet<&str> = plan
+        .local_changes
+        .iter()
+        .filter_map(|change| match change {
+            LocalChange::UnmountBranch { name, .. } => Some(name.as_str()),
+            _ => None,
+        })
+        .collect();
+    let mut errors: Vec<String> = remote
+        .prs
+        .iter()
+        .filter(|(child, pr)| {
+            unmounted.contains(pr.base.as_str())
+                && !unmounted.contains(child.as_str())
+                && local.branches.contains_key(child.as_str())
+                && !retargeted.contains(&pr.number)
+        })
+        .map(|(child, pr)| {
+            format!(
+                "PR #{} for '{}' is based on '{}', which this plan unmounts, but nothing retargets it",
+                pr.number, child, pr.base
+            )
+        })
+        .collect();
+
+    if !errors.is_empty() {
+        errors.sort();
+        bail!(
+            "Refusing to apply an unsafe sync plan:\n  - {}",
+            errors.join("\n  - ")
+        );
+    }
     Ok(())
 }
 
@@ -1391,10 +2079,13 @@ fn apply_plan(
     git_repo: &GitRepo,
     state: &mut State,
     repo: &str,
-    client: &GitHubClient,
+    client: &dyn RemoteProvider,
     repo_id: &RepoIdentifier,
     plan: &SyncPlan,
+    interactive: bool,
 ) -> Result<()> {
+    let mut confirmer = ChangeConfirmer::new(interactive);
+
     // If current branch is being unmounted, checkout a safe ancestor first
     if !plan.branches_to_unmount.is_empty() {
         let current_branch = git_repo.current_branch().unwrap_or_default();
@@ -1429,15 +2120,39 @@ fn apply_plan(
 
     // Apply local changes first (checkout, mount, update pr_number)
     for change in &plan.local_changes {
-        apply_local_change(git_repo, state, repo, change)?;
+        if confirmer.confirm(&describe_local_change(change)) {
+            apply_local_change(git_repo, state, repo, change)?;
+        }
     }
 
     // Save state after local changes
     state.save_state()?;
 
     // Apply remote changes (retarget PRs, push intermediate branches)
+    let mut retargeted: HashSet<u64> = HashSet::new();
     for change in &plan.remote_changes {
-        apply_remote_change(client, repo_id, change)?;
+        // A declined retarget leaves its PR based on the branch, so the branch must stay.
+        if let RemoteChange::DeleteRemoteBranch { branch, retargets } = change
+            && !retargets.iter().all(|number| retargeted.contains(number))
+        {
+            println!(
+                "  Keeping '{}' on remote: an open PR is still based on it",
+                branch.yellow()
+            );
+            continue;
+        }
+        if confirmer.confirm(&describe_remote_change(change)) {
+            apply_remote_change(client, repo_id, change)?;
+            if let RemoteChange::RetargetPr { number, .. } = change {
+                retargeted.insert(*number);
+            }
+            // Seen on remote, so a later sync may force-push over it after a restack.
+            if let RemoteChange::PushBranch { branch } | RemoteChange::Push { branch, .. } = change
+                && let Ok(sha) = git_repo.sha(branch)
+            {
+                state.add_seen_sha(repo, sha);
+            }
+        }
     }
 
     // Save state again if PR numbers were updated
@@ -1445,7 +2160,13 @@ fn apply_plan(
 
     // Delete local branches that are safe to delete (work preserved on remote)
     for branch_name in &plan.branches_to_delete {
-        if git_repo.branch_exists(branch_name) {
+        if git_repo.branch_exists(branch_name)
+            && confirmer.confirm(&format!(
+                "{} local branch '{}'",
+                "Delete".red().bold(),
+                branch_name.red()
+            ))
+        {
             println!("Deleting local branch {}...", branch_name.yellow());
             if let Err(e) = run_git(&["branch", "-D", branch_name]) {
                 tracing::warn!("Failed to delete local branch {}: {}", branch_name, e);
@@ -1457,7 +2178,7 @@ fn apply_plan(
 }
 
 /// Remove a branch from the git-stack tree, repointing its children to the given parent.
-fn unmount_branch_from_tree(
+pub(crate) fn unmount_branch_from_tree(
     git_repo: &GitRepo,
     state: &mut State,
     repo: &str,
@@ -1466,7 +2187,7 @@ fn unmount_branch_from_tree(
 ) -> Result<()> {
     // Find all children of this branch and repoint them
     let children: Vec<String> = if let Some(tree) = state.get_tree(repo) {
-        if let Some(branch) = find_branch_by_name(tree, name) {
+        if let Some(branch) = tree.find(name) {
             branch.branches.iter().map(|b| b.name.clone()).collect()
         } else {
             Vec::new()
@@ -1520,7 +2241,7 @@ fn apply_local_change(
                 pr_number.to_string().green()
             );
             if let Some(tree) = state.get_tree_mut(repo)
-                && let Some(b) = find_branch_by_name_mut(tree, branch)
+                && let Some(b) = tree.find_mut(branch)
             {
                 b.pr_number = Some(*pr_number);
             }
@@ -1565,10 +2286,7 @@ fn apply_local_change(
             // For AncestorOfRemote, we keep the branch in tree since remote still exists
             if !matches!(reason, DeleteReason::AncestorOfRemote) {
                 // A prior UnmountBranch in the same plan may have already removed it.
-                let still_in_tree = state
-                    .get_tree(repo)
-                    .and_then(|t| find_branch_by_name(t, name))
-                    .is_some();
+                let still_in_tree = state.get_tree(repo).and_then(|t| t.find(name)).is_some();
                 if still_in_tree {
                     let repoint_to = state
                         .get_parent_branch_of(repo, name)
@@ -1586,7 +2304,7 @@ fn apply_local_change(
 
 /// Apply a single remote change
 fn apply_remote_change(
-    client: &GitHubClient,
+    client: &dyn RemoteProvider,
     repo_id: &RepoIdentifier,
     change: &RemoteChange,
 ) -> Result<()> {
@@ -1627,15 +2345,109 @@ fn apply_remote_change(
                 &format!("{}:{}", branch, branch),
             ])?;
         }
+        RemoteChange::Push { branch, force } => {
+            println!("  Pushing '{}' to remote", branch.yellow());
+            let refspec = format!("{branch}:{branch}");
+            let mut args = vec!["push"];
+            if *force {
+                args.push("--force-with-lease");
+            }
+            args.extend([DEFAULT_REMOTE, refspec.as_str()]);
+            run_git(&args)?;
+        }
+        RemoteChange::DeleteRemoteBranch { branch, .. } => {
+            println!("  Deleting '{}' from remote", branch.yellow());
+            client
+                .delete_branch(repo_id, branch)
+                .map_err(|e| anyhow!("{}", e))?;
+            // What the next `fetch --prune` would do.
+            run_git(&[
+                "update-ref",
+                "-d",
+                &format!("refs/remotes/{DEFAULT_REMOTE}/{branch}"),
+            ])?;
+        }
     }
     Ok(())
 }
 
+/// One-line description of a local change, shared by `print_plan` and the `--interactive` prompt.
+fn describe_local_change(change: &LocalChange) -> String {
+    match change {
+        LocalChange::MountBranch { name, parent } => {
+            format!("Mount '{}' on '{}'", name.yellow(), parent.green())
+        }
+        LocalChange::UnmountBranch {
+            name,
+            repoint_children_to,
+        } => format!(
+            "Unmount '{}' (children → '{}')",
+            name.yellow(),
+            repoint_children_to.green()
+        ),
+        LocalChange::UpdatePrNumber { branch, pr_number } => format!(
+            "Update PR# for '{}' → #{}",
+            branch.yellow(),
+            pr_number.to_string().green()
+        ),
+        LocalChange::DeleteLocalBranch { name, reason } => {
+            let reason_str = match reason {
+                DeleteReason::SeenOnRemote { verified_sha } => {
+                    format!(
+                        "SHA {} verified on remote",
+                        &verified_sha[..8.min(verified_sha.len())]
+                    )
+                }
+                DeleteReason::MergedIntoMain => "merged into main".to_string(),
+                DeleteReason::AncestorOfRemote => "ancestor of remote".to_string(),
+            };
+            format!(
+                "{} local branch '{}' ({})",
+                "Delete".red().bold(),
+                name.red(),
+                reason_str
+            )
+        }
+    }
+}
+
+/// One-line description of a remote change, shared by `print_plan` and the `--interactive`
+/// prompt.
+fn describe_remote_change(change: &RemoteChange) -> String {
+    match change {
+        RemoteChange::RetargetPr {
+            number,
+            branch,
+            old_base,
+            new_base,
+        } => format!(
+            "Retarget PR #{} for '{}': {} → {}",
+            number.to_string().green(),
+            branch.yellow(),
+            old_base.red(),
+            new_base.green()
+        ),
+        RemoteChange::PushBranch { branch } => format!("Push '{}' to remote", branch.yellow()),
+        RemoteChange::Push { branch, force } => format!(
+            "Push '{}' to remote{}",
+            branch.yellow(),
+            if *force { " (force-with-lease)" } else { "" }
+        ),
+        RemoteChange::DeleteRemoteBranch { branch, .. } => format!(
+            "{} '{}' from remote (PR merged)",
+            "Delete".red().bold(),
+            branch.red()
+        ),
+    }
+}
+
 /// Print the sync plan
 fn print_plan(plan: &SyncPlan, dry_run: bool) {
     let prefix = if dry_run { "[dry-run] " } else { "" };
 
-    if plan.is_empty() {
+    print_stale_pr_heads(&plan.stale_pr_heads);
+
+    if plan.is_empty() && plan.warnings.is_empty() {
         return;
     }
 
@@ -1644,71 +2456,14 @@ fn print_plan(plan: &SyncPlan, dry_run: bool) {
     if !plan.local_changes.is_empty() {
         println!("  Local changes:");
         for change in &plan.local_changes {
-            match change {
-                LocalChange::MountBranch { name, parent } => {
-                    println!("    - Mount '{}' on '{}'", name.yellow(), parent.green());
-                }
-                LocalChange::UnmountBranch {
-                    name,
-                    repoint_children_to,
-                } => {
-                    println!(
-                        "    - Unmount '{}' (children → '{}')",
-                        name.yellow(),
-                        repoint_children_to.green()
-                    );
-                }
-                LocalChange::UpdatePrNumber { branch, pr_number } => {
-                    println!(
-                        "    - Update PR# for '{}' → #{}",
-                        branch.yellow(),
-                        pr_number.to_string().green()
-                    );
-                }
-                LocalChange::DeleteLocalBranch { name, reason } => {
-                    let reason_str = match reason {
-                        DeleteReason::SeenOnRemote { verified_sha } => {
-                            format!(
-                                "SHA {} verified on remote",
-                                &verified_sha[..8.min(verified_sha.len())]
-                            )
-                        }
-                        DeleteReason::MergedIntoMain => "merged into main".to_string(),
-                        DeleteReason::AncestorOfRemote => "ancestor of remote".to_string(),
-                    };
-                    println!(
-                        "    - {} local branch '{}' ({})",
-                        "Delete".red().bold(),
-                        name.red(),
-                        reason_str
-                    );
-                }
-            }
+            println!("    - {}", describe_local_change(change));
         }
     }
 
     if !plan.remote_changes.is_empty() {
         println!("  Remote changes:");
         for change in &plan.remote_changes {
-            match change {
-                RemoteChange::RetargetPr {
-                    number,
-                    branch,
-                    old_base,
-                    new_base,
-                } => {
-                    println!(
-                        "    - Retarget PR #{} for '{}': {} → {}",
-                        number.to_string().green(),
-                        branch.yellow(),
-                        old_base.red(),
-                        new_base.green()
-                    );
-                }
-                RemoteChange::PushBranch { branch } => {
-                    println!("    - Push '{}' to remote", branch.yellow());
-                }
-            }
+            println!("    - {}", describe_remote_change(change));
         }
     }
 
@@ -1720,30 +2475,27 @@ fn print_plan(plan: &SyncPlan, dry_run: bool) {
     }
 }
 
-/// Helper to find a branch by name in the tree (immutable)
-fn find_branch_by_name<'a>(tree: &'a Branch, name: &str) -> Option<&'a Branch> {
-    if tree.name == name {
-        return Some(tree);
-    }
-    for child in &tree.branches {
-        if let Some(found) = find_branch_by_name(child, name) {
-            return Some(found);
-        }
-    }
-    None
-}
-
-/// Helper to find a branch by name in the tree (mutable)
-fn find_branch_by_name_mut<'a>(tree: &'a mut Branch, name: &str) -> Option<&'a mut Branch> {
-    if tree.name == name {
-        return Some(tree);
+/// Called out ahead of the plan: a PR showing an old head is easy to miss and easy to merge.
+fn print_stale_pr_heads(stale: &[StalePrHead]) {
+    if stale.is_empty() {
+        return;
     }
-    for child in &mut tree.branches {
-        if let Some(found) = find_branch_by_name_mut(child, name) {
-            return Some(found);
-        }
+    println!(
+        "\n{}",
+        "Local branches that differ from their PR (not pushed):"
+            .yellow()
+            .bold()
+    );
+    for head in stale {
+        println!(
+            "  {} PR #{}: local {} ≠ PR head {}; push with `git push --force-with-lease {DEFAULT_REMOTE} {}`",
+            head.branch.yellow(),
+            head.pr_number,
+            &head.local_sha[..8.min(head.local_sha.len())],
+            &head.pr_sha[..8.min(head.pr_sha.len())],
+            head.branch,
+        );
     }
-    None
 }
 
 #[cfg(test)]
@@ -1756,7 +2508,7 @@ mod tests {
         process::{Command, Stdio},
     };
 
-    use crate::state::RepoState;
+    use crate::state::{RepoState, STATE_VERSION};
 
     fn init_sync_test_repo(dir: &Path) {
         let git = |args: &[&str]| {
@@ -1843,6 +2595,7 @@ mod tests {
             parent: parent.map(String::from),
             pr_number: None,
             pushed_to_remote: true,
+            sha: None,
         }
     }
 
@@ -1853,6 +2606,7 @@ mod tests {
             state: RemotePrState::Open,
             title: format!("PR #{number}"),
             html_url: format!("https://example.test/pr/{number}"),
+            head_sha: String::new(),
         }
     }
 
@@ -1895,6 +2649,7 @@ mod tests {
 
     fn state_with_tree(repo: &str, tree: Branch) -> State {
         State {
+            version: STATE_VERSION,
             repos: [(repo.to_string(), RepoState::new(tree))]
                 .into_iter()
                 .collect(),
@@ -2058,6 +2813,457 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_change_confirm_accepts_short_and_long_answers() {
+        assert_eq!(parse_change_confirm("y\n"), Some(ChangeConfirm::Yes));
+        assert_eq!(parse_change_confirm(" YES "), Some(ChangeConfirm::Yes));
+        assert_eq!(parse_change_confirm("n"), Some(ChangeConfirm::No));
+        assert_eq!(parse_change_confirm("a"), Some(ChangeConfirm::All));
+        assert_eq!(parse_change_confirm("quit"), Some(ChangeConfirm::Quit));
+        assert_eq!(parse_change_confirm(""), None);
+        assert_eq!(parse_change_confirm("maybe"), None);
+    }
+
+    #[test]
+    fn non_interactive_confirmer_approves_everything() {
+        let mut confirmer = ChangeConfirmer::new(false);
+        assert!(confirmer.confirm("Mount 'a' on 'main'"));
+        assert!(confirmer.confirm("Delete local branch 'a'"));
+    }
+
+    fn plan_of(local_changes: Vec<LocalChange>, remote_changes: Vec<RemoteChange>) -> SyncPlan {
+        SyncPlan {
+            local_changes,
+            remote_changes,
+            warnings: Vec::new(),
+            branches_to_unmount: Vec::new(),
+            branches_to_delete: Vec::new(),
+            stale_pr_heads: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn push_needed_follows_divergence_and_stack_method() {
+        let _state_home = redirect_sync_test_state_home();
+        let dir = tempfile::tempdir().unwrap();
+        init_sync_test_repo(dir.path());
+        let set_remote = |name: &str| {
+            test_git(
+                dir.path(),
+                &["update-ref", &format!("refs/remotes/origin/{name}"), name],
+            )
+        };
+        // Each branch starts with one pushed commit.
+        for name in [
+            "same",
+            "ahead",
+            "behind",
+            "rewritten",
+            "rewritten_elsewhere",
+            "merge_rewritten",
+        ] {
+            test_git(dir.path(), &["checkout", "-q", "-b", name, "main"]);
+            commit_test_file(dir.path(), &format!("{name}.txt"), "v1\n", name);
+            set_remote(name);
+        }
+        test_git(dir.path(), &["checkout", "-q", "ahead"]);
+        commit_test_file(dir.path(), "ahead.txt", "v2\n", "ahead");
+        test_git(dir.path(), &["checkout", "-q", "behind"]);
+        commit_test_file(dir.path(), "behind.txt", "v2\n", "behind");
+        set_remote("behind");
+        test_git(dir.path(), &["reset", "-q", "--hard", "HEAD~1"]);
+        for name in ["rewritten", "rewritten_elsewhere", "merge_rewritten"] {
+            test_git(dir.path(), &["checkout", "-q", name]);
+            test_git(
+                dir.path(),
+                &[
+                    "commit",
+                    "-q",
+                    "--amend",
+                    "-m",
+                    &format!("{name} (restacked)"),
+                ],
+            );
+        }
+        test_git(dir.path(), &["checkout", "-q", "main"]);
+
+        let git_repo =
+            GitRepo::open_with_cache_at(dir.path(), &dir.path().join("mb_cache.redb")).unwrap();
+        let repo = dir.path().display().to_string();
+        let mut main = Branch::new("main".to_string(), None);
+        for name in [
+            "same",
+            "ahead",
+            "behind",
+            "rewritten",
+            "rewritten_elsewhere",
+            "merge_rewritten",
+        ] {
+            let mut branch = Branch::new(name.to_string(), None);
+            if name == "merge_rewritten" {
+                branch.stack_method = StackMethod::Merge;
+            }
+            main.branches.push(branch);
+        }
+        let mut state = state_with_tree(&repo, main);
+        for name in ["rewritten", "merge_rewritten"] {
+            let remote_sha = git_repo.sha(&format!("origin/{name}")).unwrap();
+            state.add_seen_sha(&repo, remote_sha);
+        }
+
+        let mut warnings = Vec::new();
+        let mut needed = |name: &str| push_needed(&git_repo, &state, &repo, name, &mut warnings);
+        assert_eq!(needed("same"), None);
+        assert_eq!(needed("ahead"), Some(false));
+        assert_eq!(
+            needed("behind"),
+            None,
+            "pushing would drop the remote's commit"
+        );
+        assert_eq!(needed("rewritten"), Some(true));
+        assert_eq!(
+            needed("merge_rewritten"),
+            None,
+            "merge branches are never forced"
+        );
+        assert_eq!(
+            needed("rewritten_elsewhere"),
+            None,
+            "a remote tip git-stack never saw is not forced over"
+        );
+        assert_eq!(needed("missing"), None);
+        assert_eq!(warnings.len(), 1, "{warnings:?}");
+        assert!(
+            warnings[0].starts_with("Not force-pushing 'rewritten_elsewhere'"),
+            "{warnings:?}"
+        );
+    }
+
+    #[test]
+    fn stale_pr_heads_flag_only_unpushed_local_commits() {
+        let _state_home = redirect_sync_test_state_home();
+        let dir = tempfile::tempdir().unwrap();
+        init_sync_test_repo(dir.path());
+        // `restacked` gained a commit after its push; `pushed` was pushed, but the cached PR head
+        // is older; `synced` matches its PR.
+        for name in ["restacked", "pushed", "synced"] {
+            test_git(dir.path(), &["checkout", "-q", "-b", name, "main"]);
+            commit_test_file(dir.path(), &format!("{name}.txt"), "v1\n", name);
+            test_git(
+                dir.path(),
+                &["update-ref", &format!("refs/remotes/origin/{name}"), name],
+            );
+        }
+        let sha = |name: &str| test_git_output(dir.path(), &["rev-parse", name]);
+        let restacked_pr = sha("restacked");
+        let pushed_pr = sha("pushed");
+        for name in ["restacked", "pushed"] {
+            test_git(dir.path(), &["checkout", "-q", name]);
+            commit_test_file(dir.path(), &format!("{name}.txt"), "v2\n", "more");
+        }
+        test_git(
+            dir.path(),
+            &["update-ref", "refs/remotes/origin/pushed", "pushed"],
+        );
+        test_git(dir.path(), &["checkout", "-q", "main"]);
+
+        let git_repo =
+            GitRepo::open_with_cache_at(dir.path(), &dir.path().join("mb_cache.redb")).unwrap();
+        let mut local = local_state(
+            "main",
+            &[
+                ("main", None),
+                ("restacked", Some("main")),
+                ("pushed", Some("main")),
+                ("synced", Some("main")),
+            ],
+        );
+        for (name, branch) in local.branches.iter_mut() {
+            branch.sha = Some(test_git_output(dir.path(), &["rev-parse", name]));
+        }
+        let mut remote = remote_state(&[
+            ("restacked", "main", 1, "me"),
+            ("pushed", "main", 2, "me"),
+            ("synced", "main", 3, "me"),
+        ]);
+        remote.prs.get_mut("restacked").unwrap().head_sha = restacked_pr.clone();
+        remote.prs.get_mut("pushed").unwrap().head_sha = pushed_pr;
+        remote.prs.get_mut("synced").unwrap().head_sha = sha("synced");
+
+        assert_eq!(
+            find_stale_pr_heads(&git_repo, &local, &remote),
+            vec![StalePrHead {
+                branch: "restacked".to_string(),
+                pr_number: 1,
+                local_sha: sha("restacked"),
+                pr_sha: restacked_pr,
+            }]
+        );
+    }
+
+    #[test]
+    fn prune_remote_deletes_only_merged_heads_nothing_else_needs() {
+        let _state_home = redirect_sync_test_state_home();
+        let dir = tempfile::tempdir().unwrap();
+        init_sync_test_repo(dir.path());
+        for name in ["done", "moved", "unseen", "base", "child"] {
+            test_git(dir.path(), &["checkout", "-q", "-b", name, "main"]);
+            commit_test_file(dir.path(), &format!("{name}.txt"), "v1\n", name);
+            test_git(
+                dir.path(),
+                &["update-ref", &format!("refs/remotes/origin/{name}"), name],
+            );
+        }
+        let sha = |name: &str| test_git_output(dir.path(), &["rev-parse", name]);
+        // Someone pushed to `moved` after its PR merged.
+        let moved_head = sha("moved");
+        test_git(dir.path(), &["checkout", "-q", "moved"]);
+        commit_test_file(dir.path(), "moved.txt", "v2\n", "after merge");
+        test_git(
+            dir.path(),
+            &["update-ref", "refs/remotes/origin/moved", "moved"],
+        );
+        test_git(dir.path(), &["checkout", "-q", "main"]);
+
+        let git_repo =
+            GitRepo::open_with_cache_at(dir.path(), &dir.path().join("mb_cache.redb")).unwrap();
+        let local = local_state(
+            "main",
+            &[
+                ("main", None),
+                ("done", Some("main")),
+                ("moved", Some("main")),
+                ("unseen", Some("main")),
+                ("base", Some("main")),
+                ("child", Some("base")),
+            ],
+        );
+        let mut remote = remote_state(&[("child", "base", 5, "me")]);
+        for (number, name) in [(1, "done"), (2, "moved"), (3, "unseen"), (4, "base")] {
+            let head_sha = if name == "moved" {
+                moved_head.clone()
+            } else {
+                sha(name)
+            };
+            remote.closed_prs.insert(
+                name.to_string(),
+                RemotePr {
+                    head_sha,
+                    ..merged_remote_pr(number, "main")
+                },
+            );
+        }
+        // `unseen`'s head isn't a seen SHA.
+        let seen: HashSet<String> = [sha("done"), sha("base"), moved_head].into_iter().collect();
+
+        let pruned = |planned: &[RemoteChange]| -> Vec<String> {
+            remote_branch_prunes(&git_repo, &local, &remote, Some(&seen), planned)
+                .into_iter()
+                .map(|change| match change {
+                    RemoteChange::DeleteRemoteBranch { branch, .. } => branch,
+                    other => panic!("unexpected {other:?}"),
+                })
+                .collect()
+        };
+        assert_eq!(pruned(&[]), vec!["done"]);
+        let retarget = RemoteChange::RetargetPr {
+            number: 5,
+            branch: "child".to_string(),
+            old_base: "base".to_string(),
+            new_base: "main".to_string(),
+        };
+        assert_eq!(pruned(&[retarget]), vec!["base", "done"]);
+
+        // The local copy of a pruned branch goes in the same sync.
+        let (deletions, _) = merged_branch_deletions(
+            &git_repo,
+            &local,
+            &remote.closed_prs,
+            Some(&seen),
+            "main",
+            &scope_of(&["done"]),
+        );
+        let deleted: Vec<&str> = deletions
+            .iter()
+            .filter_map(|change| match change {
+                LocalChange::DeleteLocalBranch { name, .. } => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(deleted, vec!["done"]);
+    }
+
+    #[test]
+    fn validate_plan_drops_deletions_that_would_lose_work() {
+        let _state_home = redirect_sync_test_state_home();
+        let dir = tempfile::tempdir().unwrap();
+        init_sync_test_repo(dir.path());
+        test_git(dir.path(), &["branch", "merged"]);
+        test_git(dir.path(), &["checkout", "-q", "-b", "unpushed"]);
+        commit_test_file(dir.path(), "wip.txt", "wip\n", "unpushed work");
+        test_git(dir.path(), &["checkout", "-q", "-b", "seen"]);
+        commit_test_file(dir.path(), "seen.txt", "seen\n", "seen work");
+        let seen_sha = test_git_output(dir.path(), &["rev-parse", "seen"]);
+        test_git(dir.path(), &["checkout", "-q", "main"]);
+
+        let git_repo =
+            GitRepo::open_with_cache_at(dir.path(), &dir.path().join("mb_cache.redb")).unwrap();
+        let local = local_state("main", &[("main", None)]);
+        let remote = remote_state(&[]);
+        let delete = |name: &str| LocalChange::DeleteLocalBranch {
+            name: name.to_string(),
+            reason: DeleteReason::MergedIntoMain,
+        };
+        let mut plan = plan_of(
+            vec![delete("merged"), delete("unpushed"), delete("seen")],
+            Vec::new(),
+        );
+        plan.branches_to_delete = vec!["unpushed".to_string(), "merged".to_string()];
+        let seen: HashSet<String> = [seen_sha].into_iter().collect();
+
+        validate_plan(&git_repo, &mut plan, &local, &remote, Some(&seen)).unwrap();
+
+        let deleted: Vec<&str> = plan
+            .local_changes
+            .iter()
+            .filter_map(|change| match change {
+                LocalChange::DeleteLocalBranch { name, .. } => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(deleted, vec!["merged", "seen"]);
+        assert_eq!(plan.branches_to_delete, vec!["merged".to_string()]);
+        assert_eq!(plan.warnings.len(), 2);
+        assert!(plan.warnings.iter().all(|w| w.contains("'unpushed'")));
+    }
+
+    #[test]
+    fn validate_plan_drops_retarget_onto_missing_remote_base() {
+        let _state_home = redirect_sync_test_state_home();
+        let dir = tempfile::tempdir().unwrap();
+        init_sync_test_repo(dir.path());
+        let git_repo =
+            GitRepo::open_with_cache_at(dir.path(), &dir.path().join("mb_cache.redb")).unwrap();
+        let local = local_state("main", &[("main", None)]);
+        let remote = remote_state(&[]);
+        let retarget = |number: u64, new_base: &str| RemoteChange::RetargetPr {
+            number,
+            branch: format!("b{number}"),
+            old_base: "old".to_string(),
+            new_base: new_base.to_string(),
+        };
+        let mut plan = plan_of(
+            Vec::new(),
+            vec![
+                retarget(1, "main"),
+                retarget(2, "nowhere"),
+                RemoteChange::PushBranch {
+                    branch: "pushed-first".to_string(),
+                },
+                retarget(3, "pushed-first"),
+            ],
+        );
+
+        validate_plan(&git_repo, &mut plan, &local, &remote, None).unwrap();
+
+        let numbers: Vec<u64> = plan
+            .remote_changes
+            .iter()
+            .filter_map(|change| match change {
+                RemoteChange::RetargetPr { number, .. } => Some(*number),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(numbers, vec![1, 3]);
+        assert_eq!(plan.warnings.len(), 1);
+        assert!(plan.warnings[0].contains("PR #2"));
+    }
+
+    #[test]
+    fn validate_plan_keeps_a_remote_branch_whose_retarget_is_dropped() {
+        let _state_home = redirect_sync_test_state_home();
+        let dir = tempfile::tempdir().unwrap();
+        init_sync_test_repo(dir.path());
+        let git_repo =
+            GitRepo::open_with_cache_at(dir.path(), &dir.path().join("mb_cache.redb")).unwrap();
+        let local = local_state("main", &[("main", None)]);
+        let remote = remote_state(&[]);
+        let retarget = |number: u64, new_base: &str| RemoteChange::RetargetPr {
+            number,
+            branch: format!("b{number}"),
+            old_base: format!("base{number}"),
+            new_base: new_base.to_string(),
+        };
+        let delete = |branch: &str, retargets: Vec<u64>| RemoteChange::DeleteRemoteBranch {
+            branch: branch.to_string(),
+            retargets,
+        };
+        let mut plan = plan_of(
+            Vec::new(),
+            vec![
+                retarget(1, "main"),
+                retarget(2, "nowhere"),
+                delete("base1", vec![1]),
+                delete("base2", vec![2]),
+                delete("both", vec![1, 2]),
+                delete("leaf", Vec::new()),
+            ],
+        );
+
+        validate_plan(&git_repo, &mut plan, &local, &remote, None).unwrap();
+
+        let deleted: Vec<&str> = plan
+            .remote_changes
+            .iter()
+            .filter_map(|change| match change {
+                RemoteChange::DeleteRemoteBranch { branch, .. } => Some(branch.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(deleted, vec!["base1", "leaf"]);
+        assert_eq!(plan.warnings.len(), 3, "{:?}", plan.warnings);
+        assert!(plan.warnings[1].contains("'base2'"));
+        assert!(plan.warnings[2].contains("'both'"));
+    }
+
+    #[test]
+    fn validate_plan_rejects_unmount_that_strands_child_pr() {
+        let _state_home = redirect_sync_test_state_home();
+        let dir = tempfile::tempdir().unwrap();
+        init_sync_test_repo(dir.path());
+        let git_repo =
+            GitRepo::open_with_cache_at(dir.path(), &dir.path().join("mb_cache.redb")).unwrap();
+        let local = local_state(
+            "main",
+            &[
+                ("main", None),
+                ("parent", Some("main")),
+                ("child", Some("parent")),
+            ],
+        );
+        let remote = remote_state(&[("child", "parent", 22, "alice")]);
+        let unmount = LocalChange::UnmountBranch {
+            name: "parent".to_string(),
+            repoint_children_to: "main".to_string(),
+        };
+
+        let mut stranded = plan_of(vec![unmount.clone()], Vec::new());
+        let err = validate_plan(&git_repo, &mut stranded, &local, &remote, None).unwrap_err();
+        assert!(err.to_string().contains("PR #22 for 'child'"));
+
+        let mut retargeted = plan_of(
+            vec![unmount],
+            vec![RemoteChange::RetargetPr {
+                number: 22,
+                branch: "child".to_string(),
+                old_base: "parent".to_string(),
+                new_base: "main".to_string(),
+            }],
+        );
+        validate_plan(&git_repo, &mut retargeted, &local, &remote, None).unwrap();
+        assert_eq!(retargeted.remote_changes.len(), 1);
+    }
+
     #[test]
     fn planner_lets_parent_removal_preserve_child_lkg_after_multi_commit_squash() {
         let _state_home = redirect_sync_test_state_home();
@@ -2301,6 +3507,7 @@ mod tests {
             let mut main_branch = Branch::new("main".to_string(), None);
             main_branch.branches.push(branch_a);
             let mut state = State {
+                version: STATE_VERSION,
                 repos: [(repo.clone(), RepoState::new(main_branch))]
                     .into_iter()
                     .collect(),
@@ -2369,6 +3576,7 @@ mod tests {
         let mut main_branch = Branch::new("main".to_string(), None);
         main_branch.branches.push(parent);
         let mut state = State {
+            version: STATE_VERSION,
             repos: [(repo.clone(), RepoState::new(main_branch))]
                 .into_iter()
                 .collect(),
@@ -2444,11 +3652,13 @@ mod tests {
                 ref_name: base.to_string(),
                 sha: String::new(),
                 repo: repo(),
+                label: None,
             },
             head: PrBranchRef {
                 ref_name: head.to_string(),
                 sha: format!("sha-{head}"),
                 repo: repo(),
+                label: None,
             },
             user: PrUser {
                 login: author.to_string(),
@@ -2460,6 +3670,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn reopened_prs_leave_the_closed_set() {
+        let closed_pr = |head: &str, number: u64| PullRequest {
+            state: PrState::Closed,
+            ..discovered_pr(head, "main", number, "wbbradley")
+        };
+        let mut closed: HashMap<String, PullRequest> = [
+            ("reopened".to_string(), closed_pr("reopened", 1)),
+            ("reused".to_string(), closed_pr("reused", 2)),
+            ("done".to_string(), closed_pr("done", 3)),
+        ]
+        .into_iter()
+        .collect();
+        let open: HashMap<String, PullRequest> = [
+            (
+                "reopened".to_string(),
+                discovered_pr("reopened", "main", 1, "wbbradley"),
+            ),
+            (
+                "reused".to_string(),
+                discovered_pr("reused", "main", 9, "wbbradley"),
+            ),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(evict_reopened_prs(&open, &mut closed), ["reopened"]);
+        let mut left: Vec<&str> = closed.keys().map(String::as_str).collect();
+        left.sort();
+        assert_eq!(left, ["done", "reused"]);
+    }
+
     #[test]
     fn merge_discovered_prs_seeds_scope_and_enables_injection() {
         // Trunk-only tree, empty remote scope — the on-trunk case the feature targets.
@@ -1,11 +1,12 @@
 //! TUI application state and rendering.
 
+use std::collections::HashSet;
 use std::io::{self, Stdout};
 use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use crossterm::{
-    event::{self, Event},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
@@ -18,18 +19,24 @@ use ratatui::{
     widgets::{Block, Borders, List, ListItem, ListState},
 };
 
-use super::input::{AppAction, handle_event};
+use super::{
+    input::{AppAction, InputMode, handle_event},
+    preview::{BranchPreview, render_preview},
+};
 use crate::{
     github::PrDisplayState,
     render::{
         RenderableBranch, RenderableTree,
-        colors::{string_to_color, theme},
+        colors::{Theme, ThemeColor, string_to_color},
     },
 };
 
 /// How long a transient status message remains visible.
 const STATUS_MESSAGE_TTL: Duration = Duration::from_secs(2);
 
+/// Rows kept visible above/below the selection when the list scrolls.
+const SCROLL_PADDING: usize = 2;
+
 /// TUI application state.
 pub struct App {
     /// The renderable tree data.
@@ -42,19 +49,37 @@ pub struct App {
     pub checkout_branch: Option<String>,
     /// Whether to show verbose details.
     pub verbose: bool,
+    /// Colors to draw with.
+    pub theme: Theme,
     /// List state for ratatui.
     list_state: ListState,
     /// Transient status/error message shown in the help bar, with its expiry time.
     status_message: Option<(String, Instant)>,
+    /// Search query; when non-empty only matching branches are listed.
+    filter: String,
+    /// Whether keystrokes are currently editing `filter`.
+    searching: bool,
+    /// Indices into `tree.branches` of branches whose subtrees are folded away.
+    collapsed: HashSet<usize>,
+    /// Indices into `tree.branches` of the rows currently listed.
+    visible: Vec<usize>,
+    /// Whether the diff preview pane is shown.
+    show_preview: bool,
+    /// The preview for the named branch (or why it couldn't be loaded), reused until the
+    /// selection moves to another branch.
+    preview: Option<(String, Result<BranchPreview, String>)>,
+    /// Where the branch list was last drawn, for mapping mouse clicks to rows.
+    list_area: Rect,
 }
 
 impl App {
     /// Create a new App from a renderable tree.
-    pub fn new(tree: RenderableTree, verbose: bool) -> Self {
+    pub fn new(tree: RenderableTree, verbose: bool, theme: Theme) -> Self {
         // Start cursor at current branch if present, else 0
         let cursor = tree.current_branch_index.unwrap_or(0);
         let mut list_state = ListState::default();
         list_state.select(Some(cursor));
+        let visible = (0..tree.branches.len()).collect();
 
         Self {
             tree,
@@ -62,30 +87,155 @@ impl App {
             should_quit: false,
             checkout_branch: None,
             verbose,
+            theme,
             list_state,
             status_message: None,
+            filter: String::new(),
+            searching: false,
+            collapsed: HashSet::new(),
+            visible,
+            show_preview: false,
+            preview: None,
+            list_area: Rect::default(),
+        }
+    }
+
+    /// How the next keystroke should be interpreted.
+    pub fn input_mode(&self) -> InputMode {
+        if self.searching {
+            InputMode::Search
+        } else if !self.filter.is_empty() {
+            InputMode::Filtered
+        } else {
+            InputMode::Navigate
         }
     }
 
+    /// The branch under the cursor, if it is listed.
+    fn selected_branch(&self) -> Option<&RenderableBranch> {
+        self.visible
+            .contains(&self.cursor)
+            .then(|| self.tree.branches.get(self.cursor))
+            .flatten()
+    }
+
     /// Move cursor up.
     pub fn move_up(&mut self) {
-        if self.cursor > 0 {
-            self.cursor -= 1;
-            self.list_state.select(Some(self.cursor));
+        if let Some(&prev) = self.visible.iter().rev().find(|&&i| i < self.cursor) {
+            self.cursor = prev;
+            self.sync_list_selection();
         }
     }
 
     /// Move cursor down.
     pub fn move_down(&mut self) {
-        if self.cursor < self.tree.branches.len().saturating_sub(1) {
-            self.cursor += 1;
-            self.list_state.select(Some(self.cursor));
+        if let Some(&next) = self.visible.iter().find(|&&i| i > self.cursor) {
+            self.cursor = next;
+            self.sync_list_selection();
+        }
+    }
+
+    /// Load the preview for the selected branch if the pane is shown and it isn't cached yet.
+    fn update_preview(&mut self, load: &mut dyn FnMut(&str) -> Result<BranchPreview>) {
+        if !self.show_preview {
+            return;
+        }
+        let Some(name) = self.selected_branch().map(|branch| branch.name.clone()) else {
+            self.preview = None;
+            return;
+        };
+        if self
+            .preview
+            .as_ref()
+            .is_some_and(|(cached, _)| *cached == name)
+        {
+            return;
+        }
+        let preview = load(&name).map_err(|e| e.to_string());
+        self.preview = Some((name, preview));
+    }
+
+    /// Whether the branch at `index` has children (the next row is deeper).
+    fn has_children(&self, index: usize) -> bool {
+        let branches = &self.tree.branches;
+        branches
+            .get(index + 1)
+            .is_some_and(|next| next.depth > branches[index].depth)
+    }
+
+    /// Fold the selected branch's subtree, or unfold it if already folded. Leaves are unaffected.
+    fn toggle_collapse(&mut self) {
+        if !self.visible.contains(&self.cursor) || !self.has_children(self.cursor) {
+            return;
+        }
+        if !self.collapsed.remove(&self.cursor) {
+            self.collapsed.insert(self.cursor);
+        }
+        self.apply_filter();
+    }
+
+    /// Recompute the listed rows, keeping the cursor on a listed branch: the same one if it is
+    /// still listed, else the next listed row below it, else the last one. With a search filter
+    /// every match is listed; otherwise rows under a collapsed branch are hidden.
+    fn apply_filter(&mut self) {
+        let query = self.filter.to_lowercase();
+        // Depth of the collapsed branch whose subtree we're currently inside, if any.
+        let mut folded_at: Option<usize> = None;
+        let mut visible = Vec::new();
+        for (i, branch) in self.tree.branches.iter().enumerate() {
+            if !query.is_empty() {
+                if matches_query(branch, &query) {
+                    visible.push(i);
+                }
+                continue;
+            }
+            if folded_at.is_some_and(|depth| branch.depth > depth) {
+                continue;
+            }
+            folded_at = self.collapsed.contains(&i).then_some(branch.depth);
+            visible.push(i);
+        }
+        self.visible = visible;
+        if !self.visible.contains(&self.cursor)
+            && let Some(&nearest) = self
+                .visible
+                .iter()
+                .find(|&&i| i > self.cursor)
+                .or(self.visible.last())
+        {
+            self.cursor = nearest;
+        }
+        self.sync_list_selection();
+    }
+
+    /// Point the list widget at the cursor's row within the listed rows.
+    fn sync_list_selection(&mut self) {
+        self.list_state
+            .select(self.visible.iter().position(|&i| i == self.cursor));
+    }
+
+    /// Highlight the branch drawn at `(column, row)`; a click on the highlighted branch checks it
+    /// out. Clicks outside the list are ignored.
+    fn click(&mut self, column: u16, row: u16) {
+        let area = self.list_area;
+        if !(area.x..area.right()).contains(&column) || !(area.y..area.bottom()).contains(&row) {
+            return;
+        }
+        let position = self.list_state.offset() + usize::from(row - area.y);
+        let Some(&index) = self.visible.get(position) else {
+            return;
+        };
+        if index == self.cursor {
+            self.select();
+        } else {
+            self.cursor = index;
+            self.sync_list_selection();
         }
     }
 
     /// Select the current branch for checkout.
     pub fn select(&mut self) {
-        if let Some(branch) = self.tree.branches.get(self.cursor) {
+        if let Some(branch) = self.selected_branch() {
             self.checkout_branch = Some(branch.name.clone());
             self.should_quit = true;
         }
@@ -103,8 +253,23 @@ impl App {
             .branches
             .get(self.cursor)
             .map(|branch| branch.name.clone());
+        // Folds are keyed by row; carry them across by name.
+        let collapsed_names: HashSet<String> = self
+            .collapsed
+            .iter()
+            .filter_map(|&i| self.tree.branches.get(i))
+            .map(|branch| branch.name.clone())
+            .collect();
 
         self.tree = new_tree;
+        self.collapsed = self
+            .tree
+            .branches
+            .iter()
+            .enumerate()
+            .filter(|(_, branch)| collapsed_names.contains(&branch.name))
+            .map(|(i, _)| i)
+            .collect();
         self.cursor = selected_branch
             .and_then(|name| {
                 self.tree
@@ -115,8 +280,8 @@ impl App {
             .or(self.tree.current_branch_index)
             .unwrap_or(self.cursor)
             .min(self.tree.branches.len().saturating_sub(1));
-        self.list_state
-            .select((!self.tree.branches.is_empty()).then_some(self.cursor));
+        self.apply_filter();
+        self.preview = None;
         self.status_message = None;
     }
 
@@ -135,7 +300,7 @@ impl App {
 
     /// Resolve the PR URL for the selected branch, or set a status message if none.
     fn selected_pr_url(&mut self) -> Option<String> {
-        match self.tree.branches.get(self.cursor) {
+        match self.selected_branch() {
             Some(b) => match &b.pr_info {
                 Some(pr) => Some(pr.html_url.clone()),
                 None => {
@@ -164,8 +329,26 @@ impl App {
             AppAction::MoveUp => self.move_up(),
             AppAction::MoveDown => self.move_down(),
             AppAction::Select => self.select(),
+            AppAction::Click { column, row } => self.click(column, row),
             AppAction::OpenInBrowser => self.open_selected_in_browser(),
             AppAction::Refresh => {}
+            AppAction::TogglePreview => self.show_preview = !self.show_preview,
+            AppAction::ToggleCollapse => self.toggle_collapse(),
+            AppAction::StartSearch => self.searching = true,
+            AppAction::SearchInput(c) => {
+                self.filter.push(c);
+                self.apply_filter();
+            }
+            AppAction::SearchBackspace => {
+                self.filter.pop();
+                self.apply_filter();
+            }
+            AppAction::EndSearch => self.searching = false,
+            AppAction::ClearSearch => {
+                self.searching = false;
+                self.filter.clear();
+                self.apply_filter();
+            }
             AppAction::Quit => self.quit(),
             AppAction::None => {}
         }
@@ -179,7 +362,7 @@ type Terminal = ratatui::Terminal<CrosstermBackend<Stdout>>;
 fn setup_terminal() -> Result<Terminal> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let terminal = ratatui::Terminal::new(backend)?;
     Ok(terminal)
@@ -188,22 +371,29 @@ fn setup_terminal() -> Result<Terminal> {
 /// Restore the terminal to normal mode.
 fn restore_terminal(terminal: &mut Terminal) -> Result<()> {
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
     terminal.show_cursor()?;
     Ok(())
 }
 
-/// Run the TUI application. Returns the branch to checkout, if any.
+/// Run the TUI application. Returns the branch to checkout, if any. `preview` loads the diff
+/// preview for a branch when the pane is open.
 pub fn run_tui(
     tree: RenderableTree,
     verbose: bool,
+    theme: Theme,
     refresh: &mut dyn FnMut() -> Result<RenderableTree>,
+    preview: &mut dyn FnMut(&str) -> Result<BranchPreview>,
 ) -> Result<Option<String>> {
     let mut terminal = setup_terminal()?;
-    let mut app = App::new(tree, verbose);
+    let mut app = App::new(tree, verbose, theme);
 
     // Main event loop
-    let result = run_event_loop(&mut terminal, &mut app, refresh);
+    let result = run_event_loop(&mut terminal, &mut app, refresh, preview);
 
     // Always restore terminal, even on error
     restore_terminal(&mut terminal)?;
@@ -216,14 +406,16 @@ fn run_event_loop(
     terminal: &mut Terminal,
     app: &mut App,
     refresh: &mut dyn FnMut() -> Result<RenderableTree>,
+    preview: &mut dyn FnMut(&str) -> Result<BranchPreview>,
 ) -> Result<()> {
     while !app.should_quit {
+        app.update_preview(preview);
         terminal.draw(|frame| render(frame, app))?;
 
         // Wait for an event with a timeout
         if event::poll(std::time::Duration::from_millis(100))? {
             let event = event::read()?;
-            let action = handle_event(event);
+            let action = handle_event(event, app.input_mode());
             if action == AppAction::Refresh {
                 match refresh() {
                     Ok(tree) => app.apply_refreshed_tree(tree),
@@ -240,47 +432,100 @@ fn run_event_loop(
 /// Render the TUI.
 fn render(frame: &mut Frame, app: &mut App) {
     let area = frame.area();
+    let theme = &app.theme.clone();
 
     // Create the main block with border
     let block = Block::default()
         .title(" git-stack status ")
         .title_style(
             Style::default()
-                .fg(Color::Cyan)
+                .fg(fixed(theme, Color::Cyan))
                 .add_modifier(Modifier::BOLD),
         )
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Rgb(theme::TREE.0, theme::TREE.1, theme::TREE.2)));
+        .border_style(Style::default().fg(apply_dim(theme, theme.tree, 1.0)));
 
     let inner_area = block.inner(area);
     frame.render_widget(block, area);
 
-    // Create list items from branches
+    let (list_area, preview_area) = if app.show_preview {
+        let [list_area, preview_area] =
+            Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)])
+                .areas(inner_area);
+        (list_area, Some(preview_area))
+    } else {
+        (inner_area, None)
+    };
+
+    // Create list items from the listed (filter-matching) branches
     let items: Vec<ListItem> = app
-        .tree
-        .branches
+        .visible
         .iter()
-        .enumerate()
-        .map(|(i, branch)| render_branch_item(branch, i == app.cursor, app.verbose))
+        .map(|&i| {
+            let fold = app
+                .has_children(i)
+                .then(|| app.collapsed.contains(&i) && app.filter.is_empty());
+            render_branch_item(
+                &app.tree.branches[i],
+                i == app.cursor,
+                fold,
+                app.verbose,
+                theme,
+            )
+        })
         .collect();
 
-    let list = List::new(items).highlight_style(
-        Style::default()
-            .bg(Color::Rgb(40, 40, 45))
-            .add_modifier(Modifier::BOLD),
-    );
+    // The stateful list scrolls its offset to keep the selection (plus some context) in view.
+    let list = List::new(items)
+        .highlight_style(
+            Style::default()
+                .bg(fixed(theme, Color::Rgb(40, 40, 45)))
+                .add_modifier(Modifier::BOLD),
+        )
+        .scroll_padding(SCROLL_PADDING);
+
+    frame.render_stateful_widget(list, list_area, &mut app.list_state);
+    app.list_area = list_area;
 
-    frame.render_stateful_widget(list, inner_area, &mut app.list_state);
+    if let Some(preview_area) = preview_area {
+        let branch = app.selected_branch().map(|branch| branch.name.as_str());
+        let preview = app
+            .preview
+            .as_ref()
+            .filter(|(name, _)| Some(name.as_str()) == branch)
+            .map(|(_, preview)| preview);
+        render_preview(frame, preview_area, theme, branch, preview);
+    }
 
     // Render help text (or a transient status message) at bottom
-    render_help(frame, area, app.active_status());
+    render_help(
+        frame,
+        area,
+        theme,
+        app.active_status(),
+        app.searching,
+        &app.filter,
+    );
+}
+
+/// Case-insensitive substring match of `query` (already lowercased) against the branch name and
+/// its PR author. An empty query matches everything.
+fn matches_query(branch: &RenderableBranch, query: &str) -> bool {
+    query.is_empty()
+        || branch.name.to_lowercase().contains(query)
+        || branch
+            .pr_info
+            .as_ref()
+            .is_some_and(|pr| pr.author.to_lowercase().contains(query))
 }
 
-/// Render a single branch as a ListItem.
+/// Render a single branch as a ListItem. `fold` is `Some(collapsed)` for branches with children.
 fn render_branch_item(
     branch: &RenderableBranch,
     is_selected: bool,
+    fold: Option<bool>,
     verbose: bool,
+    theme: &Theme,
 ) -> ListItem<'static> {
     let dim = if branch.is_dimmed { 0.75 } else { 1.0 };
 
@@ -288,9 +533,12 @@ fn render_branch_item(
 
     // Arrow prefix: selection arrow takes precedence over HEAD indicator
     let arrow = if is_selected {
-        Span::styled("→ ", Style::default().fg(Color::White))
+        Span::styled("→ ", Style::default().fg(fixed(theme, Color::White)))
     } else if branch.is_current {
-        Span::styled("→ ", Style::default().fg(Color::Rgb(80, 80, 80))) // faint gray
+        Span::styled(
+            "→ ",
+            Style::default().fg(fixed(theme, Color::Rgb(80, 80, 80))),
+        ) // faint gray
     } else {
         Span::raw("  ") // spacing to maintain alignment
     };
@@ -300,19 +548,26 @@ fn render_branch_item(
     for _ in 0..branch.depth {
         spans.push(Span::styled(
             "┃ ",
-            Style::default().fg(Color::Rgb(theme::TREE.0, theme::TREE.1, theme::TREE.2)),
+            Style::default().fg(apply_dim(theme, theme.tree, 1.0)),
         ));
     }
 
+    // Fold marker
+    spans.push(match fold {
+        Some(true) => Span::styled("▸ ", Style::default().fg(fixed(theme, Color::White))),
+        Some(false) => Span::styled("▾ ", Style::default().fg(apply_dim(theme, theme.tree, 1.0))),
+        None => Span::raw("  "),
+    });
+
     // Branch name with status-based coloring
     let branch_color = if let Some(ref status) = branch.status {
         if status.is_descendent {
-            apply_dim(theme::GREEN, dim)
+            apply_dim(theme, theme.green, dim)
         } else {
-            apply_dim(theme::YELLOW, dim)
+            apply_dim(theme, theme.yellow, dim)
         }
     } else {
-        apply_dim(theme::GRAY, dim)
+        apply_dim(theme, theme.gray, dim)
     };
 
     let mut name_style = Style::default().fg(branch_color);
@@ -321,6 +576,14 @@ fn render_branch_item(
     }
     spans.push(Span::styled(branch.name.clone(), name_style));
 
+    // Mark tag
+    if let Some(mark) = branch.mark {
+        spans.push(Span::styled(
+            format!(" ({mark})"),
+            Style::default().fg(apply_dim(theme, theme.mark(mark), dim)),
+        ));
+    }
+
     // Diff stats
     if let Some(ref ds) = branch.diff_stats {
         let prefix = if ds.reliable { "" } else { "~ " };
@@ -328,11 +591,11 @@ fn render_branch_item(
         spans.push(Span::raw(prefix));
         spans.push(Span::styled(
             format!("+{}", ds.additions),
-            Style::default().fg(apply_dim(theme::GREEN, dim)),
+            Style::default().fg(apply_dim(theme, theme.green, dim)),
         ));
         spans.push(Span::styled(
             format!(" -{}", ds.deletions),
-            Style::default().fg(apply_dim(theme::RED, dim)),
+            Style::default().fg(apply_dim(theme, theme.red, dim)),
         ));
         spans.push(Span::raw("]"));
     }
@@ -344,7 +607,7 @@ fn render_branch_item(
         if ls.staged > 0 {
             parts.push(Span::styled(
                 format!("+{}", ls.staged),
-                Style::default().fg(apply_dim(theme::GREEN, dim)),
+                Style::default().fg(apply_dim(theme, theme.green, dim)),
             ));
         }
         if ls.unstaged > 0 {
@@ -353,7 +616,7 @@ fn render_branch_item(
             }
             parts.push(Span::styled(
                 format!("~{}", ls.unstaged),
-                Style::default().fg(apply_dim(theme::YELLOW, dim)),
+                Style::default().fg(apply_dim(theme, theme.yellow, dim)),
             ));
         }
         if ls.untracked > 0 {
@@ -362,28 +625,36 @@ fn render_branch_item(
             }
             parts.push(Span::styled(
                 format!("?{}", ls.untracked),
-                Style::default().fg(apply_dim(theme::GRAY, dim)),
+                Style::default().fg(apply_dim(theme, theme.gray, dim)),
             ));
         }
         spans.extend(parts);
         spans.push(Span::raw("]"));
     }
 
+    // Ahead/behind counts against the upstream
+    if let Some(label) = branch.status.as_ref().and_then(|s| s.ahead_behind_label()) {
+        spans.push(Span::styled(
+            format!(" {label}"),
+            Style::default().fg(apply_dim(theme, theme.upstream, dim)),
+        ));
+    }
+
     // PR info (non-verbose mode)
     if !verbose && let Some(ref pr) = branch.pr_info {
         let state_color = match pr.state {
-            PrDisplayState::Draft => apply_dim(theme::GRAY, dim),
-            PrDisplayState::Open => apply_dim(theme::GREEN, dim),
-            PrDisplayState::Merged => apply_dim(theme::PURPLE, dim),
-            PrDisplayState::Closed => apply_dim(theme::RED, dim),
+            PrDisplayState::Draft => apply_dim(theme, theme.gray, dim),
+            PrDisplayState::Open => apply_dim(theme, theme.green, dim),
+            PrDisplayState::Merged => apply_dim(theme, theme.purple, dim),
+            PrDisplayState::Closed => apply_dim(theme, theme.red, dim),
         };
 
         let author_rgb = string_to_color(&pr.author);
-        let author_color = apply_dim(author_rgb, dim);
+        let author_color = apply_dim(theme, author_rgb, dim);
 
         spans.push(Span::styled(
             " ",
-            Style::default().fg(apply_dim(theme::PR_ARROW, dim)),
+            Style::default().fg(apply_dim(theme, theme.pr_arrow, dim)),
         ));
         spans.push(Span::styled(
             format!("@{}", pr.author),
@@ -392,7 +663,7 @@ fn render_branch_item(
         spans.push(Span::raw(" "));
         spans.push(Span::styled(
             format!("#{}", pr.number),
-            Style::default().fg(apply_dim(theme::PR_NUMBER, dim)),
+            Style::default().fg(apply_dim(theme, theme.pr_number, dim)),
         ));
         spans.push(Span::raw(" "));
         spans.push(Span::styled(
@@ -405,24 +676,66 @@ fn render_branch_item(
 }
 
 /// Render help text (or a transient status message) at the bottom.
-fn render_help(frame: &mut Frame, area: Rect, status: Option<&str>) {
+fn render_help(
+    frame: &mut Frame,
+    area: Rect,
+    theme: &Theme,
+    status: Option<&str>,
+    searching: bool,
+    filter: &str,
+) {
+    let key_style = Style::default().fg(fixed(theme, Color::Yellow));
     let help_text = match status {
         Some(msg) => Line::from(vec![Span::styled(
             format!(" {msg}"),
-            Style::default().fg(Color::Red),
+            Style::default().fg(fixed(theme, Color::Red)),
         )]),
+        None if searching => Line::from(vec![
+            Span::styled(
+                format!(" /{filter}▏"),
+                Style::default().fg(fixed(theme, Color::Cyan)),
+            ),
+            Span::raw("  "),
+            Span::styled("Enter", key_style),
+            Span::raw(" keep filter  "),
+            Span::styled("Esc", key_style),
+            Span::raw(" clear"),
+        ]),
+        None if !filter.is_empty() => Line::from(vec![
+            Span::styled(
+                format!(" /{filter}"),
+                Style::default().fg(fixed(theme, Color::Cyan)),
+            ),
+            Span::raw("  "),
+            Span::styled("j/k", key_style),
+            Span::raw(" move  "),
+            Span::styled("Enter", key_style),
+            Span::raw(" checkout  "),
+            Span::styled("/", key_style),
+            Span::raw(" edit  "),
+            Span::styled("Esc", key_style),
+            Span::raw(" clear  "),
+            Span::styled("q", key_style),
+            Span::raw(" quit"),
+        ]),
         None => Line::from(vec![
-            Span::styled(" j/↓", Style::default().fg(Color::Yellow)),
+            Span::styled(" j/↓", Style::default().fg(fixed(theme, Color::Yellow))),
             Span::raw(" down  "),
-            Span::styled("k/↑", Style::default().fg(Color::Yellow)),
+            Span::styled("k/↑", Style::default().fg(fixed(theme, Color::Yellow))),
             Span::raw(" up  "),
-            Span::styled("Enter", Style::default().fg(Color::Yellow)),
+            Span::styled("Enter", Style::default().fg(fixed(theme, Color::Yellow))),
             Span::raw(" checkout  "),
-            Span::styled("o", Style::default().fg(Color::Yellow)),
+            Span::styled("o", Style::default().fg(fixed(theme, Color::Yellow))),
             Span::raw(" open  "),
-            Span::styled("r", Style::default().fg(Color::Yellow)),
+            Span::styled("r", Style::default().fg(fixed(theme, Color::Yellow))),
             Span::raw(" refresh  "),
-            Span::styled("q/Esc", Style::default().fg(Color::Yellow)),
+            Span::styled("Tab", Style::default().fg(fixed(theme, Color::Yellow))),
+            Span::raw(" fold  "),
+            Span::styled("p", Style::default().fg(fixed(theme, Color::Yellow))),
+            Span::raw(" preview  "),
+            Span::styled("/", Style::default().fg(fixed(theme, Color::Yellow))),
+            Span::raw(" search  "),
+            Span::styled("q/Esc", Style::default().fg(fixed(theme, Color::Yellow))),
             Span::raw(" quit"),
         ]),
     };
@@ -437,12 +750,25 @@ fn render_help(frame: &mut Frame, area: Rect, status: Option<&str>) {
     frame.render_widget(ratatui::widgets::Paragraph::new(help_text), help_area);
 }
 
-/// Apply dimming to a ThemeColor and convert to ratatui Color.
-fn apply_dim(color: crate::render::ThemeColor, factor: f32) -> Color {
+/// Apply dimming to a ThemeColor and convert to ratatui Color (the terminal's default color for a
+/// monochrome theme).
+fn apply_dim(theme: &Theme, color: ThemeColor, factor: f32) -> Color {
+    if theme.monochrome {
+        return Color::Reset;
+    }
     let dimmed = color.apply_dim(factor);
     Color::Rgb(dimmed.0, dimmed.1, dimmed.2)
 }
 
+/// A fixed UI accent color, dropped for a monochrome theme.
+fn fixed(theme: &Theme, color: Color) -> Color {
+    if theme.monochrome {
+        Color::Reset
+    } else {
+        color
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -462,7 +788,9 @@ mod tests {
             local_status: None,
             pr_info,
             note_preview: None,
+            mark: None,
             verbose: None,
+            elided_below: 0,
             index,
         }
     }
@@ -484,7 +812,7 @@ mod tests {
             branches: vec![with_pr, without_pr],
             current_branch_index: Some(0),
         };
-        App::new(tree, false)
+        App::new(tree, false, Theme::default())
     }
 
     #[test]
@@ -555,6 +883,160 @@ mod tests {
         assert_eq!(app.list_state.selected(), Some(1));
     }
 
+    fn type_query(app: &mut App, query: &str) {
+        app.handle_action(AppAction::StartSearch);
+        for c in query.chars() {
+            app.handle_action(AppAction::SearchInput(c));
+        }
+    }
+
+    #[test]
+    fn search_filters_by_name_and_pr_author() {
+        let mut app = app_with_two_branches();
+        type_query(&mut app, "B");
+        assert_eq!(app.visible, vec![1]);
+        assert_eq!(app.cursor, 1);
+        assert_eq!(app.list_state.selected(), Some(0));
+
+        app.handle_action(AppAction::ClearSearch);
+        type_query(&mut app, "octo");
+        assert_eq!(app.visible, vec![0]);
+        assert_eq!(app.cursor, 0);
+    }
+
+    #[test]
+    fn navigation_and_selection_stay_within_the_filter() {
+        let mut app = app_with_two_branches();
+        app.tree.branches.push(branch("feature-c", 2, None));
+        type_query(&mut app, "feature-");
+        app.handle_action(AppAction::SearchInput('c'));
+        assert_eq!(app.cursor, 2);
+        app.handle_action(AppAction::MoveUp);
+        assert_eq!(app.cursor, 2, "no listed branch above");
+
+        // Nothing matches: there is no selection to check out.
+        app.handle_action(AppAction::SearchInput('x'));
+        assert!(app.visible.is_empty());
+        assert_eq!(app.list_state.selected(), None);
+        app.handle_action(AppAction::Select);
+        assert!(app.checkout_branch.is_none());
+
+        // Esc brings every branch back with the cursor where it was.
+        app.handle_action(AppAction::ClearSearch);
+        assert_eq!(app.input_mode(), InputMode::Navigate);
+        assert_eq!(app.visible, vec![0, 1, 2]);
+        assert_eq!(app.list_state.selected(), Some(2));
+    }
+
+    /// main ─ a ─ a1 ─ a2, plus b at depth 1.
+    fn app_with_nested_stack() -> App {
+        let mut branches = vec![
+            branch("main", 0, None),
+            branch("a", 1, None),
+            branch("a1", 2, None),
+            branch("a2", 3, None),
+            branch("b", 4, None),
+        ];
+        for (b, depth) in branches.iter_mut().zip([0, 1, 2, 3, 1]) {
+            b.depth = depth;
+        }
+        App::new(
+            RenderableTree {
+                branches,
+                current_branch_index: Some(1),
+            },
+            false,
+            Theme::default(),
+        )
+    }
+
+    #[test]
+    fn collapsing_hides_the_subtree_and_navigation_skips_it() {
+        let mut app = app_with_nested_stack();
+        app.handle_action(AppAction::ToggleCollapse);
+        assert_eq!(app.visible, vec![0, 1, 4]);
+        app.handle_action(AppAction::MoveDown);
+        assert_eq!(app.tree.branches[app.cursor].name, "b");
+        assert_eq!(app.list_state.selected(), Some(2));
+
+        // Leaves don't fold.
+        app.handle_action(AppAction::ToggleCollapse);
+        assert_eq!(app.visible, vec![0, 1, 4]);
+
+        app.handle_action(AppAction::MoveUp);
+        app.handle_action(AppAction::ToggleCollapse);
+        assert_eq!(app.visible, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn search_reveals_matches_inside_collapsed_subtrees() {
+        let mut app = app_with_nested_stack();
+        app.handle_action(AppAction::ToggleCollapse);
+        type_query(&mut app, "a2");
+        assert_eq!(app.visible, vec![3]);
+        app.handle_action(AppAction::ClearSearch);
+        // The fold is still there once the filter is gone; the cursor lands on the next row.
+        assert_eq!(app.visible, vec![0, 1, 4]);
+        assert_eq!(app.tree.branches[app.cursor].name, "b");
+    }
+
+    #[test]
+    fn apply_refreshed_tree_keeps_folds_by_name() {
+        let mut app = app_with_nested_stack();
+        app.handle_action(AppAction::ToggleCollapse);
+        let mut tree = app_with_nested_stack().tree;
+        tree.branches.insert(1, branch("new", 0, None));
+        tree.branches[1].depth = 1;
+
+        app.apply_refreshed_tree(tree);
+
+        assert_eq!(app.collapsed, HashSet::from([2]));
+        assert_eq!(app.visible, vec![0, 1, 2, 5]);
+    }
+
+    #[test]
+    fn preview_loads_once_per_selected_branch() {
+        let mut app = app_with_two_branches();
+        let mut loads = Vec::new();
+        let mut load = |name: &str| -> Result<BranchPreview> {
+            loads.push(name.to_string());
+            anyhow::bail!("no diff for {name}")
+        };
+
+        // Hidden pane: nothing is loaded.
+        app.update_preview(&mut load);
+        app.handle_action(AppAction::TogglePreview);
+        app.update_preview(&mut load);
+        app.update_preview(&mut load);
+        app.handle_action(AppAction::MoveDown);
+        app.update_preview(&mut load);
+
+        let (name, preview) = app.preview.as_ref().unwrap();
+        assert_eq!(name, "feature-b");
+        assert_eq!(preview.as_ref().unwrap_err(), "no diff for feature-b");
+        assert_eq!(loads, vec!["feature-a", "feature-b"]);
+    }
+
+    #[test]
+    fn click_highlights_then_checks_out_the_clicked_row() {
+        let mut app = app_with_nested_stack();
+        app.list_area = Rect::new(1, 1, 40, 3);
+        *app.list_state.offset_mut() = 1;
+
+        // Row 2 is the second drawn row; with the list scrolled by one that's branch index 2.
+        app.handle_action(AppAction::Click { column: 5, row: 2 });
+        assert_eq!(app.cursor, 2);
+        assert!(app.checkout_branch.is_none());
+
+        // Outside the list: ignored.
+        app.handle_action(AppAction::Click { column: 5, row: 10 });
+        app.handle_action(AppAction::Click { column: 50, row: 2 });
+        assert_eq!(app.cursor, 2);
+
+        app.handle_action(AppAction::Click { column: 5, row: 2 });
+        assert_eq!(app.checkout_branch.as_deref(), Some("a1"));
+    }
+
     #[test]
     fn apply_refreshed_tree_handles_empty_tree() {
         let mut app = app_with_two_branches();
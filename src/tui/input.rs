@@ -1,6 +1,19 @@
 //! Keyboard event handling for TUI.
 
-use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+
+/// How keystrokes are interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputMode {
+    /// Plain navigation over the whole tree.
+    Navigate,
+    /// Navigation over a filtered tree; `Esc` clears the filter instead of quitting.
+    Filtered,
+    /// Typing a search query (after `/`).
+    Search,
+}
 
 /// Actions that can be triggered by user input.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -11,10 +24,27 @@ pub enum AppAction {
     MoveDown,
     /// Select the current item (checkout branch).
     Select,
+    /// Left click at a terminal cell: highlights the branch on that row, or checks it out if it
+    /// was already highlighted.
+    Click { column: u16, row: u16 },
     /// Open the selected branch's PR in the default browser.
     OpenInBrowser,
     /// Refresh the stack view from local state.
     Refresh,
+    /// Show or hide the diff preview pane.
+    TogglePreview,
+    /// Fold or unfold the subtree under the selected branch.
+    ToggleCollapse,
+    /// Start typing a search query.
+    StartSearch,
+    /// Append a character to the search query.
+    SearchInput(char),
+    /// Delete the last character of the search query.
+    SearchBackspace,
+    /// Stop typing, keeping the filter applied.
+    EndSearch,
+    /// Drop the filter and show every branch again.
+    ClearSearch,
     /// Quit without action.
     Quit,
     /// No action.
@@ -22,19 +52,44 @@ pub enum AppAction {
 }
 
 /// Handle a crossterm event and return the corresponding action.
-pub fn handle_event(event: Event) -> AppAction {
+pub fn handle_event(event: Event, mode: InputMode) -> AppAction {
     match event {
-        Event::Key(key_event) => handle_key(key_event),
+        Event::Key(key_event) => handle_key(key_event, mode),
+        Event::Mouse(mouse_event) => handle_mouse(mouse_event),
+        _ => AppAction::None,
+    }
+}
+
+fn handle_mouse(mouse: MouseEvent) -> AppAction {
+    match mouse.kind {
+        MouseEventKind::ScrollUp => AppAction::MoveUp,
+        MouseEventKind::ScrollDown => AppAction::MoveDown,
+        MouseEventKind::Down(MouseButton::Left) => AppAction::Click {
+            column: mouse.column,
+            row: mouse.row,
+        },
         _ => AppAction::None,
     }
 }
 
-fn handle_key(key: KeyEvent) -> AppAction {
+fn handle_key(key: KeyEvent, mode: InputMode) -> AppAction {
     // Handle Ctrl+C for quit
     if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
         return AppAction::Quit;
     }
 
+    if mode == InputMode::Search {
+        return match key.code {
+            KeyCode::Up => AppAction::MoveUp,
+            KeyCode::Down => AppAction::MoveDown,
+            KeyCode::Enter => AppAction::EndSearch,
+            KeyCode::Esc => AppAction::ClearSearch,
+            KeyCode::Backspace => AppAction::SearchBackspace,
+            KeyCode::Char(c) => AppAction::SearchInput(c),
+            _ => AppAction::None,
+        };
+    }
+
     match key.code {
         // Navigation
         KeyCode::Up | KeyCode::Char('k') => AppAction::MoveUp,
@@ -49,6 +104,16 @@ fn handle_key(key: KeyEvent) -> AppAction {
         // Refresh
         KeyCode::Char('r') => AppAction::Refresh,
 
+        // Diff preview pane
+        KeyCode::Char('p') => AppAction::TogglePreview,
+
+        // Fold/unfold the selected subtree
+        KeyCode::Tab | KeyCode::Char(' ') => AppAction::ToggleCollapse,
+
+        // Search
+        KeyCode::Char('/') => AppAction::StartSearch,
+        KeyCode::Esc if mode == InputMode::Filtered => AppAction::ClearSearch,
+
         // Quit
         KeyCode::Char('q') | KeyCode::Esc => AppAction::Quit,
 
@@ -60,9 +125,100 @@ fn handle_key(key: KeyEvent) -> AppAction {
 mod tests {
     use super::*;
 
+    fn key(code: KeyCode) -> Event {
+        Event::Key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
     #[test]
     fn r_refreshes() {
-        let event = Event::Key(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE));
-        assert_eq!(handle_event(event), AppAction::Refresh);
+        assert_eq!(
+            handle_event(key(KeyCode::Char('r')), InputMode::Navigate),
+            AppAction::Refresh
+        );
+    }
+
+    #[test]
+    fn search_mode_captures_typed_characters() {
+        assert_eq!(
+            handle_event(key(KeyCode::Char('/')), InputMode::Navigate),
+            AppAction::StartSearch
+        );
+        // Keys that navigate or quit elsewhere are query text while searching.
+        assert_eq!(
+            handle_event(key(KeyCode::Char('q')), InputMode::Search),
+            AppAction::SearchInput('q')
+        );
+        assert_eq!(
+            handle_event(key(KeyCode::Char('j')), InputMode::Search),
+            AppAction::SearchInput('j')
+        );
+        assert_eq!(
+            handle_event(key(KeyCode::Enter), InputMode::Search),
+            AppAction::EndSearch
+        );
+    }
+
+    fn mouse(kind: MouseEventKind) -> Event {
+        Event::Mouse(MouseEvent {
+            kind,
+            column: 7,
+            row: 3,
+            modifiers: KeyModifiers::NONE,
+        })
+    }
+
+    #[test]
+    fn wheel_navigates_and_left_click_reports_its_cell() {
+        assert_eq!(
+            handle_event(mouse(MouseEventKind::ScrollDown), InputMode::Navigate),
+            AppAction::MoveDown
+        );
+        assert_eq!(
+            handle_event(mouse(MouseEventKind::ScrollUp), InputMode::Search),
+            AppAction::MoveUp
+        );
+        assert_eq!(
+            handle_event(
+                mouse(MouseEventKind::Down(MouseButton::Left)),
+                InputMode::Navigate
+            ),
+            AppAction::Click { column: 7, row: 3 }
+        );
+        assert_eq!(
+            handle_event(mouse(MouseEventKind::Moved), InputMode::Navigate),
+            AppAction::None
+        );
+    }
+
+    #[test]
+    fn tab_and_space_toggle_collapse_outside_search() {
+        assert_eq!(
+            handle_event(key(KeyCode::Tab), InputMode::Navigate),
+            AppAction::ToggleCollapse
+        );
+        assert_eq!(
+            handle_event(key(KeyCode::Char(' ')), InputMode::Filtered),
+            AppAction::ToggleCollapse
+        );
+        assert_eq!(
+            handle_event(key(KeyCode::Char(' ')), InputMode::Search),
+            AppAction::SearchInput(' ')
+        );
+    }
+
+    #[test]
+    fn esc_clears_an_active_filter_before_quitting() {
+        assert_eq!(
+            handle_event(key(KeyCode::Esc), InputMode::Search),
+            AppAction::ClearSearch
+        );
+        assert_eq!(
+            handle_event(key(KeyCode::Esc), InputMode::Filtered),
+            AppAction::ClearSearch
+        );
+        assert_eq!(
+            handle_event(key(KeyCode::Esc), InputMode::Navigate),
+            AppAction::Quit
+        );
     }
 }
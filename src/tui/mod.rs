@@ -2,5 +2,7 @@
 
 mod app;
 mod input;
+mod preview;
 
 pub use app::{App, run_tui};
+pub use preview::BranchPreview;
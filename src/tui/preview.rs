@@ -0,0 +1,169 @@
+//! The diff preview pane: diff stats and the head of `git diff <base>..<branch>` for the
+//! highlighted branch.
+
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+};
+
+use crate::{
+    git::run_git,
+    git2_ops::GitRepo,
+    render::{Theme, ThemeColor},
+};
+
+/// Diff lines kept for the pane; the rest are summarized as a count.
+pub const PREVIEW_MAX_LINES: usize = 500;
+
+/// A branch's diff against its base, truncated for display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BranchPreview {
+    /// The ref the diff starts from (LKG parent SHA, or the parent branch).
+    pub base: String,
+    /// `(additions, deletions)` over the whole diff, when they could be computed.
+    pub stats: Option<(usize, usize)>,
+    /// The first `PREVIEW_MAX_LINES` lines of the diff.
+    pub lines: Vec<String>,
+    /// How many further lines were cut off.
+    pub omitted: usize,
+}
+
+impl BranchPreview {
+    /// Diff `branch` against `base`.
+    pub fn load(git_repo: &GitRepo, base: &str, branch: &str) -> Result<Self> {
+        let stats = git_repo.diff_stats(base, branch).ok();
+        let range = format!("{base}..{branch}");
+        let diff = run_git(&["diff", "--no-color", "--no-ext-diff", &range])?;
+        Ok(Self::from_diff(
+            base,
+            stats,
+            diff.as_ref(),
+            PREVIEW_MAX_LINES,
+        ))
+    }
+
+    fn from_diff(base: &str, stats: Option<(usize, usize)>, diff: &str, max_lines: usize) -> Self {
+        let mut lines = diff.lines();
+        let kept: Vec<String> = lines.by_ref().take(max_lines).map(str::to_string).collect();
+        Self {
+            base: base.to_string(),
+            stats,
+            lines: kept,
+            omitted: lines.count(),
+        }
+    }
+}
+
+fn rgb(theme: &Theme, color: ThemeColor) -> Color {
+    if theme.monochrome {
+        return Color::Reset;
+    }
+    Color::Rgb(color.0, color.1, color.2)
+}
+
+fn diff_line_style(theme: &Theme, line: &str) -> Style {
+    if theme.monochrome {
+        Style::default()
+    } else if line.starts_with("+++") || line.starts_with("---") || line.starts_with("diff ") {
+        Style::default().fg(Color::White)
+    } else if line.starts_with('+') {
+        Style::default().fg(rgb(theme, theme.green))
+    } else if line.starts_with('-') {
+        Style::default().fg(rgb(theme, theme.red))
+    } else if line.starts_with("@@") {
+        Style::default().fg(Color::Cyan)
+    } else {
+        Style::default().fg(rgb(theme, theme.gray))
+    }
+}
+
+/// Draw the pane for `branch`: its preview, or the message explaining why there is none. Empty
+/// when nothing is selected.
+pub(super) fn render_preview(
+    frame: &mut Frame,
+    area: Rect,
+    theme: &Theme,
+    branch: Option<&str>,
+    preview: Option<&Result<BranchPreview, String>>,
+) {
+    let title = match (branch, preview) {
+        (Some(branch), Some(Ok(preview))) => {
+            let base = &preview.base[..preview.base.len().min(12)];
+            format!(" {base}..{branch} ")
+        }
+        _ => " diff ".to_string(),
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(rgb(theme, theme.tree)));
+
+    let lines: Vec<Line> = match preview {
+        None => Vec::new(),
+        Some(Err(message)) => vec![Line::styled(
+            message.clone(),
+            Style::default().fg(rgb(theme, theme.gray)),
+        )],
+        Some(Ok(preview)) => {
+            let mut lines = Vec::with_capacity(preview.lines.len() + 2);
+            if let Some((additions, deletions)) = preview.stats {
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!("+{additions}"),
+                        Style::default().fg(rgb(theme, theme.green)),
+                    ),
+                    Span::styled(
+                        format!(" -{deletions}"),
+                        Style::default().fg(rgb(theme, theme.red)),
+                    ),
+                ]));
+            }
+            if preview.lines.is_empty() {
+                lines.push(Line::styled(
+                    "No changes.",
+                    Style::default().fg(rgb(theme, theme.gray)),
+                ));
+            }
+            lines.extend(
+                preview
+                    .lines
+                    .iter()
+                    .map(|line| Line::styled(line.clone(), diff_line_style(theme, line))),
+            );
+            if preview.omitted > 0 {
+                lines.push(Line::styled(
+                    format!(
+                        "… {} more lines (see `git stack diff {}`)",
+                        preview.omitted,
+                        branch.unwrap_or_default()
+                    ),
+                    Style::default().fg(rgb(theme, theme.muted)),
+                ));
+            }
+            lines
+        }
+    };
+
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_diff_truncates_and_counts_the_rest() {
+        let diff = "diff --git a/x b/x\n@@ -1 +1 @@\n-old\n+new\n";
+        let preview = BranchPreview::from_diff("abc", Some((1, 1)), diff, 2);
+        assert_eq!(preview.lines, vec!["diff --git a/x b/x", "@@ -1 +1 @@"]);
+        assert_eq!(preview.omitted, 2);
+
+        let preview = BranchPreview::from_diff("abc", None, diff, 10);
+        assert_eq!(preview.lines.len(), 4);
+        assert_eq!(preview.omitted, 0);
+    }
+}
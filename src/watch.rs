@@ -0,0 +1,179 @@
+//! `status --watch`: redraw the status tree whenever the repo's refs or `HEAD` change, until Ctrl-C.
+
+use std::{
+    io::{IsTerminal, Write},
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError},
+    },
+    time::Duration,
+};
+
+use anyhow::{Context, Result, bail};
+use crossterm::{cursor, execute, terminal};
+use notify::{Event, EventKind, RecursiveMode, Watcher, event::ModifyKind};
+use signal_hook::consts::{SIGINT, SIGTERM};
+
+use crate::git2_ops::GitRepo;
+
+/// How often the loop wakes up to notice Ctrl-C when nothing changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// One git command rewrites several refs (and their lock files); redraw once this long passes with
+/// no further changes.
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Call `draw` now and again after every change to `git_repo`'s refs, `packed-refs`, or `HEAD`,
+/// clearing the screen first when stdout is a terminal. Returns on Ctrl-C (a second Ctrl-C exits
+/// immediately, e.g. while a draw waits on the network). A failed draw is printed in place of the
+/// tree and the watch goes on.
+pub(crate) fn watch(git_repo: &GitRepo, mut draw: impl FnMut() -> Result<()>) -> Result<()> {
+    let (git_dir, common_dir) = git_repo.git_dirs();
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).context("Failed to start a file watcher")?;
+    watcher.watch(&common_dir.join("refs"), RecursiveMode::Recursive)?;
+    watcher.watch(git_dir, RecursiveMode::NonRecursive)?;
+    if common_dir != git_dir {
+        watcher.watch(common_dir, RecursiveMode::NonRecursive)?;
+    }
+
+    let interrupted = Arc::new(AtomicBool::new(false));
+    for signal in [SIGINT, SIGTERM] {
+        signal_hook::flag::register_conditional_shutdown(signal, 130, Arc::clone(&interrupted))?;
+        signal_hook::flag::register(signal, Arc::clone(&interrupted))?;
+    }
+    let interrupted = || interrupted.load(Ordering::Relaxed);
+
+    let screen = Screen::enter();
+    loop {
+        screen.redraw(&mut draw);
+        // Anything the draw itself set off (e.g. `--fetch` moving remote refs) is already on screen.
+        while rx.try_recv().is_ok() {}
+
+        loop {
+            if interrupted() {
+                return Ok(());
+            }
+            match rx.recv_timeout(POLL_INTERVAL) {
+                Ok(Ok(event)) if is_ref_change(git_dir, common_dir, &event) => break,
+                Ok(Ok(_)) | Err(RecvTimeoutError::Timeout) => {}
+                Ok(Err(e)) => tracing::warn!("File watcher error: {e}"),
+                Err(RecvTimeoutError::Disconnected) => bail!("The file watcher stopped."),
+            }
+        }
+        while !interrupted() && rx.recv_timeout(DEBOUNCE).is_ok() {}
+        if interrupted() {
+            return Ok(());
+        }
+    }
+}
+
+/// Whether `event` changed `HEAD`, `packed-refs`, or anything under `refs/`. Lock files and reads
+/// (including git-stack's own, while drawing) don't count.
+fn is_ref_change(git_dir: &Path, common_dir: &Path, event: &Event) -> bool {
+    let writes = match event.kind {
+        EventKind::Create(_) | EventKind::Remove(_) | EventKind::Any => true,
+        EventKind::Modify(kind) => !matches!(kind, ModifyKind::Metadata(_)),
+        EventKind::Access(_) | EventKind::Other => false,
+    };
+    writes
+        && event.paths.iter().any(|path| {
+            path.extension().is_none_or(|extension| extension != "lock")
+                && (path == &git_dir.join("HEAD")
+                    || path.strip_prefix(common_dir).is_ok_and(|rest| {
+                        rest == Path::new("packed-refs") || rest.starts_with("refs")
+                    }))
+        })
+}
+
+/// The terminal while watching: the cursor is hidden, and shown again when this drops.
+struct Screen {
+    tty: bool,
+}
+
+impl Screen {
+    fn enter() -> Self {
+        let tty = std::io::stdout().is_terminal();
+        if tty {
+            let _ = execute!(std::io::stdout(), cursor::Hide);
+        }
+        Self { tty }
+    }
+
+    fn redraw(&self, draw: &mut impl FnMut() -> Result<()>) {
+        if self.tty {
+            let _ = execute!(
+                std::io::stdout(),
+                cursor::MoveTo(0, 0),
+                terminal::Clear(terminal::ClearType::All)
+            );
+        }
+        if let Err(e) = draw() {
+            println!("{e:#}");
+        }
+        let _ = std::io::stdout().flush();
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        if self.tty {
+            let _ = execute!(std::io::stdout(), cursor::Show);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use notify::event::{AccessKind, CreateKind, DataChange, RemoveKind};
+
+    use super::*;
+
+    fn event(kind: EventKind, path: &str) -> Event {
+        Event::new(kind).add_path(PathBuf::from(path))
+    }
+
+    #[test]
+    fn only_ref_writes_trigger_a_redraw() {
+        let git_dir = Path::new("/repo/.git/worktrees/wt");
+        let common_dir = Path::new("/repo/.git");
+        let write = EventKind::Modify(ModifyKind::Data(DataChange::Any));
+        for (kind, path) in [
+            (write, "/repo/.git/worktrees/wt/HEAD"),
+            (write, "/repo/.git/packed-refs"),
+            (
+                EventKind::Create(CreateKind::File),
+                "/repo/.git/refs/heads/feature/x",
+            ),
+            (
+                EventKind::Remove(RemoveKind::File),
+                "/repo/.git/refs/remotes/origin/a",
+            ),
+        ] {
+            assert!(
+                is_ref_change(git_dir, common_dir, &event(kind, path)),
+                "{path}"
+            );
+        }
+        for (kind, path) in [
+            (
+                EventKind::Access(AccessKind::Any),
+                "/repo/.git/worktrees/wt/HEAD",
+            ),
+            (write, "/repo/.git/refs/heads/a.lock"),
+            (write, "/repo/.git/HEAD"),
+            (write, "/repo/.git/index"),
+            (write, "/repo/.git/worktrees/wt/index"),
+        ] {
+            assert!(
+                !is_ref_change(git_dir, common_dir, &event(kind, path)),
+                "{path}"
+            );
+        }
+    }
+}
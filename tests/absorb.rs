@@ -0,0 +1,89 @@
+mod common;
+
+use std::fs;
+
+use common::{git, git_init, git_output, git_stack_command, point_origin_at, write_state};
+
+#[test]
+fn absorb_folds_staged_hunks_into_the_commits_that_touched_them() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    git_init(repo);
+
+    fs::write(
+        repo.join("a.txt"),
+        "one\ntwo\nthree\nfour\nfive\nsix\nseven\n",
+    )
+    .unwrap();
+    git(repo, &["add", "a.txt"]);
+    git(repo, &["commit", "-q", "-m", "root"]);
+    let root_sha = git_output(repo, &["rev-parse", "HEAD"]);
+    point_origin_at(repo, &root_sha);
+
+    git(repo, &["checkout", "-q", "-b", "feature"]);
+    fs::write(
+        repo.join("a.txt"),
+        "one\nTWO\nthree\nfour\nfive\nsix\nseven\n",
+    )
+    .unwrap();
+    git(repo, &["commit", "-q", "-am", "first change"]);
+    fs::write(
+        repo.join("a.txt"),
+        "one\nTWO\nthree\nfour\nfive\nSIX\nseven\n",
+    )
+    .unwrap();
+    git(repo, &["commit", "-q", "-am", "second change"]);
+
+    // Line 2 belongs to "first change", line 6 to "second change", line 4 to the trunk commit.
+    fs::write(
+        repo.join("a.txt"),
+        "one\nTWO!\nthree\nFOUR\nfive\nSIX!\nseven\n",
+    )
+    .unwrap();
+    git(repo, &["add", "a.txt"]);
+
+    let state_home = tempfile::tempdir().unwrap();
+    let canonical_repo = repo.canonicalize().unwrap();
+    let state = format!(
+        "{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: feature\n    stack_method: apply_merge\n    lkg_parent: {}\n    branches: []\n",
+        canonical_repo.display(),
+        root_sha,
+    );
+    write_state(state_home.path(), &state);
+
+    let output = git_stack_command(repo, state_home.path(), &["absorb"])
+        .env("GIT_EDITOR", "true")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "absorb failed:\nstdout: {}\nstderr: {}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("no single owning commit"), "{stderr}");
+
+    // Still two commits, each carrying its own fix; the trunk-owned line went to the tip.
+    assert_eq!(
+        git_output(repo, &["log", "--format=%s", "main..feature"]),
+        "second change\nfirst change"
+    );
+    assert_eq!(
+        git_output(repo, &["show", "feature~1:a.txt"]),
+        "one\nTWO!\nthree\nfour\nfive\nsix\nseven"
+    );
+    assert_eq!(
+        git_output(repo, &["diff", "feature~1", "feature", "--", "a.txt"])
+            .lines()
+            .filter(|l| l.starts_with('+') && !l.starts_with("+++"))
+            .collect::<Vec<_>>(),
+        vec!["+FOUR", "+SIX!"]
+    );
+    assert_eq!(
+        git_output(repo, &["show", "feature:a.txt"]),
+        "one\nTWO!\nthree\nFOUR\nfive\nSIX!\nseven"
+    );
+    assert_eq!(git_output(repo, &["status", "--porcelain"]), "");
+    assert_eq!(git_output(repo, &["branch", "--show-current"]), "feature");
+}
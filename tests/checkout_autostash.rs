@@ -0,0 +1,59 @@
+mod common;
+
+use common::{git, git_output, init_repo, run_git_stack};
+
+#[test]
+fn checkout_refuses_over_local_changes_unless_autostashing() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    init_repo(repo);
+    std::fs::write(repo.join("notes.txt"), "v1\n").unwrap();
+    git(repo, &["add", "notes.txt"]);
+    git(repo, &["commit", "-q", "-m", "notes"]);
+    git(repo, &["update-ref", "refs/remotes/origin/main", "HEAD"]);
+    let state_home = tempfile::tempdir().unwrap();
+
+    let output = run_git_stack(repo, state_home.path(), &["checkout", "other"]);
+    assert!(output.status.success(), "{output:?}");
+    git(repo, &["commit", "-q", "--allow-empty", "-m", "other"]);
+    git(repo, &["checkout", "-q", "main"]);
+    std::fs::write(repo.join("notes.txt"), "v2\n").unwrap();
+
+    // Switching commits is refused, leaving everything as it was.
+    let output = run_git_stack(repo, state_home.path(), &["checkout", "other"]);
+    assert!(!output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("You have uncommitted changes, so other wasn't checked out."),
+        "{stdout}"
+    );
+    assert!(stdout.contains("--autostash"), "{stdout}");
+    assert_eq!(git_output(repo, &["branch", "--show-current"]), "main");
+    assert_eq!(
+        std::fs::read_to_string(repo.join("notes.txt")).unwrap(),
+        "v2\n"
+    );
+
+    // A new branch on the current commit takes the changes along.
+    let output = run_git_stack(repo, state_home.path(), &["checkout", "feature"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(git_output(repo, &["branch", "--show-current"]), "feature");
+
+    let output = run_git_stack(
+        repo,
+        state_home.path(),
+        &["checkout", "other", "--autostash"],
+    );
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Restored stashed local changes."),
+        "{stdout}"
+    );
+    assert_eq!(git_output(repo, &["branch", "--show-current"]), "other");
+    assert_eq!(
+        std::fs::read_to_string(repo.join("notes.txt")).unwrap(),
+        "v2\n"
+    );
+    assert_eq!(git_output(repo, &["stash", "list"]), "");
+}
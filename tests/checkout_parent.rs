@@ -0,0 +1,64 @@
+mod common;
+
+use std::fs;
+
+use common::{git, git_output, init_repo, run_git_stack, write_state};
+
+#[test]
+fn checkout_parent_creates_the_branch_on_another_tracked_branch() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    init_repo(repo);
+    git(repo, &["checkout", "-q", "-b", "base"]);
+    git(repo, &["commit", "-q", "--allow-empty", "-m", "base"]);
+    let base_sha = git_output(repo, &["rev-parse", "HEAD"]);
+    git(repo, &["checkout", "-q", "-b", "other", "main"]);
+
+    let state_home = tempfile::tempdir().unwrap();
+    let state_file = write_state(
+        state_home.path(),
+        &format!(
+            "version: 1\n{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: base\n    stack_method: apply_merge\n    lkg_parent: null\n    branches: []\n  - name: other\n    stack_method: apply_merge\n    lkg_parent: null\n    branches: []\n",
+            repo.canonicalize().unwrap().display()
+        ),
+    );
+
+    // Untracked parents are refused.
+    let output = run_git_stack(
+        repo,
+        state_home.path(),
+        &["checkout", "new", "--parent", "nope"],
+    );
+    assert!(!output.status.success(), "{output:?}");
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("not being tracked"),
+        "{output:?}"
+    );
+
+    let output = run_git_stack(
+        repo,
+        state_home.path(),
+        &["checkout", "new", "--parent", "base"],
+    );
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(git_output(repo, &["branch", "--show-current"]), "new");
+    assert_eq!(git_output(repo, &["rev-parse", "new"]), base_sha);
+    // `new` is tracked under `base`, not the checked-out `other`.
+    let state = fs::read_to_string(&state_file).unwrap();
+    let (under_base, under_other) = state.split_once("  - name: other\n").unwrap();
+    assert!(!under_other.contains("name: new"), "{state}");
+    assert!(
+        under_base.contains(&format!(
+            "    branches:\n    - name: new\n      stack_method: apply_merge\n      lkg_parent: {base_sha}\n"
+        )),
+        "{state}"
+    );
+
+    // An existing branch can't be re-parented this way.
+    let output = run_git_stack(
+        repo,
+        state_home.path(),
+        &["checkout", "other", "--parent", "base"],
+    );
+    assert!(!output.status.success(), "{output:?}");
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use std::{path::Path, process::Command};
+
+use common::{git, init_repo, run_git_stack};
+
+fn current_branch(repo: &Path) -> String {
+    let output = Command::new("git")
+        .args(["branch", "--show-current"])
+        .current_dir(repo)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+#[test]
+fn checkout_dash_returns_to_the_last_tracked_branch() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    init_repo(repo);
+    let state_home = tempfile::tempdir().unwrap();
+    let checkout = |branch: &str| {
+        let output = run_git_stack(repo, state_home.path(), &["checkout", branch]);
+        assert!(output.status.success(), "{output:?}");
+    };
+
+    checkout("a");
+    checkout("b");
+    checkout("-");
+    assert_eq!(current_branch(repo), "a");
+    checkout("-");
+    assert_eq!(current_branch(repo), "b");
+
+    // git's `@{-1}` is now `b`, but only git-stack commands update the stack history, so going
+    // back from `main` lands on `a`.
+    git(repo, &["checkout", "-q", "main"]);
+    checkout("-");
+    assert_eq!(current_branch(repo), "a");
+}
+
+#[test]
+fn checkout_dash_falls_back_to_git_without_stack_history() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    init_repo(repo);
+    git(repo, &["checkout", "-q", "-b", "untracked"]);
+    git(repo, &["checkout", "-q", "main"]);
+    let state_home = tempfile::tempdir().unwrap();
+
+    let output = run_git_stack(repo, state_home.path(), &["checkout", "-"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(current_branch(repo), "untracked");
+}
+
+#[test]
+fn checkout_dash_after_several_moves_returns_to_the_last_one() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    init_repo(repo);
+    let state_home = tempfile::tempdir().unwrap();
+
+    // `create-stack` moves `HEAD` from `main` to `a`, then `b`, then `c`.
+    let output = run_git_stack(repo, state_home.path(), &["create-stack", "a", "b", "c"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(current_branch(repo), "c");
+
+    let output = run_git_stack(repo, state_home.path(), &["checkout", "-"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(current_branch(repo), "b");
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::{git, git_output, init_repo, run_git_stack};
+
+#[test]
+fn checkout_rejects_invalid_names_and_slugifies_on_request() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    init_repo(repo);
+    let state_home = tempfile::tempdir().unwrap();
+
+    let output = run_git_stack(repo, state_home.path(), &["checkout", "Fix the login bug?"]);
+    assert!(!output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(
+            "'Fix the login bug?' is not a valid branch name: it contains ' ' (space), '?'."
+        ),
+        "{stdout}"
+    );
+    assert!(stdout.contains("Pass --slug"), "{stdout}");
+    assert_eq!(git_output(repo, &["branch", "--show-current"]), "main");
+
+    let output = run_git_stack(
+        repo,
+        state_home.path(),
+        &["checkout", "--slug", "Fix the login bug?"],
+    );
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        git_output(repo, &["branch", "--show-current"]),
+        "fix-the-login-bug"
+    );
+}
+
+#[test]
+fn branch_prefix_applies_to_new_branches_only() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    init_repo(repo);
+    git(repo, &["branch", "existing"]);
+    let state_home = tempfile::tempdir().unwrap();
+
+    let output = run_git_stack(repo, state_home.path(), &["init", "--branch-prefix", "me/"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("will be named me/<name>"),
+        "{output:?}"
+    );
+
+    let output = run_git_stack(
+        repo,
+        state_home.path(),
+        &["checkout", "--slug", "Add OAuth"],
+    );
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        git_output(repo, &["branch", "--show-current"]),
+        "me/add-oauth"
+    );
+
+    // Already prefixed, or an existing branch: used as given.
+    let output = run_git_stack(repo, state_home.path(), &["checkout", "me/add-oauth"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        git_output(repo, &["branch", "--show-current"]),
+        "me/add-oauth"
+    );
+    let output = run_git_stack(repo, state_home.path(), &["checkout", "existing"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(git_output(repo, &["branch", "--show-current"]), "existing");
+
+    let output = run_git_stack(
+        repo,
+        state_home.path(),
+        &["init", "--branch-prefix", "bad prefix/"],
+    );
+    assert!(!output.status.success(), "{output:?}");
+
+    let output = run_git_stack(repo, state_home.path(), &["init", "--branch-prefix", ""]);
+    assert!(output.status.success(), "{output:?}");
+    git(repo, &["checkout", "-q", "main"]);
+    let output = run_git_stack(repo, state_home.path(), &["checkout", "plain"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(git_output(repo, &["branch", "--show-current"]), "plain");
+}
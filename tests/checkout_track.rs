@@ -0,0 +1,48 @@
+mod common;
+
+use std::process::Command;
+
+use common::{git, git_output, git_stack, repo_with_bare_origin};
+
+#[test]
+fn track_pushes_the_new_branch_and_sets_its_upstream() {
+    let dir = tempfile::tempdir().unwrap();
+    let (origin, repo) = repo_with_bare_origin(dir.path());
+    git(&repo, &["push", "-q", "origin", "main"]);
+    git(
+        &repo,
+        &[
+            "symbolic-ref",
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/main",
+        ],
+    );
+    let state_home = tempfile::tempdir().unwrap();
+
+    let out = git_stack(
+        &repo,
+        state_home.path(),
+        &["checkout", "feature", "--track"],
+    );
+    assert!(
+        out.contains("Pushed feature; it tracks origin/feature."),
+        "{out}"
+    );
+    assert_eq!(
+        git_output(&repo, &["rev-parse", "--abbrev-ref", "feature@{upstream}"]),
+        "origin/feature"
+    );
+    assert_eq!(
+        git_output(&origin, &["rev-parse", "refs/heads/feature"]),
+        git_output(&repo, &["rev-parse", "feature"])
+    );
+
+    // Without --track the new branch has no upstream.
+    git_stack(&repo, state_home.path(), &["checkout", "other"]);
+    let output = Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "other@{upstream}"])
+        .current_dir(&repo)
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "{output:?}");
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use std::{fs, path::Path};
+
+use common::{
+    commit_file, git, git_init, git_output, git_stack_command, point_origin_at, succeeded,
+    write_state,
+};
+
+fn run(repo: &Path, state_home: &Path, args: &[&str]) -> String {
+    let output = git_stack_command(repo, state_home, args)
+        .env("XDG_CACHE_HOME", state_home.join("cache"))
+        .output()
+        .unwrap();
+    succeeded(args, output)
+}
+
+#[test]
+fn cleanup_merged_deletes_only_landed_branches() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    git_init(repo);
+
+    let root_sha = commit_file(repo, "base.txt");
+    for branch in ["landed", "checked-out"] {
+        git(repo, &["checkout", "-q", "-b", branch, "main"]);
+        commit_file(repo, &format!("{branch}.txt"));
+        git(repo, &["checkout", "-q", "main"]);
+        git(repo, &["merge", "-q", "--no-ff", "--no-edit", branch]);
+    }
+    git(repo, &["checkout", "-q", "-b", "wip", &root_sha]);
+    commit_file(repo, "wip.txt");
+    let main_sha = git_output(repo, &["rev-parse", "main"]);
+    point_origin_at(repo, &main_sha);
+    git(repo, &["checkout", "-q", "checked-out"]);
+
+    let state_home = tempfile::tempdir().unwrap();
+    let state_dir = state_home.path().join("git-stack");
+    let mut state = format!(
+        "{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n",
+        repo.canonicalize().unwrap().display()
+    );
+    for branch in ["landed", "checked-out", "wip"] {
+        state.push_str(&format!(
+            "  - name: {branch}\n    stack_method: apply_merge\n    lkg_parent: {root_sha}\n    branches: []\n"
+        ));
+    }
+    write_state(state_home.path(), &state);
+
+    let preview = run(
+        repo,
+        state_home.path(),
+        &["cleanup", "--merged", "--dry-run"],
+    );
+    assert!(preview.contains("[dry-run] Plan:"), "{preview}");
+    assert!(preview.contains("'landed'"), "{preview}");
+    assert!(!preview.contains("'checked-out'"), "{preview}");
+    assert!(!preview.contains("'wip'"), "{preview}");
+    assert!(!git_output(repo, &["branch", "--list", "landed"]).is_empty());
+
+    run(repo, state_home.path(), &["cleanup", "--merged"]);
+    assert!(git_output(repo, &["branch", "--list", "landed"]).is_empty());
+    assert!(!git_output(repo, &["branch", "--list", "checked-out"]).is_empty());
+    assert!(!git_output(repo, &["branch", "--list", "wip"]).is_empty());
+    let state = fs::read_to_string(state_dir.join("state.yaml")).unwrap();
+    assert!(!state.contains("name: landed"), "{state}");
+    assert!(state.contains("name: checked-out"), "{state}");
+    assert!(state.contains("name: wip"), "{state}");
+}
@@ -0,0 +1,145 @@
+//! Fixtures shared by the integration tests: scratch git repos, and `git-stack` runs whose XDG
+//! state and config live under a temporary `state_home`.
+
+// Each test crate uses only some of these.
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+pub fn git(repo: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(repo)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {args:?} failed with {status}");
+}
+
+/// `git <args>`'s trimmed stdout.
+pub fn git_output(repo: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(repo)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {args:?} failed: {output:?}");
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+/// Commit a new file `name` (containing its own name) and return the commit's SHA.
+pub fn commit_file(repo: &Path, name: &str) -> String {
+    fs::write(repo.join(name), format!("{name}\n")).unwrap();
+    git(repo, &["add", name]);
+    git(repo, &["commit", "-q", "-m", name]);
+    git_output(repo, &["rev-parse", "HEAD"])
+}
+
+/// `git init` on `main`, with a committer identity and no background maintenance.
+pub fn git_init(repo: &Path) {
+    git(repo, &["init", "-q", "-b", "main"]);
+    git(repo, &["config", "user.email", "test@example.com"]);
+    git(repo, &["config", "user.name", "Test"]);
+    git(repo, &["config", "maintenance.auto", "false"]);
+    git(repo, &["config", "gc.auto", "0"]);
+}
+
+/// A repo on `main` with one empty root commit, which `origin/main` and `origin/HEAD` point at so
+/// git-stack can resolve trunk without a remote.
+pub fn init_repo(repo: &Path) {
+    git_init(repo);
+    git(repo, &["commit", "-q", "--allow-empty", "-m", "root"]);
+    point_origin_at(repo, "HEAD");
+}
+
+/// [`init_repo`], plus an `origin` remote at `github.com/acme/app` (which tests never contact).
+pub fn init_github_repo(repo: &Path) {
+    init_repo(repo);
+    git(
+        repo,
+        &["remote", "add", "origin", "git@github.com:acme/app.git"],
+    );
+}
+
+/// Under `dir`, a bare `origin.git` and a `repo` whose `origin` remote is it. `repo` has one empty
+/// root commit on `main`; nothing is pushed yet. Returns `(origin, repo)`.
+pub fn repo_with_bare_origin(dir: &Path) -> (PathBuf, PathBuf) {
+    let origin = dir.join("origin.git");
+    let repo = dir.join("repo");
+    fs::create_dir_all(&repo).unwrap();
+    git(
+        dir,
+        &[
+            "init",
+            "-q",
+            "--bare",
+            "-b",
+            "main",
+            origin.to_str().unwrap(),
+        ],
+    );
+    git_init(&repo);
+    git(
+        &repo,
+        &["remote", "add", "origin", origin.to_str().unwrap()],
+    );
+    git(&repo, &["commit", "-q", "--allow-empty", "-m", "root"]);
+    (origin, repo)
+}
+
+/// Set `origin/main` to `rev`, and `origin/HEAD` to `origin/main`.
+pub fn point_origin_at(repo: &Path, rev: &str) {
+    git(repo, &["update-ref", "refs/remotes/origin/main", rev]);
+    git(
+        repo,
+        &[
+            "symbolic-ref",
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/main",
+        ],
+    );
+}
+
+/// Write `contents` as git-stack's state file under `state_home`, returning its path.
+pub fn write_state(state_home: &Path, contents: &str) -> PathBuf {
+    let state_dir = state_home.join("git-stack");
+    fs::create_dir_all(&state_dir).unwrap();
+    let state_file = state_dir.join("state.yaml");
+    fs::write(&state_file, contents).unwrap();
+    state_file
+}
+
+/// A `git-stack <args>` command run in `dir`, with uncolored output.
+pub fn git_stack_command(dir: &Path, state_home: &Path, args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_git-stack"));
+    command
+        .args(args)
+        .current_dir(dir)
+        .env("XDG_STATE_HOME", state_home)
+        .env("XDG_CONFIG_HOME", state_home.join("config"))
+        .env("NO_COLOR", "1");
+    command
+}
+
+pub fn run_git_stack(dir: &Path, state_home: &Path, args: &[&str]) -> Output {
+    git_stack_command(dir, state_home, args).output().unwrap()
+}
+
+/// Run `git-stack <args>`, which must succeed, and return its stdout.
+pub fn git_stack(dir: &Path, state_home: &Path, args: &[&str]) -> String {
+    succeeded(args, run_git_stack(dir, state_home, args))
+}
+
+/// `output`'s stdout, after checking that `git-stack <args>` succeeded.
+pub fn succeeded(args: &[&str], output: Output) -> String {
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    assert!(
+        output.status.success(),
+        "git stack {args:?} failed:\nstdout: {stdout}\nstderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    stdout
+}
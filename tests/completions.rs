@@ -0,0 +1,55 @@
+mod common;
+
+use common::{git, git_init, run_git_stack, write_state};
+
+#[test]
+fn complete_branches_lists_the_tracked_tree() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    let state_home = tempfile::tempdir().unwrap();
+    git_init(repo);
+    git(repo, &["commit", "-q", "--allow-empty", "-m", "root"]);
+    // `untracked` exists in git but not in the stack tree, so it isn't offered.
+    git(repo, &["branch", "untracked"]);
+
+    write_state(
+        state_home.path(),
+        &format!(
+            "version: 1\n{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: a\n    stack_method: apply_merge\n    lkg_parent: null\n    branches:\n    - name: b\n      stack_method: apply_merge\n      lkg_parent: null\n      branches: []\n  - name: c\n    stack_method: apply_merge\n    lkg_parent: null\n    branches: []\n",
+            repo.canonicalize().unwrap().display()
+        ),
+    );
+
+    let output = run_git_stack(repo, state_home.path(), &["__complete_branches"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "main\na\nb\nc\n");
+
+    // Outside a repo it completes nothing rather than printing an error.
+    let elsewhere = tempfile::tempdir().unwrap();
+    let output = run_git_stack(
+        elsewhere.path(),
+        state_home.path(),
+        &["__complete_branches"],
+    );
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+}
+
+#[test]
+fn completions_scripts_call_back_for_branches() {
+    let dir = tempfile::tempdir().unwrap();
+    for shell in ["bash", "zsh", "fish"] {
+        let output = run_git_stack(dir.path(), dir.path(), &["completions", shell]);
+        assert!(output.status.success(), "{output:?}");
+        assert!(
+            String::from_utf8_lossy(&output.stdout).contains("git-stack __complete_branches"),
+            "{shell}"
+        );
+    }
+
+    let output = run_git_stack(dir.path(), dir.path(), &["--help"]);
+    assert!(
+        !String::from_utf8_lossy(&output.stdout).contains("__complete_branches"),
+        "{output:?}"
+    );
+}
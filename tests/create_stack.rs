@@ -0,0 +1,46 @@
+mod common;
+
+use std::fs;
+
+use common::{git, git_init, git_output, point_origin_at, run_git_stack};
+
+#[test]
+fn create_stack_builds_a_linear_stack_or_nothing() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    git_init(repo);
+    git(repo, &["commit", "-q", "--allow-empty", "-m", "root"]);
+    let root_sha = git_output(repo, &["rev-parse", "HEAD"]);
+    point_origin_at(repo, "HEAD");
+    let state_home = tempfile::tempdir().unwrap();
+
+    let output = run_git_stack(repo, state_home.path(), &["create-stack", "a", "b", "c"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "create-stack failed: {output:?}");
+    assert!(
+        stdout.contains("Created a -> b -> c on top of main; c is checked out."),
+        "{stdout}"
+    );
+    assert_eq!(git_output(repo, &["branch", "--show-current"]), "c");
+    for branch in ["a", "b", "c"] {
+        assert_eq!(git_output(repo, &["rev-parse", branch]), root_sha);
+    }
+    let state = fs::read_to_string(state_home.path().join("git-stack/state.yaml")).unwrap();
+    assert!(
+        state.contains("  - name: a\n")
+            && state.contains("    - name: b\n")
+            && state.contains("      - name: c\n"),
+        "{state}"
+    );
+
+    // One taken name stops the whole batch before anything is created.
+    let output = run_git_stack(repo, state_home.path(), &["create-stack", "d", "b"]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Branch b already exists; no branches were created."),
+        "{stdout}"
+    );
+    assert_eq!(git_output(repo, &["branch", "--list", "d"]), "");
+    assert_eq!(git_output(repo, &["branch", "--show-current"]), "c");
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use std::{fs, path::Path};
+
+use common::{commit_file, git, git_output, init_repo, run_git_stack, write_state};
+
+/// main -> a -> b -> c, each with one commit, with `main` checked out. Returns the state file.
+fn stacked_repo(repo: &Path, state_home: &Path) -> std::path::PathBuf {
+    init_repo(repo);
+    let root_sha = git_output(repo, &["rev-parse", "HEAD"]);
+    let mut shas = Vec::new();
+    for branch in ["a", "b", "c"] {
+        git(repo, &["checkout", "-q", "-b", branch]);
+        commit_file(repo, branch);
+        shas.push(git_output(repo, &["rev-parse", "HEAD"]));
+    }
+    git(repo, &["checkout", "-q", "main"]);
+
+    let state = format!(
+        "version: 1\n{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: a\n    stack_method: apply_merge\n    lkg_parent: {root_sha}\n    branches:\n    - name: b\n      stack_method: apply_merge\n      lkg_parent: {}\n      branches:\n      - name: c\n        stack_method: apply_merge\n        lkg_parent: {}\n        branches: []\n",
+        repo.canonicalize().unwrap().display(),
+        shas[0],
+        shas[1],
+    );
+    write_state(state_home, &state)
+}
+
+#[test]
+fn delete_force_removes_the_git_branch_and_keeps_its_children() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    let state_home = tempfile::tempdir().unwrap();
+    let state_file = stacked_repo(repo, state_home.path());
+    let c_lkg = git_output(repo, &["rev-parse", "b"]);
+
+    // `b` isn't merged anywhere, so plain `--branch` refuses and changes nothing.
+    let output = run_git_stack(repo, state_home.path(), &["delete", "b", "--branch"]);
+    assert!(!output.status.success(), "{output:?}");
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("pass --force"),
+        "{output:?}"
+    );
+    assert!(git_output(repo, &["branch", "--list", "b"]).contains('b'));
+    let state = fs::read_to_string(&state_file).unwrap();
+    assert!(state.contains("- name: b\n"), "{state}");
+
+    let output = run_git_stack(repo, state_home.path(), &["delete", "b", "--force"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(git_output(repo, &["branch", "--list", "b"]), "");
+    let state = fs::read_to_string(&state_file).unwrap();
+    assert!(!state.contains("- name: b\n"), "{state}");
+    assert!(
+        state.contains(&format!(
+            "    - name: c\n      stack_method: apply_merge\n      lkg_parent: {c_lkg}\n"
+        )),
+        "c moves up under a, keeping its LKG parent: {state}"
+    );
+}
+
+#[test]
+fn delete_without_branch_flag_hints_that_the_git_branch_remains() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    let state_home = tempfile::tempdir().unwrap();
+    stacked_repo(repo, state_home.path());
+
+    let output = run_git_stack(repo, state_home.path(), &["delete", "c"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(
+        String::from_utf8_lossy(&output.stdout)
+            .contains("The git branch c still exists; pass --branch to delete it too."),
+        "{output:?}"
+    );
+    assert!(git_output(repo, &["branch", "--list", "c"]).contains('c'));
+
+    // The checked-out branch can't be deleted.
+    git(repo, &["checkout", "-q", "a"]);
+    let output = run_git_stack(repo, state_home.path(), &["delete", "a", "--force"]);
+    assert!(!output.status.success(), "{output:?}");
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("it is checked out"),
+        "{output:?}"
+    );
+}
+
+#[test]
+fn deleting_a_mid_stack_branch_keeps_its_children_tracked() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    let state_home = tempfile::tempdir().unwrap();
+    let state_file = stacked_repo(repo, state_home.path());
+    let c_lkg = git_output(repo, &["rev-parse", "b"]);
+
+    let output = run_git_stack(repo, state_home.path(), &["delete", "b"]);
+    assert!(output.status.success(), "{output:?}");
+
+    // a -> c, not just a.
+    let state = fs::read_to_string(&state_file).unwrap();
+    assert!(!state.contains("- name: b\n"), "{state}");
+    assert!(
+        state.contains(&format!(
+            "  - name: a\n    stack_method: apply_merge\n    lkg_parent: {}\n    branches:\n    - name: c\n      stack_method: apply_merge\n      lkg_parent: {c_lkg}\n",
+            git_output(repo, &["rev-parse", "main"]),
+        )),
+        "{state}"
+    );
+}
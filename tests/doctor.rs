@@ -0,0 +1,82 @@
+mod common;
+
+use std::{fs, path::Path};
+
+use common::{git, git_output, repo_with_bare_origin, run_git_stack, write_state};
+
+/// Run git-stack, returning whether it succeeded and its stdout.
+fn git_stack(repo: &Path, state_home: &Path, args: &[&str]) -> (bool, String) {
+    let output = run_git_stack(repo, state_home, args);
+    (
+        output.status.success(),
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+    )
+}
+
+#[test]
+fn fix_repairs_what_it_can_and_keeps_a_backup() {
+    let dir = tempfile::tempdir().unwrap();
+    let (_origin, repo) = repo_with_bare_origin(dir.path());
+    git(&repo, &["push", "-q", "origin", "main"]);
+    // `a` is tracked; `b` is stacked on it but was never mounted. `origin/HEAD` is left unset.
+    git(&repo, &["checkout", "-q", "-b", "a"]);
+    git(&repo, &["commit", "-q", "--allow-empty", "-m", "a"]);
+    git(&repo, &["checkout", "-q", "-b", "b"]);
+    git(&repo, &["commit", "-q", "--allow-empty", "-m", "b"]);
+    git(&repo, &["checkout", "-q", "a"]);
+
+    let state_home = tempfile::tempdir().unwrap();
+    let state_dir = state_home.path().join("git-stack");
+    let state = format!(
+        "version: 2\n{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: a\n    stack_method: apply_merge\n    lkg_parent: {}\n    branches: []\n  - name: gone\n    stack_method: apply_merge\n    lkg_parent: null\n    branches: []\n",
+        repo.canonicalize().unwrap().display(),
+        "d".repeat(40)
+    );
+    write_state(state_home.path(), &state);
+
+    let (ok, out) = git_stack(&repo, state_home.path(), &["doctor"]);
+    assert!(!ok, "{out}");
+    for problem in [
+        "origin/HEAD is not set",
+        "branch 'gone' is tracked but exists neither locally nor on origin",
+        "branch 'a' has LKG parent dddddddddddddddddddddddddddddddddddddddd, which isn't a known commit",
+        "local branch 'b' is stacked on 'a' but isn't in the tree",
+        "Run `git stack doctor --fix`",
+    ] {
+        assert!(out.contains(problem), "{problem}: {out}");
+    }
+
+    // Without a terminal to confirm on, the missing branch stays.
+    let (ok, out) = git_stack(&repo, state_home.path(), &["doctor", "--fix"]);
+    assert!(!ok, "{out}");
+    assert!(
+        out.contains("Kept the missing branches in the tree."),
+        "{out}"
+    );
+    let backups: Vec<_> = fs::read_dir(&state_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.to_string_lossy().ends_with(".bak"))
+        .collect();
+    assert_eq!(backups.len(), 1, "{backups:?}");
+    assert!(
+        out.contains(&format!(
+            "Backed up the state file to {}",
+            backups[0].display()
+        )),
+        "{out}"
+    );
+    assert_eq!(fs::read_to_string(&backups[0]).unwrap(), state);
+    assert_eq!(
+        git_output(&repo, &["symbolic-ref", "refs/remotes/origin/HEAD"]),
+        "refs/remotes/origin/main"
+    );
+
+    let (ok, out) = git_stack(&repo, state_home.path(), &["doctor"]);
+    assert!(!ok, "{out}");
+    assert_eq!(out.matches("problem:").count(), 1, "{out}");
+    assert!(out.contains("'gone'"), "{out}");
+    let saved = fs::read_to_string(state_dir.join("state.yaml")).unwrap();
+    assert!(saved.contains("- name: b"), "{saved}");
+    assert!(!saved.contains(&"d".repeat(40)), "{saved}");
+}
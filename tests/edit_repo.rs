@@ -0,0 +1,72 @@
+mod common;
+
+use std::{fs, path::Path, process::Command};
+
+use common::{git, git_stack_command, init_repo, write_state};
+
+/// Run `git stack edit --repo` with an "editor" that runs `sed <script>` on the file.
+fn edit_repo(repo: &Path, state_home: &Path, sed_script: &str) -> std::process::Output {
+    let editor = state_home.join("editor.sh");
+    fs::write(
+        &editor,
+        format!("#!/bin/sh\nsed -i '{sed_script}' \"$1\"\n"),
+    )
+    .unwrap();
+    Command::new("chmod")
+        .arg("+x")
+        .arg(&editor)
+        .status()
+        .unwrap();
+    git_stack_command(repo, state_home, &["edit", "--repo"])
+        .env("EDITOR", &editor)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn edit_repo_replaces_only_this_repos_tree_and_keeps_invalid_edits() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    init_repo(repo);
+    for branch in ["a", "b", "bee"] {
+        git(repo, &["branch", branch]);
+    }
+
+    let state_home = tempfile::tempdir().unwrap();
+    let state_file = write_state(
+        state_home.path(),
+        &format!(
+            "version: 1\n/elsewhere:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: b\n    stack_method: apply_merge\n    lkg_parent: null\n    branches: []\n{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: a\n    stack_method: apply_merge\n    lkg_parent: null\n    branches: []\n  - name: b\n    stack_method: apply_merge\n    lkg_parent: null\n    branches: []\n",
+            repo.canonicalize().unwrap().display()
+        ),
+    );
+
+    let output = edit_repo(repo, state_home.path(), "s/name: b$/name: bee/");
+    assert!(output.status.success(), "{output:?}");
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("Saved the stack tree"),
+        "{output:?}"
+    );
+    let state = fs::read_to_string(&state_file).unwrap();
+    assert!(state.contains("  - name: bee\n"), "{state}");
+    // The other repo's `b` is untouched.
+    assert!(
+        state.contains("/elsewhere:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: b\n"),
+        "{state}"
+    );
+
+    // A duplicate name is refused; the state stays as it was and the edits are kept.
+    let output = edit_repo(repo, state_home.path(), "s/name: bee$/name: a/");
+    assert!(!output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("branch 'a' appears 2 times"), "{stdout}");
+    let kept = stdout
+        .split("Your edits are saved in ")
+        .nth(1)
+        .and_then(|rest| rest.split(".yaml").next())
+        .map(|path| format!("{path}.yaml"))
+        .unwrap_or_else(|| panic!("no kept path in {stdout}"));
+    assert!(fs::read_to_string(&kept).unwrap().contains("name: a\n"));
+    fs::remove_file(kept).unwrap();
+    assert_eq!(fs::read_to_string(&state_file).unwrap(), state);
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use std::{fs, path::Path};
+
+use common::{git, git_init, git_output, run_git_stack, write_state};
+
+fn commit_on_new_branch(repo: &Path, branch: &str) -> String {
+    git(repo, &["checkout", "-q", "-b", branch]);
+    fs::write(repo.join(format!("{branch}.txt")), format!("{branch}\n")).unwrap();
+    git(repo, &["add", "."]);
+    git(repo, &["commit", "-q", "-m", branch]);
+    git_output(repo, &["rev-parse", "HEAD"])
+}
+
+#[test]
+fn export_then_import_into_another_clone() {
+    let origin_dir = tempfile::tempdir().unwrap();
+    let origin = origin_dir.path();
+    git_init(origin);
+    fs::write(origin.join("base.txt"), "base\n").unwrap();
+    git(origin, &["add", "base.txt"]);
+    git(origin, &["commit", "-q", "-m", "root"]);
+    let root_sha = git_output(origin, &["rev-parse", "HEAD"]);
+    let a_sha = commit_on_new_branch(origin, "a");
+    commit_on_new_branch(origin, "b");
+    git(origin, &["checkout", "-q", "main"]);
+    commit_on_new_branch(origin, "c");
+    git(origin, &["checkout", "-q", "main"]);
+
+    // The first checkout tracks `a -> b`, plus `c` under a branch that no longer exists anywhere.
+    let first_dir = tempfile::tempdir().unwrap();
+    let first = first_dir.path().join("first");
+    git(
+        first_dir.path(),
+        &["clone", "-q", origin.to_str().unwrap(), "first"],
+    );
+    let state_home = tempfile::tempdir().unwrap();
+    let state_dir = state_home.path().join("git-stack");
+    let state = format!(
+        "{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: a\n    stack_method: merge\n    note: first half\n    lkg_parent: {root_sha}\n    branches:\n    - name: b\n      stack_method: apply_merge\n      lkg_parent: {a_sha}\n      branches: []\n  - name: gone\n    stack_method: apply_merge\n    lkg_parent: 0123456789012345678901234567890123456789\n    branches:\n    - name: c\n      stack_method: apply_merge\n      lkg_parent: 0123456789012345678901234567890123456789\n      branches: []\n",
+        first.canonicalize().unwrap().display(),
+    );
+    write_state(state_home.path(), &state);
+
+    let export_path = first_dir.path().join("stack.json");
+    let output = run_git_stack(
+        &first,
+        state_home.path(),
+        &["export", export_path.to_str().unwrap()],
+    );
+    assert!(output.status.success(), "export failed: {output:?}");
+    let exported = fs::read_to_string(&export_path).unwrap();
+    assert!(exported.contains("\"note\": \"first half\""), "{exported}");
+
+    // A second clone somewhere else picks the layout up.
+    let second_dir = tempfile::tempdir().unwrap();
+    let second = second_dir.path().join("second");
+    git(
+        second_dir.path(),
+        &["clone", "-q", origin.to_str().unwrap(), "second"],
+    );
+    let output = run_git_stack(
+        &second,
+        state_home.path(),
+        &["import", export_path.to_str().unwrap()],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "import failed: {output:?}");
+    assert!(
+        stdout.contains("Skipping gone: no such branch locally or on origin."),
+        "{stdout}"
+    );
+    assert!(stdout.contains("Imported 3 branches"), "{stdout}");
+
+    let state = fs::read_to_string(state_dir.join("state.yaml")).unwrap();
+    let second_key = format!("{}:\n", second.canonicalize().unwrap().display());
+    // Just this repo's entry: up to the next top-level key.
+    let imported = state[state.find(&second_key).expect(&state)..]
+        .split("\n/")
+        .next()
+        .unwrap();
+    assert!(
+        imported.contains(&format!(
+            "  - name: a\n    stack_method: merge\n    note: first half\n    lkg_parent: {root_sha}\n"
+        )),
+        "{imported}"
+    );
+    assert!(imported.contains("    - name: b\n"), "{imported}");
+    // `c` moved up to trunk and lost the LKG parent this clone doesn't have.
+    assert!(
+        imported.contains("  - name: c\n    stack_method: apply_merge\n    lkg_parent: null\n"),
+        "{imported}"
+    );
+    assert!(!imported.contains("gone"), "{imported}");
+
+    // Importing over a tree with branches needs --force.
+    let output = run_git_stack(
+        &second,
+        state_home.path(),
+        &["import", export_path.to_str().unwrap()],
+    );
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("Pass --force to replace it."),
+        "{output:?}"
+    );
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use common::{git, git_output, git_stack, repo_with_bare_origin};
+
+#[test]
+fn fetch_refreshes_origin_and_prunes_unless_told_not_to() {
+    let dir = tempfile::tempdir().unwrap();
+    let (origin, repo) = repo_with_bare_origin(dir.path());
+    git(&repo, &["branch", "gone"]);
+    git(&repo, &["push", "-q", "origin", "main", "gone"]);
+    git(&origin, &["symbolic-ref", "HEAD", "refs/heads/main"]);
+    let state_home = tempfile::tempdir().unwrap();
+
+    // origin/HEAD is detected from the remote.
+    git_stack(&repo, state_home.path(), &["fetch"]);
+    assert_eq!(
+        git_output(&repo, &["symbolic-ref", "refs/remotes/origin/HEAD"]),
+        "refs/remotes/origin/main"
+    );
+
+    git(&origin, &["branch", "-D", "gone"]);
+    git_stack(&repo, state_home.path(), &["fetch", "--no-prune"]);
+    assert_eq!(
+        git_output(&repo, &["branch", "-r", "--list", "origin/gone"]),
+        "origin/gone"
+    );
+    let stdout = git_stack(&repo, state_home.path(), &["fetch"]);
+    assert!(stdout.contains("Fetched from origin."), "{stdout}");
+    assert_eq!(
+        git_output(&repo, &["branch", "-r", "--list", "origin/gone"]),
+        ""
+    );
+}
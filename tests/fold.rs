@@ -0,0 +1,83 @@
+mod common;
+
+use std::fs;
+
+use common::{commit_file, git, git_output, init_repo, run_git_stack, write_state};
+
+#[test]
+fn fold_squashes_into_parent_and_repoints_children() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    init_repo(repo);
+    let root_sha = git_output(repo, &["rev-parse", "HEAD"]);
+    git(repo, &["checkout", "-q", "-b", "mid"]);
+    commit_file(repo, "m1");
+    let mid_sha = git_output(repo, &["rev-parse", "HEAD"]);
+    git(repo, &["checkout", "-q", "-b", "feature"]);
+    commit_file(repo, "f1");
+    commit_file(repo, "f2");
+    let feature_sha = git_output(repo, &["rev-parse", "HEAD"]);
+    git(repo, &["checkout", "-q", "-b", "child"]);
+    commit_file(repo, "c1");
+    git(repo, &["checkout", "-q", "feature"]);
+
+    let state_home = tempfile::tempdir().unwrap();
+    let state_dir = state_home.path().join("git-stack");
+    let state = format!(
+        "{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: mid\n    stack_method: apply_merge\n    lkg_parent: {root_sha}\n    branches:\n    - name: feature\n      stack_method: apply_merge\n      lkg_parent: {mid_sha}\n      branches:\n      - name: child\n        stack_method: apply_merge\n        lkg_parent: {feature_sha}\n        branches: []\n",
+        repo.canonicalize().unwrap().display(),
+    );
+    write_state(state_home.path(), &state);
+
+    let output = run_git_stack(repo, state_home.path(), &["fold"]);
+    assert!(
+        output.status.success(),
+        "fold failed:\nstdout: {}\nstderr: {}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    assert_eq!(git_output(repo, &["branch", "--show-current"]), "mid");
+    assert_eq!(git_output(repo, &["rev-parse", "mid~1"]), mid_sha);
+    assert_eq!(
+        git_output(repo, &["show", "--format=", "--name-only", "mid"]),
+        "f1\nf2"
+    );
+    assert_eq!(git_output(repo, &["branch", "--list", "feature"]), "");
+
+    let saved = fs::read_to_string(state_dir.join("state.yaml")).unwrap();
+    let state: serde_yaml::Value = serde_yaml::from_str(&saved).unwrap();
+    let root = &state[repo.canonicalize().unwrap().to_str().unwrap()];
+    let mid = &root["branches"][0];
+    assert_eq!(mid["name"].as_str(), Some("mid"));
+    let children = mid["branches"].as_sequence().unwrap();
+    assert_eq!(children.len(), 1);
+    assert_eq!(children[0]["name"].as_str(), Some("child"));
+    // The child's replay boundary is untouched, so a restack replays only its own commit.
+    assert_eq!(
+        children[0]["lkg_parent"].as_str(),
+        Some(feature_sha.as_str())
+    );
+}
+
+#[test]
+fn fold_refuses_a_branch_stacked_on_trunk() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    init_repo(repo);
+    let root_sha = git_output(repo, &["rev-parse", "HEAD"]);
+    git(repo, &["checkout", "-q", "-b", "feature"]);
+    commit_file(repo, "f1");
+
+    let state_home = tempfile::tempdir().unwrap();
+    let state = format!(
+        "{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: feature\n    stack_method: apply_merge\n    lkg_parent: {root_sha}\n    branches: []\n",
+        repo.canonicalize().unwrap().display(),
+    );
+    write_state(state_home.path(), &state);
+
+    let output = run_git_stack(repo, state_home.path(), &["fold"]);
+    assert!(!output.status.success());
+    assert_eq!(git_output(repo, &["rev-parse", "main"]), root_sha);
+    assert_eq!(git_output(repo, &["branch", "--show-current"]), "feature");
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use std::fs;
+
+use common::{git_stack, init_repo};
+
+#[test]
+fn init_tracks_the_repo_idempotently_and_sets_the_default_stack_method() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    init_repo(repo);
+
+    let state_home = tempfile::tempdir().unwrap();
+    let state_file = state_home.path().join("git-stack").join("state.yaml");
+
+    let stdout = git_stack(
+        repo,
+        state_home.path(),
+        &[
+            "init",
+            "--stack-method",
+            "merge",
+            "--merge-method",
+            "squash",
+        ],
+    );
+    assert!(stdout.contains("with trunk main."), "{stdout}");
+    assert!(
+        stdout.contains("New branches will use the merge stack method."),
+        "{stdout}"
+    );
+    let state = fs::read_to_string(&state_file).unwrap();
+    assert!(state.contains("  name: main\n"), "{state}");
+    assert!(state.contains("  default_stack_method: merge\n"), "{state}");
+    assert!(state.contains("  merge_method: squash\n"), "{state}");
+
+    // Running it again keeps the recorded method.
+    let stdout = git_stack(repo, state_home.path(), &["init"]);
+    assert!(stdout.contains("Already tracking"), "{stdout}");
+    assert!(
+        stdout.contains("`git stack land` will squash PRs."),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("New branches will use the merge stack method."),
+        "{stdout}"
+    );
+
+    git_stack(repo, state_home.path(), &["checkout", "feature"]);
+    let state = fs::read_to_string(&state_file).unwrap();
+    assert!(
+        state.contains("  - name: feature\n    stack_method: merge\n"),
+        "{state}"
+    );
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use common::{git, git_output, init_repo, run_git_stack, write_state};
+
+#[test]
+fn log_stack_prints_a_section_per_branch_from_leaf_to_trunk() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    init_repo(repo);
+    let _root_sha = git_output(repo, &["rev-parse", "HEAD"]);
+    // main <- a <- b <- c, each with one commit.
+    for name in ["a", "b", "c"] {
+        git(repo, &["checkout", "-q", "-b", name]);
+        git(
+            repo,
+            &[
+                "commit",
+                "-q",
+                "--allow-empty",
+                "-m",
+                &format!("{name} work"),
+            ],
+        );
+    }
+    git(repo, &["checkout", "-q", "b"]);
+
+    let state_home = tempfile::tempdir().unwrap();
+    let state = format!(
+        "{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: a\n    stack_method: apply_merge\n    lkg_parent: null\n    branches:\n    - name: b\n      stack_method: apply_merge\n      lkg_parent: null\n      branches:\n      - name: c\n        stack_method: apply_merge\n        lkg_parent: null\n        branches: []\n",
+        repo.canonicalize().unwrap().display(),
+    );
+    write_state(state_home.path(), &state);
+
+    let output = run_git_stack(repo, state_home.path(), &["log", "--stack"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "log --stack failed: {stdout}");
+
+    let position = |needle: &str| {
+        stdout
+            .find(needle)
+            .unwrap_or_else(|| panic!("{needle:?} missing from:\n{stdout}"))
+    };
+    // Sections run leaf-first, each header followed by that branch's own commit only.
+    let order = [
+        position("── c on b"),
+        position("c work"),
+        position("── b on a"),
+        position("b work"),
+        position("── a on main"),
+        position("a work"),
+    ];
+    assert!(order.windows(2).all(|w| w[0] < w[1]), "{stdout}");
+    assert!(!stdout.contains("root"), "{stdout}");
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use std::fs;
+
+use common::{git, git_stack, init_repo, run_git_stack, write_state};
+
+#[test]
+fn marks_show_in_status_and_hold_back_land() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    init_repo(repo);
+    git(repo, &["branch", "a"]);
+    git(repo, &["branch", "b"]);
+
+    // A state file from before marks existed.
+    let state_home = tempfile::tempdir().unwrap();
+    let state_dir = state_home.path().join("git-stack");
+    write_state(
+        state_home.path(),
+        &format!(
+            "version: 1\n{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: a\n    stack_method: apply_merge\n    lkg_parent: null\n    branches:\n    - name: b\n      stack_method: apply_merge\n      lkg_parent: null\n      branches: []\n",
+            repo.canonicalize().unwrap().display()
+        ),
+    );
+
+    let out = git_stack(repo, state_home.path(), &["mark", "wip", "a"]);
+    assert_eq!(out, "Marked a as wip.\n");
+    git_stack(repo, state_home.path(), &["mark", "ready", "b"]);
+    let saved = fs::read_to_string(state_dir.join("state.yaml")).unwrap();
+    assert!(saved.contains("mark: wip"), "{saved}");
+
+    let status = git_stack(repo, state_home.path(), &["status"]);
+    assert!(status.contains("a (wip)"), "{status}");
+    assert!(status.contains("b (ready)"), "{status}");
+
+    // `a` is the bottom of `b`'s stack, so it's what `land` would merge.
+    git(repo, &["checkout", "-q", "b"]);
+    let output = run_git_stack(repo, state_home.path(), &["land"]);
+    assert!(!output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("a is marked wip; run `git stack mark ready a` first, or pass --force."),
+        "{stdout}"
+    );
+
+    git_stack(repo, state_home.path(), &["mark", "clear", "a"]);
+    let status = git_stack(repo, state_home.path(), &["status"]);
+    assert!(!status.contains("(wip)"), "{status}");
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use std::{fs, path::Path};
+
+use common::{commit_file, git, git_init, git_output, point_origin_at, run_git_stack, write_state};
+
+fn files_on(repo: &Path, branch: &str) -> Vec<String> {
+    git_output(repo, &["ls-tree", "--name-only", branch])
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+/// `main → a → b → c`, each branch adding one file, tracked by git-stack.
+fn fixture(repo: &Path, state_home: &Path) {
+    git_init(repo);
+
+    let main_sha = commit_file(repo, "base.txt");
+    point_origin_at(repo, &main_sha);
+    git(repo, &["checkout", "-q", "-b", "a"]);
+    let a_sha = commit_file(repo, "a.txt");
+    git(repo, &["checkout", "-q", "-b", "b"]);
+    let b_sha = commit_file(repo, "b.txt");
+    git(repo, &["checkout", "-q", "-b", "c"]);
+    commit_file(repo, "c.txt");
+
+    let state = format!(
+        "{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: a\n    stack_method: apply_merge\n    lkg_parent: {main_sha}\n    branches:\n    - name: b\n      stack_method: apply_merge\n      lkg_parent: {a_sha}\n      branches:\n      - name: c\n        stack_method: apply_merge\n        lkg_parent: {b_sha}\n        branches: []\n",
+        repo.canonicalize().unwrap().display(),
+    );
+    write_state(state_home, &state);
+}
+
+#[test]
+fn move_carries_only_the_branch_commits_and_restacks_descendants() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    let state_home = tempfile::tempdir().unwrap();
+    fixture(repo, state_home.path());
+    let a_sha = git_output(repo, &["rev-parse", "a"]);
+
+    let output = run_git_stack(repo, state_home.path(), &["move", "b", "--onto", "main"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "move failed:\nstdout: {stdout}\nstderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // `b` now sits directly on main without `a`'s work, and `c` followed it.
+    assert_eq!(
+        git_output(repo, &["rev-parse", "b~1"]),
+        git_output(repo, &["rev-parse", "main"])
+    );
+    assert_eq!(files_on(repo, "b"), ["b.txt", "base.txt"]);
+    assert_eq!(
+        git_output(repo, &["rev-parse", "c~1"]),
+        git_output(repo, &["rev-parse", "b"])
+    );
+    assert_eq!(files_on(repo, "c"), ["b.txt", "base.txt", "c.txt"]);
+    assert_eq!(git_output(repo, &["rev-parse", "a"]), a_sha);
+    assert_eq!(git_output(repo, &["branch", "--show-current"]), "c");
+
+    let state = fs::read_to_string(state_home.path().join("git-stack/state.yaml")).unwrap();
+    let tree: Vec<&str> = state
+        .lines()
+        .filter(|line| line.trim_start().starts_with("- name:") || line.contains("  name: main"))
+        .collect();
+    assert_eq!(
+        tree,
+        [
+            "  name: main",
+            "  - name: a",
+            "  - name: b",
+            "    - name: c",
+        ],
+        "{state}"
+    );
+}
+
+#[test]
+fn move_refuses_to_move_a_branch_under_its_own_descendant() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    let state_home = tempfile::tempdir().unwrap();
+    fixture(repo, state_home.path());
+    let before = git_output(repo, &["rev-parse", "b"]);
+
+    let output = run_git_stack(repo, state_home.path(), &["move", "a", "--onto", "c"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success(), "{stdout}");
+    assert!(stdout.contains("one of its descendants"), "{stdout}");
+    assert_eq!(git_output(repo, &["rev-parse", "b"]), before);
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use std::{fs, os::unix::fs::MetadataExt, path::Path};
+
+use common::{git, git_stack, init_github_repo, run_git_stack, succeeded, write_state};
+
+/// [`init_github_repo`] with a `feature` branch.
+fn init_feature_repo(repo: &Path) {
+    init_github_repo(repo);
+    git(repo, &["branch", "feature"]);
+}
+
+#[test]
+fn a_moved_repo_keeps_its_stack() {
+    let dir = tempfile::tempdir().unwrap();
+    let old_path = dir.path().join("old");
+    let new_path = dir.path().join("new");
+    fs::create_dir_all(&old_path).unwrap();
+    init_feature_repo(&old_path);
+
+    let state_home = tempfile::tempdir().unwrap();
+    let state_dir = state_home.path().join("git-stack");
+    let old_key = old_path.canonicalize().unwrap().display().to_string();
+    write_state(
+        state_home.path(),
+        &format!(
+            "{old_key}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: feature\n    stack_method: apply_merge\n    note: keep me\n    lkg_parent: null\n    branches: []\n"
+        ),
+    );
+
+    // Upgrading a legacy file stamps each entry with its repo's identity, whichever repo the
+    // command runs in.
+    let elsewhere = dir.path().join("elsewhere");
+    fs::create_dir_all(&elsewhere).unwrap();
+    init_github_repo(&elsewhere);
+    git_stack(
+        &elsewhere,
+        state_home.path(),
+        &["status", "--no-fetch-trunk"],
+    );
+    let state = fs::read_to_string(state_dir.join("state.yaml")).unwrap();
+    assert!(
+        state.contains("  identity: github.com/acme/app\n"),
+        "{state}"
+    );
+
+    assert!(state.starts_with("version: 2\n"), "{state}");
+
+    fs::rename(&old_path, &new_path).unwrap();
+    let output = run_git_stack(&new_path, state_home.path(), &["export"]);
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    let stdout = succeeded(&["export"], output);
+    // The notice stays off stdout, which here is the export itself.
+    assert!(
+        stderr.contains("Moving the stack for github.com/acme/app"),
+        "{stderr}"
+    );
+    assert!(!stdout.contains("Moving the stack"), "{stdout}");
+    assert!(stdout.contains("note: keep me"), "{stdout}");
+
+    let state = fs::read_to_string(state_dir.join("state.yaml")).unwrap();
+    let new_key = new_path.canonicalize().unwrap().display().to_string();
+    assert!(state.contains(&format!("{new_key}:\n")), "{state}");
+    assert!(!state.contains(&format!("{old_key}:\n")), "{state}");
+
+    // Once moved, later commands find nothing to relocate and don't rewrite the file.
+    let inode = fs::metadata(state_dir.join("state.yaml")).unwrap().ino();
+    git_stack(&new_path, state_home.path(), &["export"]);
+    assert_eq!(
+        fs::metadata(state_dir.join("state.yaml")).unwrap().ino(),
+        inode
+    );
+}
+
+#[test]
+fn a_repo_moved_before_its_identity_was_recorded_keeps_its_stack() {
+    let dir = tempfile::tempdir().unwrap();
+    let new_path = dir.path().join("new");
+    fs::create_dir_all(&new_path).unwrap();
+    init_feature_repo(&new_path);
+
+    // A version 1 entry with no identity, for a checkout that is already gone.
+    let state_home = tempfile::tempdir().unwrap();
+    let old_key = dir.path().join("old").display().to_string();
+    let state_file = write_state(
+        state_home.path(),
+        &format!(
+            "version: 1\n{old_key}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: feature\n    stack_method: apply_merge\n    note: keep me\n    lkg_parent: null\n    branches: []\n/gone/unrelated:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: other\n    stack_method: apply_merge\n    lkg_parent: null\n    branches: []\n"
+        ),
+    );
+
+    let output = run_git_stack(&new_path, state_home.path(), &["export"]);
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    let stdout = succeeded(&["export"], output);
+    assert!(stderr.contains(&format!("from {old_key}")), "{stderr}");
+    assert!(stdout.contains("note: keep me"), "{stdout}");
+
+    let state = fs::read_to_string(state_file).unwrap();
+    let new_key = new_path.canonicalize().unwrap().display().to_string();
+    assert!(
+        state.contains(&format!("{new_key}:\n"))
+            && state.contains("  identity: github.com/acme/app\n"),
+        "{state}"
+    );
+    // Its branches aren't here, so the other orphan stays where it was.
+    assert!(state.contains("/gone/unrelated:\n"), "{state}");
+}
+
+#[test]
+fn a_second_clone_gets_its_own_entry() {
+    let dir = tempfile::tempdir().unwrap();
+    let first = dir.path().join("first");
+    let second = dir.path().join("second");
+    fs::create_dir_all(&first).unwrap();
+    fs::create_dir_all(&second).unwrap();
+    init_feature_repo(&first);
+    init_feature_repo(&second);
+
+    let state_home = tempfile::tempdir().unwrap();
+    git_stack(&first, state_home.path(), &["init"]);
+    let stdout = git_stack(&second, state_home.path(), &["init"]);
+    assert!(!stdout.contains("Moving the stack"), "{stdout}");
+
+    let state = fs::read_to_string(state_home.path().join("git-stack").join("state.yaml")).unwrap();
+    for repo in [&first, &second] {
+        let key = repo.canonicalize().unwrap().display().to_string();
+        assert!(state.contains(&format!("{key}:\n")), "{state}");
+    }
+}
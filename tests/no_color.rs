@@ -0,0 +1,70 @@
+mod common;
+
+use std::{fs, path::Path};
+
+use common::{
+    git, git_init, git_output, git_stack_command, point_origin_at, succeeded, write_state,
+};
+
+/// A repo with `feature` stacked on `main`, and git-stack state tracking it.
+fn fixture(repo: &Path, state_home: &Path) {
+    git_init(repo);
+
+    fs::write(repo.join("base.txt"), "base\n").unwrap();
+    git(repo, &["add", "base.txt"]);
+    git(repo, &["commit", "-q", "-m", "root"]);
+    let main_sha = git_output(repo, &["rev-parse", "HEAD"]);
+    point_origin_at(repo, &main_sha);
+
+    git(repo, &["checkout", "-q", "-b", "feature"]);
+    fs::write(repo.join("feature.txt"), "feature\n").unwrap();
+    git(repo, &["add", "feature.txt"]);
+    git(repo, &["commit", "-q", "-m", "feature"]);
+
+    let state = format!(
+        "{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: feature\n    stack_method: apply_merge\n    lkg_parent: {}\n    branches: []\n",
+        repo.canonicalize().unwrap().display(),
+        main_sha,
+    );
+    write_state(state_home, &state);
+}
+
+/// Run git-stack with colors forced on by the environment, so only the flag under test can turn
+/// them off.
+fn run(repo: &Path, state_home: &Path, args: &[&str], no_color_env: bool) -> String {
+    let mut command = git_stack_command(repo, state_home, args);
+    command.env("CLICOLOR_FORCE", "1");
+    if !no_color_env {
+        command.env_remove("NO_COLOR");
+    }
+    succeeded(args, command.output().unwrap())
+}
+
+#[test]
+fn no_color_strips_escapes_but_keeps_the_tree() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    let state_home = tempfile::tempdir().unwrap();
+    fixture(repo, state_home.path());
+
+    let colored = run(repo, state_home.path(), &["status"], false);
+    assert!(colored.contains("\x1b["), "{colored:?}");
+    let colored = run(repo, state_home.path(), &["note"], false);
+    assert!(colored.contains("\x1b["), "{colored:?}");
+
+    for (args, no_color_env) in [
+        (&["status", "--no-color"][..], false),
+        (&["--no-color", "restack", "--dry-run"][..], false),
+        (&["status"][..], true),
+        (&["note", "--no-color"][..], false),
+    ] {
+        let stdout = run(repo, state_home.path(), args, no_color_env);
+        assert!(
+            !stdout.contains('\x1b'),
+            "{args:?} (NO_COLOR={no_color_env}): {stdout:?}"
+        );
+        assert!(stdout.contains("feature"), "{args:?}: {stdout}");
+    }
+    let plain = run(repo, state_home.path(), &["status", "--no-color"], false);
+    assert!(plain.contains("┃ feature"), "{plain}");
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use common::{git, git_stack, init_repo, write_state};
+
+#[test]
+fn append_adds_lines_and_all_lists_the_stack() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    init_repo(repo);
+    git(repo, &["branch", "a"]);
+    git(repo, &["branch", "b"]);
+
+    let state_home = tempfile::tempdir().unwrap();
+    write_state(
+        state_home.path(),
+        &format!(
+            "version: 1\n{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: a\n    stack_method: apply_merge\n    lkg_parent: null\n    branches:\n    - name: b\n      stack_method: apply_merge\n      lkg_parent: null\n      branches: []\n",
+            repo.canonicalize().unwrap().display()
+        ),
+    );
+
+    git_stack(repo, state_home.path(), &["note", "a", "--append", "wip"]);
+    git_stack(
+        repo,
+        state_home.path(),
+        &["note", "a", "--append", "blocked on #123"],
+    );
+    let note = git_stack(repo, state_home.path(), &["note", "a"]);
+    assert_eq!(note, "wip\nblocked on #123\n");
+
+    let all = git_stack(repo, state_home.path(), &["note", "b", "--all"]);
+    assert_eq!(all, "a: wip\nb: (no note)\n");
+}
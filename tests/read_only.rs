@@ -0,0 +1,88 @@
+mod common;
+
+use std::path::Path;
+
+use common::{
+    commit_file, git, git_init, git_output, git_stack_command, point_origin_at, write_state,
+};
+
+fn git_stack(repo: &Path, state_home: &Path, args: &[&str]) -> std::process::Output {
+    git_stack_command(repo, state_home, args)
+        .env("GIT_SEQUENCE_EDITOR", "true")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn read_only_allows_status_but_blocks_a_restack() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    git_init(repo);
+    commit_file(repo, "root");
+    let root_sha = git_output(repo, &["rev-parse", "HEAD"]);
+    git(repo, &["checkout", "-q", "-b", "feature"]);
+    commit_file(repo, "feature");
+    git(repo, &["checkout", "-q", "main"]);
+    commit_file(repo, "trunk");
+    point_origin_at(repo, "main");
+    git(repo, &["checkout", "-q", "feature"]);
+    let state_home = tempfile::tempdir().unwrap();
+    write_state(
+        state_home.path(),
+        &format!(
+            "version: 1\n{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: feature\n    stack_method: apply_merge\n    lkg_parent: {root_sha}\n    branches: []\n",
+            repo.canonicalize().unwrap().display()
+        ),
+    );
+    let feature_sha = git_output(repo, &["rev-parse", "feature"]);
+
+    let output = git_stack(repo, state_home.path(), &["--read-only", "status"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("feature"),
+        "{output:?}"
+    );
+
+    let output = git_stack(repo, state_home.path(), &["restack", "--read-only"]);
+    assert!(!output.status.success(), "{output:?}");
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("running with --read-only"),
+        "{output:?}"
+    );
+    assert_eq!(git_output(repo, &["rev-parse", "feature"]), feature_sha);
+    assert_eq!(git_output(repo, &["branch", "--show-current"]), "feature");
+}
+
+#[test]
+fn read_only_blocks_git_commands_that_take_over_the_terminal() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    git_init(repo);
+    commit_file(repo, "root");
+    let root_sha = git_output(repo, &["rev-parse", "HEAD"]);
+    point_origin_at(repo, "main");
+    git(repo, &["checkout", "-q", "-b", "feature"]);
+    commit_file(repo, "feature");
+    let state_home = tempfile::tempdir().unwrap();
+    write_state(
+        state_home.path(),
+        &format!(
+            "version: 1\n{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: feature\n    stack_method: apply_merge\n    lkg_parent: {root_sha}\n    branches: []\n",
+            repo.canonicalize().unwrap().display()
+        ),
+    );
+
+    // `rebase-interactive` hands the terminal to `git rebase -i`.
+    let output = git_stack(
+        repo,
+        state_home.path(),
+        &["--read-only", "rebase-interactive"],
+    );
+    assert!(!output.status.success(), "{output:?}");
+    assert!(
+        String::from_utf8_lossy(&output.stdout)
+            .contains(&format!("Refusing to run `git rebase -i {root_sha}`")),
+        "{output:?}"
+    );
+    assert!(!repo.join(".git/rebase-merge").exists());
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use std::{path::Path, process::Output};
+
+use common::{commit_file, git, git_output, git_stack_command, init_repo, write_state};
+
+/// Run git-stack with `sequence_editor` standing in for the user editing the rebase todo list.
+fn run_with_sequence_editor(
+    repo: &Path,
+    state_home: &Path,
+    sequence_editor: &str,
+    args: &[&str],
+) -> Output {
+    git_stack_command(repo, state_home, args)
+        .env("GIT_SEQUENCE_EDITOR", sequence_editor)
+        .output()
+        .unwrap()
+}
+
+/// main <- a (a1, a2) <- b (b1), tracked with no LKG parents; `b` is checked out.
+fn setup() -> (tempfile::TempDir, tempfile::TempDir) {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    init_repo(repo);
+    git(repo, &["checkout", "-q", "-b", "a"]);
+    commit_file(repo, "a1");
+    commit_file(repo, "a2");
+    git(repo, &["checkout", "-q", "-b", "b"]);
+    commit_file(repo, "b1");
+
+    let state_home = tempfile::tempdir().unwrap();
+    write_state(
+        state_home.path(),
+        &format!(
+            "version: 1\n{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: a\n    stack_method: apply_merge\n    lkg_parent: null\n    branches:\n    - name: b\n      stack_method: apply_merge\n      lkg_parent: null\n      branches: []\n",
+            repo.canonicalize().unwrap().display()
+        ),
+    );
+    (repo_dir, state_home)
+}
+
+#[test]
+fn rewritten_branch_restacks_its_children() {
+    let (repo_dir, state_home) = setup();
+    let repo = repo_dir.path();
+
+    // Drop `a1` from `a`.
+    let output = run_with_sequence_editor(
+        repo,
+        state_home.path(),
+        "sed -i 1s/^pick/drop/",
+        &["rebase-interactive", "a"],
+    );
+    assert!(output.status.success(), "{output:?}");
+
+    assert_eq!(git_output(repo, &["log", "--format=%s", "main..a"]), "a2");
+    assert_eq!(
+        git_output(repo, &["log", "--format=%s", "main..b"]),
+        "b1\na2"
+    );
+    assert_eq!(git_output(repo, &["branch", "--show-current"]), "b");
+}
+
+#[test]
+fn aborted_rebase_restacks_nothing() {
+    let (repo_dir, state_home) = setup();
+    let repo = repo_dir.path();
+    let b_before = git_output(repo, &["rev-parse", "b"]);
+
+    // An empty todo list makes git abort the rebase.
+    let output = run_with_sequence_editor(
+        repo,
+        state_home.path(),
+        "sed -i d",
+        &["rebase-interactive", "a"],
+    );
+    assert!(!output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("nothing was restacked"), "{stdout}");
+    assert_eq!(git_output(repo, &["rev-parse", "b"]), b_before);
+    assert_eq!(
+        git_output(repo, &["log", "--format=%s", "main..a"]),
+        "a2\na1"
+    );
+}
@@ -19,10 +19,10 @@ fn git_output(repo: &Path, args: &[&str]) -> String {
     String::from_utf8(output.stdout).unwrap().trim().to_string()
 }
 
-#[test]
-fn aborting_conflicted_am_restores_head_and_branch_ref() {
-    let repo_dir = tempfile::tempdir().unwrap();
-    let repo = repo_dir.path();
+/// A repo paused mid-`git am` conflict while restacking `feature` onto `main`, with the matching
+/// `pending_restack` recorded (`orig_branch` is where the restack was started from). Returns
+/// `(original feature sha, main sha)`.
+fn conflicted_am_fixture(repo: &Path, state_dir: &Path, orig_branch: &str) -> (String, String) {
     git(repo, &["init", "-q", "-b", "main"]);
     git(repo, &["config", "user.email", "test@example.com"]);
     git(repo, &["config", "user.name", "Test"]);
@@ -71,23 +71,25 @@ fn aborting_conflicted_am_restores_head_and_branch_ref() {
         "the fixture must enter an am conflict"
     );
 
-    let state_home = tempfile::tempdir().unwrap();
-    let state_dir = state_home.path().join("git-stack");
-    fs::create_dir_all(&state_dir).unwrap();
+    fs::create_dir_all(state_dir).unwrap();
     let canonical_repo = repo.canonicalize().unwrap();
     let state = format!(
-        "{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: feature\n    stack_method: apply_merge\n    lkg_parent: {}\n    branches: []\n  pending_restack:\n    method: am\n    branch_name: feature\n    parent: main\n    original_sha: {}\n    resume:\n      restack_branch: feature\n      orig_branch: feature\n      ancestors: false\n      push: false\n      squash: false\n",
+        "{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: feature\n    stack_method: apply_merge\n    lkg_parent: {}\n    branches: []\n  pending_restack:\n    method: am\n    branch_name: feature\n    parent: main\n    original_sha: {}\n    resume:\n      restack_branch: feature\n      orig_branch: {}\n      ancestors: false\n      push: false\n      squash: false\n",
         canonical_repo.display(),
         root_sha,
-        original_feature_sha
+        original_feature_sha,
+        orig_branch
     );
     fs::write(state_dir.join("state.yaml"), state).unwrap();
+    (original_feature_sha, main_sha)
+}
 
+fn run_abort(repo: &Path, state_home: &Path) {
     let output = Command::new(env!("CARGO_BIN_EXE_git-stack"))
         .args(["restack", "--abort"])
         .current_dir(repo)
-        .env("XDG_STATE_HOME", state_home.path())
-        .env("XDG_CONFIG_HOME", state_home.path().join("config"))
+        .env("XDG_STATE_HOME", state_home)
+        .env("XDG_CONFIG_HOME", state_home.join("config"))
         .output()
         .unwrap();
     assert!(
@@ -96,6 +98,17 @@ fn aborting_conflicted_am_restores_head_and_branch_ref() {
         String::from_utf8_lossy(&output.stdout),
         String::from_utf8_lossy(&output.stderr)
     );
+}
+
+#[test]
+fn aborting_conflicted_am_restores_head_and_branch_ref() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    let state_home = tempfile::tempdir().unwrap();
+    let state_dir = state_home.path().join("git-stack");
+    let (original_feature_sha, main_sha) = conflicted_am_fixture(repo, &state_dir, "feature");
+
+    run_abort(repo, state_home.path());
 
     assert_eq!(
         git_output(repo, &["rev-parse", "HEAD"]),
@@ -112,3 +125,21 @@ fn aborting_conflicted_am_restores_head_and_branch_ref() {
     assert!(!saved_state.contains("pending_restack:"));
     assert_ne!(git_output(repo, &["rev-parse", "HEAD"]), main_sha);
 }
+
+#[test]
+fn aborting_returns_to_the_branch_the_restack_started_from() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    let state_home = tempfile::tempdir().unwrap();
+    let state_dir = state_home.path().join("git-stack");
+    let (original_feature_sha, main_sha) = conflicted_am_fixture(repo, &state_dir, "main");
+
+    run_abort(repo, state_home.path());
+
+    assert_eq!(
+        git_output(repo, &["rev-parse", "refs/heads/feature"]),
+        original_feature_sha
+    );
+    assert_eq!(git_output(repo, &["branch", "--show-current"]), "main");
+    assert_eq!(git_output(repo, &["rev-parse", "HEAD"]), main_sha);
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use std::fs;
+
+use common::{git, git_init, git_output, point_origin_at, run_git_stack, write_state};
+
+#[test]
+fn dry_run_reports_the_plan_without_touching_refs() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    git_init(repo);
+
+    fs::write(repo.join("base.txt"), "base\n").unwrap();
+    git(repo, &["add", "base.txt"]);
+    git(repo, &["commit", "-q", "-m", "root"]);
+    let root_sha = git_output(repo, &["rev-parse", "HEAD"]);
+
+    git(repo, &["checkout", "-q", "-b", "a"]);
+    fs::write(repo.join("a.txt"), "a\n").unwrap();
+    git(repo, &["add", "a.txt"]);
+    git(repo, &["commit", "-q", "-m", "a"]);
+    let a_sha = git_output(repo, &["rev-parse", "HEAD"]);
+
+    git(repo, &["checkout", "-q", "-b", "b"]);
+    fs::write(repo.join("b.txt"), "b\n").unwrap();
+    git(repo, &["add", "b.txt"]);
+    git(repo, &["commit", "-q", "-m", "b"]);
+    let b_sha = git_output(repo, &["rev-parse", "HEAD"]);
+
+    // Trunk moves on, so `a` needs a restack, and `b` (stacked on the old `a`) follows it.
+    git(repo, &["checkout", "-q", "main"]);
+    fs::write(repo.join("main.txt"), "main\n").unwrap();
+    git(repo, &["add", "main.txt"]);
+    git(repo, &["commit", "-q", "-m", "trunk moves"]);
+    let main_sha = git_output(repo, &["rev-parse", "HEAD"]);
+    point_origin_at(repo, &main_sha);
+    git(repo, &["checkout", "-q", "b"]);
+
+    let state_home = tempfile::tempdir().unwrap();
+    let canonical_repo = repo.canonicalize().unwrap();
+    let state = format!(
+        "{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: a\n    stack_method: apply_merge\n    lkg_parent: {}\n    branches:\n    - name: b\n      stack_method: apply_merge\n      lkg_parent: {}\n      branches: []\n",
+        canonical_repo.display(),
+        root_sha,
+        a_sha,
+    );
+    write_state(state_home.path(), &state);
+
+    let output = run_git_stack(
+        repo,
+        state_home.path(),
+        &["restack", "--dry-run", "--ancestors"],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "dry run failed:\nstdout: {stdout}\nstderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains("  a: would restack onto main via apply_merge (format-patch/am)\n"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("  b: would restack onto a via apply_merge (format-patch/am)\n"),
+        "{stdout}"
+    );
+
+    assert_eq!(git_output(repo, &["rev-parse", "refs/heads/a"]), a_sha);
+    assert_eq!(git_output(repo, &["rev-parse", "refs/heads/b"]), b_sha);
+    assert_eq!(
+        git_output(repo, &["rev-parse", "refs/heads/main"]),
+        main_sha
+    );
+    assert_eq!(git_output(repo, &["branch", "--show-current"]), "b");
+}
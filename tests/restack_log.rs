@@ -0,0 +1,123 @@
+mod common;
+
+use std::{fs, path::Path};
+
+use common::{
+    commit_file, git, git_init, git_output, git_stack, point_origin_at, run_git_stack, write_state,
+};
+
+fn logged_path(stdout: &str) -> &str {
+    stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("Restack log: "))
+        .unwrap_or_else(|| panic!("no log path in: {stdout}"))
+}
+
+#[test]
+fn log_records_each_step_and_prints_its_path() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    git_init(repo);
+    commit_file(repo, "root");
+    let root_sha = git_output(repo, &["rev-parse", "HEAD"]);
+    git(repo, &["checkout", "-q", "-b", "feature"]);
+    commit_file(repo, "feature");
+    let feature_sha = git_output(repo, &["rev-parse", "HEAD"]);
+    git(repo, &["checkout", "-q", "main"]);
+    commit_file(repo, "trunk");
+    point_origin_at(repo, "main");
+    git(repo, &["checkout", "-q", "feature"]);
+
+    let state_home = tempfile::tempdir().unwrap();
+    let state_dir = state_home.path().join("git-stack");
+    write_state(
+        state_home.path(),
+        &format!(
+            "version: 1\n{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: feature\n    stack_method: apply_merge\n    lkg_parent: {root_sha}\n    branches: []\n",
+            repo.canonicalize().unwrap().display()
+        ),
+    );
+
+    let stdout = git_stack(repo, state_home.path(), &["restack", "--log"]);
+    let path = logged_path(&stdout);
+    assert!(
+        Path::new(path).starts_with(state_dir.join("restack-logs")),
+        "{path}"
+    );
+
+    let log = fs::read_to_string(path).unwrap();
+    let lines: Vec<serde_json::Value> = log
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 1, "{log}");
+    let step = &lines[0];
+    assert_eq!(step["branch"], "feature");
+    assert_eq!(step["parent"], "main");
+    assert_eq!(step["source_sha"], feature_sha.as_str());
+    assert_eq!(step["method"], "am");
+    assert_eq!(step["outcome"], "patched");
+    assert_eq!(
+        step["result_sha"],
+        git_output(repo, &["rev-parse", "feature"]).as_str()
+    );
+
+    // Without --log the file is still written, just not announced.
+    let stdout = git_stack(repo, state_home.path(), &["restack"]);
+    assert!(!stdout.contains("Restack log:"), "{stdout}");
+}
+
+#[test]
+fn a_continued_restack_appends_to_the_log_its_first_run_started() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    git_init(repo);
+    fs::write(repo.join("shared.txt"), "base\n").unwrap();
+    git(repo, &["add", "shared.txt"]);
+    git(repo, &["commit", "-q", "-m", "root"]);
+    let root_sha = git_output(repo, &["rev-parse", "HEAD"]);
+    git(repo, &["checkout", "-q", "-b", "feature"]);
+    fs::write(repo.join("shared.txt"), "feature\n").unwrap();
+    git(repo, &["commit", "-q", "-am", "feature work"]);
+    git(repo, &["checkout", "-q", "main"]);
+    fs::write(repo.join("shared.txt"), "main\n").unwrap();
+    git(repo, &["commit", "-q", "-am", "main work"]);
+    point_origin_at(repo, "main");
+    git(repo, &["checkout", "-q", "feature"]);
+
+    let state_home = tempfile::tempdir().unwrap();
+    let state_dir = state_home.path().join("git-stack");
+    write_state(
+        state_home.path(),
+        &format!(
+            "version: 1\n{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: feature\n    stack_method: apply_merge\n    lkg_parent: {root_sha}\n    branches: []\n",
+            repo.canonicalize().unwrap().display()
+        ),
+    );
+
+    let output = run_git_stack(repo, state_home.path(), &["restack", "--log"]);
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let path = logged_path(&stdout).to_string();
+
+    fs::write(repo.join("shared.txt"), "resolved\n").unwrap();
+    git(repo, &["add", "shared.txt"]);
+    let stdout = git_stack(repo, state_home.path(), &["restack", "--continue"]);
+    assert_eq!(logged_path(&stdout), path);
+
+    let outcomes: Vec<String> = fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let step: serde_json::Value = serde_json::from_str(line).unwrap();
+            step["outcome"].as_str().unwrap().to_string()
+        })
+        .collect();
+    assert_eq!(outcomes, ["conflict", "already_stacked"]);
+    assert_eq!(
+        fs::read_dir(state_dir.join("restack-logs"))
+            .unwrap()
+            .count(),
+        1
+    );
+}
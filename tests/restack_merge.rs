@@ -0,0 +1,149 @@
+mod common;
+
+use std::{fs, path::Path, process::Command};
+
+use common::{git, git_init, point_origin_at, run_git_stack, write_state};
+
+/// `feature` (stack method `merge`) and `main` both rewrote `shared.txt`, so merging `main` into
+/// `feature` conflicts. `feature` is checked out.
+fn diverged_merge_fixture(repo: &Path, state_home: &Path) {
+    git_init(repo);
+    fs::write(repo.join("shared.txt"), "base\n").unwrap();
+    fs::write(repo.join("notes.txt"), "notes\n").unwrap();
+    git(repo, &["add", "shared.txt", "notes.txt"]);
+    git(repo, &["commit", "-q", "-m", "root"]);
+    point_origin_at(repo, "HEAD");
+    git(repo, &["checkout", "-q", "-b", "feature"]);
+    fs::write(repo.join("shared.txt"), "feature\n").unwrap();
+    git(repo, &["commit", "-q", "-am", "feature work"]);
+    git(repo, &["checkout", "-q", "main"]);
+    fs::write(repo.join("shared.txt"), "main\n").unwrap();
+    git(repo, &["add", "."]);
+    git(repo, &["commit", "-q", "-m", "main work"]);
+    git(repo, &["update-ref", "refs/remotes/origin/main", "main"]);
+    git(repo, &["checkout", "-q", "feature"]);
+
+    write_state(
+        state_home,
+        &format!(
+            "version: 1\n{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: feature\n    stack_method: merge\n    lkg_parent: null\n    branches: []\n",
+            repo.canonicalize().unwrap().display()
+        ),
+    );
+}
+
+fn restack(repo: &Path, state_home: &Path) -> std::process::Output {
+    run_git_stack(repo, state_home, &["restack"])
+}
+
+#[test]
+fn a_merge_conflict_pauses_the_restack_with_guidance() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    let state_home = tempfile::tempdir().unwrap();
+    diverged_merge_fixture(repo, state_home.path());
+
+    let output = restack(repo, state_home.path());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    assert!(stderr.contains("Merge hit a conflict."), "{stderr}");
+    assert!(stderr.contains("git stack restack --continue"), "{stderr}");
+    let state = fs::read_to_string(state_home.path().join("git-stack/state.yaml")).unwrap();
+    assert!(state.contains("    method: merge\n"), "{state}");
+}
+
+#[test]
+fn restack_refuses_a_dirty_working_tree_up_front() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    let state_home = tempfile::tempdir().unwrap();
+    diverged_merge_fixture(repo, state_home.path());
+    fs::write(repo.join("shared.txt"), "uncommitted\n").unwrap();
+
+    let output = restack(repo, state_home.path());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success(), "{output:?}");
+    assert!(
+        stdout.contains("commit or stash them before restacking"),
+        "{stdout}"
+    );
+    // Nothing was touched: still on `feature`, with the edit in place.
+    assert_eq!(
+        fs::read_to_string(repo.join("shared.txt")).unwrap(),
+        "uncommitted\n"
+    );
+    let head = Command::new("git")
+        .args(["branch", "--show-current"])
+        .current_dir(repo)
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&head.stdout).trim(), "feature");
+}
+
+#[test]
+fn autostash_survives_a_conflict_and_comes_back_on_continue() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    let state_home = tempfile::tempdir().unwrap();
+    diverged_merge_fixture(repo, state_home.path());
+    fs::write(repo.join("notes.txt"), "edited notes\n").unwrap();
+    fs::write(repo.join("scratch.txt"), "work in progress\n").unwrap();
+
+    let output = run_git_stack(repo, state_home.path(), &["restack", "--autostash"]);
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Your local changes are stashed as"),
+        "{stderr}"
+    );
+    assert!(!repo.join("scratch.txt").exists());
+    assert_eq!(
+        fs::read_to_string(repo.join("notes.txt")).unwrap(),
+        "notes\n"
+    );
+    let state = fs::read_to_string(state_home.path().join("git-stack/state.yaml")).unwrap();
+    assert!(state.contains("      autostash: "), "{state}");
+
+    fs::write(repo.join("shared.txt"), "resolved\n").unwrap();
+    git(repo, &["add", "shared.txt"]);
+    let output = run_git_stack(repo, state_home.path(), &["restack", "--continue"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{output:?}");
+    assert!(
+        stdout.contains("Restored stashed local changes."),
+        "{stdout}"
+    );
+    assert_eq!(
+        fs::read_to_string(repo.join("scratch.txt")).unwrap(),
+        "work in progress\n"
+    );
+    assert_eq!(
+        fs::read_to_string(repo.join("notes.txt")).unwrap(),
+        "edited notes\n"
+    );
+    let stashes = Command::new("git")
+        .args(["stash", "list"])
+        .current_dir(repo)
+        .output()
+        .unwrap();
+    assert!(stashes.stdout.is_empty(), "{stashes:?}");
+}
+
+#[test]
+fn restack_ignores_untracked_files() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    let state_home = tempfile::tempdir().unwrap();
+    diverged_merge_fixture(repo, state_home.path());
+    fs::write(repo.join("scratch.txt"), "work in progress\n").unwrap();
+
+    // Gets as far as the merge conflict rather than refusing up front.
+    let output = restack(repo, state_home.path());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    assert!(stderr.contains("Merge hit a conflict."), "{stderr}");
+    assert_eq!(
+        fs::read_to_string(repo.join("scratch.txt")).unwrap(),
+        "work in progress\n"
+    );
+}
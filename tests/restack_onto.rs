@@ -0,0 +1,122 @@
+mod common;
+
+use std::fs;
+
+use common::{commit_file, git, git_init, git_output, point_origin_at, run_git_stack, write_state};
+
+#[test]
+fn onto_moves_the_bottom_branch_and_chains_the_rest() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    git_init(repo);
+    let root_sha = commit_file(repo, "base.txt");
+    point_origin_at(repo, &root_sha);
+
+    git(repo, &["checkout", "-q", "-b", "release"]);
+    let release_sha = commit_file(repo, "release.txt");
+    git(repo, &["checkout", "-q", "-b", "a", "main"]);
+    let a_sha = commit_file(repo, "a.txt");
+    git(repo, &["checkout", "-q", "-b", "b"]);
+    commit_file(repo, "b.txt");
+
+    let state_home = tempfile::tempdir().unwrap();
+    let state_dir = state_home.path().join("git-stack");
+    let state = format!(
+        "{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: a\n    stack_method: apply_merge\n    lkg_parent: {root_sha}\n    branches:\n    - name: b\n      stack_method: apply_merge\n      lkg_parent: {a_sha}\n      branches: []\n",
+        repo.canonicalize().unwrap().display(),
+    );
+    write_state(state_home.path(), &state);
+
+    let output = run_git_stack(
+        repo,
+        state_home.path(),
+        &["restack", "--ancestors", "--onto", "nope"],
+    );
+    assert!(!output.status.success(), "{output:?}");
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("--onto nope doesn't name a commit."),
+        "{output:?}"
+    );
+
+    let output = run_git_stack(
+        repo,
+        state_home.path(),
+        &["restack", "--ancestors", "--dry-run", "--onto", "release"],
+    );
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("  a: would restack onto release"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("  b: would restack onto a"), "{stdout}");
+
+    let output = run_git_stack(
+        repo,
+        state_home.path(),
+        &["restack", "--ancestors", "--onto", "release"],
+    );
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(git_output(repo, &["rev-parse", "a^"]), release_sha);
+    assert_eq!(
+        git_output(repo, &["rev-parse", "b^"]),
+        git_output(repo, &["rev-parse", "a"])
+    );
+    assert_eq!(git_output(repo, &["branch", "--show-current"]), "b");
+
+    // `a`'s replay boundary is now the release tip: release's commits aren't counted as `a`'s.
+    let state = fs::read_to_string(state_dir.join("state.yaml")).unwrap();
+    assert!(
+        state.contains(&format!(
+            "- name: a\n    stack_method: apply_merge\n    lkg_parent: {release_sha}"
+        )),
+        "{state}"
+    );
+}
+
+#[test]
+fn onto_refuses_a_mid_stack_branch() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    git_init(repo);
+    let root_sha = commit_file(repo, "base.txt");
+    point_origin_at(repo, &root_sha);
+
+    git(repo, &["checkout", "-q", "-b", "release"]);
+    commit_file(repo, "release.txt");
+    git(repo, &["checkout", "-q", "-b", "a", "main"]);
+    let a_sha = commit_file(repo, "a.txt");
+    git(repo, &["checkout", "-q", "-b", "b"]);
+    let b_sha = commit_file(repo, "b.txt");
+
+    let state_home = tempfile::tempdir().unwrap();
+    let state = format!(
+        "{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: a\n    stack_method: apply_merge\n    lkg_parent: {root_sha}\n    branches:\n    - name: b\n      stack_method: apply_merge\n      lkg_parent: {a_sha}\n      branches: []\n",
+        repo.canonicalize().unwrap().display(),
+    );
+    let state_file = write_state(state_home.path(), &state);
+
+    // Without `-a` the plan's bottom is `b`, whose tree parent stays `a`: the next plain restack
+    // would undo the move, so both the dry run and the real run refuse.
+    for args in [
+        &["restack", "--dry-run", "--onto", "release"][..],
+        &["restack", "--onto", "release"],
+    ] {
+        let output = run_git_stack(repo, state_home.path(), args);
+        assert!(!output.status.success(), "{output:?}");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            stdout
+                .contains("--onto only moves a branch stacked on main, and 'b' is stacked on 'a'."),
+            "{stdout}"
+        );
+    }
+    assert_eq!(git_output(repo, &["rev-parse", "b"]), b_sha);
+    let state = fs::read_to_string(&state_file).unwrap();
+    assert!(
+        state.contains(&format!(
+            "- name: b\n      stack_method: apply_merge\n      lkg_parent: {a_sha}"
+        )),
+        "{state}"
+    );
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use std::{fs, path::Path};
+
+use common::{git, git_output, git_stack, repo_with_bare_origin, write_state};
+
+/// main -> a -> b, all pushed to a bare `origin`, with `b` checked out.
+fn pushed_stack(dir: &Path, state_home: &Path) -> std::path::PathBuf {
+    let (_origin, repo) = repo_with_bare_origin(dir);
+    for branch in ["a", "b"] {
+        git(&repo, &["checkout", "-q", "-b", branch]);
+        fs::write(repo.join(branch), branch).unwrap();
+        git(&repo, &["add", branch]);
+        git(&repo, &["commit", "-q", "-m", branch]);
+    }
+    git(&repo, &["push", "-q", "origin", "main", "a", "b"]);
+    git(
+        &repo,
+        &[
+            "symbolic-ref",
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/main",
+        ],
+    );
+
+    write_state(
+        state_home,
+        &format!(
+            "version: 1\n{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: a\n    stack_method: apply_merge\n    lkg_parent: null\n    branches:\n    - name: b\n      stack_method: apply_merge\n      lkg_parent: null\n      branches: []\n",
+            repo.canonicalize().unwrap().display()
+        ),
+    );
+    repo
+}
+
+#[test]
+fn push_reports_branches_it_left_alone() {
+    let dir = tempfile::tempdir().unwrap();
+    let state_home = tempfile::tempdir().unwrap();
+    let repo = pushed_stack(dir.path(), state_home.path());
+
+    let stdout = git_stack(&repo, state_home.path(), &["restack", "-a", "--push"]);
+    assert!(
+        stdout.contains("Already up to date on origin: a, b"),
+        "{stdout}"
+    );
+    assert!(!stdout.contains("Pushed:"), "{stdout}");
+}
+
+#[test]
+fn push_all_pushes_branches_whose_remote_already_matches() {
+    let dir = tempfile::tempdir().unwrap();
+    let state_home = tempfile::tempdir().unwrap();
+    let repo = pushed_stack(dir.path(), state_home.path());
+
+    let stdout = git_stack(&repo, state_home.path(), &["restack", "-a", "--push-all"]);
+    assert!(stdout.contains("Pushed: a, b"), "{stdout}");
+    assert!(!stdout.contains("Already up to date"), "{stdout}");
+    assert_eq!(
+        git_output(&repo, &["rev-parse", "origin/b"]),
+        git_output(&repo, &["rev-parse", "b"])
+    );
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use std::{fs, path::Path, process::Command};
+
+use common::{git, git_output, init_repo, run_git_stack, write_state};
+
+/// A `main` ← `feature` repo where `feature` has three commits (`c1`, `c2`, `c3`) above the
+/// trunk commit. Returns the trunk SHA.
+fn init_fixture(repo: &Path, state_home: &Path) -> String {
+    init_repo(repo);
+    let root_sha = git_output(repo, &["rev-parse", "HEAD"]);
+    git(repo, &["checkout", "-q", "-b", "feature"]);
+    for name in ["c1", "c2", "c3"] {
+        fs::write(repo.join(name), name).unwrap();
+        git(repo, &["add", name]);
+        git(repo, &["commit", "-q", "-m", name]);
+    }
+
+    let state = format!(
+        "{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: feature\n    stack_method: apply_merge\n    lkg_parent: {}\n    branches: []\n",
+        repo.canonicalize().unwrap().display(),
+        root_sha,
+    );
+    write_state(state_home, &state);
+    root_sha
+}
+
+#[test]
+fn split_creates_lower_branch_and_restacks_original_on_it() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    let state_home = tempfile::tempdir().unwrap();
+    let root_sha = init_fixture(repo, state_home.path());
+    let c2 = git_output(repo, &["rev-parse", "feature~1"]);
+    let tip = git_output(repo, &["rev-parse", "feature"]);
+
+    let output = run_git_stack(
+        repo,
+        state_home.path(),
+        &["split", "feature~1", "--name", "lower"],
+    );
+    assert!(
+        output.status.success(),
+        "split failed:\nstdout: {}\nstderr: {}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    assert_eq!(git_output(repo, &["rev-parse", "lower"]), c2);
+    assert_eq!(git_output(repo, &["rev-parse", "feature"]), tip);
+
+    let saved = fs::read_to_string(state_home.path().join("git-stack/state.yaml")).unwrap();
+    let state: serde_yaml::Value = serde_yaml::from_str(&saved).unwrap();
+    let root = state[repo.canonicalize().unwrap().to_str().unwrap()].clone();
+    let lower = &root["branches"][0];
+    assert_eq!(lower["name"].as_str(), Some("lower"));
+    assert_eq!(lower["lkg_parent"].as_str(), Some(root_sha.as_str()));
+    let feature = &lower["branches"][0];
+    assert_eq!(feature["name"].as_str(), Some("feature"));
+    assert_eq!(feature["lkg_parent"].as_str(), Some(c2.as_str()));
+}
+
+#[test]
+fn split_rejects_points_outside_the_branch() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    let state_home = tempfile::tempdir().unwrap();
+    init_fixture(repo, state_home.path());
+
+    for at in ["main", "feature"] {
+        let output = run_git_stack(repo, state_home.path(), &["split", at]);
+        assert!(!output.status.success(), "split at {at} should fail");
+        // Errors are reported through the tracing subscriber, which writes to stdout.
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("strictly between"), "{stdout}");
+    }
+    assert!(
+        Command::new("git")
+            .args(["rev-parse", "--verify", "-q", "feature-base"])
+            .current_dir(repo)
+            .status()
+            .map(|s| !s.success())
+            .unwrap()
+    );
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use std::{
+    fs,
+    os::unix::fs::{MetadataExt, PermissionsExt},
+    path::{Path, PathBuf},
+};
+
+use common::{git, git_stack, git_stack_command, init_repo, run_git_stack, write_state};
+
+/// A repo with branch `a` on `main`, and a state file at the returned path tracking it.
+fn tracked_repo(repo: &Path, state_home: &Path) -> PathBuf {
+    init_repo(repo);
+    git(repo, &["checkout", "-q", "-b", "a"]);
+    git(repo, &["commit", "-q", "--allow-empty", "-m", "a"]);
+    write_state(
+        state_home,
+        &format!(
+            "version: 1\n{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: a\n    stack_method: apply_merge\n    lkg_parent: null\n    branches: []\n",
+            repo.canonicalize().unwrap().display()
+        ),
+    )
+}
+
+#[test]
+fn read_only_commands_leave_the_state_file_alone() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    let state_home = tempfile::tempdir().unwrap();
+    let state_file = tracked_repo(repo, state_home.path());
+
+    // The first run records a's LKG parent.
+    git_stack(repo, state_home.path(), &["status"]);
+    let contents = fs::read_to_string(&state_file).unwrap();
+    assert!(
+        !contents.contains("lkg_parent: null\n    branches: []"),
+        "{contents}"
+    );
+
+    // Saves replace the file, so an unchanged inode means nothing was written.
+    let inode = fs::metadata(&state_file).unwrap().ino();
+    for args in [&["status"][..], &["log"], &["note"], &["diff", "--stat"]] {
+        git_stack(repo, state_home.path(), args);
+        assert_eq!(fs::metadata(&state_file).unwrap().ino(), inode, "{args:?}");
+    }
+    assert_eq!(fs::read_to_string(&state_file).unwrap(), contents);
+
+    // A command that changes the state still saves it.
+    git_stack(repo, state_home.path(), &["note", "a", "--append", "hello"]);
+    assert!(fs::read_to_string(&state_file).unwrap().contains("hello"));
+}
+
+#[test]
+fn only_commands_that_change_the_stack_wait_on_the_state_lock() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    let state_home = tempfile::tempdir().unwrap();
+    let state_file = tracked_repo(repo, state_home.path());
+    git_stack(repo, state_home.path(), &["status"]);
+
+    // Stand in for a long-running git-stack, such as `status --watch` or an open editor.
+    let mut lock_path = state_file.into_os_string();
+    lock_path.push(".lock");
+    let holder = fs::File::create(&lock_path).unwrap();
+    holder.lock().unwrap();
+
+    for args in [
+        &["status"][..],
+        &["log"],
+        &["whoami"],
+        &["__complete_branches"],
+    ] {
+        let output = run_git_stack(repo, state_home.path(), args);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            !stdout.contains("Another git-stack process is running"),
+            "{args:?}: {stdout}"
+        );
+    }
+    let output = run_git_stack(repo, state_home.path(), &["note", "a", "--append", "x"]);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("Another git-stack process is running")
+    );
+
+    drop(holder);
+    git_stack(repo, state_home.path(), &["note", "a", "--append", "x"]);
+}
+
+#[test]
+fn a_save_refuses_to_overwrite_a_change_made_meanwhile() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    let state_home = tempfile::tempdir().unwrap();
+    let state_file = tracked_repo(repo, state_home.path());
+    git_stack(repo, state_home.path(), &["status"]);
+
+    // While the note is open in the "editor", another git-stack process saves the state.
+    let editor = state_home.path().join("editor.sh");
+    fs::write(
+        &editor,
+        format!(
+            "#!/bin/sh\necho mine > \"$1\"\nsed -i 's/^  name: main$/  name: main\\n  note: theirs/' {}\n",
+            state_file.display()
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&editor, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let output = git_stack_command(repo, state_home.path(), &["note", "--edit", "a"])
+        .env("EDITOR", &editor)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success(), "{stdout}");
+    assert!(
+        stdout.contains("changed since this git-stack process read it"),
+        "{stdout}"
+    );
+    let contents = fs::read_to_string(&state_file).unwrap();
+    assert!(contents.contains("note: theirs"), "{contents}");
+    assert!(!contents.contains("mine"), "{contents}");
+}
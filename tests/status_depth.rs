@@ -0,0 +1,32 @@
+mod common;
+
+use common::{git, git_stack, init_repo, write_state};
+
+#[test]
+fn depth_elides_deep_branches_with_a_count() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    init_repo(repo);
+    for branch in ["a", "b", "c", "d"] {
+        git(repo, &["branch", branch]);
+    }
+    git(repo, &["checkout", "-q", "a"]);
+
+    let state_home = tempfile::tempdir().unwrap();
+    // main -> a -> b -> c -> d
+    write_state(
+        state_home.path(),
+        &format!(
+            "version: 1\n{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: a\n    stack_method: apply_merge\n    lkg_parent: null\n    branches:\n    - name: b\n      stack_method: apply_merge\n      lkg_parent: null\n      branches:\n      - name: c\n        stack_method: apply_merge\n        lkg_parent: null\n        branches:\n        - name: d\n          stack_method: apply_merge\n          lkg_parent: null\n          branches: []\n",
+            repo.canonicalize().unwrap().display()
+        ),
+    );
+
+    let full = git_stack(repo, state_home.path(), &["status"]);
+    assert!(full.contains(" d"), "{full}");
+
+    let stdout = git_stack(repo, state_home.path(), &["status", "--depth", "1"]);
+    assert!(stdout.contains(" b"), "{stdout}");
+    assert!(!stdout.contains(" c"), "{stdout}");
+    assert!(stdout.contains("┃ ┃ ┃ … (2 more)"), "{stdout}");
+}
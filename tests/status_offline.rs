@@ -0,0 +1,56 @@
+mod common;
+
+use std::{fs, io::ErrorKind, net::TcpListener};
+
+use common::{git, git_stack_command, init_github_repo, succeeded, write_state};
+
+#[test]
+fn no_fetch_trunk_makes_no_network_calls() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path();
+    init_github_repo(repo);
+    git(repo, &["branch", "feature"]);
+
+    // An API that would see any request status made: a token and an `api_base` pointing at a
+    // listener nobody answers.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let api_base = format!("http://{}", listener.local_addr().unwrap());
+    let state_home = tempfile::tempdir().unwrap();
+    let config_dir = state_home.path().join("config/git-stack");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("github.yaml"),
+        format!(
+            "hosts:\n  github.com:\n    token: t\n    api_base: {api_base}/\nread_timeout_secs: 1\n"
+        ),
+    )
+    .unwrap();
+    // A years-old sync, so status would also want its whole-repo PR refresh.
+    write_state(
+        state_home.path(),
+        &format!(
+            "version: 1\n{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  last_sync: 2020-01-01T00:00:00Z\n  branches:\n  - name: feature\n    stack_method: apply_merge\n    lkg_parent: null\n    branches: []\n",
+            repo.canonicalize().unwrap().display()
+        ),
+    );
+
+    for flag in ["--no-fetch-trunk", "--no-pr-refresh"] {
+        let args = ["status", flag];
+        let output = git_stack_command(repo, state_home.path(), &args)
+            .env_remove("GITHUB_TOKEN")
+            .env_remove("GH_TOKEN")
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+        let stdout = succeeded(&args, output);
+        assert!(stdout.contains("feature"), "{stdout}");
+        assert!(!stderr.contains("a live fetch failed"), "{stderr}");
+    }
+
+    listener.set_nonblocking(true).unwrap();
+    let accepted = listener.accept();
+    assert!(
+        matches!(&accepted, Err(e) if e.kind() == ErrorKind::WouldBlock),
+        "status connected to the API: {accepted:?}"
+    );
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use std::{
+    io::Read,
+    process::{Command, Stdio},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use common::{git, git_stack_command, init_repo, write_state};
+
+/// Poll `output` until `done` holds, failing after a generous timeout.
+fn wait_for(output: &Mutex<String>, what: &str, done: impl Fn(&str) -> bool) {
+    let start = Instant::now();
+    while !done(&output.lock().unwrap()) {
+        assert!(
+            start.elapsed() < Duration::from_secs(20),
+            "timed out waiting for {what}:\n{}",
+            output.lock().unwrap()
+        );
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[test]
+fn watch_redraws_when_head_moves_and_exits_on_sigint() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    init_repo(repo);
+    git(repo, &["branch", "a"]);
+
+    let state_home = tempfile::tempdir().unwrap();
+    write_state(
+        state_home.path(),
+        &format!(
+            "version: 1\n{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: a\n    stack_method: apply_merge\n    lkg_parent: null\n    branches: []\n",
+            repo.canonicalize().unwrap().display()
+        ),
+    );
+
+    let mut child = git_stack_command(repo, state_home.path(), &["status", "--watch"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let output = Arc::new(Mutex::new(String::new()));
+    let reader = {
+        let output = Arc::clone(&output);
+        let mut stdout = child.stdout.take().unwrap();
+        std::thread::spawn(move || {
+            let mut buf = [0; 4096];
+            while let Ok(n) = stdout.read(&mut buf) {
+                if n == 0 {
+                    break;
+                }
+                output
+                    .lock()
+                    .unwrap()
+                    .push_str(&String::from_utf8_lossy(&buf[..n]));
+            }
+        })
+    };
+    let draws = |output: &str| output.matches(" main").count();
+
+    wait_for(&output, "the first draw", |output| draws(output) >= 1);
+    git(repo, &["checkout", "-q", "a"]);
+    wait_for(&output, "a redraw after checkout", |output| {
+        draws(output) >= 2
+    });
+
+    let status = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let status = child.wait().unwrap();
+    reader.join().unwrap();
+    assert!(status.success(), "{status}: {}", output.lock().unwrap());
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use std::fs;
+
+use common::{git, git_stack, init_repo, write_state};
+
+#[test]
+fn tidy_prunes_missing_branches_clears_stale_lkgs_and_sorts() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repo_dir.path();
+    init_repo(repo);
+    git(repo, &["branch", "zeta"]);
+    git(repo, &["branch", "alpha"]);
+    git(repo, &["branch", "child"]);
+    git(
+        repo,
+        &["update-ref", "refs/remotes/origin/remote-only", "HEAD"],
+    );
+
+    // `zeta` sorts after `alpha`, `gone` exists nowhere, and `alpha`'s LKG parent is unknown.
+    let state_home = tempfile::tempdir().unwrap();
+    let state_file = write_state(
+        state_home.path(),
+        &format!(
+            "version: 1\n{}:\n  name: main\n  stack_method: apply_merge\n  lkg_parent: null\n  branches:\n  - name: zeta\n    stack_method: apply_merge\n    lkg_parent: null\n    branches: []\n  - name: gone\n    stack_method: apply_merge\n    lkg_parent: null\n    branches:\n    - name: child\n      stack_method: apply_merge\n      lkg_parent: null\n      branches: []\n  - name: remote-only\n    stack_method: apply_merge\n    lkg_parent: null\n    branches: []\n  - name: alpha\n    stack_method: apply_merge\n    lkg_parent: 0123456789012345678901234567890123456789\n    branches: []\n",
+            repo.canonicalize().unwrap().display()
+        ),
+    );
+
+    let stdout = git_stack(repo, state_home.path(), &["tidy", "--dry-run"]);
+    assert!(stdout.contains("[dry-run] Tidy summary"), "{stdout}");
+    assert!(
+        stdout.contains("Removed gone (branch no longer exists)"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("Remounted child onto main"), "{stdout}");
+    let state = fs::read_to_string(&state_file).unwrap();
+    assert!(state.contains("- name: gone\n"), "{state}");
+
+    let stdout = git_stack(repo, state_home.path(), &["tidy"]);
+    assert!(stdout.contains("Removed gone"), "{stdout}");
+    let state = fs::read_to_string(&state_file).unwrap();
+    assert!(!state.contains("gone"), "{state}");
+    assert!(!state.contains("0123456789"), "{state}");
+    let names: Vec<&str> = state
+        .lines()
+        .filter_map(|line| line.strip_prefix("  - name: "))
+        .collect();
+    assert_eq!(names, ["alpha", "child", "remote-only", "zeta"]);
+
+    // Nothing left to do, until remote-only branches are pruned too.
+    let stdout = git_stack(repo, state_home.path(), &["tidy"]);
+    assert!(stdout.contains("is already tidy"), "{stdout}");
+    let stdout = git_stack(repo, state_home.path(), &["tidy", "--prune-remote-only"]);
+    assert!(stdout.contains("Removed remote-only"), "{stdout}");
+    let state = fs::read_to_string(&state_file).unwrap();
+    assert!(!state.contains("remote-only"), "{state}");
+}
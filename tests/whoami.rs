@@ -0,0 +1,130 @@
+mod common;
+
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    path::Path,
+    process::Output,
+};
+
+use common::{git, git_stack_command, init_github_repo, init_repo};
+
+/// Run git-stack with no token in the environment or the global git config.
+fn run_without_tokens(repo: &Path, state_home: &Path, args: &[&str]) -> Output {
+    git_stack_command(repo, state_home, args)
+        .env_remove("GITHUB_TOKEN")
+        .env_remove("GH_TOKEN")
+        .env_remove("GITLAB_TOKEN")
+        .env("GIT_CONFIG_GLOBAL", "/dev/null")
+        .output()
+        .unwrap()
+}
+
+/// Answer one `GET /user` with `body`, returning the request's `auth_header` header.
+fn serve_user(
+    listener: TcpListener,
+    body: String,
+    auth_header: &'static str,
+) -> std::thread::JoinHandle<String> {
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut authorization = String::new();
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+            if let Some((name, value)) = line.split_once(':')
+                && name.eq_ignore_ascii_case(auth_header)
+            {
+                authorization = value.trim().to_string();
+            }
+            line.clear();
+        }
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+        .unwrap();
+        authorization
+    })
+}
+
+#[test]
+fn whoami_reports_the_token_source_and_login_but_not_the_token() {
+    let dir = tempfile::tempdir().unwrap();
+    init_github_repo(dir.path());
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let api_base = format!("http://{}", listener.local_addr().unwrap());
+    let state_home = tempfile::tempdir().unwrap();
+    let config_dir = state_home.path().join("config/git-stack");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("github.yaml"),
+        format!(
+            "hosts:\n  github.com:\n    token: s3cret-token\n    api_base: {api_base}/\nauthors_filter: [alice, bob]\n"
+        ),
+    )
+    .unwrap();
+    let server = serve_user(
+        listener,
+        "{\"login\":\"octocat\"}".to_string(),
+        "authorization",
+    );
+
+    let output = run_without_tokens(dir.path(), state_home.path(), &["whoami"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+    assert_eq!(server.join().unwrap(), "Bearer s3cret-token");
+    assert!(stdout.contains("Host:           github.com\n"), "{stdout}");
+    assert!(
+        stdout.contains("Token source:   config file (host-specific token)\n"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("Login:          octocat\n"), "{stdout}");
+    assert!(stdout.contains("Authors filter: alice, bob\n"), "{stdout}");
+    assert!(stdout.contains("Fork PRs:       hidden"), "{stdout}");
+    assert!(!stdout.contains("s3cret"), "{stdout}");
+}
+
+#[test]
+fn whoami_reports_a_gitlab_token_from_the_environment_over_the_config_file() {
+    let dir = tempfile::tempdir().unwrap();
+    init_repo(dir.path());
+    git(
+        dir.path(),
+        &["remote", "add", "origin", "git@gitlab.com:acme/app.git"],
+    );
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let api_base = format!("http://{}", listener.local_addr().unwrap());
+    let state_home = tempfile::tempdir().unwrap();
+    let config_dir = state_home.path().join("config/git-stack");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("github.yaml"),
+        format!("hosts:\n  gitlab.com:\n    token: config-token\n    api_base: {api_base}/\n"),
+    )
+    .unwrap();
+    let server = serve_user(
+        listener,
+        "{\"username\":\"tanuki\"}".to_string(),
+        "private-token",
+    );
+
+    let output = git_stack_command(dir.path(), state_home.path(), &["whoami"])
+        .env("GITLAB_TOKEN", "env-token")
+        .env("GIT_CONFIG_GLOBAL", "/dev/null")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+    assert_eq!(server.join().unwrap(), "env-token");
+    assert!(stdout.contains("Host:           gitlab.com\n"), "{stdout}");
+    assert!(
+        stdout.contains("Token source:   GITLAB_TOKEN environment variable\n"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("Login:          tanuki\n"), "{stdout}");
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use std::fs;
+
+use common::{git, git_init, git_stack, init_repo, point_origin_at, run_git_stack};
+
+#[test]
+fn linked_worktrees_share_the_main_repo_state() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path().join("repo");
+    let worktree = dir.path().join("wt");
+    fs::create_dir_all(&repo).unwrap();
+    init_repo(&repo);
+    git(
+        &repo,
+        &[
+            "worktree",
+            "add",
+            "-q",
+            "-b",
+            "feature",
+            worktree.to_str().unwrap(),
+        ],
+    );
+
+    // Track `feature` from the worktree where it is checked out...
+    let state_home = tempfile::tempdir().unwrap();
+    git_stack(&worktree, state_home.path(), &["mount", "main"]);
+
+    // ...and the main working tree sees the same tree.
+    let exported = git_stack(&repo, state_home.path(), &["export"]);
+    assert!(exported.contains("- name: feature\n"), "{exported}");
+
+    let state = fs::read_to_string(state_home.path().join("git-stack/state.yaml")).unwrap();
+    let state: serde_yaml::Value = serde_yaml::from_str(&state).unwrap();
+    let keys: Vec<&str> = state
+        .as_mapping()
+        .unwrap()
+        .keys()
+        .filter_map(|key| key.as_str())
+        .filter(|key| *key != "version")
+        .collect();
+    assert_eq!(
+        keys,
+        [repo.canonicalize().unwrap().to_str().unwrap()],
+        "one state entry, keyed by the main working tree"
+    );
+}
+
+#[test]
+fn a_paused_restack_is_resumed_only_from_its_own_worktree() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path().join("repo");
+    let worktree = dir.path().join("wt");
+    fs::create_dir_all(&repo).unwrap();
+    git_init(&repo);
+    fs::write(repo.join("shared.txt"), "base\n").unwrap();
+    git(&repo, &["add", "shared.txt"]);
+    git(&repo, &["commit", "-q", "-m", "root"]);
+    point_origin_at(&repo, "HEAD");
+    git(&repo, &["checkout", "-q", "-b", "feature"]);
+    fs::write(repo.join("shared.txt"), "feature\n").unwrap();
+    git(&repo, &["commit", "-q", "-am", "feature work"]);
+
+    let state_home = tempfile::tempdir().unwrap();
+    git_stack(&repo, state_home.path(), &["mount", "main"]);
+    git(&repo, &["checkout", "-q", "main"]);
+    fs::write(repo.join("shared.txt"), "main\n").unwrap();
+    git(&repo, &["commit", "-q", "-am", "main work"]);
+    git(&repo, &["update-ref", "refs/remotes/origin/main", "HEAD"]);
+    git(&repo, &["checkout", "-q", "feature"]);
+    git(
+        &repo,
+        &[
+            "worktree",
+            "add",
+            "-q",
+            "--detach",
+            worktree.to_str().unwrap(),
+        ],
+    );
+
+    let restack = run_git_stack(&repo, state_home.path(), &["restack"]);
+    assert!(!restack.status.success(), "the restack must conflict");
+
+    // Every worktree shares the repo's state, but only the paused one may resume or abort it.
+    let abort = run_git_stack(&worktree, state_home.path(), &["restack", "--abort"]);
+    let stdout = String::from_utf8_lossy(&abort.stdout);
+    assert!(!abort.status.success(), "{stdout}");
+    let expected = format!(
+        "A restack operation is in progress in the worktree at {}.",
+        repo.canonicalize().unwrap().display()
+    );
+    assert!(stdout.contains(&expected), "{stdout}");
+
+    git_stack(&repo, state_home.path(), &["restack", "--abort"]);
+    let state = fs::read_to_string(state_home.path().join("git-stack/state.yaml")).unwrap();
+    assert!(!state.contains("pending_restack:"), "{state}");
+}